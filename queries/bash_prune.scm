@@ -0,0 +1,2 @@
+; Capture shell function bodies
+(function_definition body: (_) @body)
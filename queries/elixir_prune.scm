@@ -0,0 +1,4 @@
+; Capture Elixir def/defp/defmacro do-block bodies (module bodies are kept)
+(call
+  target: (identifier) @_kw (#match? @_kw "^(def|defp|defmacro|defmacrop)$")
+  (do_block) @body)
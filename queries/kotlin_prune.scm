@@ -0,0 +1,2 @@
+; Capture Kotlin function bodies (block and expression bodies)
+(function_declaration (function_body) @body)
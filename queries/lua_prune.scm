@@ -0,0 +1,3 @@
+; Capture Lua function bodies (declarations and function expressions)
+(function_declaration body: (block) @body)
+(function_definition body: (block) @body)
@@ -0,0 +1,4 @@
+; Capture OCaml function and method bodies (module structures are kept)
+(let_binding (parameter) body: (_) @body)
+(let_binding body: [(fun_expression) (function_expression)] @body)
+(method_definition body: (_) @body)
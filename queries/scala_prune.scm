@@ -0,0 +1,2 @@
+; Capture Scala def bodies (blocks and single-expression bodies)
+(function_definition body: (_) @body)
@@ -0,0 +1,2 @@
+; Capture SQL function bodies (table and view definitions are kept whole)
+(create_function (function_body) @body)
@@ -0,0 +1,2 @@
+; Capture Zig function bodies
+(function_declaration body: (block) @body)
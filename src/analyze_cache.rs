@@ -0,0 +1,133 @@
+//! Persistent cache for [`analyze_file`] results.
+//!
+//! Entries live in `<repo>/.context-slicer/cache/<blake3>.json`, keyed by the file's content
+//! hash (plus file name, crate version and any query pack), so unchanged files skip parsing
+//! across processes. Stale entries are simply never hit again; deleting the directory is
+//! always safe.
+
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::inspector::{analyze_file, query_pack_source, FileSymbols};
+
+pub const CACHE_DIR: &str = ".context-slicer/cache";
+
+/// [`analyze_file`] backed by the on-disk cache under `repo_root`. Cache I/O failures fall
+/// back to a fresh analysis; only read and parse errors are returned.
+pub fn analyze_file_cached(repo_root: &Path, path: &Path) -> Result<FileSymbols> {
+    let abs = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        repo_root.join(path)
+    };
+    let bytes = std::fs::read(&abs).with_context(|| format!("Failed to read {}", abs.display()))?;
+    let entry = entry_path(repo_root, &abs, &bytes);
+    let file = path.to_string_lossy().replace('\\', "/");
+
+    if let Some(mut hit) = std::fs::read(&entry)
+        .ok()
+        .and_then(|raw| serde_json::from_slice::<FileSymbols>(&raw).ok())
+    {
+        // Identical content under another path shares the entry.
+        hit.file = file;
+        return Ok(hit);
+    }
+
+    let mut fresh = analyze_file(&abs)?;
+    fresh.file = file;
+    let _ = store_json(&entry, &fresh);
+    Ok(fresh)
+}
+
+/// [`analyze_file_cached`] over many paths in parallel, results in input order.
+pub fn analyze_files_cached(repo_root: &Path, paths: &[PathBuf]) -> Vec<Result<FileSymbols>> {
+    paths
+        .par_iter()
+        .map(|p| analyze_file_cached(repo_root, p))
+        .collect()
+}
+
+fn entry_path(repo_root: &Path, abs: &Path, bytes: &[u8]) -> PathBuf {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+    hasher.update(b"\0");
+    // The file name selects the driver (`x.ts` vs `x.tsx`, `.d.ts`, notebooks).
+    let name = abs
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    hasher.update(name.as_bytes());
+    hasher.update(b"\0");
+    if let Some(pack) = query_pack_source(abs) {
+        hasher.update(pack.as_bytes());
+    }
+    hasher.update(b"\0");
+    hasher.update(bytes);
+    repo_root
+        .join(CACHE_DIR)
+        .join(format!("{}.json", hasher.finalize().to_hex()))
+}
+
+/// Write via a temp file + rename so concurrent readers never see a partial entry.
+pub(crate) fn store_json<T: Serialize>(entry: &Path, value: &T) -> Result<()> {
+    let dir = entry.parent().context("cache entry has no parent")?;
+    std::fs::create_dir_all(dir)?;
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    tmp.write_all(&serde_json::to_vec(value)?)?;
+    tmp.persist(entry)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged_files_are_served_from_the_cache() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let file = root.join("lib.rs");
+        std::fs::write(&file, "// TODO: x\npub fn a() {}\n").unwrap();
+
+        let cold = analyze_file_cached(root, &file).unwrap();
+        let entries: Vec<_> = std::fs::read_dir(root.join(CACHE_DIR))
+            .unwrap()
+            .flatten()
+            .map(|e| e.path())
+            .collect();
+        assert_eq!(entries.len(), 1);
+
+        // Tamper with the entry: a hit must come from disk, not a re-parse.
+        let mut cached: FileSymbols =
+            serde_json::from_slice(&std::fs::read(&entries[0]).unwrap()).unwrap();
+        cached.symbols[0].name = "from_cache".to_string();
+        std::fs::write(&entries[0], serde_json::to_vec(&cached).unwrap()).unwrap();
+        let warm = analyze_file_cached(root, &file).unwrap();
+        assert_eq!(warm.symbols[0].name, "from_cache");
+        assert_eq!(warm.markers, cold.markers);
+        assert_eq!(warm.file, cold.file);
+
+        // Edits change the key.
+        std::fs::write(&file, "pub fn b() {}\n").unwrap();
+        let edited = analyze_file_cached(root, &file).unwrap();
+        assert_eq!(edited.symbols[0].name, "b");
+    }
+
+    #[test]
+    fn concurrent_writes_of_one_entry_all_succeed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let entry = tmp.path().join(CACHE_DIR).join("entry.json");
+        (0..64u32)
+            .into_par_iter()
+            .for_each(|i| store_json(&entry, &vec![i; 256]).unwrap());
+        let stored: Vec<u32> = serde_json::from_slice(&std::fs::read(&entry).unwrap()).unwrap();
+        assert_eq!(stored.len(), 256);
+        assert_eq!(
+            std::fs::read_dir(entry.parent().unwrap()).unwrap().count(),
+            1
+        );
+    }
+}
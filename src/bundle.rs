@@ -0,0 +1,72 @@
+//! Context bundles: the files of one slice run packed into a single `.tar.gz`, so a
+//! complete context package can be attached to a ticket or handed to another agent.
+
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::path::Path;
+
+/// Write `entries` (archive name, contents) to a gzipped tarball at `out`, in order.
+/// Entries get fixed metadata (mode 0644, mtime 0), so the same inputs always produce
+/// the same bytes.
+pub fn write_context_bundle(out: &Path, entries: &[(&str, &[u8])]) -> Result<()> {
+    if let Some(parent) = out.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::File::create(out)
+        .with_context(|| format!("Failed to create bundle {}", out.display()))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    for (name, contents) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, name, *contents)
+            .with_context(|| format!("Failed to add {name} to bundle"))?;
+    }
+    archive.into_inner()?.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn bundle_round_trips_entries_in_order_and_is_reproducible() {
+        let tmp = tempfile::tempdir().unwrap();
+        let out = tmp.path().join("nested/bundle.tar.gz");
+        let entries: [(&str, &[u8]); 2] = [
+            ("active_context.xml", b"<cortexast/>"),
+            ("active_context.meta.json", b"{}"),
+        ];
+        write_context_bundle(&out, &entries).unwrap();
+        let first = std::fs::read(&out).unwrap();
+        write_context_bundle(&out, &entries).unwrap();
+        assert_eq!(first, std::fs::read(&out).unwrap());
+
+        let mut archive = tar::Archive::new(GzDecoder::new(first.as_slice()));
+        let unpacked: Vec<(String, String)> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let name = entry.path().unwrap().to_string_lossy().to_string();
+                let mut body = String::new();
+                entry.read_to_string(&mut body).unwrap();
+                (name, body)
+            })
+            .collect();
+        assert_eq!(
+            unpacked,
+            vec![
+                ("active_context.xml".to_string(), "<cortexast/>".to_string()),
+                ("active_context.meta.json".to_string(), "{}".to_string()),
+            ]
+        );
+    }
+}
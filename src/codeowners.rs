@@ -0,0 +1,157 @@
+//! GitHub `CODEOWNERS` parsing for ownership attribution on map and module nodes.
+//!
+//! The file is looked up where GitHub looks for it (`.github/`, the repo root, `docs/`).
+//! Patterns follow GitHub's gitignore-like rules: a pattern without a leading or inner `/`
+//! matches at any depth, a trailing `/` only matches directories, a match on a directory
+//! covers everything below it, and the last matching line wins.
+
+use glob::{MatchOptions, Pattern};
+use std::path::Path;
+
+const LOCATIONS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+const MATCH: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug, Clone)]
+struct Rule {
+    pattern: Pattern,
+    dir_only: bool,
+    owners: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CodeOwners {
+    rules: Vec<Rule>,
+}
+
+impl CodeOwners {
+    /// The first `CODEOWNERS` file found under `repo_root`; empty when there is none.
+    pub fn load(repo_root: &Path) -> Self {
+        LOCATIONS
+            .iter()
+            .find_map(|loc| std::fs::read_to_string(repo_root.join(loc)).ok())
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut rules = Vec::new();
+        for line in text.lines() {
+            let line = line.split(" #").next().unwrap_or("").trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let Some(raw) = parts.next() else { continue };
+            let owners: Vec<String> = parts.map(str::to_string).collect();
+
+            let dir_only = raw.ends_with('/');
+            let trimmed = raw.trim_end_matches('/');
+            let anchored = trimmed.starts_with('/') || trimmed.contains('/');
+            let body = trimmed.trim_start_matches('/');
+            let glob = match (anchored, body) {
+                (_, "" | "*") => "**".to_string(),
+                (true, _) => body.to_string(),
+                (false, _) => format!("**/{body}"),
+            };
+            if let Ok(pattern) = Pattern::new(&glob) {
+                rules.push(Rule {
+                    pattern,
+                    dir_only,
+                    owners,
+                });
+            }
+        }
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Owners of a repo-relative path (`/`-separated; `.` or empty for the root). A rule
+    /// with no owners clears ownership, as on GitHub.
+    pub fn owners_of(&self, rel_path: &str, is_dir: bool) -> Vec<String> {
+        let rel_path = rel_path.trim_matches('/');
+        if rel_path.is_empty() || rel_path == "." {
+            return self
+                .rules
+                .iter()
+                .rev()
+                .find(|r| r.pattern.as_str() == "**")
+                .map(|r| r.owners.clone())
+                .unwrap_or_default();
+        }
+
+        // The path itself plus each ancestor directory, which a rule may match instead.
+        let mut candidates: Vec<(&str, bool)> = vec![(rel_path, is_dir)];
+        let mut rest = rel_path;
+        while let Some((dir, _)) = rest.rsplit_once('/') {
+            candidates.push((dir, true));
+            rest = dir;
+        }
+
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                candidates.iter().any(|&(path, path_is_dir)| {
+                    (path_is_dir || !rule.dir_only) && rule.pattern.matches_with(path, MATCH)
+                })
+            })
+            .map(|rule| rule.owners.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_matching_rule_wins_with_gitignore_style_patterns() {
+        let owners = CodeOwners::parse(
+            "# Default owners\n\
+             *       @org/core\n\
+             *.js    @org/frontend  # inline comment\n\
+             /docs/  @org/docs\n\
+             apps/   @org/apps\n\
+             /apps/billing/** @alice @bob\n\
+             build/logs/ \n",
+        );
+        let of = |p: &str, dir: bool| owners.owners_of(p, dir);
+
+        assert_eq!(of(".", true), vec!["@org/core"]);
+        assert_eq!(of("src/main.rs", false), vec!["@org/core"]);
+        assert_eq!(of("web/src/app.js", false), vec!["@org/frontend"]);
+        assert_eq!(of("docs", true), vec!["@org/docs"]);
+        assert_eq!(of("docs/guide/intro.md", false), vec!["@org/docs"]);
+        // Unanchored directory patterns match at any depth.
+        assert_eq!(of("services/apps/x.rs", false), vec!["@org/apps"]);
+        assert_eq!(
+            of("apps/billing/api/index.ts", false),
+            vec!["@alice", "@bob"]
+        );
+        // `apps/` only matches directories; a file named `apps` falls back to `*`.
+        assert_eq!(of("apps", false), vec!["@org/core"]);
+        // Owner-less rules clear ownership.
+        assert!(of("build/logs/today.txt", false).is_empty());
+    }
+
+    #[test]
+    fn load_prefers_the_github_directory() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(CodeOwners::load(tmp.path()).is_empty());
+        std::fs::write(tmp.path().join("CODEOWNERS"), "* @root\n").unwrap();
+        std::fs::create_dir_all(tmp.path().join(".github")).unwrap();
+        std::fs::write(tmp.path().join(".github/CODEOWNERS"), "* @github\n").unwrap();
+        assert_eq!(
+            CodeOwners::load(tmp.path()).owners_of("a.rs", false),
+            vec!["@github"]
+        );
+    }
+}
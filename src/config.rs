@@ -1,11 +1,102 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct TokenEstimatorConfig {
     pub chars_per_token: usize,
+    /// Per-extension chars-per-token overrides (e.g. `{"py": 3.4, "js": 2.8}`).
+    /// Extensions not listed use `chars_per_token`.
+    pub per_extension: BTreeMap<String, f64>,
     pub max_file_bytes: u64,
+    /// What slicing does with files larger than `max_file_bytes`.
+    pub oversize_policy: OversizePolicy,
+    /// Leading bytes kept by the `head_outline` oversize policy (capped at `max_file_bytes`).
+    pub oversize_head_bytes: u64,
+    /// What slicing does with files that no longer fit in the remaining budget.
+    pub budget_overflow: OverflowPolicy,
+    /// Most leading lines kept by the `head` and `outline_head` overflow policies.
+    pub overflow_head_lines: usize,
+}
+
+/// Handling of files over `token_estimator.max_file_bytes` when building slices.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OversizePolicy {
+    /// Drop the file from the slice (historical behaviour).
+    #[default]
+    Skip,
+    /// Include only the first `max_file_bytes` (streamed) with a truncation marker.
+    Head,
+    /// Include a symbol outline from `analyze_file` instead of the content.
+    Outline,
+    /// Include the first `oversize_head_bytes`, then an outline of the symbols after them.
+    #[serde(rename = "head_outline")]
+    HeadOutline,
+}
+
+/// Handling of files that don't fit in what is left of the slice budget.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Leave the file out (historical behaviour).
+    #[default]
+    Skip,
+    /// Include as many leading lines as fit, up to `overflow_head_lines`, with a
+    /// truncation marker.
+    Head,
+    /// Like `head`, preceded by the file's symbol outline.
+    OutlineHead,
+}
+
+/// Document format slices are rendered in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SliceFormat {
+    /// `<cortexast>` document with one CDATA `<file>` per file.
+    #[default]
+    Xml,
+    /// `## path` headers with fenced, language-tagged code blocks.
+    Markdown,
+    /// `{repository_map, files: [{path, language, tokens, content, symbols}]}`.
+    Json,
+    /// Raw contents separated by `===== path =====` lines.
+    Text,
+}
+
+impl SliceFormat {
+    /// File extension for the written `active_context.*`.
+    pub fn extension(self) -> &'static str {
+        match self {
+            SliceFormat::Xml => "xml",
+            SliceFormat::Markdown => "md",
+            SliceFormat::Json => "json",
+            SliceFormat::Text => "txt",
+        }
+    }
+
+    /// MIME type of the written slice, for MCP resources.
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            SliceFormat::Xml => "application/xml",
+            SliceFormat::Markdown => "text/markdown",
+            SliceFormat::Json => "application/json",
+            SliceFormat::Text => "text/plain",
+        }
+    }
+}
+
+/// Order in which ranked files are packed into a slice.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackingStrategy {
+    /// Most relevant files first (see `slicer::rank_entries`).
+    #[default]
+    Relevance,
+    /// READMEs, architecture/design notes and `docs/` pages of the target ahead of source
+    /// files, shallowest first, so tight budgets still carry orientation material.
+    DocsFirst,
 }
 
 /// Controls workspace scanning behavior (what to skip).
@@ -19,6 +110,19 @@ pub struct ScanConfig {
     ///
     /// These are compared against path components, not full paths.
     pub exclude_dir_names: Vec<String>,
+    /// Leave test files and directories out of slices and maps (see
+    /// [`crate::scanner::is_test_path`]).
+    pub exclude_tests: bool,
+    /// Descend into symlinked directories (e.g. shared packages linked into a monorepo).
+    /// Each directory is visited once, so links to the same place and link cycles are
+    /// skipped.
+    pub follow_symlinks: bool,
+    /// Keep files that the root `.gitattributes` marks `linguist-generated` or
+    /// `linguist-vendored` (see [`crate::gitattributes`]); they are left out by default.
+    pub include_generated: bool,
+    /// More roots, relative to the repo, sliced together with the target in one pass (e.g.
+    /// `proto` next to `src`); overlapping roots are deduplicated.
+    pub extra_targets: Vec<PathBuf>,
 }
 
 /// Hard safety ceiling: files larger than this are **always** skipped, regardless of config.
@@ -29,8 +133,13 @@ impl Default for TokenEstimatorConfig {
     fn default() -> Self {
         Self {
             chars_per_token: 4,
+            per_extension: BTreeMap::new(),
             // 512 KB default — enough for any real source file, blocks log/generated bloat.
             max_file_bytes: 512 * 1024,
+            oversize_policy: OversizePolicy::Skip,
+            oversize_head_bytes: 32 * 1024,
+            budget_overflow: OverflowPolicy::Skip,
+            overflow_head_lines: 40,
         }
     }
 }
@@ -92,6 +201,32 @@ pub struct Config {
     pub token_estimator: TokenEstimatorConfig,
     /// When true, generate "skeleton" file content (function bodies pruned) for supported languages.
     pub skeleton_mode: bool,
+    /// When true, slice files as an API outline instead: doc comments, signatures and
+    /// type definitions only (overrides `skeleton_mode`).
+    pub outline_mode: bool,
+    /// When true, strip comments (license headers included), blank lines and trailing
+    /// whitespace from sliced source files before budget fitting.
+    pub compress: bool,
+    /// Slice output format (`"xml"`, `"markdown"`, `"json"` or `"text"`).
+    pub slice_format: SliceFormat,
+    /// Extra per-file metadata in XML slices.
+    pub xml_metadata: XmlMetadataConfig,
+    /// Globs of files packed first (after the focus file), in list order, ahead of relevance
+    /// ranking. A glob without `/` matches file names at any depth (`lib.rs`, `*.prisma`);
+    /// others match repo-relative paths (`docs/**/*.md`).
+    pub priority_globs: Vec<String>,
+    /// Globs (as in `priority_globs`) of files added to every slice, even outside the
+    /// target, and packed before everything but the focus file.
+    pub always_include: Vec<String>,
+    /// Packing order after the focus file and pinned files (`"relevance"` or `"docs_first"`).
+    pub packing: PackingStrategy,
+    /// Past decisions from the memory journal embedded in slices.
+    pub memory: SliceMemoryConfig,
+    /// When true, slices open with a `<rules>` section holding the merged rule tiers
+    /// (global, team and project `.cortex_rules.yml`), as returned by `cortex_get_rules`.
+    pub slice_rules: bool,
+    /// Secrets masked in file content before it is sliced.
+    pub redaction: RedactionConfig,
     /// Vector search defaults when using `--query`.
     pub vector_search: VectorSearchConfig,
     /// Settings that govern huge monorepo / multi-service workspace behaviour.
@@ -99,6 +234,192 @@ pub struct Config {
     /// List of active languages for dynamic grammar loading (Wasm).
     /// Defaults to ["rust", "typescript", "python"].
     pub active_languages: Vec<String>,
+    /// Declared architecture layers, checked by `--check-layers`.
+    pub architecture: ArchitectureConfig,
+    /// Named model budget profiles, on top of (or replacing) the built-in ones.
+    pub profiles: BTreeMap<String, BudgetProfile>,
+}
+
+/// Slice defaults for one target model, selected with `--profile` or the `profile` argument
+/// of `deep_slice`. Unset fields keep the config value; an explicit budget or format on the
+/// command line or tool call still wins.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetProfile {
+    pub budget_tokens: Option<usize>,
+    pub chars_per_token: Option<usize>,
+    pub slice_format: Option<SliceFormat>,
+}
+
+/// Profiles available without any config. Budgets leave headroom below each context
+/// window for the conversation and the reply.
+pub fn builtin_profiles() -> BTreeMap<String, BudgetProfile> {
+    let profile = |budget, cpt, format| BudgetProfile {
+        budget_tokens: Some(budget),
+        chars_per_token: Some(cpt),
+        slice_format: Some(format),
+    };
+    BTreeMap::from([
+        (
+            "claude-200k".to_string(),
+            profile(150_000, 4, SliceFormat::Xml),
+        ),
+        (
+            "gpt-4o-128k".to_string(),
+            profile(96_000, 4, SliceFormat::Markdown),
+        ),
+        ("local-8k".to_string(), profile(6_000, 3, SliceFormat::Text)),
+    ])
+}
+
+impl Config {
+    /// The profile called `name`, from `profiles` first and then the built-ins.
+    pub fn profile(&self, name: &str) -> Option<BudgetProfile> {
+        self.profiles
+            .get(name)
+            .cloned()
+            .or_else(|| builtin_profiles().remove(name))
+    }
+
+    /// Every selectable profile name, sorted.
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = builtin_profiles().into_keys().collect();
+        names.extend(self.profiles.keys().cloned());
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Apply profile `name`'s chars-per-token and format defaults, returning its budget.
+    pub fn apply_profile(&mut self, name: &str) -> anyhow::Result<Option<usize>> {
+        let Some(profile) = self.profile(name) else {
+            anyhow::bail!(
+                "Unknown profile '{name}' (available: {})",
+                self.profile_names().join(", ")
+            );
+        };
+        if let Some(cpt) = profile.chars_per_token {
+            self.token_estimator.chars_per_token = cpt;
+        }
+        if let Some(format) = profile.slice_format {
+            self.slice_format = format;
+        }
+        Ok(profile.budget_tokens)
+    }
+}
+
+/// Layering rules: which layer may import which.
+///
+/// ```json
+/// "architecture": {
+///   "layers": [
+///     { "name": "ui", "paths": ["apps/web"] },
+///     { "name": "core", "paths": ["packages/core/**"] },
+///     { "name": "data", "paths": ["packages/db", "packages/cache"] }
+///   ],
+///   "allow": ["ui -> core -> data"]
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchitectureConfig {
+    pub layers: Vec<LayerConfig>,
+    /// Allowed dependencies as chains: `"ui -> core -> data"` allows `ui -> core` and
+    /// `core -> data` only (not `ui -> data`). Imports within a layer are always allowed.
+    pub allow: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayerConfig {
+    pub name: String,
+    /// Repo-relative globs; a file belongs to the first layer matching it or any of its
+    /// parent directories, so a plain directory path covers everything below it.
+    pub paths: Vec<String>,
+}
+
+impl ArchitectureConfig {
+    /// Every `(from, to)` pair spelled out by `allow`.
+    pub fn allowed_pairs(&self) -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+        for chain in &self.allow {
+            let layers: Vec<&str> = chain.split("->").map(str::trim).collect();
+            for pair in layers.windows(2) {
+                pairs.push((pair[0].to_string(), pair[1].to_string()));
+            }
+        }
+        pairs
+    }
+}
+
+/// Optional metadata on each `<file>` of an XML slice, so agents can navigate the slice
+/// without re-parsing it. All off by default; none of it counts toward `budget_tokens`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct XmlMetadataConfig {
+    /// `language` attribute (`rust`, `typescript`, ...), omitted when unknown.
+    pub language: bool,
+    /// `est_tokens` attribute: estimated tokens of the emitted content.
+    pub est_tokens: bool,
+    /// `sha256` attribute: hex digest of the file on disk (not of a skeleton), so stale
+    /// slices can be detected.
+    pub sha256: bool,
+    /// `<symbols>` child listing the file's symbols with kinds and 0-indexed lines.
+    pub symbols: bool,
+}
+
+/// Memory journal entries embedded in a `<memory>` section ahead of the sliced files,
+/// picked by overlap of their `files_touched` with the slice and by keyword match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SliceMemoryConfig {
+    /// Off by default.
+    pub enabled: bool,
+    /// At most this many entries, best matches first.
+    pub max_entries: usize,
+    /// Journal to read; defaults to `~/.cortexast/global_memory.jsonl`.
+    pub journal: Option<PathBuf>,
+}
+
+impl Default for SliceMemoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: 5,
+            journal: None,
+        }
+    }
+}
+
+/// Secret redaction applied to every sliced file (see [`crate::redact`]). Matches are
+/// replaced with `[REDACTED:<rule>]` and counted in the slice meta.
+///
+/// ```json
+/// "redaction": {
+///   "patterns": { "internal_token": "itk_[0-9a-f]{32}" },
+///   "disable": ["generic_secret"]
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// On by default.
+    pub enabled: bool,
+    /// Extra rules by name. When a regex has a capture group, only the first group is
+    /// masked (e.g. the value after `key = `), otherwise the whole match.
+    pub patterns: BTreeMap<String, String>,
+    /// Built-in rules to turn off, by name.
+    pub disable: Vec<String>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            patterns: BTreeMap::new(),
+            disable: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -130,8 +451,20 @@ impl Default for Config {
             scan: ScanConfig::default(),
             token_estimator: TokenEstimatorConfig::default(),
             skeleton_mode: true,
+            outline_mode: false,
+            compress: false,
+            slice_format: SliceFormat::default(),
+            xml_metadata: XmlMetadataConfig::default(),
+            priority_globs: Vec::new(),
+            always_include: Vec::new(),
+            packing: PackingStrategy::default(),
+            memory: SliceMemoryConfig::default(),
+            slice_rules: false,
+            redaction: RedactionConfig::default(),
             vector_search: VectorSearchConfig::default(),
             huge_codebase: HugeCodebaseConfig::default(),
+            architecture: ArchitectureConfig::default(),
+            profiles: BTreeMap::new(),
             active_languages: vec![
                 "rust".to_string(),
                 "typescript".to_string(),
@@ -151,3 +484,34 @@ pub fn load_config(repo_root: &Path) -> Config {
 
     serde_json::from_str::<Config>(&text).unwrap_or_else(|_| Config::default())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_set_slice_defaults_and_config_overrides_builtins() {
+        let mut cfg: Config = serde_json::from_str(
+            r#"{"profiles": {"local-8k": {"budget_tokens": 7000}, "tiny": {"slice_format": "json"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            cfg.profile_names(),
+            vec!["claude-200k", "gpt-4o-128k", "local-8k", "tiny"]
+        );
+
+        assert_eq!(cfg.apply_profile("gpt-4o-128k").unwrap(), Some(96_000));
+        assert_eq!(cfg.slice_format, SliceFormat::Markdown);
+
+        // A config profile replaces the built-in of the same name; unset fields are kept.
+        assert_eq!(cfg.apply_profile("local-8k").unwrap(), Some(7_000));
+        assert_eq!(cfg.token_estimator.chars_per_token, 4);
+        assert_eq!(cfg.slice_format, SliceFormat::Markdown);
+
+        assert_eq!(cfg.apply_profile("tiny").unwrap(), None);
+        assert_eq!(cfg.slice_format, SliceFormat::Json);
+
+        let err = cfg.apply_profile("gpt-5").unwrap_err().to_string();
+        assert!(err.contains("available: claude-200k, gpt-4o-128k"), "{err}");
+    }
+}
@@ -0,0 +1,638 @@
+//! Key outlines for config files (`package.json`, `Cargo.toml`, `tsconfig.json`, YAML).
+//!
+//! Config files have no meaningful tree-sitter skeleton, so skeleton mode used to include them
+//! in full (or truncated). Instead we list their top-level keys, expand `scripts` and
+//! dependency sections one level, and report line spans so `read_symbol` can slice them.
+
+use crate::inspector::Symbol;
+use serde_json::Value;
+use std::path::Path;
+
+/// Sections whose entries become symbols of their own (`scripts.build`, `dependencies.serde`).
+const SECTIONS: &[&str] = &[
+    "scripts",
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+    "dev-dependencies",
+    "build-dependencies",
+    "dev_dependencies",
+];
+
+/// Expanded sections list at most this many entries in the rendered outline.
+const MAX_SECTION_ENTRIES: usize = 50;
+/// Non-section objects list at most this many of their keys inline.
+const MAX_INLINE_KEYS: usize = 8;
+const MAX_SCALAR_CHARS: usize = 80;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Json,
+    Toml,
+    Yaml,
+}
+
+fn format_for_path(path: &Path) -> Option<Format> {
+    let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    if name.ends_with(".yaml") || name.ends_with(".yml") {
+        return Some(Format::Yaml);
+    }
+    if name == "cargo.toml" {
+        return Some(Format::Toml);
+    }
+    let tsconfig =
+        (name.starts_with("tsconfig.") || name.starts_with("jsconfig.")) && name.ends_with(".json");
+    if name == "package.json" || tsconfig {
+        return Some(Format::Json);
+    }
+    None
+}
+
+/// True when `path` is a config file summarised by [`outline_symbols`] / [`render_outline`].
+pub fn is_config_outline_path(path: &Path) -> bool {
+    format_for_path(path).is_some()
+}
+
+fn is_section(key: &str) -> bool {
+    SECTIONS.contains(&key)
+}
+
+/// A key found by the line scanners: `parent` is set for entries of an expanded section.
+struct Entry {
+    parent: Option<String>,
+    key: String,
+    line: usize,
+}
+
+/// Top-level keys plus `scripts`/dependency entries, as symbols with 0-indexed line spans.
+///
+/// Top-level keys have kind `"key"`; section entries are named `section.entry` with kind
+/// `"script"` or `"dependency"`. Returns `None` for paths that aren't outlined config files.
+pub fn outline_symbols(path: &Path, source: &str) -> Option<Vec<Symbol>> {
+    let format = format_for_path(path)?;
+    let entries = scan_entries(format, source);
+    let lines: Vec<&str> = source.lines().collect();
+    let mut line_starts = Vec::with_capacity(lines.len() + 1);
+    let mut off = 0usize;
+    for l in source.split_inclusive('\n') {
+        line_starts.push(off);
+        off += l.len();
+    }
+    line_starts.push(source.len());
+
+    let last_line = lines.len().saturating_sub(1);
+    let mut symbols = Vec::with_capacity(entries.len());
+    for (i, e) in entries.iter().enumerate() {
+        // An entry runs until the next key at its own level (or the end of its parent/file).
+        let next = entries[i + 1..]
+            .iter()
+            .find(|n| n.parent.is_none() || (e.parent.is_some() && n.parent == e.parent))
+            .map(|n| n.line.saturating_sub(1))
+            .unwrap_or(last_line);
+        let end = trim_span_end(&lines, e.line, next);
+
+        let (name, kind) = match &e.parent {
+            None => (e.key.clone(), "key"),
+            Some(p) if p == "scripts" => (format!("{p}.{}", e.key), "script"),
+            Some(p) => (format!("{p}.{}", e.key), "dependency"),
+        };
+        let signature = lines.get(e.line).map(|l| truncate(l.trim(), 120));
+        symbols.push(Symbol {
+            name,
+            kind: kind.to_string(),
+            line: e.line as u32,
+            line_end: end as u32,
+            start_byte: line_starts.get(e.line).copied().unwrap_or(source.len()),
+            end_byte: line_starts
+                .get(end + 1)
+                .copied()
+                .unwrap_or(source.len())
+                .min(source.len()),
+            signature,
+            ..Default::default()
+        });
+    }
+    Some(symbols)
+}
+
+/// Compact `key: value` outline used in place of the full file in skeleton mode.
+///
+/// Returns `None` for paths that aren't outlined config files.
+pub fn render_outline(path: &Path, source: &str) -> Option<String> {
+    let format = format_for_path(path)?;
+    let entries = scan_entries(format, source);
+
+    let Some(Value::Object(root)) = parse_value(format, source) else {
+        // Unparseable (or non-mapping) document: fall back to the scanned key lines.
+        let lines: Vec<&str> = source.lines().collect();
+        let mut out = String::from("# config outline (unparsed)\n");
+        for e in &entries {
+            let indent = if e.parent.is_some() { "  " } else { "" };
+            let text = lines.get(e.line).map(|l| l.trim()).unwrap_or_default();
+            out.push_str(&format!("{indent}{}\n", truncate(text, 120)));
+        }
+        return Some(out);
+    };
+
+    // Keep file order: scanned keys first, then anything the scanner missed (map order).
+    let mut order: Vec<&str> = Vec::new();
+    for e in entries.iter().filter(|e| e.parent.is_none()) {
+        let top = if format == Format::Toml {
+            e.key.split('.').next().unwrap_or(&e.key)
+        } else {
+            e.key.as_str()
+        };
+        if root.contains_key(top) && !order.contains(&top) {
+            order.push(top);
+        }
+    }
+    for k in root.keys() {
+        if !order.contains(&k.as_str()) {
+            order.push(k);
+        }
+    }
+
+    let mut out = format!("# config outline: {} top-level keys\n", order.len());
+    for key in order {
+        let value = &root[key];
+        match value {
+            Value::Object(map) if is_section(key) => {
+                out.push_str(&format!("{key}: ({} entries)\n", map.len()));
+                for (k, v) in map.iter().take(MAX_SECTION_ENTRIES) {
+                    out.push_str(&format!("  {k}: {}\n", render_section_value(v)));
+                }
+                if map.len() > MAX_SECTION_ENTRIES {
+                    out.push_str(&format!("  … +{} more\n", map.len() - MAX_SECTION_ENTRIES));
+                }
+            }
+            // TOML tables like `[target.'cfg(unix)'.dependencies]` nest sections one level down.
+            Value::Object(map) if map.keys().any(|k| is_section(k)) => {
+                out.push_str(&format!("{key}: {}\n", render_value(value)));
+                for (sub, v) in map.iter().filter(|(k, _)| is_section(k)) {
+                    if let Value::Object(deps) = v {
+                        let names: Vec<&str> = deps.keys().map(String::as_str).collect();
+                        out.push_str(&format!("  {sub}: {}\n", names.join(", ")));
+                    }
+                }
+            }
+            _ => out.push_str(&format!("{key}: {}\n", render_value(value))),
+        }
+    }
+    Some(out)
+}
+
+fn parse_value(format: Format, source: &str) -> Option<Value> {
+    match format {
+        Format::Json => serde_json::from_str(source)
+            .ok()
+            .or_else(|| serde_json::from_str(&strip_jsonc(source)).ok()),
+        Format::Toml => {
+            let v: toml::Value = toml::from_str(source).ok()?;
+            serde_json::to_value(v).ok()
+        }
+        Format::Yaml => {
+            let v: serde_yaml::Value = serde_yaml::from_str(source).ok()?;
+            serde_json::to_value(v).ok()
+        }
+    }
+}
+
+/// Dependency entries are usually a version string or a table with a `version` key.
+fn render_section_value(v: &Value) -> String {
+    match v {
+        Value::Object(map) => match map.get("version") {
+            Some(Value::String(ver)) if map.len() == 1 => truncate(ver, MAX_SCALAR_CHARS),
+            Some(Value::String(ver)) => {
+                format!("{} {}", truncate(ver, MAX_SCALAR_CHARS), render_value(v))
+            }
+            _ => render_value(v),
+        },
+        Value::String(s) => truncate(s, MAX_SCALAR_CHARS),
+        _ => render_value(v),
+    }
+}
+
+fn render_value(v: &Value) -> String {
+    match v {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => format!("\"{}\"", truncate(s, MAX_SCALAR_CHARS)),
+        Value::Array(items) => {
+            let scalars: Option<Vec<String>> = items
+                .iter()
+                .map(|i| match i {
+                    Value::Object(_) | Value::Array(_) => None,
+                    other => Some(render_value(other)),
+                })
+                .collect();
+            match scalars {
+                Some(s) if s.join(", ").chars().count() <= MAX_SCALAR_CHARS => {
+                    format!("[{}]", s.join(", "))
+                }
+                _ => format!("[{} items]", items.len()),
+            }
+        }
+        Value::Object(map) => {
+            let mut keys: Vec<&str> = map
+                .keys()
+                .take(MAX_INLINE_KEYS)
+                .map(String::as_str)
+                .collect();
+            let more = map.len().saturating_sub(MAX_INLINE_KEYS);
+            let extra = format!("… +{more}");
+            if more > 0 {
+                keys.push(&extra);
+            }
+            format!("{{ {} }}", keys.join(", "))
+        }
+    }
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        return s.to_string();
+    }
+    let mut t: String = s.chars().take(max).collect();
+    t.push('…');
+    t
+}
+
+/// Pull a span's end back over blank lines and closing-bracket-only lines.
+fn trim_span_end(lines: &[&str], start: usize, mut end: usize) -> usize {
+    while end > start {
+        let t = lines.get(end).map(|l| l.trim()).unwrap_or_default();
+        if t.is_empty() || t.starts_with('#') || t.chars().all(|c| "}],".contains(c)) {
+            end -= 1;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+fn scan_entries(format: Format, source: &str) -> Vec<Entry> {
+    match format {
+        Format::Json => scan_json(source),
+        Format::Toml => scan_toml(source),
+        Format::Yaml => scan_yaml(source),
+    }
+}
+
+/// Object keys at depth 1 (and depth 2 inside sections), tolerating JSONC comments.
+fn scan_json(source: &str) -> Vec<Entry> {
+    let bytes = source.as_bytes();
+    let mut out = Vec::new();
+    // Open containers: (is_object, key that opened it).
+    let mut stack: Vec<(bool, Option<String>)> = Vec::new();
+    let mut pending_key: Option<String> = None;
+    let mut line = 0usize;
+    let mut i = 0usize;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' => line += 1,
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                    if bytes[i] == b'\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+                i += 2;
+                continue;
+            }
+            b'"' => {
+                let start_line = line;
+                let start = i + 1;
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    } else if bytes[i] == b'\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+                let text = source.get(start..i.min(bytes.len())).unwrap_or_default();
+                let mut j = i + 1;
+                while j < bytes.len() && bytes[j].is_ascii_whitespace() {
+                    j += 1;
+                }
+                let in_object = matches!(stack.last(), Some((true, _)));
+                if in_object && bytes.get(j) == Some(&b':') {
+                    let parent = match stack.len() {
+                        1 => Some(None),
+                        2 => match &stack[1].1 {
+                            Some(p) if is_section(p) => Some(Some(p.clone())),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    if let Some(parent) = parent {
+                        out.push(Entry {
+                            parent,
+                            key: text.to_string(),
+                            line: start_line,
+                        });
+                    }
+                    pending_key = Some(text.to_string());
+                }
+            }
+            b'{' | b'[' => {
+                stack.push((bytes[i] == b'{', pending_key.take()));
+            }
+            b'}' | b']' => {
+                stack.pop();
+                pending_key = None;
+            }
+            b',' => pending_key = None,
+            _ => {}
+        }
+        i += 1;
+    }
+    out
+}
+
+/// `[table]` headers and bare keys; entries of dependency tables (including dotted
+/// `[dependencies.serde]` headers) become section entries.
+fn scan_toml(source: &str) -> Vec<Entry> {
+    let mut out = Vec::new();
+    let mut table: Option<String> = None;
+    for (line, raw) in source.lines().enumerate() {
+        let t = raw.trim();
+        if t.is_empty() || t.starts_with('#') {
+            continue;
+        }
+        if t.starts_with('[') {
+            let name = t
+                .trim_start_matches('[')
+                .split(']')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            match name.split_once('.') {
+                Some((section, rest)) if is_section(section) => out.push(Entry {
+                    parent: Some(section.to_string()),
+                    key: rest.trim_matches('"').to_string(),
+                    line,
+                }),
+                _ => out.push(Entry {
+                    parent: None,
+                    key: name.clone(),
+                    line,
+                }),
+            }
+            table = Some(name);
+            continue;
+        }
+        let Some(key) = toml_key(t) else { continue };
+        match table.as_deref() {
+            None => out.push(Entry {
+                parent: None,
+                key,
+                line,
+            }),
+            Some(tbl) if is_section(tbl) => out.push(Entry {
+                parent: Some(tbl.to_string()),
+                key,
+                line,
+            }),
+            _ => {}
+        }
+    }
+    out
+}
+
+fn toml_key(line: &str) -> Option<String> {
+    let (key, _) = line.split_once('=')?;
+    let key = key.trim();
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    let quoted = key.len() >= 2 && key.starts_with('"') && key.ends_with('"');
+    (bare || quoted).then(|| key.trim_matches('"').to_string())
+}
+
+/// Column-0 mapping keys, plus the first indentation level under sections.
+fn scan_yaml(source: &str) -> Vec<Entry> {
+    let mut out = Vec::new();
+    let mut section: Option<String> = None;
+    let mut child_indent: Option<usize> = None;
+    for (line, raw) in source.lines().enumerate() {
+        let t = raw.trim_start();
+        if t.is_empty() || t.starts_with('#') || t.starts_with("---") || t.starts_with("...") {
+            continue;
+        }
+        let indent = raw.len() - t.len();
+        if indent == 0 {
+            section = None;
+            child_indent = None;
+            let Some(key) = yaml_key(t) else { continue };
+            if is_section(&key) {
+                section = Some(key.clone());
+            }
+            out.push(Entry {
+                parent: None,
+                key,
+                line,
+            });
+            continue;
+        }
+        let Some(sec) = &section else { continue };
+        let want = *child_indent.get_or_insert(indent);
+        if indent == want {
+            if let Some(key) = yaml_key(t) {
+                out.push(Entry {
+                    parent: Some(sec.clone()),
+                    key,
+                    line,
+                });
+            }
+        }
+    }
+    out
+}
+
+fn yaml_key(t: &str) -> Option<String> {
+    if t.starts_with('-') || t.starts_with('[') || t.starts_with('{') {
+        return None;
+    }
+    let (key, rest) = if let Some(q) = t.strip_prefix('"').or_else(|| t.strip_prefix('\'')) {
+        let close = q.find(['"', '\''])?;
+        (&q[..close], &q[close + 1..])
+    } else {
+        let idx = t.find(':')?;
+        (&t[..idx], &t[idx..])
+    };
+    let rest = rest.trim_start();
+    let after = rest.strip_prefix(':')?;
+    (after.is_empty() || after.starts_with(char::is_whitespace))
+        .then(|| key.trim().to_string())
+        .filter(|k| !k.is_empty())
+}
+
+/// Drop `//` / `/* */` comments and trailing commas so tsconfig-style JSONC parses.
+pub(crate) fn strip_jsonc(source: &str) -> String {
+    let bytes = source.as_bytes();
+    let mut out = String::with_capacity(source.len());
+    let mut i = 0usize;
+    let mut seg_start = 0usize;
+    let mut in_string = false;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if in_string {
+            if b == b'\\' {
+                i += 1;
+            } else if b == b'"' {
+                in_string = false;
+            }
+            i += 1;
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'/' if matches!(bytes.get(i + 1), Some(b'/') | Some(b'*')) => {
+                out.push_str(&source[seg_start..i]);
+                if bytes[i + 1] == b'/' {
+                    while i < bytes.len() && bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                } else {
+                    i += 2;
+                    while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/'))
+                    {
+                        i += 1;
+                    }
+                    i = (i + 2).min(bytes.len());
+                }
+                seg_start = i;
+                continue;
+            }
+            b',' => {
+                let mut j = i + 1;
+                while j < bytes.len() && bytes[j].is_ascii_whitespace() {
+                    j += 1;
+                }
+                if matches!(bytes.get(j), Some(b'}') | Some(b']')) {
+                    out.push_str(&source[seg_start..i]);
+                    seg_start = i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    out.push_str(&source[seg_start.min(source.len())..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKAGE_JSON: &str = r#"{
+  "name": "web",
+  "version": "1.2.0",
+  "scripts": {
+    "build": "tsc -p .",
+    "test": "vitest"
+  },
+  "dependencies": {
+    "react": "^18.2.0"
+  },
+  "files": ["dist"]
+}
+"#;
+
+    fn names(path: &str, src: &str) -> Vec<(String, String, u32)> {
+        outline_symbols(Path::new(path), src)
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.name, s.kind, s.line))
+            .collect()
+    }
+
+    #[test]
+    fn package_json_lists_keys_scripts_and_dependencies() {
+        let syms = names("web/package.json", PACKAGE_JSON);
+        assert!(syms.contains(&("name".into(), "key".into(), 1)));
+        assert!(syms.contains(&("scripts".into(), "key".into(), 3)));
+        assert!(syms.contains(&("scripts.test".into(), "script".into(), 5)));
+        assert!(syms.contains(&("dependencies.react".into(), "dependency".into(), 8)));
+        assert!(syms.contains(&("files".into(), "key".into(), 10)));
+
+        let scripts = outline_symbols(Path::new("package.json"), PACKAGE_JSON)
+            .unwrap()
+            .into_iter()
+            .find(|s| s.name == "scripts")
+            .unwrap();
+        assert_eq!(scripts.line_end, 5);
+
+        let text = render_outline(Path::new("package.json"), PACKAGE_JSON).unwrap();
+        assert!(text.contains("name: \"web\""));
+        assert!(text.contains("  build: tsc -p ."));
+        assert!(text.contains("  react: ^18.2.0"));
+        assert!(text.contains("files: [\"dist\"]"));
+    }
+
+    #[test]
+    fn tsconfig_tolerates_comments_and_trailing_commas() {
+        let src = "{\n  // base options\n  \"compilerOptions\": {\n    \"strict\": true,\n  },\n  \"include\": [\"src\"],\n}\n";
+        let syms = names("tsconfig.build.json", src);
+        assert_eq!(
+            syms,
+            vec![
+                ("compilerOptions".into(), "key".into(), 2),
+                ("include".into(), "key".into(), 5),
+            ]
+        );
+        let text = render_outline(Path::new("tsconfig.build.json"), src).unwrap();
+        assert!(text.contains("compilerOptions: { strict }"));
+    }
+
+    #[test]
+    fn cargo_toml_tables_and_dependencies() {
+        let src = "[package]\nname = \"demo\"\n\n[dependencies]\nserde = { version = \"1\", features = [\"derive\"] }\nanyhow = \"1.0\"\n\n[dependencies.tokio]\nversion = \"1\"\n";
+        let syms = names("Cargo.toml", src);
+        assert_eq!(
+            syms,
+            vec![
+                ("package".into(), "key".into(), 0),
+                ("dependencies".into(), "key".into(), 3),
+                ("dependencies.serde".into(), "dependency".into(), 4),
+                ("dependencies.anyhow".into(), "dependency".into(), 5),
+                ("dependencies.tokio".into(), "dependency".into(), 7),
+            ]
+        );
+        let text = render_outline(Path::new("Cargo.toml"), src).unwrap();
+        assert!(text.contains("package: { name }"));
+        assert!(text.contains("  anyhow: 1.0"));
+        assert!(text.contains("  tokio: 1"));
+    }
+
+    #[test]
+    fn yaml_top_level_keys() {
+        let src = "# CI\nname: ci\non:\n  push: {}\njobs:\n  build:\n    runs-on: ubuntu\n";
+        let syms = names(".github/workflows/ci.yml", src);
+        assert_eq!(
+            syms,
+            vec![
+                ("name".into(), "key".into(), 1),
+                ("on".into(), "key".into(), 2),
+                ("jobs".into(), "key".into(), 4),
+            ]
+        );
+        assert!(!is_config_outline_path(Path::new("data/fixtures.json")));
+    }
+}
@@ -0,0 +1,162 @@
+//! `.gitattributes` parsing for GitHub Linguist's `linguist-generated` and
+//! `linguist-vendored` markers, which keep lockfiles, generated code and vendored
+//! dependencies out of slices and maps.
+//!
+//! Only the repo-root `.gitattributes` is read. Patterns follow git's rules: a pattern
+//! without a `/` matches the file name at any depth, one with a `/` is anchored to the
+//! root, patterns never match directories (use `dir/**`), and for each attribute the last
+//! matching line wins.
+
+use glob::{MatchOptions, Pattern};
+use std::path::Path;
+
+pub const GITATTRIBUTES_FILE: &str = ".gitattributes";
+
+const MATCH: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug, Clone)]
+struct Rule {
+    pattern: Pattern,
+    /// `Some(true)` when the line sets the attribute, `Some(false)` when it unsets it.
+    generated: Option<bool>,
+    vendored: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LinguistAttributes {
+    rules: Vec<Rule>,
+}
+
+impl LinguistAttributes {
+    /// The repo-root `.gitattributes`; empty when there is none.
+    pub fn load(repo_root: &Path) -> Self {
+        std::fs::read_to_string(repo_root.join(GITATTRIBUTES_FILE))
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut rules = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let Some(raw) = parts.next() else { continue };
+            let (mut generated, mut vendored) = (None, None);
+            for attr in parts {
+                let (name, value) = parse_attr(attr);
+                match name {
+                    "linguist-generated" => generated = Some(value),
+                    "linguist-vendored" => vendored = Some(value),
+                    _ => {}
+                }
+            }
+            // A trailing `/` only matches directories, which attributes never apply to.
+            if (generated.is_none() && vendored.is_none()) || raw.ends_with('/') {
+                continue;
+            }
+            let anchored = raw.contains('/');
+            let body = raw.trim_start_matches('/');
+            let glob = if anchored {
+                body.to_string()
+            } else {
+                format!("**/{body}")
+            };
+            if let Ok(pattern) = Pattern::new(&glob) {
+                rules.push(Rule {
+                    pattern,
+                    generated,
+                    vendored,
+                });
+            }
+        }
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The marker that excludes a repo-relative file (`/`-separated), if any:
+    /// `"linguist-generated"` or `"linguist-vendored"`.
+    pub fn excluded_by(&self, rel_path: &str) -> Option<&'static str> {
+        let rel_path = rel_path.trim_start_matches("./");
+        let last = |attr: fn(&Rule) -> Option<bool>| {
+            self.rules
+                .iter()
+                .rev()
+                .filter(|rule| rule.pattern.matches_with(rel_path, MATCH))
+                .find_map(attr)
+                .unwrap_or(false)
+        };
+        if last(|r| r.generated) {
+            Some("linguist-generated")
+        } else if last(|r| r.vendored) {
+            Some("linguist-vendored")
+        } else {
+            None
+        }
+    }
+}
+
+/// `name`, `name=true` and `name=<anything else>` set an attribute; `-name`, `!name` and
+/// `name=false` unset it.
+fn parse_attr(attr: &str) -> (&str, bool) {
+    if let Some(name) = attr.strip_prefix('-').or_else(|| attr.strip_prefix('!')) {
+        return (name, false);
+    }
+    match attr.split_once('=') {
+        Some((name, value)) => (name, !value.eq_ignore_ascii_case("false")),
+        None => (attr, true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_matching_line_decides_each_marker() {
+        let attrs = LinguistAttributes::parse(
+            "# Generated code\n\
+             *.pb.go          linguist-generated=true\n\
+             package-lock.json linguist-generated\n\
+             api/gen/**       linguist-generated\n\
+             api/gen/keep.go  -linguist-generated\n\
+             third_party/**   linguist-vendored\n\
+             third_party/ours/** linguist-vendored=false\n\
+             *.rs             text eol=lf\n\
+             docs/            linguist-documentation\n",
+        );
+        let of = |p: &str| attrs.excluded_by(p);
+
+        assert_eq!(of("svc/user.pb.go"), Some("linguist-generated"));
+        assert_eq!(of("web/package-lock.json"), Some("linguist-generated"));
+        assert_eq!(of("api/gen/v1/types.go"), Some("linguist-generated"));
+        assert_eq!(of("api/gen/keep.go"), None);
+        // Anchored patterns only match from the root.
+        assert_eq!(of("svc/api/gen/types.go"), None);
+        assert_eq!(of("third_party/zlib/zlib.c"), Some("linguist-vendored"));
+        assert_eq!(of("third_party/ours/patch.c"), None);
+        assert_eq!(of("src/main.rs"), None);
+    }
+
+    #[test]
+    fn load_reads_the_root_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(LinguistAttributes::load(tmp.path()).is_empty());
+        std::fs::write(
+            tmp.path().join(GITATTRIBUTES_FILE),
+            "Cargo.lock linguist-generated\n",
+        )
+        .unwrap();
+        let attrs = LinguistAttributes::load(tmp.path());
+        assert_eq!(attrs.excluded_by("Cargo.lock"), Some("linguist-generated"));
+    }
+}
@@ -85,6 +85,16 @@ fn github_wasm_url(lang: &str) -> String {
         "c_sharp"  => return "https://github.com/tree-sitter/tree-sitter-c-sharp/releases/latest/download/tree-sitter-c_sharp.wasm".to_string(),
         "cpp"      => return "https://github.com/tree-sitter/tree-sitter-cpp/releases/latest/download/tree-sitter-cpp.wasm".to_string(),
         "c"        => return "https://github.com/tree-sitter/tree-sitter-c/releases/latest/download/tree-sitter-c.wasm".to_string(),
+        // kotlin: the tree-sitter-grammars rewrite (node names differ from fwcd's grammar).
+        "kotlin"   => return "https://github.com/tree-sitter-grammars/tree-sitter-kotlin/releases/latest/download/tree-sitter-kotlin.wasm".to_string(),
+        "zig"      => return "https://github.com/tree-sitter-grammars/tree-sitter-zig/releases/latest/download/tree-sitter-zig.wasm".to_string(),
+        "lua"      => return "https://github.com/tree-sitter-grammars/tree-sitter-lua/releases/latest/download/tree-sitter-lua.wasm".to_string(),
+        // dart: the grammar published to crates.io (node names differ from older forks).
+        "dart"     => return "https://github.com/nielsenko/tree-sitter-dart/releases/latest/download/tree-sitter-dart.wasm".to_string(),
+        "elixir"   => return "https://github.com/elixir-lang/tree-sitter-elixir/releases/latest/download/tree-sitter-elixir.wasm".to_string(),
+        "sql"      => return "https://github.com/DerekStride/tree-sitter-sql/releases/latest/download/tree-sitter-sql.wasm".to_string(),
+        "graphql"  => return "https://github.com/joowani/tree-sitter-graphql/releases/latest/download/tree-sitter-graphql.wasm".to_string(),
+        "proto"    => return "https://github.com/coder3101/tree-sitter-proto/releases/latest/download/tree-sitter-proto.wasm".to_string(),
         // yaml grammar is maintained by ikatyang, not the main tree-sitter org.
         "yaml"     => return "https://github.com/ikatyang/tree-sitter-yaml/releases/latest/download/tree-sitter-yaml.wasm".to_string(),
         // toml: nickel-lang maintains a wasm-releasing fork.
@@ -170,6 +180,14 @@ pub fn bootstrap_embedded_queries() {
         (include_str!("../queries/cpp_prune.scm"),    "cpp"),
         (include_str!("../queries/ruby_prune.scm"),   "ruby"),
         (include_str!("../queries/c_prune.scm"),      "c"),
+        (include_str!("../queries/kotlin_prune.scm"), "kotlin"),
+        (include_str!("../queries/scala_prune.scm"),  "scala"),
+        (include_str!("../queries/zig_prune.scm"),    "zig"),
+        (include_str!("../queries/lua_prune.scm"),    "lua"),
+        (include_str!("../queries/elixir_prune.scm"), "elixir"),
+        (include_str!("../queries/bash_prune.scm"),   "bash"),
+        (include_str!("../queries/sql_prune.scm"),    "sql"),
+        (include_str!("../queries/ocaml_prune.scm"),  "ocaml"),
     ];
 
     let Ok(dir) = grammar_cache_dir() else { return };
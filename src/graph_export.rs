@@ -0,0 +1,617 @@
+//! Diagram renderings of [`ModuleGraph`] and [`RepoMap`] for pasting into docs and PRs.
+//!
+//! Both graph types are first flattened into an [`ExportGraph`] so every renderer handles
+//! one shape: module edges carry import counts, repo map edges are either containment
+//! (directory → entry) or imports. [`write_csv`] turns the same shape into node and edge
+//! tables; [`to_html_report`] instead bundles both graphs into a standalone page for
+//! sharing architecture snapshots.
+
+use crate::mapper::{ModuleGraph, RepoMap};
+use anyhow::{Context, Result};
+use quick_xml::escape::escape;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Import,
+    Contains,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportNode {
+    pub id: String,
+    pub label: String,
+    /// The source node's remaining fields (metrics, ownership, ...), for formats that
+    /// carry attributes.
+    pub attrs: BTreeMap<String, Value>,
+}
+
+/// Every serialized field of `node` except its id and label.
+fn node_attrs<T: Serialize>(node: &T) -> BTreeMap<String, Value> {
+    match serde_json::to_value(node) {
+        Ok(Value::Object(fields)) => fields
+            .into_iter()
+            .filter(|(k, _)| k != "id" && k != "label")
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportEdge {
+    pub source: String,
+    pub target: String,
+    /// Import count for module edges; repo map edges are unweighted.
+    pub weight: Option<u64>,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportGraph {
+    pub nodes: Vec<ExportNode>,
+    pub edges: Vec<ExportEdge>,
+}
+
+impl From<&ModuleGraph> for ExportGraph {
+    fn from(graph: &ModuleGraph) -> Self {
+        Self {
+            nodes: graph
+                .nodes
+                .iter()
+                .map(|n| ExportNode {
+                    id: n.id.clone(),
+                    label: n.label.clone(),
+                    attrs: node_attrs(n),
+                })
+                .collect(),
+            edges: graph
+                .edges
+                .iter()
+                .map(|e| ExportEdge {
+                    source: e.source.clone(),
+                    target: e.target.clone(),
+                    weight: Some(e.weight),
+                    kind: EdgeKind::Import,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&RepoMap> for ExportGraph {
+    fn from(map: &RepoMap) -> Self {
+        Self {
+            nodes: map
+                .nodes
+                .iter()
+                .map(|n| ExportNode {
+                    id: n.id.clone(),
+                    label: n.label.clone(),
+                    attrs: node_attrs(n),
+                })
+                .collect(),
+            edges: map
+                .edges
+                .iter()
+                .map(|e| ExportEdge {
+                    source: e.source.clone(),
+                    target: e.target.clone(),
+                    weight: None,
+                    kind: if e.id.starts_with("import:") {
+                        EdgeKind::Import
+                    } else {
+                        EdgeKind::Contains
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A Mermaid `graph TD` diagram. Nodes get positional ids (`n0`, `n1`, ...) since paths
+/// aren't valid Mermaid identifiers; import edges are solid and labelled with their weight,
+/// containment edges are dotted.
+pub fn to_mermaid(graph: &ExportGraph) -> String {
+    let ids: BTreeMap<&str, usize> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.id.as_str(), i))
+        .collect();
+
+    let mut out = String::from("graph TD\n");
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let _ = writeln!(out, "    n{i}[\"{}\"]", mermaid_escape(&node.label));
+    }
+    for edge in &graph.edges {
+        let (Some(s), Some(t)) = (ids.get(edge.source.as_str()), ids.get(edge.target.as_str()))
+        else {
+            continue;
+        };
+        let arrow = match (edge.kind, edge.weight) {
+            (EdgeKind::Contains, _) => "-.->".to_string(),
+            (EdgeKind::Import, Some(w)) => format!("-->|{w}|"),
+            (EdgeKind::Import, None) => "-->".to_string(),
+        };
+        let _ = writeln!(out, "    n{s} {arrow} n{t}");
+    }
+    out
+}
+
+/// [`to_mermaid`] kept within `max_chars` by dropping whole edges instead of cutting the
+/// diagram mid-line: the heaviest edges are kept (ties in original order) along with the
+/// nodes they touch, and a `%%` comment says how much was left out.
+pub fn to_mermaid_within(graph: &ExportGraph, max_chars: usize) -> String {
+    let full = to_mermaid(graph);
+    if full.len() <= max_chars {
+        return full;
+    }
+
+    let mut by_weight: Vec<&ExportEdge> = graph.edges.iter().collect();
+    by_weight.sort_by_key(|e| std::cmp::Reverse(e.weight.unwrap_or(0)));
+    let render = |kept: usize| {
+        let edges: Vec<ExportEdge> = by_weight[..kept].iter().map(|e| (*e).clone()).collect();
+        let touched: BTreeSet<&str> = edges
+            .iter()
+            .flat_map(|e| [e.source.as_str(), e.target.as_str()])
+            .collect();
+        let nodes: Vec<ExportNode> = graph
+            .nodes
+            .iter()
+            .filter(|n| touched.contains(n.id.as_str()))
+            .cloned()
+            .collect();
+        let note = format!(
+            "graph TD\n    %% {kept}/{} edges, {}/{} nodes shown (heaviest first)\n",
+            graph.edges.len(),
+            nodes.len(),
+            graph.nodes.len()
+        );
+        to_mermaid(&ExportGraph { nodes, edges }).replacen("graph TD\n", &note, 1)
+    };
+
+    // Output only grows with the number of kept edges, so search for the largest that fits.
+    let (mut lo, mut hi) = (0, by_weight.len());
+    while lo < hi {
+        let mid = (lo + hi).div_ceil(2);
+        if render(mid).len() <= max_chars {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    render(lo)
+}
+
+/// A Graphviz `digraph`. Nodes sit in one `cluster_*` subgraph per parent directory (top-level
+/// ids stay outside any cluster), import edge thickness grows with the log of the weight, and
+/// containment edges are dashed.
+pub fn to_dot(graph: &ExportGraph) -> String {
+    let mut by_dir: BTreeMap<&str, Vec<&ExportNode>> = BTreeMap::new();
+    for node in &graph.nodes {
+        let dir = node.id.rsplit_once('/').map_or("", |(dir, _)| dir);
+        by_dir.entry(dir).or_default().push(node);
+    }
+
+    let mut out = String::from("digraph G {\n    rankdir=LR;\n    node [shape=box];\n");
+    let node_line = |node: &ExportNode| {
+        format!(
+            "\"{}\" [label=\"{}\"];",
+            dot_escape(&node.id),
+            dot_escape(&node.label)
+        )
+    };
+    for (i, (dir, nodes)) in by_dir.iter().enumerate() {
+        if dir.is_empty() {
+            for node in nodes {
+                let _ = writeln!(out, "    {}", node_line(node));
+            }
+            continue;
+        }
+        let _ = writeln!(out, "    subgraph \"cluster_{i}\" {{");
+        let _ = writeln!(out, "        label=\"{}\";", dot_escape(dir));
+        for node in nodes {
+            let _ = writeln!(out, "        {}", node_line(node));
+        }
+        out.push_str("    }\n");
+    }
+    for edge in &graph.edges {
+        let attrs = match (edge.kind, edge.weight) {
+            (EdgeKind::Contains, _) => "style=dashed".to_string(),
+            (EdgeKind::Import, Some(w)) => format!(
+                "label=\"{w}\", penwidth={:.2}",
+                1.0 + (w.max(1) as f64).log2()
+            ),
+            (EdgeKind::Import, None) => String::new(),
+        };
+        let _ = write!(
+            out,
+            "    \"{}\" -> \"{}\"",
+            dot_escape(&edge.source),
+            dot_escape(&edge.target)
+        );
+        if attrs.is_empty() {
+            out.push_str(";\n");
+        } else {
+            let _ = writeln!(out, " [{attrs}];");
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// A GraphML document (Gephi, yEd, networkx). Node attributes become typed `<key>`s:
+/// integers as `long`, other numbers as `double`, lists as comma-separated strings and
+/// nested objects as JSON text. Edges carry `kind` and, when known, `weight`.
+pub fn to_graphml(graph: &ExportGraph) -> String {
+    // Attribute name -> GraphML type, widened across nodes (long < double < string).
+    let mut keys: BTreeMap<&str, &str> = BTreeMap::from([("label", "string")]);
+    for node in &graph.nodes {
+        for (name, value) in &node.attrs {
+            let ty = match value {
+                Value::Null => continue,
+                Value::Bool(_) => "boolean",
+                Value::Number(n) if n.is_i64() || n.is_u64() => "long",
+                Value::Number(_) => "double",
+                _ => "string",
+            };
+            let slot = keys.entry(name.as_str()).or_insert(ty);
+            *slot = match (*slot, ty) {
+                (a, b) if a == b => a,
+                ("long", "double") | ("double", "long") => "double",
+                _ => "string",
+            };
+        }
+    }
+    let key_ids: BTreeMap<&str, String> = keys
+        .keys()
+        .enumerate()
+        .map(|(i, name)| (*name, format!("n{i}")))
+        .collect();
+
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+    );
+    for (name, ty) in &keys {
+        let _ = writeln!(
+            out,
+            "  <key id=\"{}\" for=\"node\" attr.name=\"{}\" attr.type=\"{ty}\"/>",
+            key_ids[name],
+            escape(name)
+        );
+    }
+    out.push_str("  <key id=\"e0\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>\n");
+    out.push_str("  <key id=\"e1\" for=\"edge\" attr.name=\"weight\" attr.type=\"long\"/>\n");
+    out.push_str("  <graph id=\"G\" edgedefault=\"directed\">\n");
+
+    for node in &graph.nodes {
+        let _ = writeln!(out, "    <node id=\"{}\">", escape(node.id.as_str()));
+        let label = Value::String(node.label.clone());
+        for (name, value) in std::iter::once(("label", &label))
+            .chain(node.attrs.iter().map(|(k, v)| (k.as_str(), v)))
+        {
+            let Some(text) = attr_text(value) else {
+                continue;
+            };
+            let _ = writeln!(
+                out,
+                "      <data key=\"{}\">{}</data>",
+                key_ids[name],
+                escape(text.as_str())
+            );
+        }
+        out.push_str("    </node>\n");
+    }
+    for edge in &graph.edges {
+        let _ = writeln!(
+            out,
+            "    <edge source=\"{}\" target=\"{}\">",
+            escape(edge.source.as_str()),
+            escape(edge.target.as_str())
+        );
+        let kind = match edge.kind {
+            EdgeKind::Import => "import",
+            EdgeKind::Contains => "contains",
+        };
+        let _ = writeln!(out, "      <data key=\"e0\">{kind}</data>");
+        if let Some(w) = edge.weight {
+            let _ = writeln!(out, "      <data key=\"e1\">{w}</data>");
+        }
+        out.push_str("    </edge>\n");
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+/// An attribute as flat text: lists comma-separated, nested objects as JSON; `None` for null.
+fn attr_text(value: &Value) -> Option<String> {
+    Some(match value {
+        Value::Null => return None,
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    })
+}
+
+/// Write `nodes.csv` (`id`, `label`, then every attribute seen on any node, sorted) and
+/// `edges.csv` (`source`, `target`, `kind`, `weight`) into `out_dir`, for spreadsheets and
+/// dataframes. Missing and null attributes are empty cells.
+pub fn write_csv(graph: &ExportGraph, out_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+
+    let columns: BTreeSet<&str> = graph
+        .nodes
+        .iter()
+        .flat_map(|n| n.attrs.keys().map(String::as_str))
+        .collect();
+    let mut nodes = csv::Writer::from_path(out_dir.join("nodes.csv"))?;
+    nodes.write_record(["id", "label"].into_iter().chain(columns.iter().copied()))?;
+    for node in &graph.nodes {
+        let mut record = vec![node.id.clone(), node.label.clone()];
+        record.extend(
+            columns
+                .iter()
+                .map(|c| node.attrs.get(*c).and_then(attr_text).unwrap_or_default()),
+        );
+        nodes.write_record(&record)?;
+    }
+    nodes.flush()?;
+
+    let mut edges = csv::Writer::from_path(out_dir.join("edges.csv"))?;
+    edges.write_record(["source", "target", "kind", "weight"])?;
+    for edge in &graph.edges {
+        let kind = match edge.kind {
+            EdgeKind::Import => "import",
+            EdgeKind::Contains => "contains",
+        };
+        let weight = edge.weight.map(|w| w.to_string()).unwrap_or_default();
+        edges.write_record([edge.source.as_str(), edge.target.as_str(), kind, &weight])?;
+    }
+    edges.flush()?;
+    Ok(())
+}
+
+const REPORT_TEMPLATE: &str = include_str!("../templates/report.html");
+
+/// A self-contained HTML report: the repo map, module graph and (when present) the last
+/// slice's metadata embedded as JSON, plus a small inline viewer (module graph, sortable
+/// tables). No network access is needed to open it.
+pub fn to_html_report(
+    title: &str,
+    map: &RepoMap,
+    graph: &ModuleGraph,
+    slice_meta: Option<&Value>,
+) -> Result<String> {
+    let generated_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let data = serde_json::to_string(&serde_json::json!({
+        "generated_at": generated_at,
+        "repo_map": map,
+        "module_graph": graph,
+        "slice_meta": slice_meta,
+    }))?;
+    // Keep paths like `</script>` from ending the data block early.
+    let data = data.replace("</", "<\\/").replace("<!--", "<\\u0021--");
+    Ok(REPORT_TEMPLATE
+        .replace("__TITLE__", &escape(title))
+        .replace("__DATA__", &data))
+}
+
+/// Mermaid labels are double-quoted; quotes and angle brackets need entity codes.
+fn mermaid_escape(label: &str) -> String {
+    label
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> ExportGraph {
+        let node = |id: &str, label: &str| ExportNode {
+            id: id.into(),
+            label: label.into(),
+            attrs: BTreeMap::new(),
+        };
+        let edge = |source: &str, target: &str, weight: Option<u64>, kind| ExportEdge {
+            source: source.into(),
+            target: target.into(),
+            weight,
+            kind,
+        };
+        ExportGraph {
+            nodes: vec![
+                node("apps/web", "web"),
+                node("packages/db", "db"),
+                node("packages/ui", "ui \"kit\""),
+                node("packages", "packages"),
+            ],
+            edges: vec![
+                edge("apps/web", "packages/db", Some(4), EdgeKind::Import),
+                edge("apps/web", "packages/ui", Some(1), EdgeKind::Import),
+                edge("packages", "packages/db", None, EdgeKind::Contains),
+            ],
+        }
+    }
+
+    #[test]
+    fn dot_clusters_by_directory_and_scales_edges() {
+        assert_eq!(
+            to_dot(&sample()),
+            r#"digraph G {
+    rankdir=LR;
+    node [shape=box];
+    "packages" [label="packages"];
+    subgraph "cluster_1" {
+        label="apps";
+        "apps/web" [label="web"];
+    }
+    subgraph "cluster_2" {
+        label="packages";
+        "packages/db" [label="db"];
+        "packages/ui" [label="ui \"kit\""];
+    }
+    "apps/web" -> "packages/db" [label="4", penwidth=3.00];
+    "apps/web" -> "packages/ui" [label="1", penwidth=1.00];
+    "packages" -> "packages/db" [style=dashed];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn mermaid_labels_weights_and_edge_kinds() {
+        let graph = ExportGraph {
+            nodes: vec![
+                ExportNode {
+                    id: "apps/web".into(),
+                    label: "web \"app\"".into(),
+                    attrs: BTreeMap::new(),
+                },
+                ExportNode {
+                    id: "packages/db".into(),
+                    label: "db".into(),
+                    attrs: BTreeMap::new(),
+                },
+                ExportNode {
+                    id: "packages".into(),
+                    label: "packages".into(),
+                    attrs: BTreeMap::new(),
+                },
+            ],
+            edges: vec![
+                ExportEdge {
+                    source: "apps/web".into(),
+                    target: "packages/db".into(),
+                    weight: Some(3),
+                    kind: EdgeKind::Import,
+                },
+                ExportEdge {
+                    source: "packages".into(),
+                    target: "packages/db".into(),
+                    weight: None,
+                    kind: EdgeKind::Contains,
+                },
+                ExportEdge {
+                    source: "apps/web".into(),
+                    target: "missing".into(),
+                    weight: Some(1),
+                    kind: EdgeKind::Import,
+                },
+            ],
+        };
+        assert_eq!(
+            to_mermaid(&graph),
+            "graph TD\n\
+             \x20   n0[\"web #quot;app#quot;\"]\n\
+             \x20   n1[\"db\"]\n\
+             \x20   n2[\"packages\"]\n\
+             \x20   n0 -->|3| n1\n\
+             \x20   n2 -.-> n1\n"
+        );
+    }
+
+    #[test]
+    fn mermaid_within_keeps_heaviest_edges_that_fit() {
+        let graph = sample();
+        assert_eq!(to_mermaid_within(&graph, 10_000), to_mermaid(&graph));
+
+        let max_chars = to_mermaid(&graph).len() - 1;
+        let trimmed = to_mermaid_within(&graph, max_chars);
+        assert!(trimmed.len() <= max_chars);
+        assert!(trimmed.starts_with("graph TD\n    %% 1/3 edges, 2/4 nodes shown"));
+        assert!(trimmed.ends_with("    n0[\"web\"]\n    n1[\"db\"]\n    n0 -->|4| n1\n"));
+    }
+
+    #[test]
+    fn graphml_types_node_attributes_and_escapes_text() {
+        let mut graph = sample();
+        graph.nodes[0].attrs = BTreeMap::from([
+            ("bytes".to_string(), serde_json::json!(120)),
+            ("importance".to_string(), serde_json::json!(1.5)),
+            ("owners".to_string(), serde_json::json!(["@a", "@b"])),
+            ("last_changed".to_string(), Value::Null),
+        ]);
+        graph.nodes[1].attrs = BTreeMap::from([("importance".to_string(), serde_json::json!(2))]);
+        let xml = to_graphml(&graph);
+
+        assert!(xml.contains(r#"<key id="n0" for="node" attr.name="bytes" attr.type="long"/>"#));
+        assert!(xml.contains(r#"attr.name="importance" attr.type="double"/>"#));
+        assert!(xml.contains(r#"attr.name="owners" attr.type="string"/>"#));
+        assert!(!xml.contains("last_changed"));
+        assert!(xml.contains("<data key=\"n3\">@a, @b</data>"));
+        assert!(xml.contains("<data key=\"n2\">ui &quot;kit&quot;</data>"));
+        assert!(xml.contains(
+            "<edge source=\"apps/web\" target=\"packages/db\">\n      <data key=\"e0\">import</data>\n      <data key=\"e1\">4</data>"
+        ));
+        assert!(xml.contains("<data key=\"e0\">contains</data>\n    </edge>"));
+    }
+
+    #[test]
+    fn html_report_embeds_data_without_breaking_out_of_the_script() {
+        let map = RepoMap {
+            nodes: vec![],
+            edges: vec![],
+        };
+        let graph = ModuleGraph {
+            nodes: vec![],
+            edges: vec![],
+        };
+        let meta = serde_json::json!({ "target": "src/</script><!--x", "totalTokens": 12 });
+        let html = to_html_report("a<b>", &map, &graph, Some(&meta)).unwrap();
+
+        assert!(html.contains("<h1>a&lt;b&gt;</h1>"));
+        assert_eq!(html.matches("</script>").count(), 2);
+        let start = html.find("id=\"data\">").unwrap() + "id=\"data\">".len();
+        let end = start + html[start..].find("</script>").unwrap();
+        let data: Value = serde_json::from_str(&html[start..end]).unwrap();
+        assert_eq!(data["slice_meta"], meta);
+        assert_eq!(data["module_graph"]["nodes"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn csv_writes_node_attribute_columns_and_typed_edges() {
+        let mut graph = sample();
+        graph.nodes[0].attrs = BTreeMap::from([
+            ("bytes".to_string(), serde_json::json!(120)),
+            ("owners".to_string(), serde_json::json!(["@a", "@b"])),
+        ]);
+        graph.nodes[1].attrs = BTreeMap::from([("last_changed".to_string(), Value::Null)]);
+        let tmp = tempfile::tempdir().unwrap();
+        write_csv(&graph, tmp.path()).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("nodes.csv")).unwrap(),
+            "id,label,bytes,last_changed,owners\n\
+             apps/web,web,120,,\"@a, @b\"\n\
+             packages/db,db,,,\n\
+             packages/ui,\"ui \"\"kit\"\"\",,,\n\
+             packages,packages,,,\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("edges.csv")).unwrap(),
+            "source,target,kind,weight\n\
+             apps/web,packages/db,import,4\n\
+             apps/web,packages/ui,import,1\n\
+             packages,packages/db,contains,\n"
+        );
+    }
+}
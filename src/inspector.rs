@@ -1,13 +1,20 @@
 use anyhow::{anyhow, Context, Result};
-use serde::Serialize;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::OnceLock;
-use tree_sitter::{Language, Node, Parser, Query, QueryCursor, StreamingIterator};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use tree_sitter::{
+    InputEdit, Language, Node, Parser, Point, Query, QueryCursor, StreamingIterator, Tree,
+};
 
+use crate::config_outline;
+use crate::notebook;
 use crate::universal::render_universal_skeleton;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Symbol {
     pub name: String,
     pub kind: String,
@@ -18,19 +25,95 @@ pub struct Symbol {
     /// 0-indexed end line (inclusive-ish; derived from tree-sitter end position)
     pub line_end: u32,
 
+    /// Byte range of the definition in the analysed source (the code view for notebooks).
     pub start_byte: usize,
     pub end_byte: usize,
 
+    /// 0-indexed byte column of `start_byte` within `line`.
+    pub column: u32,
+
+    /// 0-indexed byte column of `end_byte` within `line_end`.
+    pub column_end: u32,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub signature: Option<String>,
+
+    /// Notebook cell index (0-based) for symbols extracted from `.ipynb` files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cell: Option<u32>,
+
+    /// Rust `#[derive(...)]` trait names on this item (`Debug`, `serde::Serialize`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub derives: Vec<String>,
+
+    /// Other outer attribute paths on this item (`tokio::main`, `test`, `cfg`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<String>,
+
+    /// Members declared inside this symbol (struct fields, enum variants, interface
+    /// members), in source order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Symbol>,
+
+    /// Call targets inside a function-like symbol's body, in first-seen order
+    /// (`helper`, `self.save`, `std::fs::read`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<String>,
+
+    /// Qualified name of the enclosing module/class/impl (`net::Client`), when nested.
+    /// `name` is then qualified too (`net::Client::send`, `Job.run`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+
+    /// Declared visibility: `pub`/`pub(crate)`/`private` (Rust), `exported`/`default`/`internal`
+    /// (TS/JS, Python top level) or `public`/`protected`/`private` (class members).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<String>,
+
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub is_async: bool,
+
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub is_unsafe: bool,
+
+    /// Type parameters as written (`T: Clone`, `'a`, `K extends string`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub generics: Vec<String>,
+
+    /// Size and branching metrics, for function-like symbols.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<SymbolMetrics>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolMetrics {
+    /// Non-blank lines in the definition.
+    pub loc: u32,
+    /// Decision points: conditionals, loops, match/switch arms, catch clauses, `&&`/`||`.
+    pub branches: u32,
+    /// Cyclomatic complexity estimate (`branches + 1`).
+    pub complexity: u32,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileSymbols {
     pub file: String,
     pub imports: Vec<String>,
     pub exports: Vec<String>,
     pub symbols: Vec<Symbol>,
+    /// TODO/FIXME/HACK comments, in source order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub markers: Vec<Marker>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Marker {
+    /// `TODO`, `FIXME` or `HACK`.
+    pub kind: String,
+    /// 0-indexed line of the tag.
+    pub line: u32,
+    /// Comment text after the tag (`TODO(ana): x` → `x`).
+    pub text: String,
 }
 
 fn normalize_path_for_output(p: &Path) -> String {
@@ -480,6 +563,18 @@ pub fn render_skeleton(path: &Path) -> Result<String> {
             .join(path)
     };
 
+    if config_outline::is_config_outline_path(&abs) {
+        let text = std::fs::read_to_string(&abs)
+            .with_context(|| format!("Failed to read {}", abs.display()))?;
+        return Ok(config_outline::render_outline(&abs, &text).unwrap_or(text));
+    }
+    if notebook::is_notebook_path(&abs) {
+        let text = std::fs::read_to_string(&abs)
+            .with_context(|| format!("Failed to read {}", abs.display()))?;
+        let code = notebook::extract_code_cells(&text)?;
+        return render_skeleton_from_source(&notebook::code_view_path(&abs), &code.source);
+    }
+
     let cfg = language_config().read().unwrap();
     let driver = cfg
         .driver_for_path(&abs)
@@ -521,10 +616,19 @@ pub fn render_skeleton_from_source(path: &Path, source_text: &str) -> Result<Str
             .join(path)
     };
 
+    // Notebook JSON (with base64 outputs) would trip the minified check; use the code view.
+    if notebook::is_notebook_path(&abs) {
+        let code = notebook::extract_code_cells(source_text)?;
+        return render_skeleton_from_source(&notebook::code_view_path(&abs), &code.source);
+    }
+
     // Safety net.
     if is_minified_or_generated(source_text) {
         return Ok("/* MINIFIED_OR_GENERATED — skipped */\n".to_string());
     }
+    if let Some(outline) = config_outline::render_outline(&abs, source_text) {
+        return Ok(outline);
+    }
 
     let cfg = language_config().read().unwrap();
     let driver = cfg
@@ -563,6 +667,10 @@ pub fn is_minified_or_generated(source_text: &str) -> bool {
 ///
 /// This is intended for slicer fallbacks: unsupported file types should not default to full content.
 pub fn try_render_skeleton_from_source(path: &Path, source_text: &str) -> Result<Option<String>> {
+    if notebook::is_notebook_path(path) {
+        let code = notebook::extract_code_cells(source_text)?;
+        return try_render_skeleton_from_source(&notebook::code_view_path(path), &code.source);
+    }
     // Safety net: skip minified / machine-generated files before any parsing.
     if is_minified_or_generated(source_text) {
         return Ok(Some("/* MINIFIED_OR_GENERATED — skipped */\n".to_string()));
@@ -574,6 +682,10 @@ pub fn try_render_skeleton_from_source(path: &Path, source_text: &str) -> Result
             .context("Failed to get current dir")?
             .join(path)
     };
+    // Config files get a key outline instead of a (useless) tree-sitter skeleton.
+    if let Some(outline) = config_outline::render_outline(&abs, source_text) {
+        return Ok(Some(outline));
+    }
 
     let cfg = language_config().read().unwrap();
     let Some(driver) = cfg.driver_for_path(&abs) else {
@@ -617,6 +729,53 @@ pub fn try_render_skeleton_from_source(path: &Path, source_text: &str) -> Result
     Ok(Some(clean_skeleton_text(&abs, &out)))
 }
 
+/// `source_text` without comments (license headers included), blank lines or trailing
+/// whitespace, for token-lean slices. Multi-line string literals are kept verbatim.
+/// `None` when no tree-sitter driver handles `path`.
+pub fn strip_comments(path: &Path, source_text: &str) -> Result<Option<String>> {
+    let cfg = language_config().read().unwrap();
+    let Some(driver) = cfg.driver_for_path(path) else {
+        return Ok(None);
+    };
+    let mut parser = driver.make_parser(path)?;
+    let Some(tree) = parser.parse(source_text, None) else {
+        return Ok(None);
+    };
+
+    // Comments become their own newlines so rows stay put for the string check below.
+    let mut reps: Vec<(usize, usize, String)> = Vec::new();
+    let mut verbatim_rows: std::collections::HashSet<usize> = std::collections::HashSet::new();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        let kind = node.kind();
+        if kind.contains("comment") {
+            let newlines = "\n".repeat(node.end_position().row - node.start_position().row);
+            reps.push((node.start_byte(), node.end_byte(), newlines));
+            continue;
+        }
+        if kind.contains("string") && node.end_position().row > node.start_position().row {
+            verbatim_rows.extend(node.start_position().row + 1..=node.end_position().row);
+            continue;
+        }
+        let mut cursor = node.walk();
+        stack.extend(node.children(&mut cursor));
+    }
+    let stripped = apply_replacements(source_text, reps);
+
+    let mut out = String::with_capacity(stripped.len());
+    for (row, line) in stripped.lines().enumerate() {
+        if verbatim_rows.contains(&row) {
+            out.push_str(line);
+        } else if line.trim().is_empty() {
+            continue;
+        } else {
+            out.push_str(line.trim_end());
+        }
+        out.push('\n');
+    }
+    Ok(Some(out))
+}
+
 pub struct LanguageConfig {
     drivers: Vec<Box<dyn LanguageDriver>>,
     by_ext: HashMap<String, usize>,
@@ -676,6 +835,16 @@ fn lang_extensions(lang: &str) -> Vec<&'static str> {
         "java"     => vec!["java"],
         "ruby"     => vec!["rb", "rake"],
         "dart"     => vec!["dart"],
+        "kotlin"   => vec!["kt", "kts"],
+        "scala"    => vec!["scala", "sc"],
+        "zig"      => vec!["zig"],
+        "lua"      => vec!["lua"],
+        "elixir"   => vec!["ex", "exs"],
+        "bash"     => vec!["sh", "bash", "zsh"],
+        "sql"      => vec!["sql"],
+        "graphql"  => vec!["graphql", "gql"],
+        "proto"    => vec!["proto"],
+        "ocaml"    => vec!["ml"],
         // Structured markup / config — routed through tree-sitter so
         // cortex_act patchers can target byte-accurate AST nodes.
         "json"     => vec!["json"],
@@ -755,6 +924,23 @@ fn language_config() -> &'static std::sync::RwLock<LanguageConfig> {
     })
 }
 
+/// Activates the Wasm grammar for `lang` unless it already is. `false` when it can't be
+/// loaded (e.g. offline with an empty grammar cache); tests skip their checks then.
+#[cfg(test)]
+pub(crate) fn wasm_grammar(lang: &str) -> bool {
+    let cfg = language_config();
+    if cfg.read().unwrap().active_languages().iter().any(|l| l == lang) {
+        return true;
+    }
+    match cfg.write().unwrap().add_wasm_driver(lang) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("skipping {lang} checks: {e:#}");
+            false
+        }
+    }
+}
+
 fn path_ext_lower(path: &Path) -> String {
     path.extension()
         .and_then(|e| e.to_str())
@@ -794,13 +980,26 @@ impl LanguageDriver for RustDriver {
         root: Node,
         language: Language,
     ) -> Result<Vec<String>> {
-        run_query_strings(
+        let mut imports = run_query_strings(
             source,
             root,
             &language,
             r#"(use_declaration argument: (_) @path)"#,
             "path",
-        )
+        )?;
+        // Out-of-line `mod foo;` declarations pull in `foo.rs` / `foo/mod.rs`.
+        imports.extend(
+            run_query_strings(
+                source,
+                root,
+                &language,
+                r#"(mod_item name: (identifier) @name !body)"#,
+                "name",
+            )?
+            .into_iter()
+            .map(|name| format!("mod {name}")),
+        );
+        Ok(imports)
     }
 
     fn find_exports(
@@ -820,7 +1019,7 @@ impl LanguageDriver for RustDriver {
                                     (visibility_modifier) @vis
                   name: (identifier) @name
               )
-              (#match? @vis \"^pub\")"#,
+              (#match? @vis "^pub")"#,
             "name",
         )?);
         exports.extend(run_query_strings(
@@ -832,7 +1031,7 @@ impl LanguageDriver for RustDriver {
                                     (visibility_modifier) @vis
                   name: (type_identifier) @name
               )
-              (#match? @vis \"^pub\")"#,
+              (#match? @vis "^pub")"#,
             "name",
         )?);
         exports.extend(run_query_strings(
@@ -844,7 +1043,7 @@ impl LanguageDriver for RustDriver {
                                     (visibility_modifier) @vis
                   name: (type_identifier) @name
               )
-              (#match? @vis \"^pub\")"#,
+              (#match? @vis "^pub")"#,
             "name",
         )?);
         exports.extend(run_query_strings(
@@ -856,7 +1055,35 @@ impl LanguageDriver for RustDriver {
                                     (visibility_modifier) @vis
                   name: (type_identifier) @name
               )
-              (#match? @vis \"^pub\")"#,
+              (#match? @vis "^pub")"#,
+            "name",
+        )?);
+        for (item, name_kind) in [
+            ("const_item", "identifier"),
+            ("static_item", "identifier"),
+            ("type_item", "type_identifier"),
+        ] {
+            let q = format!(
+                r#"(
+                    {item}
+                      (visibility_modifier) @vis
+                      name: ({name_kind}) @name
+                  )
+                  (#match? @vis "^pub")"#
+            );
+            exports.extend(run_query_strings(source, root, &language, &q, "name")?);
+        }
+        // `#[macro_export]` makes a `macro_rules!` macro public at the crate root.
+        exports.extend(run_query_strings(
+            source,
+            root,
+            &language,
+            r#"(
+                (attribute_item (attribute (identifier) @attr))
+                .
+                (macro_definition name: (identifier) @name)
+              )
+              (#eq? @attr "macro_export")"#,
             "name",
         )?);
         Ok(exports)
@@ -948,6 +1175,36 @@ impl LanguageDriver for RustDriver {
             "type",
             false,
         )?);
+        symbols.extend(run_query(
+            source,
+            root,
+            &language,
+            r#"(macro_definition name: (identifier) @name) @def"#,
+            "macro",
+            true,
+        )?);
+        annotate_rust_attributes(source, root, &mut symbols);
+
+        let mut members = run_query(
+            source,
+            root,
+            &language,
+            r#"(field_declaration name: (field_identifier) @name) @def"#,
+            "field",
+            true,
+        )?;
+        members.extend(run_query(
+            source,
+            root,
+            &language,
+            r#"(enum_variant name: (identifier) @name) @def"#,
+            "variant",
+            true,
+        )?);
+        members.sort_by_key(|m| m.start_byte);
+        annotate_rust_attributes(source, root, &mut members);
+        nest_children(&mut symbols, members, &["struct", "enum"]);
+        qualify_nested_symbols(source, root, &mut symbols, "rust");
         Ok(symbols)
     }
 }
@@ -992,13 +1249,40 @@ impl LanguageDriver for TypeScriptDriver {
         root: Node,
         language: Language,
     ) -> Result<Vec<String>> {
-        let import_srcs = run_query_strings(
+        let mut import_srcs = run_query_strings(
             source,
             root,
             &language,
             r#"(import_statement source: (string) @src)"#,
             "src",
         )?;
+        // CommonJS: `require("./x")` with a literal specifier.
+        import_srcs.extend(run_query_strings(
+            source,
+            root,
+            &language,
+            r#"(call_expression
+                function: (identifier) @fn
+                arguments: (arguments . (string) @src .)
+                (#eq? @fn "require"))"#,
+            "src",
+        )?);
+        // Dynamic `import("./x")`, including inside `React.lazy(() => import(...))`.
+        // Template literals count only when they have no `${...}` substitutions.
+        import_srcs.extend(
+            run_query_strings(
+                source,
+                root,
+                &language,
+                r#"(call_expression
+                    function: (import)
+                    arguments: (arguments . [(string) (template_string)] @src))"#,
+                "src",
+            )?
+            .into_iter()
+            .filter(|s| !s.contains("${"))
+            .map(|s| s.trim_matches('`').to_string()),
+        );
         Ok(import_srcs
             .into_iter()
             .map(|s| strip_string_quotes(&s))
@@ -1038,6 +1322,18 @@ impl LanguageDriver for TypeScriptDriver {
             "name",
         )?);
 
+        exports.extend(run_query_strings(
+            source,
+            root,
+            &language,
+            r#"(export_statement declaration: [
+                (interface_declaration name: (type_identifier) @name)
+                (type_alias_declaration name: (type_identifier) @name)
+                (enum_declaration name: (identifier) @name)
+            ])"#,
+            "name",
+        )?);
+
         exports.extend(run_query_strings(
             source,
             root,
@@ -1046,12 +1342,14 @@ impl LanguageDriver for TypeScriptDriver {
             "name",
         )?);
 
+        exports.extend(commonjs_exports(source, root, &language)?);
+
         Ok(exports)
     }
 
     fn extract_skeleton(
         &self,
-        _path: &Path,
+        path: &Path,
         source: &[u8],
         root: Node,
         language: Language,
@@ -1119,6 +1417,34 @@ impl LanguageDriver for TypeScriptDriver {
             true,
         )?);
 
+        // Pure type surface: interfaces, type aliases and enums.
+        let type_queries: [(&str, &str); 3] = [
+            (r#"(interface_declaration name: (type_identifier) @name) @def"#, "interface"),
+            (r#"(type_alias_declaration name: (type_identifier) @name) @def"#, "type"),
+            (r#"(enum_declaration name: (identifier) @name) @def"#, "enum"),
+        ];
+        for (q, kind) in type_queries {
+            symbols.extend(run_query(source, root, &language, q, kind, true)?);
+        }
+
+        let member_queries: [(&str, &str); 4] = [
+            (r#"(property_signature name: (property_identifier) @name) @def"#, "property"),
+            (r#"(method_signature name: (property_identifier) @name) @def"#, "method"),
+            (r#"(enum_body name: (property_identifier) @name)"#, "variant"),
+            (r#"(enum_assignment name: (property_identifier) @name) @def"#, "variant"),
+        ];
+        let mut members: Vec<Symbol> = Vec::new();
+        for (q, kind) in member_queries {
+            members.extend(run_query(source, root, &language, q, kind, true)?);
+        }
+        members.sort_by_key(|m| m.start_byte);
+        nest_children(&mut symbols, members, &["interface", "type", "enum"]);
+
+        if matches!(path_ext_lower(path).as_str(), "tsx" | "jsx") {
+            classify_react_components(source, root, &mut symbols);
+        }
+        qualify_nested_symbols(source, root, &mut symbols, "typescript");
+
         Ok(symbols)
     }
 
@@ -1189,6 +1515,17 @@ impl LanguageDriver for PythonDriver {
             "class",
             false,
         )?);
+        // Module-level constants by convention: `MAX_RETRIES = 3`, `API_URL: str = "..."`.
+        symbols.extend(run_query(
+            source,
+            root,
+            &language,
+            r#"(module (expression_statement (assignment left: (identifier) @name)) @def
+                (#match? @name "^_*[A-Z][A-Z0-9_]*$"))"#,
+            "const",
+            true,
+        )?);
+        qualify_nested_symbols(source, root, &mut symbols, "python");
         Ok(symbols)
     }
 
@@ -1215,6 +1552,105 @@ impl LanguageDriver for PythonDriver {
         }
         Ok(out)
     }
+
+    fn find_imports(
+        &self,
+        _path: &Path,
+        source: &[u8],
+        root: Node,
+        language: Language,
+    ) -> Result<Vec<String>> {
+        // Module paths only: `import a.b as c` → `a.b`, `from ..pkg import x` → `..pkg`.
+        let mut imports = run_query_strings(
+            source,
+            root,
+            &language,
+            r#"
+            (import_statement name: [(dotted_name) @m (aliased_import name: (dotted_name) @m)])
+            (import_from_statement module_name: (_) @m)
+            "#,
+            "m",
+        )?;
+
+        // `from . import x` names sibling modules (or package attributes): record `.x`
+        // instead of the bare package so the edge can land on `x.py`.
+        let query = cached_query(
+            &language,
+            r#"(import_from_statement
+                module_name: (relative_import) @m
+                name: [(dotted_name) @n (aliased_import name: (dotted_name) @n)])"#,
+        )?;
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(&query, root, source);
+        let mut named: Vec<String> = Vec::new();
+        while let Some(m) = matches.next() {
+            let text = |idx: u32| {
+                m.captures
+                    .iter()
+                    .find(|c| c.index == idx)
+                    .map(|c| node_text(source, c.node).trim().to_string())
+            };
+            let (Some(module), Some(name)) = (text(0), text(1)) else {
+                continue;
+            };
+            if module.chars().all(|c| c == '.') {
+                named.push(format!("{module}{name}"));
+            }
+        }
+        if !named.is_empty() {
+            imports.retain(|i| !i.chars().all(|c| c == '.'));
+            imports.extend(named);
+        }
+        Ok(imports)
+    }
+
+    fn find_exports(
+        &self,
+        _path: &Path,
+        source: &[u8],
+        root: Node,
+        language: Language,
+    ) -> Result<Vec<String>> {
+        // An explicit `__all__` list is the module's public API.
+        let all = run_query_strings(
+            source,
+            root,
+            &language,
+            r#"(module (expression_statement (assignment
+                left: (identifier) @lhs
+                right: [(list (string) @name) (tuple (string) @name)]))
+                (#eq? @lhs "__all__"))"#,
+            "name",
+        )?;
+        if !all.is_empty() {
+            return Ok(all.iter().map(|s| strip_string_quotes(s)).collect());
+        }
+
+        // Otherwise: top-level definitions and constants without a leading underscore.
+        let mut names = run_query_strings(
+            source,
+            root,
+            &language,
+            r#"(module [
+                (function_definition name: (identifier) @name)
+                (class_definition name: (identifier) @name)
+                (decorated_definition definition: [
+                    (function_definition name: (identifier) @name)
+                    (class_definition name: (identifier) @name)
+                ])
+            ])"#,
+            "name",
+        )?;
+        names.extend(run_query_strings(
+            source,
+            root,
+            &language,
+            r#"(module (expression_statement (assignment left: (identifier) @name))
+                (#match? @name "^[A-Z][A-Z0-9_]*$"))"#,
+            "name",
+        )?);
+        Ok(names.into_iter().filter(|n| !n.starts_with('_')).collect())
+    }
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -1376,6 +1812,144 @@ impl LanguageDriver for WasmDriver {
         self.language.clone()
     }
 
+    fn find_imports(
+        &self,
+        _path: &Path,
+        source: &[u8],
+        root: Node,
+        language: Language,
+    ) -> Result<Vec<String>> {
+        let imports = match self.lang.as_str() {
+            // `#include "local.h"` → `local.h`; `#include <stdio.h>` keeps its brackets
+            // so system headers stay distinguishable from project headers.
+            "c" | "cpp" => run_query_strings(source, root, &language, r#"(preproc_include path: (_) @path)"#, "path")
+                .unwrap_or_default()
+                .into_iter()
+                .map(|p| strip_string_quotes(&p))
+                .collect(),
+            // `using System.Text;`, `using static System.Math;`, `global using X;` and
+            // `using Json = Newtonsoft.Json;` all yield the imported namespace/type.
+            "c_sharp" | "csharp" => run_query_strings(source, root, &language, r#"(using_directive) @path"#, "path")
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|d| csharp_using_target(d))
+                .collect(),
+            // `use` clauses yield fully qualified names (resolved via composer PSR-4 in the
+            // mapper); `require`/`include` yield the literal path.
+            "php" => {
+                let q_use = r#"(namespace_use_declaration) @path"#;
+                let q_inc = r#"
+                    [(require_expression) (require_once_expression)
+                     (include_expression) (include_once_expression)] @path
+                "#;
+                let mut out: Vec<String> = run_query_strings(source, root, &language, q_use, "path")
+                    .unwrap_or_default()
+                    .iter()
+                    .flat_map(|d| php_use_targets(d))
+                    .collect();
+                out.extend(
+                    run_query_strings(source, root, &language, q_inc, "path")
+                        .unwrap_or_default()
+                        .iter()
+                        .filter_map(|e| php_include_target(e)),
+                );
+                out
+            }
+            "kotlin" => run_query_strings(source, root, &language, r#"(import) @path"#, "path")
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|i| kotlin_import_target(i))
+                .collect(),
+            "scala" => run_query_strings(source, root, &language, r#"(import_declaration) @path"#, "path")
+                .unwrap_or_default()
+                .iter()
+                .flat_map(|d| scala_import_targets(d))
+                .collect(),
+            // `@import("std")` / `@import("util.zig")` → the literal argument.
+            "zig" => {
+                let q = r#"
+                    (builtin_function
+                      (builtin_identifier) @b (#eq? @b "@import")
+                      (arguments (string (string_content) @path)))
+                "#;
+                run_query_strings(source, root, &language, q, "path").unwrap_or_default()
+            }
+            // `require("a.b")` / `require "a.b"` → the module name.
+            "lua" => {
+                let q = r#"
+                    (function_call
+                      name: (identifier) @fn (#eq? @fn "require")
+                      arguments: (arguments (string content: (string_content) @path)))
+                "#;
+                run_query_strings(source, root, &language, q, "path").unwrap_or_default()
+            }
+            // `import`, `export` and `part` URIs as written (`package:app/x.dart`, `../y.dart`).
+            "dart" => {
+                let q = r#"
+                    (import_specification uri: (configurable_uri (uri (string_literal) @path)))
+                    (library_export       uri: (configurable_uri (uri (string_literal) @path)))
+                    (part_directive       uri: (uri (string_literal) @path))
+                "#;
+                run_query_strings(source, root, &language, q, "path")
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|p| strip_string_quotes(&p))
+                    .collect()
+            }
+            // `alias`/`import`/`use`/`require` module names, with `alias A.{B, C}` expanded.
+            "elixir" => {
+                let q = r#"
+                    (call target: (identifier) @k (#match? @k "^(alias|import|use|require)$")
+                      (arguments) @path)
+                "#;
+                run_query_strings(source, root, &language, q, "path")
+                    .unwrap_or_default()
+                    .iter()
+                    .flat_map(|args| elixir_import_targets(args))
+                    .collect()
+            }
+            // `source path` / `. path` — first argument, unquoted.
+            "bash" => {
+                let q = r#"
+                    (command
+                      name: (command_name (word) @c (#match? @c "^(source|\\.)$"))
+                      .
+                      argument: (_) @path)
+                "#;
+                run_query_strings(source, root, &language, q, "path")
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|p| strip_string_quotes(&p))
+                    .collect()
+            }
+            // `import "a/b.proto";` (including `import public` / `import weak`).
+            "proto" => run_query_strings(source, root, &language, r#"(import path: (string) @path)"#, "path")
+                .unwrap_or_default()
+                .into_iter()
+                .map(|p| strip_string_quotes(&p))
+                .collect(),
+            // `open M` / `open! M` / `include M`, as dotted module paths.
+            "ocaml" => run_query_strings(source, root, &language, r#"(open_module module: (module_path) @m) (include_module module: (module_path) @m)"#, "m")
+                .unwrap_or_default(),
+            _ => vec![],
+        };
+        Ok(imports)
+    }
+
+    fn find_exports(
+        &self,
+        _path: &Path,
+        source: &[u8],
+        root: Node,
+        _language: Language,
+    ) -> Result<Vec<String>> {
+        let exports = match self.lang.as_str() {
+            "scala" => scala_exports(source, root),
+            _ => vec![],
+        };
+        Ok(exports)
+    }
+
     /// Extract symbols using language-specific tree-sitter queries.
     /// Each Wasm language gets accurate node-type patterns so that
     /// `read_source`, `find_usages`, and `deep_slice` all work correctly.
@@ -1402,17 +1976,18 @@ impl LanguageDriver for WasmDriver {
 
             // ── PHP ───────────────────────────────────────────────────────────
             "php" => {
-                let q_fn = r#"
-                    (function_definition name: (name) @name) @def
-                    (method_declaration  name: (name) @name) @def
-                "#;
-                let q_class = r#"
-                    (class_declaration     name: (name) @name) @def
-                    (interface_declaration name: (name) @name) @def
-                    (trait_declaration     name: (name) @name) @def
-                "#;
-                syms.extend(run_query(source, root, &language, q_fn,    "function", true).unwrap_or_default());
-                syms.extend(run_query(source, root, &language, q_class,  "class",    true).unwrap_or_default());
+                let queries: [(&str, &str); 7] = [
+                    (r#"(namespace_definition  name: (namespace_name) @name) @def"#, "namespace"),
+                    (r#"(class_declaration     name: (name) @name) @def"#,           "class"),
+                    (r#"(interface_declaration name: (name) @name) @def"#,           "interface"),
+                    (r#"(trait_declaration     name: (name) @name) @def"#,           "trait"),
+                    (r#"(enum_declaration      name: (name) @name) @def"#,           "enum"),
+                    (r#"(function_definition   name: (name) @name) @def"#,           "function"),
+                    (r#"(method_declaration    name: (name) @name) @def"#,           "method"),
+                ];
+                for (q, kind) in queries {
+                    syms.extend(run_query(source, root, &language, q, kind, true).unwrap_or_default());
+                }
             }
 
             // ── Java ──────────────────────────────────────────────────────────
@@ -1432,74 +2007,361 @@ impl LanguageDriver for WasmDriver {
 
             // ── C++ ───────────────────────────────────────────────────────────
             "cpp" => {
-                // Plain identifier: void foo(...)
-                let q_fn_simple = r#"
+                // `(_) @name` keeps qualified declarators (`Foo<T>::bar`, `ns::f`, `~Foo`,
+                // `operator<<`) intact; `qualify_cpp_symbols` adds the enclosing scopes.
+                let q_fn = r#"
                     (function_definition
-                      declarator: (function_declarator
-                        declarator: (identifier) @name)) @def
+                      declarator: [
+                        (function_declarator declarator: (_) @name)
+                        (pointer_declarator declarator: (function_declarator declarator: (_) @name))
+                        (reference_declarator (function_declarator declarator: (_) @name))
+                      ]) @def
                 "#;
-                // Qualified: Foo::bar(...)
-                let q_fn_qualified = r#"
-                    (function_definition
-                      declarator: (function_declarator
-                        declarator: (qualified_identifier
-                          name: (identifier) @name))) @def
+                let q_class  = r#"(class_specifier name: (_) @name body: (field_declaration_list)) @def"#;
+                let q_struct = r#"
+                    (struct_specifier name: (_) @name body: (field_declaration_list)) @def
+                    (union_specifier  name: (_) @name body: (field_declaration_list)) @def
                 "#;
-                let q_class = r#"
-                    (class_specifier  name: (type_identifier) @name) @def
-                    (struct_specifier name: (type_identifier) @name) @def
+                let q_enum   = r#"(enum_specifier name: (_) @name body: (enumerator_list)) @def"#;
+                let q_type   = r#"
+                    (type_definition   declarator: (type_identifier) @name) @def
+                    (alias_declaration name: (type_identifier) @name) @def
                 "#;
-                syms.extend(run_query(source, root, &language, q_fn_simple,    "function", true).unwrap_or_default());
-                syms.extend(run_query(source, root, &language, q_fn_qualified,  "function", true).unwrap_or_default());
-                syms.extend(run_query(source, root, &language, q_class,         "class",    true).unwrap_or_default());
+                let q_ns     = r#"(namespace_definition name: (_) @name) @def"#;
+                let mut cpp = Vec::new();
+                cpp.extend(run_query(source, root, &language, q_fn,     "function",  true).unwrap_or_default());
+                cpp.extend(run_query(source, root, &language, q_class,  "class",     true).unwrap_or_default());
+                cpp.extend(run_query(source, root, &language, q_struct, "struct",    true).unwrap_or_default());
+                cpp.extend(run_query(source, root, &language, q_enum,   "enum",      true).unwrap_or_default());
+                cpp.extend(run_query(source, root, &language, q_type,   "type",      true).unwrap_or_default());
+                cpp.extend(run_query(source, root, &language, q_ns,     "namespace", true).unwrap_or_default());
+                qualify_cpp_symbols(source, root, &mut cpp);
+                syms.extend(cpp);
             }
 
             // ── C# ────────────────────────────────────────────────────────────
             "c_sharp" | "csharp" => {
-                let q_fn = r#"
+                let q_method = r#"
                     (method_declaration      name: (identifier) @name) @def
                     (constructor_declaration name: (identifier) @name) @def
                 "#;
-                let q_class = r#"
-                    (class_declaration     name: (identifier) @name) @def
-                    (interface_declaration name: (identifier) @name) @def
-                    (struct_declaration    name: (identifier) @name) @def
-                    (enum_declaration      name: (identifier) @name) @def
+                let q_ns = r#"
+                    (namespace_declaration             name: (_) @name) @def
+                    (file_scoped_namespace_declaration name: (_) @name) @def
                 "#;
-                syms.extend(run_query(source, root, &language, q_fn,    "function", true).unwrap_or_default());
-                syms.extend(run_query(source, root, &language, q_class,  "class",    true).unwrap_or_default());
+                // One query per node type so a grammar build lacking e.g. records
+                // only loses that kind instead of the whole outline.
+                let queries: [(&str, &str); 9] = [
+                    (q_ns,                                                                "namespace"),
+                    (r#"(class_declaration     name: (identifier) @name) @def"#,          "class"),
+                    (r#"(record_declaration    name: (identifier) @name) @def"#,          "record"),
+                    (r#"(interface_declaration name: (identifier) @name) @def"#,          "interface"),
+                    (r#"(struct_declaration    name: (identifier) @name) @def"#,          "struct"),
+                    (r#"(enum_declaration      name: (identifier) @name) @def"#,          "enum"),
+                    (r#"(delegate_declaration  name: (identifier) @name) @def"#,          "type"),
+                    (q_method,                                                            "method"),
+                    (r#"(property_declaration  name: (identifier) @name) @def"#,          "property"),
+                ];
+                for (q, kind) in queries {
+                    syms.extend(run_query(source, root, &language, q, kind, true).unwrap_or_default());
+                }
             }
 
-            // ── Ruby ──────────────────────────────────────────────────────────
-            "ruby" => {
-                let q_fn = r#"
-                    (method           name: (identifier) @name) @def
-                    (singleton_method name: (identifier) @name) @def
-                "#;
-                let q_class = r#"
-                    (class  name: (constant) @name) @def
-                    (module name: (constant) @name) @def
+            // ── Kotlin ────────────────────────────────────────────────────────
+            "kotlin" => {
+                // `class`, `data class`, `enum class` and `interface` share one node type;
+                // the declaration header decides the kind.
+                let q_class = r#"(class_declaration name: (identifier) @name) @def"#;
+                let mut classes = run_query(source, root, &language, q_class, "class", true).unwrap_or_default();
+                for c in &mut classes {
+                    let header = c.signature.as_deref().unwrap_or("");
+                    let header = header.split(c.name.as_str()).next().unwrap_or("");
+                    if header.split_whitespace().any(|w| w == "interface") {
+                        c.kind = "interface".to_string();
+                    } else if header.split_whitespace().any(|w| w == "enum") {
+                        c.kind = "enum".to_string();
+                    }
+                }
+                syms.extend(classes);
+
+                let q_object = r#"(object_declaration name: (identifier) @name) @def"#;
+                // Extension functions carry a receiver type right before the name (`fun String.shout()`).
+                let q_ext = r#"
+                    (function_declaration
+                      [(user_type) (nullable_type)] . name: (identifier) @name) @def
                 "#;
-                syms.extend(run_query(source, root, &language, q_fn,    "function", true).unwrap_or_default());
-                syms.extend(run_query(source, root, &language, q_class,  "class",    true).unwrap_or_default());
+                let q_fn    = r#"(function_declaration name: (identifier) @name) @def"#;
+                let q_alias = r#"(type_alias type: (identifier) @name) @def"#;
+                let extensions = run_query(source, root, &language, q_ext, "extension", true).unwrap_or_default();
+                let functions = run_query(source, root, &language, q_fn, "function", true)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|f| !extensions.iter().any(|e| e.start_byte == f.start_byte));
+                syms.extend(run_query(source, root, &language, q_object, "object", true).unwrap_or_default());
+                syms.extend(functions.collect::<Vec<_>>());
+                syms.extend(extensions);
+                syms.extend(run_query(source, root, &language, q_alias, "type", true).unwrap_or_default());
             }
 
-            // ── C ─────────────────────────────────────────────────────────────
-            "c" => {
-                let q_fn = r#"
-                    (function_definition
-                      declarator: (function_declarator
-                        declarator: (identifier) @name)) @def
+            // ── Scala ─────────────────────────────────────────────────────────
+            "scala" => {
+                let queries: [(&str, &str); 8] = [
+                    (r#"(object_definition    name: (identifier) @name) @def"#,      "object"),
+                    (r#"(class_definition     name: (identifier) @name) @def"#,      "class"),
+                    (r#"(trait_definition     name: (identifier) @name) @def"#,      "trait"),
+                    (r#"(enum_definition      name: (identifier) @name) @def"#,      "enum"),
+                    (r#"(function_definition  name: (identifier) @name) @def"#,      "function"),
+                    // Abstract `def`s in traits have no body.
+                    (r#"(function_declaration name: (identifier) @name) @def"#,      "function"),
+                    (r#"(type_definition      name: (type_identifier) @name) @def"#, "type"),
+                    (r#"(val_definition       pattern: (identifier) @name) @def"#,   "const"),
+                ];
+                for (q, kind) in queries {
+                    syms.extend(run_query(source, root, &language, q, kind, true).unwrap_or_default());
+                }
+            }
+
+            // ── Zig ───────────────────────────────────────────────────────────
+            "zig" => {
+                // Containers are `const Name = struct { ... };` declarations.
+                let q_fn     = r#"(function_declaration name: (identifier) @name) @def"#;
+                let q_struct = r#"(variable_declaration (identifier) @name (struct_declaration)) @def"#;
+                let q_enum   = r#"(variable_declaration (identifier) @name (enum_declaration)) @def"#;
+                let q_union  = r#"(variable_declaration (identifier) @name (union_declaration)) @def"#;
+                // Top-level constants, minus containers and `@import` bindings.
+                let q_const  = r#"(source_file (variable_declaration (identifier) @name) @def)"#;
+                let q_import = r#"
+                    (source_file
+                      (variable_declaration
+                        (identifier) @name
+                        (builtin_function (builtin_identifier) @b (#eq? @b "@import"))) @def)
                 "#;
-                let q_type = r#"
-                    (struct_specifier  name: (type_identifier) @name) @def
-                    (type_definition   declarator: (type_identifier) @name) @def
+                let mut containers = Vec::new();
+                containers.extend(run_query(source, root, &language, q_struct, "struct", true).unwrap_or_default());
+                containers.extend(run_query(source, root, &language, q_enum,   "enum",   true).unwrap_or_default());
+                containers.extend(run_query(source, root, &language, q_union,  "union",  true).unwrap_or_default());
+                let imports = run_query(source, root, &language, q_import, "import", false).unwrap_or_default();
+                let consts = run_query(source, root, &language, q_const, "const", true)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|c| {
+                        !containers.iter().chain(&imports).any(|o| o.start_byte == c.start_byte)
+                    })
+                    .collect::<Vec<_>>();
+                syms.extend(run_query(source, root, &language, q_fn, "function", true).unwrap_or_default());
+                syms.extend(containers);
+                syms.extend(consts);
+            }
+
+            // ── Lua ───────────────────────────────────────────────────────────
+            "lua" => {
+                // Named as written: `helper`, `M.greet`, `M:method`.
+                let q_decl = r#"(function_declaration name: (_) @name) @def"#;
+                // `M.handler = function(...) end` and `local f = function(...) end`.
+                let q_assign = r#"
+                    (assignment_statement
+                      (variable_list name: (_) @name)
+                      (expression_list value: (function_definition))) @def
                 "#;
-                syms.extend(run_query(source, root, &language, q_fn,    "function", true).unwrap_or_default());
-                syms.extend(run_query(source, root, &language, q_type,   "type",     true).unwrap_or_default());
+                for q in [q_decl, q_assign] {
+                    for mut sym in run_query(source, root, &language, q, "function", true).unwrap_or_default() {
+                        if sym.name.contains(['.', ':']) {
+                            sym.kind = "method".to_string();
+                        }
+                        syms.push(sym);
+                    }
+                }
             }
 
-            // ── Generic fallback for any other Wasm language ──────────────────
+            // ── Dart ──────────────────────────────────────────────────────────
+            "dart" => {
+                let q_method = r#"
+                    (method_declaration
+                      signature: (method_signature [
+                        (function_signature name: (identifier) @name)
+                        (getter_signature   name: (identifier) @name)
+                        (setter_signature   name: (identifier) @name)
+                      ])) @def
+                    (class_member (declaration (function_signature name: (identifier) @name)) @def)
+                "#;
+                let queries: [(&str, &str); 7] = [
+                    (r#"(class_declaration     name: (identifier) @name) @def"#, "class"),
+                    (r#"(mixin_declaration     name: (identifier) @name) @def"#, "mixin"),
+                    (r#"(extension_declaration name: (identifier) @name) @def"#, "extension"),
+                    (r#"(enum_declaration      name: (identifier) @name) @def"#, "enum"),
+                    (r#"(type_alias (type_identifier) @name) @def"#,             "type"),
+                    (r#"(function_declaration signature: (function_signature name: (identifier) @name)) @def"#, "function"),
+                    (q_method,                                                    "method"),
+                ];
+                for (q, kind) in queries {
+                    syms.extend(run_query(source, root, &language, q, kind, true).unwrap_or_default());
+                }
+            }
+
+            // ── Elixir ────────────────────────────────────────────────────────
+            "elixir" => {
+                // Everything is a macro call; the target identifier decides the kind.
+                let def_query = |keywords: &str| {
+                    format!(
+                        r#"(call target: (identifier) @k (#match? @k "^({keywords})$")
+                              (arguments [
+                                (call target: (identifier) @name)
+                                (binary_operator left: (call target: (identifier) @name))
+                                (identifier) @name
+                              ])) @def"#
+                    )
+                };
+                let q_module = r#"
+                    (call target: (identifier) @k (#match? @k "^(defmodule|defprotocol)$")
+                      (arguments (alias) @name)) @def
+                "#;
+                syms.extend(run_query(source, root, &language, q_module, "module", true).unwrap_or_default());
+                let q_fn    = def_query("def|defp|defdelegate|defguard|defguardp");
+                let q_macro = def_query("defmacro|defmacrop");
+                syms.extend(run_query(source, root, &language, &q_fn,    "function", true).unwrap_or_default());
+                syms.extend(run_query(source, root, &language, &q_macro, "macro",    true).unwrap_or_default());
+            }
+
+            // ── Shell (bash / zsh) ────────────────────────────────────────────
+            "bash" => {
+                let q_fn = r#"(function_definition name: (word) @name) @def"#;
+                syms.extend(run_query(source, root, &language, q_fn, "function", true).unwrap_or_default());
+            }
+
+            // ── SQL ───────────────────────────────────────────────────────────
+            "sql" => {
+                // Names keep their schema qualifier (`public.orders`).
+                let queries: [(&str, &str); 5] = [
+                    (r#"(create_table             (object_reference) @name) @def"#, "table"),
+                    (r#"(create_view              (object_reference) @name) @def"#, "view"),
+                    (r#"(create_materialized_view (object_reference) @name) @def"#, "view"),
+                    (r#"(create_function          (object_reference) @name) @def"#, "function"),
+                    (r#"(create_type              (object_reference) @name) @def"#, "type"),
+                ];
+                for (q, kind) in queries {
+                    syms.extend(run_query(source, root, &language, q, kind, true).unwrap_or_default());
+                }
+                // The grammar has no CREATE PROCEDURE rule; those statements parse as ERROR.
+                syms.extend(sql_procedures(source));
+            }
+
+            // ── GraphQL ───────────────────────────────────────────────────────
+            "graphql" => {
+                let operation = |op: &str| {
+                    format!(r#"(operation_definition (operation_type) @op (#eq? @op "{op}") (name) @name) @def"#)
+                };
+                let (q_query, q_mutation, q_subscription) =
+                    (operation("query"), operation("mutation"), operation("subscription"));
+                let queries: [(&str, &str); 12] = [
+                    (r#"(object_type_definition       (name) @name) @def"#, "type"),
+                    (r#"(object_type_extension        (name) @name) @def"#, "type"),
+                    (r#"(input_object_type_definition (name) @name) @def"#, "input"),
+                    (r#"(enum_type_definition         (name) @name) @def"#, "enum"),
+                    (r#"(interface_type_definition    (name) @name) @def"#, "interface"),
+                    (r#"(union_type_definition        (name) @name) @def"#, "union"),
+                    (r#"(scalar_type_definition       (name) @name) @def"#, "scalar"),
+                    (r#"(directive_definition         (name) @name) @def"#, "directive"),
+                    (r#"(fragment_definition (fragment_name (name) @name)) @def"#, "fragment"),
+                    (&q_query,        "query"),
+                    (&q_mutation,     "mutation"),
+                    (&q_subscription, "subscription"),
+                ];
+                for (q, kind) in queries {
+                    syms.extend(run_query(source, root, &language, q, kind, true).unwrap_or_default());
+                }
+            }
+
+            // ── Protocol Buffers ──────────────────────────────────────────────
+            "proto" => {
+                let queries: [(&str, &str); 4] = [
+                    (r#"(message (message_name (identifier) @name)) @def"#, "message"),
+                    (r#"(enum    (enum_name    (identifier) @name)) @def"#, "enum"),
+                    (r#"(service (service_name (identifier) @name)) @def"#, "service"),
+                    (r#"(rpc     (rpc_name     (identifier) @name)) @def"#, "rpc"),
+                ];
+                for (q, kind) in queries {
+                    syms.extend(run_query(source, root, &language, q, kind, true).unwrap_or_default());
+                }
+            }
+
+            // ── OCaml ─────────────────────────────────────────────────────────
+            "ocaml" => {
+                // Only structure-level lets: `let ... in` locals are expressions, not items.
+                let top_let = |inner: &str| {
+                    format!(
+                        r#"[(compilation_unit (value_definition (let_binding pattern: [(value_name) (parenthesized_operator)] @name {inner}) @def))
+                            (structure        (value_definition (let_binding pattern: [(value_name) (parenthesized_operator)] @name {inner}) @def))]"#
+                    )
+                };
+                let mut functions = run_query(source, root, &language, &top_let("(parameter)"), "function", true).unwrap_or_default();
+                functions.extend(
+                    run_query(source, root, &language, &top_let("body: [(fun_expression) (function_expression)]"), "function", true)
+                        .unwrap_or_default(),
+                );
+                let values = run_query(source, root, &language, &top_let(""), "value", true)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|v| !functions.iter().any(|f| f.start_byte == v.start_byte));
+                syms.extend(values);
+                syms.extend(functions);
+
+                let queries: [(&str, &str); 7] = [
+                    (r#"(module_definition (module_binding (module_name) @name)) @def"#, "module"),
+                    (r#"(module_type_definition (module_type_name) @name) @def"#, "interface"),
+                    (r#"(type_binding name: (type_constructor) @name) @def"#, "type"),
+                    (r#"(exception_definition (constructor_declaration (constructor_name) @name)) @def"#, "exception"),
+                    (r#"(external (value_name) @name) @def"#, "function"),
+                    (r#"(class_binding (class_name) @name) @def"#, "class"),
+                    (r#"(method_definition (method_name) @name) @def"#, "method"),
+                ];
+                for (q, kind) in queries {
+                    syms.extend(run_query(source, root, &language, q, kind, true).unwrap_or_default());
+                }
+            }
+
+            // ── Ruby ──────────────────────────────────────────────────────────
+            "ruby" => {
+                let q_fn = r#"
+                    (method           name: (identifier) @name) @def
+                    (singleton_method name: (identifier) @name) @def
+                "#;
+                let q_class = r#"
+                    (class  name: (constant) @name) @def
+                    (module name: (constant) @name) @def
+                "#;
+                syms.extend(run_query(source, root, &language, q_fn,    "function", true).unwrap_or_default());
+                syms.extend(run_query(source, root, &language, q_class,  "class",    true).unwrap_or_default());
+            }
+
+            // ── C ─────────────────────────────────────────────────────────────
+            "c" => {
+                // Definitions and header prototypes, including pointer-returning ones.
+                let q_fn = r#"
+                    (function_definition
+                      declarator: [
+                        (function_declarator declarator: (identifier) @name)
+                        (pointer_declarator declarator: (function_declarator declarator: (identifier) @name))
+                      ]) @def
+                    (declaration
+                      declarator: [
+                        (function_declarator declarator: (identifier) @name)
+                        (pointer_declarator declarator: (function_declarator declarator: (identifier) @name))
+                      ]) @def
+                "#;
+                // Only specifiers with a body are definitions; `struct foo *p` is a use.
+                let q_struct = r#"
+                    (struct_specifier name: (type_identifier) @name body: (field_declaration_list)) @def
+                    (union_specifier  name: (type_identifier) @name body: (field_declaration_list)) @def
+                "#;
+                let q_enum    = r#"(enum_specifier name: (type_identifier) @name body: (enumerator_list)) @def"#;
+                let q_typedef = r#"(type_definition declarator: (type_identifier) @name) @def"#;
+                syms.extend(run_query(source, root, &language, q_fn,      "function", true).unwrap_or_default());
+                syms.extend(run_query(source, root, &language, q_struct,  "struct",   true).unwrap_or_default());
+                syms.extend(run_query(source, root, &language, q_enum,    "enum",     true).unwrap_or_default());
+                syms.extend(run_query(source, root, &language, q_typedef, "type",     true).unwrap_or_default());
+            }
+
+            // ── Generic fallback for any other Wasm language ──────────────────
             _ => {
                 let generic_query = r#"
                     (function_declaration name: (_) @name) @def
@@ -1536,6 +2398,51 @@ impl LanguageDriver for WasmDriver {
     }
 }
 
+/// Process-wide cache of compiled queries, keyed by grammar and query source.
+///
+/// Compiling a `Query` dominates the cost of `analyze_file` on small files, and the same
+/// handful of query strings is used for every file of a given language. Compile failures are
+/// cached too (some generic Wasm queries don't fit every grammar). `QueryCursor` stays
+/// per-call since it is cheap to create.
+///
+/// Keys are the query source because many queries are assembled at runtime (`format!`ed per
+/// kind, or read from a Wasm driver's `.scm`), and grammars are loaded at runtime too, so
+/// there is no fixed set of statics to hang them on. Lookups take the read lock only, so
+/// rayon workers inspecting files in parallel don't serialize once a language is warm.
+type QueryCache = HashMap<Language, HashMap<String, Result<Arc<Query>, String>>>;
+
+fn query_cache() -> &'static RwLock<QueryCache> {
+    static CACHE: OnceLock<RwLock<QueryCache>> = OnceLock::new();
+    CACHE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Return the compiled query for `(language, query_src)`, compiling it on first use.
+fn cached_query(language: &Language, query_src: &str) -> Result<Arc<Query>> {
+    let hit = query_cache()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(language)
+        .and_then(|per_lang| per_lang.get(query_src))
+        .cloned();
+    let entry = match hit {
+        Some(e) => e,
+        None => {
+            // Compiled outside the lock; when two threads race, the first insert is kept.
+            let compiled = Query::new(language, query_src)
+                .map(Arc::new)
+                .map_err(|e| e.to_string());
+            let mut cache = query_cache().write().unwrap_or_else(|e| e.into_inner());
+            cache
+                .entry(language.clone())
+                .or_default()
+                .entry(query_src.to_string())
+                .or_insert(compiled)
+                .clone()
+        }
+    };
+    entry.map_err(|e| anyhow!("Failed to compile tree-sitter query: {}", e))
+}
+
 fn run_query_byte_ranges(
     source: &[u8],
     root: Node,
@@ -1543,7 +2450,7 @@ fn run_query_byte_ranges(
     query_src: &str,
     cap: &str,
 ) -> Result<Vec<(usize, usize)>> {
-    let query = Query::new(language, query_src).context("Failed to compile tree-sitter query")?;
+    let query = cached_query(language, query_src)?;
     let mut cursor = QueryCursor::new();
     let mut out: Vec<(usize, usize)> = Vec::new();
 
@@ -1561,6 +2468,1057 @@ fn run_query_byte_ranges(
     Ok(out)
 }
 
+/// The namespace or type named by a C# `using` directive, ignoring `global`/`static`
+/// modifiers and aliases. `using (var x = ...)` statements never reach here.
+fn csharp_using_target(directive: &str) -> Option<String> {
+    let mut rest = directive.trim().trim_end_matches(';').trim();
+    rest = rest
+        .strip_prefix("global")
+        .map(str::trim_start)
+        .unwrap_or(rest);
+    rest = rest.strip_prefix("using")?.trim_start();
+    rest = rest
+        .strip_prefix("static ")
+        .map(str::trim_start)
+        .unwrap_or(rest);
+    if let Some((_, target)) = rest.split_once('=') {
+        rest = target.trim();
+    }
+    (!rest.is_empty()).then(|| rest.to_string())
+}
+
+/// Fully qualified names imported by a PHP `use` declaration, expanding group
+/// uses (`use App\Models\{User, Post as P};`) and dropping `function`/`const`
+/// modifiers and aliases.
+fn php_use_targets(decl: &str) -> Vec<String> {
+    let Some(rest) = decl.trim().trim_end_matches(';').strip_prefix("use") else {
+        return vec![];
+    };
+    let mut rest = rest.trim_start();
+    for modifier in ["function ", "const "] {
+        rest = rest
+            .strip_prefix(modifier)
+            .map(str::trim_start)
+            .unwrap_or(rest);
+    }
+    let unalias = |clause: &str| {
+        let clause = clause.trim();
+        let name = clause.split_once(" as ").map_or(clause, |(n, _)| n).trim();
+        name.trim_start_matches('\\').to_string()
+    };
+
+    if let (Some(open), Some(close)) = (rest.find('{'), rest.rfind('}')) {
+        let prefix = rest[..open].trim().trim_end_matches('\\');
+        return rest[open + 1..close]
+            .split(',')
+            .map(unalias)
+            .filter(|n| !n.is_empty())
+            .map(|n| format!("{}\\{}", prefix.trim_start_matches('\\'), n))
+            .collect();
+    }
+    rest.split(',')
+        .map(unalias)
+        .filter(|n| !n.is_empty())
+        .collect()
+}
+
+/// The path literal of a PHP `require`/`include` expression. `__DIR__ . '/x.php'`
+/// becomes `./x.php` so it resolves against the including file's directory.
+fn php_include_target(expr: &str) -> Option<String> {
+    let start = expr.find(['\'', '"'])?;
+    let quote = expr[start..].chars().next()?;
+    let len = expr[start + 1..].find(quote)?;
+    let literal = &expr[start + 1..start + 1 + len];
+    if literal.is_empty() {
+        return None;
+    }
+    if expr[..start].contains("__DIR__") || expr[..start].contains("dirname(__FILE__)") {
+        return Some(format!(".{}", literal));
+    }
+    Some(literal.to_string())
+}
+
+/// Target of a Kotlin `import`: `import a.b.C`, `import a.b.*` and `import a.b.C as D`
+/// give `a.b.C` / `a.b.*`.
+fn kotlin_import_target(decl: &str) -> Option<String> {
+    let path = decl.trim().strip_prefix("import")?.trim();
+    let path = path.split_once(" as ").map_or(path, |(p, _)| p).trim();
+    (!path.is_empty()).then(|| path.to_string())
+}
+
+/// Fully qualified names imported by a Scala `import`, expanding selectors
+/// (`import a.b.{C, D => E}` → `a.b.C`, `a.b.D`) and comma-separated clauses.
+/// Wildcards stay as written (`a.b._`, `a.b.*`).
+fn scala_import_targets(decl: &str) -> Vec<String> {
+    let Some(rest) = decl.trim().strip_prefix("import") else {
+        return vec![];
+    };
+
+    // Split on commas outside `{...}` selectors.
+    let mut clauses: Vec<&str> = Vec::new();
+    let (mut depth, mut start) = (0usize, 0usize);
+    for (i, ch) in rest.char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                clauses.push(&rest[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    clauses.push(&rest[start..]);
+
+    let mut out = Vec::new();
+    for clause in clauses {
+        let clause = clause.trim();
+        match (clause.find('{'), clause.rfind('}')) {
+            (Some(open), Some(close)) => {
+                let prefix = clause[..open].trim().trim_end_matches('.');
+                for selector in clause[open + 1..close].split(',') {
+                    let name = selector.split("=>").next().unwrap_or("").trim();
+                    let name = name.split(" as ").next().unwrap_or("").trim();
+                    if !name.is_empty() {
+                        out.push(format!("{prefix}.{name}"));
+                    }
+                }
+            }
+            _ if !clause.is_empty() => out.push(clause.to_string()),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Names of top-level Scala definitions that are not `private`/`protected`,
+/// including those nested in `package foo { ... }` blocks.
+fn scala_exports(source: &[u8], root: Node) -> Vec<String> {
+    const DEFINITIONS: &[&str] = &[
+        "object_definition",
+        "class_definition",
+        "trait_definition",
+        "enum_definition",
+        "function_definition",
+        "type_definition",
+        "val_definition",
+    ];
+
+    let mut out = Vec::new();
+    let mut stack = vec![root];
+    while let Some(parent) = stack.pop() {
+        let mut cursor = parent.walk();
+        for child in parent.named_children(&mut cursor) {
+            if child.kind() == "package_clause" {
+                if let Some(body) = child.child_by_field_name("body") {
+                    stack.push(body);
+                }
+                continue;
+            }
+            if !DEFINITIONS.contains(&child.kind()) {
+                continue;
+            }
+            let hidden = (0..child.named_child_count())
+                .filter_map(|i| child.named_child(i as _))
+                .filter(|c| c.kind() == "modifiers")
+                .any(|m| {
+                    let text = node_text(source, m);
+                    text.contains("private") || text.contains("protected")
+                });
+            let name = child
+                .child_by_field_name("name")
+                .or_else(|| child.child_by_field_name("pattern"));
+            if let (false, Some(name)) = (hidden, name) {
+                out.push(node_text(source, name).to_string());
+            }
+        }
+    }
+    out
+}
+
+/// Module names referenced by the arguments of an Elixir `alias`/`import`/`use`/`require`:
+/// options after the first top-level comma are dropped and `A.{B, C}` becomes `A.B`, `A.C`.
+fn elixir_import_targets(args: &str) -> Vec<String> {
+    let args = args.trim();
+    if let (Some(open), Some(close)) = (args.find(".{"), args.rfind('}')) {
+        let prefix = &args[..open];
+        return args[open + 2..close]
+            .split(',')
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .map(|m| format!("{prefix}.{m}"))
+            .collect();
+    }
+    let module = args.split(',').next().unwrap_or("").trim();
+    if module.is_empty() {
+        vec![]
+    } else {
+        vec![module.to_string()]
+    }
+}
+
+/// `CREATE [OR REPLACE] PROCEDURE name` statements, found by a line scan. Each symbol
+/// runs until the next top-level `CREATE` (or end of file), minus trailing blank lines.
+fn sql_procedures(source: &[u8]) -> Vec<Symbol> {
+    static RE: OnceLock<regex::Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+        regex::Regex::new(r#"(?i)^\s*create\s+(?:or\s+replace\s+)?procedure\s+([\w."]+)"#).unwrap()
+    });
+    let text = String::from_utf8_lossy(source);
+    let lines: Vec<&str> = text.lines().collect();
+    let offsets = line_byte_offsets(&text);
+    let starts_statement = |l: &str| l.trim_start().to_ascii_lowercase().starts_with("create ");
+
+    let mut out = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(caps) = re.captures(line) else {
+            continue;
+        };
+        let mut end = (i + 1..lines.len())
+            .find(|&j| starts_statement(lines[j]))
+            .unwrap_or(lines.len())
+            - 1;
+        while end > i && lines[end].trim().is_empty() {
+            end -= 1;
+        }
+        let end_byte = offsets
+            .get(end + 1)
+            .copied()
+            .unwrap_or(text.len())
+            .min(text.len());
+        out.push(Symbol {
+            name: caps[1].trim_matches('"').to_string(),
+            kind: "procedure".to_string(),
+            line: i as u32,
+            line_end: end as u32,
+            start_byte: offsets.get(i).copied().unwrap_or(0),
+            end_byte,
+            signature: Some(first_line_signature(line)),
+            ..Default::default()
+        });
+    }
+    out
+}
+
+/// CommonJS exports: `exports.foo = ...` / `module.exports.foo = ...` name `foo`;
+/// `module.exports = { a, b: c }` names its keys; `module.exports = foo` (or a named
+/// function/class) names the binding.
+fn commonjs_exports(source: &[u8], root: Node, language: &Language) -> Result<Vec<String>> {
+    let mut out = run_query_strings(
+        source,
+        root,
+        language,
+        r#"(assignment_expression
+            left: (member_expression
+                object: [
+                    (identifier) @obj
+                    (member_expression object: (identifier) @module property: (property_identifier) @obj)
+                ]
+                property: (property_identifier) @name)
+            (#eq? @obj "exports"))"#,
+        "name",
+    )?;
+
+    let query = cached_query(
+        language,
+        r#"(assignment_expression
+            left: (member_expression
+                object: (identifier) @module
+                property: (property_identifier) @prop)
+            right: (_) @value
+            (#eq? @module "module")
+            (#eq? @prop "exports"))"#,
+    )?;
+    let value_idx = query.capture_index_for_name("value").unwrap_or(u32::MAX);
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(&query, root, source);
+    while let Some(m) = matches.next() {
+        for cap in m.captures.iter().filter(|c| c.index == value_idx) {
+            let value = cap.node;
+            match value.kind() {
+                "identifier" => out.push(node_text(source, value).to_string()),
+                "function_expression" | "function" | "class" => {
+                    if let Some(n) = value.child_by_field_name("name") {
+                        out.push(node_text(source, n).to_string());
+                    }
+                }
+                "object" => {
+                    let mut c = value.walk();
+                    for prop in value.named_children(&mut c) {
+                        let key = match prop.kind() {
+                            "shorthand_property_identifier" => Some(prop),
+                            "pair" => prop.child_by_field_name("key"),
+                            "method_definition" => prop.child_by_field_name("name"),
+                            _ => None,
+                        };
+                        if let Some(k) = key {
+                            out.push(strip_string_quotes(node_text(source, k)));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Fill `metrics` for function-like symbols. Nested closures and functions count towards
+/// their enclosing symbol, so complexity is an estimate rather than a strict McCabe number.
+fn annotate_metrics(source: &[u8], root: Node, syms: &mut [Symbol]) {
+    for sym in syms.iter_mut() {
+        if !matches!(sym.kind.as_str(), "function" | "method" | "component") {
+            continue;
+        }
+        let Some(def) = root.descendant_for_byte_range(sym.start_byte, sym.end_byte) else {
+            continue;
+        };
+        let loc = node_text(source, def)
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .count() as u32;
+        let mut branches = 0u32;
+        let mut stack = vec![def];
+        while let Some(node) = stack.pop() {
+            if is_branch_node(source, node) {
+                branches += 1;
+            }
+            let mut cursor = node.walk();
+            stack.extend(node.named_children(&mut cursor));
+        }
+        sym.metrics = Some(SymbolMetrics {
+            loc,
+            branches,
+            complexity: branches + 1,
+        });
+    }
+}
+
+/// Whether `node` is a decision point in any bundled grammar. `else` branches aren't
+/// counted; `else if` chains count through their nested `if`.
+fn is_branch_node(source: &[u8], node: Node) -> bool {
+    match node.kind() {
+        "if_expression"
+        | "if_statement"
+        | "elif_clause"
+        | "if_clause"
+        | "while_expression"
+        | "while_statement"
+        | "do_statement"
+        | "for_expression"
+        | "for_statement"
+        | "for_in_statement"
+        | "enhanced_for_statement"
+        | "for_in_clause"
+        | "loop_expression"
+        | "match_arm"
+        | "case_clause"
+        | "switch_case"
+        | "switch_label"
+        | "expression_case"
+        | "type_case"
+        | "catch_clause"
+        | "except_clause"
+        | "conditional_expression"
+        | "ternary_expression"
+        | "boolean_operator" => true,
+        "binary_expression" => node
+            .child_by_field_name("operator")
+            .is_some_and(|op| matches!(node_text(source, op), "&&" | "||")),
+        _ => false,
+    }
+}
+
+const MARKER_TAGS: &[&str] = &["TODO", "FIXME", "HACK"];
+
+/// Collect TODO/FIXME/HACK tags from every comment node (one per comment line at most).
+fn extract_markers(source: &[u8], root: Node) -> Vec<Marker> {
+    let mut out = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if node.kind().contains("comment") {
+            let start = node.start_position().row as u32;
+            for (offset, line) in node_text(source, node).lines().enumerate() {
+                if let Some((kind, text)) = marker_in_line(line) {
+                    out.push(Marker {
+                        kind: kind.to_string(),
+                        line: start + offset as u32,
+                        text,
+                    });
+                }
+            }
+            continue;
+        }
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+    }
+    out
+}
+
+/// The first marker tag in a comment line and the text following it. Tags must be whole
+/// uppercase words; an `(owner)` suffix and `:` separator are skipped.
+fn marker_in_line(line: &str) -> Option<(&'static str, String)> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let (pos, tag) = MARKER_TAGS
+        .iter()
+        .flat_map(|tag| line.match_indices(tag).map(move |(i, _)| (i, *tag)))
+        .filter(|(i, tag)| {
+            !line[..*i].ends_with(is_word) && !line[i + tag.len()..].starts_with(is_word)
+        })
+        .min_by_key(|(i, _)| *i)?;
+
+    let mut rest = &line[pos + tag.len()..];
+    if rest.starts_with('(') {
+        rest = rest.find(')').map_or(rest, |end| &rest[end + 1..]);
+    }
+    let rest = rest.trim_start().trim_start_matches(':').trim();
+    let text = rest
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim_end();
+    Some((tag, text.to_string()))
+}
+
+/// Fill `visibility` for Rust, TypeScript/JavaScript and Python symbols (children included).
+/// `exports` catches names exported away from their definition (`export { a }`, `__all__`).
+fn annotate_visibility(
+    lang: &str,
+    source: &[u8],
+    root: Node,
+    syms: &mut [Symbol],
+    exports: &[String],
+) {
+    for sym in syms.iter_mut() {
+        let Some(def) = root.descendant_for_byte_range(sym.start_byte, sym.end_byte) else {
+            continue;
+        };
+        let local = match &sym.parent {
+            Some(parent) => sym.name[parent.len()..].trim_start_matches([':', '.']),
+            None => sym.name.as_str(),
+        };
+        let top_level = sym.parent.is_none();
+        let vis = match lang {
+            "rust" => rust_visibility(source, def, sym),
+            "typescript" => ts_visibility(source, def, local, top_level, exports),
+            "python" if top_level && exports.iter().any(|e| e == local) => {
+                Some("exported".to_string())
+            }
+            "python" if top_level => Some(
+                if local.starts_with('_') {
+                    "private"
+                } else {
+                    "internal"
+                }
+                .to_string(),
+            ),
+            "python" => Some(python_member_visibility(local).to_string()),
+            _ => None,
+        };
+
+        // Enum variants (and TS enum members) share their enum's visibility.
+        for child in &mut sym.children {
+            child.visibility = if child.kind == "variant" {
+                vis.clone()
+            } else {
+                root.descendant_for_byte_range(child.start_byte, child.end_byte)
+                    .and_then(|n| match lang {
+                        "rust" => rust_visibility(source, n, child),
+                        "typescript" => Some(ts_member_visibility(source, n)),
+                        _ => None,
+                    })
+            };
+        }
+        sym.visibility = vis;
+    }
+}
+
+/// Rust: the item's `pub(...)` modifier, `private` without one. Trait items follow the
+/// trait; trait impl items and `#[macro_export]` macros are reachable wherever the
+/// trait/crate is, so they report `pub`.
+fn rust_visibility(source: &[u8], def: Node, sym: &Symbol) -> Option<String> {
+    if sym.kind == "macro" {
+        let exported = sym.attributes.iter().any(|a| a == "macro_export");
+        return Some(if exported { "pub" } else { "private" }.to_string());
+    }
+    let modifier = |n: Node| {
+        let mut cursor = n.walk();
+        let found = n
+            .named_children(&mut cursor)
+            .find(|c| c.kind() == "visibility_modifier");
+        found.map(|m| node_text(source, m).split_whitespace().collect::<String>())
+    };
+    if let Some(v) = modifier(def) {
+        return Some(v);
+    }
+    let container = def
+        .parent()
+        .filter(|p| p.kind() == "declaration_list")
+        .and_then(|p| p.parent());
+    match container {
+        Some(c) if c.kind() == "trait_item" => modifier(c).or(Some("private".to_string())),
+        Some(c) if c.kind() == "impl_item" && c.child_by_field_name("trait").is_some() => {
+            Some("pub".to_string())
+        }
+        _ => Some("private".to_string()),
+    }
+}
+
+/// TS/JS: `exported`/`default` for declarations under an `export` statement (or exported
+/// by name elsewhere), `internal` otherwise; class members use their accessibility.
+fn ts_visibility(
+    source: &[u8],
+    def: Node,
+    local: &str,
+    top_level: bool,
+    exports: &[String],
+) -> Option<String> {
+    if matches!(
+        def.kind(),
+        "method_definition"
+            | "public_field_definition"
+            | "method_signature"
+            | "abstract_method_signature"
+            | "property_signature"
+    ) {
+        return Some(ts_member_visibility(source, def));
+    }
+    // `export const X = ...` puts the declarator two levels below the export statement.
+    let export = std::iter::successors(Some(def), |n| n.parent())
+        .take(4)
+        .find(|n| n.kind() == "export_statement");
+    if let Some(export) = export {
+        let mut cursor = export.walk();
+        let is_default = export.children(&mut cursor).any(|c| c.kind() == "default");
+        return Some(if is_default { "default" } else { "exported" }.to_string());
+    }
+    if top_level && exports.iter().any(|e| e == local) {
+        return Some("exported".to_string());
+    }
+    Some("internal".to_string())
+}
+
+fn ts_member_visibility(source: &[u8], member: Node) -> String {
+    let mut cursor = member.walk();
+    let children: Vec<Node> = member.named_children(&mut cursor).collect();
+    if let Some(m) = children
+        .iter()
+        .find(|c| c.kind() == "accessibility_modifier")
+    {
+        return node_text(source, *m).trim().to_string();
+    }
+    if member
+        .child_by_field_name("name")
+        .is_some_and(|n| n.kind() == "private_property_identifier")
+    {
+        return "private".to_string();
+    }
+    "public".to_string()
+}
+
+/// Python naming conventions: `__name` is private (name-mangled), `_name` protected,
+/// dunders and everything else public.
+fn python_member_visibility(name: &str) -> &'static str {
+    if name.starts_with("__") && !name.ends_with("__") {
+        "private"
+    } else if name.starts_with('_') && !name.starts_with("__") {
+        "protected"
+    } else {
+        "public"
+    }
+}
+
+/// Fill `is_async`, `is_unsafe` and `generics` from the definition's keyword tokens and
+/// `type_parameters`. Variables bound to functions (`const f = async <T>() => ...`) are
+/// read through their value.
+fn annotate_signature_flags(source: &[u8], root: Node, syms: &mut [Symbol]) {
+    for sym in syms.iter_mut() {
+        let Some(def) = root.descendant_for_byte_range(sym.start_byte, sym.end_byte) else {
+            continue;
+        };
+        let target = declared_value(def).unwrap_or(def);
+
+        let mut cursor = target.walk();
+        for child in target.children(&mut cursor) {
+            let kind = child.kind();
+            let words: Vec<&str> = if kind.ends_with("modifiers") || kind == "modifier" {
+                node_text(source, child).split_whitespace().collect()
+            } else {
+                vec![kind]
+            };
+            sym.is_async |= words.contains(&"async");
+            sym.is_unsafe |= words.contains(&"unsafe");
+        }
+
+        if let Some(params) = target.child_by_field_name("type_parameters") {
+            let mut cursor = params.walk();
+            sym.generics = params
+                .named_children(&mut cursor)
+                .map(|p| {
+                    node_text(source, p)
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect();
+        }
+    }
+}
+
+/// The function value of a variable declaration (`const f = () => ...`), if any.
+fn declared_value(def: Node) -> Option<Node> {
+    let declarator = if def.kind() == "variable_declarator" {
+        def
+    } else if matches!(def.kind(), "lexical_declaration" | "variable_declaration") {
+        let mut cursor = def.walk();
+        let found = def
+            .named_children(&mut cursor)
+            .find(|c| c.kind() == "variable_declarator");
+        found?
+    } else {
+        return None;
+    };
+    declarator.child_by_field_name("value").filter(|v| {
+        matches!(
+            v.kind(),
+            "arrow_function" | "function_expression" | "function"
+        )
+    })
+}
+
+/// Fill `calls` for function-like symbols by walking their definition for call nodes.
+/// Node kinds cover every bundled grammar plus the Wasm languages' call shapes.
+fn annotate_calls(source: &[u8], root: Node, syms: &mut [Symbol]) {
+    for sym in syms.iter_mut() {
+        if !matches!(sym.kind.as_str(), "function" | "method" | "component") {
+            continue;
+        }
+        let Some(def) = root.descendant_for_byte_range(sym.start_byte, sym.end_byte) else {
+            continue;
+        };
+        let mut calls: Vec<String> = Vec::new();
+        let mut stack = vec![def];
+        while let Some(node) = stack.pop() {
+            if let Some(target) = call_target(source, node) {
+                if !calls.contains(&target) {
+                    calls.push(target);
+                }
+            }
+            let mut cursor = node.walk();
+            // Push in reverse so the walk visits calls in source order.
+            let children: Vec<Node> = node.named_children(&mut cursor).collect();
+            stack.extend(children.into_iter().rev());
+        }
+        sym.calls = calls;
+    }
+}
+
+/// The callee of a call node as written (`foo`, `self.bar`, `Vec::new`), or `None` for
+/// non-call nodes. Chained receivers (`a().b()`) keep only the final segment.
+fn call_target(source: &[u8], node: Node) -> Option<String> {
+    let text = match node.kind() {
+        "call_expression" | "invocation_expression" | "function_call_expression" => {
+            node_text(source, node.child_by_field_name("function")?)
+        }
+        "call" => {
+            let callee = node
+                .child_by_field_name("function")
+                .or_else(|| node.child_by_field_name("method"))
+                .or_else(|| node.child_by_field_name("target"))?;
+            // Ruby `recv.method`: the receiver is a sibling field.
+            match node.child_by_field_name("receiver") {
+                Some(recv) => {
+                    std::str::from_utf8(&source[recv.start_byte()..callee.end_byte()]).unwrap_or("")
+                }
+                None => node_text(source, callee),
+            }
+        }
+        // Java `obj.name(...)`, PHP `$obj->name(...)` / `Cls::name(...)`.
+        "method_invocation" | "member_call_expression" | "scoped_call_expression" => {
+            let name = node.child_by_field_name("name")?;
+            let start = node
+                .child_by_field_name("object")
+                .or_else(|| node.child_by_field_name("scope"))
+                .map(|o| o.start_byte())
+                .unwrap_or(name.start_byte());
+            std::str::from_utf8(&source[start..name.end_byte()]).unwrap_or("")
+        }
+        _ => return None,
+    };
+
+    // `a().b` / `x[0].c` → `b` / `c`: intermediate results have no stable name.
+    let tail = match text.rfind([')', ']']) {
+        Some(i) => text[i + 1..].trim_start_matches(['.', '?', ':', '-', '>']),
+        None => text,
+    };
+    // Drop turbofish / generic arguments and whitespace (`parse::<u32>`, `a\n  .b`).
+    let mut out = String::with_capacity(tail.len());
+    let mut depth = 0i32;
+    for c in tail.chars() {
+        match c {
+            '<' => depth += 1,
+            '>' if depth > 0 => depth -= 1,
+            c if depth == 0 && !c.is_whitespace() => out.push(c),
+            _ => {}
+        }
+    }
+    let out = out.trim_end_matches("::").to_string();
+    (!out.is_empty() && out.len() <= 120).then_some(out)
+}
+
+/// Reclassify capitalized functions, arrow functions (including `memo`/`forwardRef`
+/// wrappers) and `Component` subclasses that render JSX as `kind: "component"`.
+/// The signature becomes `Name(props: PropsType)` (`Name()` when props are untyped).
+fn classify_react_components(source: &[u8], root: Node, syms: &mut [Symbol]) {
+    for sym in syms.iter_mut() {
+        if !matches!(sym.kind.as_str(), "function" | "const" | "class")
+            || !sym.name.starts_with(|c: char| c.is_ascii_uppercase())
+        {
+            continue;
+        }
+        let Some(def) = root.descendant_for_byte_range(sym.start_byte, sym.end_byte) else {
+            continue;
+        };
+
+        let props = if sym.kind == "class" {
+            let Some(props) = react_class_component_props(source, def) else {
+                continue;
+            };
+            props
+        } else {
+            let Some((func, declared_type)) = react_function_node(source, def, &sym.name, None)
+            else {
+                continue;
+            };
+            if !contains_jsx(func) {
+                continue;
+            }
+            // `React.FC<Props>` on the binding wins; otherwise the first parameter's annotation.
+            declared_type.or_else(|| {
+                let param = func
+                    .child_by_field_name("parameters")
+                    .and_then(|p| p.named_child(0))?;
+                let ty = param.child_by_field_name("type")?;
+                Some(
+                    node_text(source, ty)
+                        .trim_start_matches(':')
+                        .trim()
+                        .to_string(),
+                )
+            })
+        };
+
+        sym.kind = "component".to_string();
+        sym.signature = Some(match props {
+            Some(ty) => format!("{}(props: {})", sym.name, ty),
+            None => format!("{}()", sym.name),
+        });
+    }
+}
+
+/// The function node behind a symbol definition, plus the props type from a
+/// `React.FC<Props>`-style annotation on its binding.
+fn react_function_node<'a>(
+    source: &[u8],
+    node: Node<'a>,
+    name: &str,
+    declared_type: Option<String>,
+) -> Option<(Node<'a>, Option<String>)> {
+    match node.kind() {
+        "function_declaration" | "function" | "function_expression" | "arrow_function" => {
+            Some((node, declared_type))
+        }
+        "export_statement" => react_function_node(
+            source,
+            node.child_by_field_name("declaration")?,
+            name,
+            declared_type,
+        ),
+        "lexical_declaration" | "variable_declaration" => {
+            let mut cursor = node.walk();
+            let declarator = node.named_children(&mut cursor).find(|d| {
+                d.kind() == "variable_declarator"
+                    && d.child_by_field_name("name").map(|n| node_text(source, n)) == Some(name)
+            })?;
+            react_function_node(source, declarator, name, declared_type)
+        }
+        "variable_declarator" => {
+            let declared = node
+                .child_by_field_name("type")
+                .and_then(|t| first_type_argument(node_text(source, t)));
+            react_function_node(source, node.child_by_field_name("value")?, name, declared)
+        }
+        // `memo((props: P) => ...)`, `forwardRef<R, P>(...)`.
+        "call_expression" => {
+            let args = node.child_by_field_name("arguments")?;
+            let mut cursor = args.walk();
+            let inner = args.named_children(&mut cursor).find(|a| {
+                matches!(
+                    a.kind(),
+                    "arrow_function" | "function" | "function_expression"
+                )
+            })?;
+            let wrapper_props = node.child_by_field_name("type_arguments").and_then(|t| {
+                let text = node_text(source, t);
+                // forwardRef<Ref, Props> puts props second; memo<Props> first.
+                let inner_text = text.trim().trim_start_matches('<').trim_end_matches('>');
+                let parts = split_top_level_commas(inner_text);
+                parts.last().map(|p| p.to_string())
+            });
+            react_function_node(source, inner, name, declared_type.or(wrapper_props))
+        }
+        _ => None,
+    }
+}
+
+/// Props type of `class X extends React.Component<Props, State>` (outer `Some` = is a component).
+fn react_class_component_props(source: &[u8], class: Node) -> Option<Option<String>> {
+    let mut cursor = class.walk();
+    let heritage = class
+        .named_children(&mut cursor)
+        .find(|c| c.kind() == "class_heritage")?;
+    let text = node_text(source, heritage);
+    let extends = text.trim().strip_prefix("extends")?.trim_start();
+    let base = extends.split(['<', ' ', '{']).next().unwrap_or_default();
+    if !matches!(
+        base,
+        "Component" | "PureComponent" | "React.Component" | "React.PureComponent"
+    ) {
+        return None;
+    }
+    Some(first_type_argument(extends))
+}
+
+/// `React.FC<Props>` → `Props` (the first top-level generic argument).
+fn first_type_argument(text: &str) -> Option<String> {
+    let start = text.find('<')?;
+    let end = text.rfind('>')?;
+    let inner = text.get(start + 1..end)?;
+    split_top_level_commas(inner)
+        .first()
+        .map(|s| s.to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn split_top_level_commas(s: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut depth = 0i32;
+    let mut last = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' | '(' | '{' | '[' => depth += 1,
+            '>' | ')' | '}' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                out.push(s[last..i].trim());
+                last = i + 1;
+            }
+            _ => {}
+        }
+    }
+    out.push(s[last..].trim());
+    out
+}
+
+fn contains_jsx(node: Node) -> bool {
+    if matches!(node.kind(), "jsx_element" | "jsx_self_closing_element") {
+        return true;
+    }
+    let mut cursor = node.walk();
+    let found = node.children(&mut cursor).any(contains_jsx);
+    found
+}
+
+/// Attach each member to the innermost symbol of `container_kinds` that encloses it.
+/// Members outside any container (e.g. inline object types in a signature) are dropped.
+fn nest_children(parents: &mut [Symbol], members: Vec<Symbol>, container_kinds: &[&str]) {
+    for member in members {
+        let owner = parents
+            .iter_mut()
+            .filter(|p| container_kinds.contains(&p.kind.as_str()))
+            .filter(|p| p.start_byte <= member.start_byte && member.end_byte <= p.end_byte)
+            .min_by_key(|p| p.end_byte - p.start_byte);
+        if let Some(owner) = owner {
+            owner.children.push(member);
+        }
+    }
+}
+
+/// Fill `derives`/`attributes` from the outer attributes (`#[...]`) preceding each item.
+fn annotate_rust_attributes(source: &[u8], root: Node, syms: &mut [Symbol]) {
+    for sym in syms.iter_mut() {
+        let Some(def) = root.descendant_for_byte_range(sym.start_byte, sym.end_byte) else {
+            continue;
+        };
+        let mut prev = def.prev_sibling();
+        while let Some(p) = prev {
+            match p.kind() {
+                "line_comment" | "block_comment" => {}
+                "attribute_item" => {
+                    if let Some(attr) = p.named_child(0) {
+                        let text = node_text(source, attr);
+                        let path_end = attr
+                            .child_by_field_name("arguments")
+                            .or_else(|| attr.child_by_field_name("value"))
+                            .map(|a| a.start_byte() - attr.start_byte())
+                            .unwrap_or(text.len());
+                        let path = text[..path_end].trim_end_matches(['=', ' ']).trim();
+                        if path == "derive" {
+                            let args = text[path_end..].trim_matches(|c| "()[]{} ".contains(c));
+                            // Attributes are visited bottom-up; keep source order within one.
+                            let names: Vec<String> = args
+                                .split(',')
+                                .map(|d| d.trim().to_string())
+                                .filter(|d| !d.is_empty())
+                                .collect();
+                            sym.derives.splice(0..0, names);
+                        } else if path != "doc" {
+                            sym.attributes.insert(0, path.to_string());
+                        }
+                    }
+                }
+                _ => break,
+            }
+            prev = p.prev_sibling();
+        }
+    }
+}
+
+/// Qualify symbols nested in Rust modules/impls/traits (`net::Client::send`), TS
+/// namespaces/classes (`Api.Client.send`) and Python classes/functions (`Job.run`),
+/// recording the enclosing path in `parent`. Functions directly inside an impl, trait
+/// or Python class become `method`s.
+fn qualify_nested_symbols(source: &[u8], root: Node, syms: &mut [Symbol], lang: &str) {
+    let sep = if lang == "rust" { "::" } else { "." };
+    for sym in syms.iter_mut() {
+        let Some(def) = root.descendant_for_byte_range(sym.start_byte, sym.end_byte) else {
+            continue;
+        };
+
+        let mut scopes: Vec<String> = Vec::new();
+        // Whether the innermost scope is a type (impl/trait/class) rather than a module or function.
+        let mut innermost_is_type: Option<bool> = None;
+        let mut cur = def.parent();
+        while let Some(n) = cur {
+            let scope = match (lang, n.kind()) {
+                ("rust", "mod_item") => n.child_by_field_name("name").map(|x| (x, false)),
+                ("rust", "trait_item") => n.child_by_field_name("name").map(|x| (x, true)),
+                ("rust", "impl_item") => n.child_by_field_name("type").map(|x| (x, true)),
+                ("typescript", "internal_module" | "module") => {
+                    n.child_by_field_name("name").map(|x| (x, false))
+                }
+                ("typescript", "class_declaration" | "abstract_class_declaration" | "class") => {
+                    n.child_by_field_name("name").map(|x| (x, true))
+                }
+                ("python", "class_definition") => n.child_by_field_name("name").map(|x| (x, true)),
+                ("python", "function_definition") => {
+                    n.child_by_field_name("name").map(|x| (x, false))
+                }
+                _ => None,
+            };
+            if let Some((name, is_type)) = scope {
+                let text = strip_string_quotes(node_text(source, name));
+                scopes.push(strip_template_args(&text));
+                innermost_is_type.get_or_insert(is_type);
+            }
+            cur = n.parent();
+        }
+        if scopes.is_empty() {
+            continue;
+        }
+        scopes.reverse();
+
+        let parent = scopes.join(sep);
+        sym.name = format!("{parent}{sep}{}", sym.name);
+        sym.parent = Some(parent);
+        if sym.kind == "function" && innermost_is_type == Some(true) {
+            sym.kind = "method".to_string();
+        }
+    }
+}
+
+/// Rewrite C++ symbol names to their fully qualified form (`ns::Class::method`).
+///
+/// Enclosing namespaces and classes are prepended, template arguments are dropped
+/// (`Box<T>::set` → `Box::set`), functions nested in a class become `method`s, and
+/// templated definitions are widened to include their `template <...>` header.
+fn qualify_cpp_symbols(source: &[u8], root: Node, syms: &mut [Symbol]) {
+    for sym in syms.iter_mut() {
+        let Some(def) = root.descendant_for_byte_range(sym.start_byte, sym.end_byte) else {
+            continue;
+        };
+
+        let mut scopes: Vec<String> = Vec::new();
+        let mut in_class = false;
+        let mut cur = def.parent();
+        while let Some(n) = cur {
+            match n.kind() {
+                "namespace_definition" => {
+                    // Anonymous namespaces add no qualifier.
+                    if let Some(name) = n.child_by_field_name("name") {
+                        scopes.push(strip_template_args(node_text(source, name)));
+                    }
+                }
+                "class_specifier" | "struct_specifier" | "union_specifier" => {
+                    if let Some(name) = n.child_by_field_name("name") {
+                        scopes.push(strip_template_args(node_text(source, name)));
+                        in_class = true;
+                    }
+                }
+                _ => {}
+            }
+            cur = n.parent();
+        }
+        scopes.reverse();
+
+        if !scopes.is_empty() {
+            sym.parent = Some(scopes.join("::"));
+        }
+        let local = strip_template_args(&sym.name);
+        let local = local.trim_start_matches("::");
+        scopes.push(local.to_string());
+        sym.name = scopes.join("::");
+
+        if sym.kind == "function" && in_class {
+            sym.kind = "method".to_string();
+        }
+
+        if let Some(tpl) = def.parent().filter(|p| p.kind() == "template_declaration") {
+            sym.start_byte = tpl.start_byte();
+            sym.line = tpl.start_position().row as u32;
+        }
+    }
+}
+
+/// Drop balanced `<...>` template argument lists and whitespace from a C++ name.
+/// Unbalanced angle brackets (`operator<`, `operator<<`) are kept verbatim.
+fn strip_template_args(name: &str) -> String {
+    let chars: Vec<char> = name.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut out = String::with_capacity(chars.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '<' && !out.ends_with("operator") {
+            let mut depth = 0usize;
+            let close = (i..chars.len()).find(|&j| {
+                match chars[j] {
+                    '<' => depth += 1,
+                    '>' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            });
+            if let Some(j) = close {
+                i = j + 1;
+                continue;
+            }
+            out.extend(&chars[i..]);
+            break;
+        }
+        out.push(chars[i]);
+        i += 1;
+    }
+    out
+}
+
 fn first_line_signature(def_text: &str) -> String {
     let mut s = def_text;
     if let Some(i) = s.find('{') {
@@ -1620,7 +3578,7 @@ fn run_query_strings(
     query_src: &str,
     cap: &str,
 ) -> Result<Vec<String>> {
-    let query = Query::new(language, query_src).context("Failed to compile tree-sitter query")?;
+    let query = cached_query(language, query_src)?;
     let mut cursor = QueryCursor::new();
 
     let mut out: Vec<String> = Vec::new();
@@ -1654,7 +3612,7 @@ fn run_query(
     kind: &str,
     include_signature: bool,
 ) -> Result<Vec<Symbol>> {
-    let query = Query::new(language, query_src).context("Failed to compile tree-sitter query")?;
+    let query = cached_query(language, query_src)?;
     let mut cursor = QueryCursor::new();
 
     let mut out: Vec<Symbol> = Vec::new();
@@ -1662,47 +3620,159 @@ fn run_query(
     let mut matches = cursor.matches(&query, root, source);
     while let Some(m) = matches.next() {
         let mut name_node: Option<Node> = None;
-        let mut def_node: Option<Node> = None;
-
+        let mut def_node: Option<Node> = None;
+
+        for cap in m.captures {
+            let cap_name = query.capture_names()[cap.index as usize];
+            match cap_name {
+                "name" => name_node = Some(cap.node),
+                "def" => def_node = Some(cap.node),
+                _ => {}
+            }
+        }
+
+        let Some(name_node) = name_node else { continue };
+        let def_node = def_node.unwrap_or(name_node);
+
+        let name = node_text(source, name_node).trim().to_string();
+        if name.is_empty() {
+            continue;
+        }
+
+        let start = def_node.start_position();
+        let end = def_node.end_position();
+
+        let signature = if include_signature {
+            let def_text = node_text(source, def_node);
+            Some(first_line_signature(def_text))
+        } else {
+            None
+        };
+
+        out.push(Symbol {
+            name,
+            kind: kind.to_string(),
+            line: start.row as u32,
+            line_end: end.row as u32,
+            start_byte: def_node.start_byte(),
+            end_byte: def_node.end_byte(),
+            signature,
+            ..Default::default()
+        });
+    }
+
+    Ok(out)
+}
+
+/// Directory (searched upward from the analysed file) holding user query packs:
+/// `.context-slicer/queries/<language>/symbols.scm`.
+const QUERY_PACK_DIR: &str = ".context-slicer/queries";
+
+/// The nearest `symbols.scm` query pack for `lang` above `path`, if any.
+fn find_query_pack(path: &Path, lang: &str) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .map(|dir| dir.join(QUERY_PACK_DIR).join(lang).join("symbols.scm"))
+        .find(|p| p.is_file())
+}
+
+/// Contents of the query pack that applies to `path`, if any (part of analyze cache keys).
+pub(crate) fn query_pack_source(path: &Path) -> Option<String> {
+    let view = if notebook::is_notebook_path(path) {
+        notebook::code_view_path(path)
+    } else {
+        path.to_path_buf()
+    };
+    let cfg = language_config().read().unwrap();
+    let driver = cfg.driver_for_path(&view)?;
+    let pack = find_query_pack(&view, driver.name())?;
+    std::fs::read_to_string(pack).ok()
+}
+
+/// Apply a user query pack on top of the built-in symbols. Patterns follow the tree-sitter
+/// tags convention: `@name` plus `@definition.<kind>` (e.g. `@definition.provider`). Pack
+/// symbols replace built-in ones for the same node; a `; mode: override` line discards the
+/// built-in symbols entirely. A pack that can't be read or compiled is skipped (and logged
+/// once), leaving the built-in symbols.
+fn apply_query_pack(
+    path: &Path,
+    lang: &str,
+    source: &[u8],
+    root: Node,
+    language: &Language,
+    symbols: &mut Vec<Symbol>,
+) {
+    let Some(pack) = find_query_pack(path, lang) else {
+        return;
+    };
+    let loaded = std::fs::read_to_string(&pack)
+        .with_context(|| format!("Failed to read query pack {}", pack.display()))
+        .and_then(|query_src| {
+            let query = cached_query(language, &query_src)
+                .with_context(|| format!("Invalid query pack {}", pack.display()))?;
+            Ok((query_src, query))
+        });
+    let (query_src, query) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            report_bad_query_pack(&pack, &e);
+            return;
+        }
+    };
+
+    let mut found: Vec<Symbol> = Vec::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(&query, root, source);
+    while let Some(m) = matches.next() {
+        let mut name_node: Option<Node> = None;
+        let mut def: Option<(Node, &str)> = None;
         for cap in m.captures {
             let cap_name = query.capture_names()[cap.index as usize];
-            match cap_name {
-                "name" => name_node = Some(cap.node),
-                "def" => def_node = Some(cap.node),
-                _ => {}
+            if cap_name == "name" {
+                name_node = Some(cap.node);
+            } else if let Some(kind) = cap_name.strip_prefix("definition.") {
+                def = Some((cap.node, kind));
             }
         }
-
-        let Some(name_node) = name_node else { continue };
-        let def_node = def_node.unwrap_or(name_node);
-
+        let (Some(name_node), Some((def_node, kind))) = (name_node, def) else {
+            continue;
+        };
         let name = node_text(source, name_node).trim().to_string();
         if name.is_empty() {
             continue;
         }
-
-        let start = def_node.start_position();
-        let end = def_node.end_position();
-
-        let signature = if include_signature {
-            let def_text = node_text(source, def_node);
-            Some(first_line_signature(def_text))
-        } else {
-            None
-        };
-
-        out.push(Symbol {
+        found.push(Symbol {
             name,
             kind: kind.to_string(),
-            line: start.row as u32,
-            line_end: end.row as u32,
+            line: def_node.start_position().row as u32,
+            line_end: def_node.end_position().row as u32,
             start_byte: def_node.start_byte(),
             end_byte: def_node.end_byte(),
-            signature,
+            signature: Some(first_line_signature(node_text(source, def_node))),
+            ..Default::default()
         });
     }
 
-    Ok(out)
+    if query_src.lines().any(|l| l.trim() == "; mode: override") {
+        symbols.clear();
+    } else {
+        symbols.retain(|s| !found.iter().any(|f| f.start_byte == s.start_byte));
+    }
+    symbols.extend(found);
+}
+
+/// Log a broken query pack the first time it is met rather than once per analysed file.
+#[cfg_attr(not(debug_assertions), allow(unused_variables))]
+fn report_bad_query_pack(pack: &Path, err: &anyhow::Error) {
+    static REPORTED: OnceLock<Mutex<std::collections::HashSet<PathBuf>>> = OnceLock::new();
+    let first = REPORTED
+        .get_or_init(Default::default)
+        .lock()
+        .map(|mut seen| seen.insert(pack.to_path_buf()))
+        .unwrap_or(false);
+    if first {
+        crate::debug_log!("[cortexast] ignoring query pack: {err:#}");
+    }
 }
 
 /// Parse a single file and extract symbols (functions/structs/classes) using tree-sitter.
@@ -1718,42 +3788,257 @@ pub fn analyze_file(path: &Path) -> Result<FileSymbols> {
             .join(path)
     };
 
+    if config_outline::is_config_outline_path(&abs) {
+        let source_text = std::fs::read_to_string(&abs)
+            .with_context(|| format!("Failed to read {}", abs.display()))?;
+        let mut symbols = config_outline::outline_symbols(&abs, &source_text).unwrap_or_default();
+        fill_columns(source_text.as_bytes(), &mut symbols);
+        return Ok(FileSymbols {
+            file: normalize_path_for_output(path),
+            imports: vec![],
+            exports: vec![],
+            symbols,
+            markers: vec![],
+        });
+    }
+
+    // Notebooks are analysed through their Python code view.
+    let notebook = if notebook::is_notebook_path(&abs) {
+        let text = std::fs::read_to_string(&abs)
+            .with_context(|| format!("Failed to read {}", abs.display()))?;
+        Some(notebook::extract_code_cells(&text)?)
+    } else {
+        None
+    };
+    let abs = match notebook {
+        Some(_) => notebook::code_view_path(&abs),
+        None => abs,
+    };
+
     let cfg = language_config().read().unwrap();
     let driver = cfg
         .driver_for_path(&abs)
         .ok_or_else(|| anyhow!("Unsupported file extension: {}", abs.display()))?;
-    let language = driver.language_for_path(&abs);
 
-    let source_text = std::fs::read_to_string(&abs)
-        .with_context(|| format!("Failed to read {}", abs.display()))?;
+    let source_text = match &notebook {
+        Some(code) => code.source.clone(),
+        None => std::fs::read_to_string(&abs)
+            .with_context(|| format!("Failed to read {}", abs.display()))?,
+    };
     let source = source_text.as_bytes();
 
-    let mut parser = driver.make_parser(&abs)?;
+    let tree = parse_with_thread_parser(driver, &abs, &source_text)?;
 
-    let tree = parser
-        .parse(source_text.as_str(), None)
-        .ok_or_else(|| anyhow!("Failed to parse file"))?;
+    analyze_tree(path, &abs, driver, source, tree.root_node(), notebook.as_ref())
+}
 
-    let root = tree.root_node();
+/// [`analyze_file`] over many paths in parallel (rayon), results in input order. Each
+/// worker thread reuses one parser per language across its files.
+pub fn analyze_files(paths: &[PathBuf]) -> Vec<Result<FileSymbols>> {
+    paths.par_iter().map(|p| analyze_file(p)).collect()
+}
+
+thread_local! {
+    static THREAD_PARSERS: RefCell<HashMap<Language, Parser>> = RefCell::new(HashMap::new());
+}
+
+/// Parse with this thread's cached parser for the file's language, creating it on first use.
+fn parse_with_thread_parser(
+    driver: &dyn LanguageDriver,
+    abs: &Path,
+    source_text: &str,
+) -> Result<Tree> {
+    let key = driver.language_for_path(abs);
+    THREAD_PARSERS.with(|cell| {
+        let mut parsers = cell.borrow_mut();
+        let parser = match parsers.entry(key) {
+            std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
+            std::collections::hash_map::Entry::Vacant(v) => v.insert(driver.make_parser(abs)?),
+        };
+        parser.reset();
+        parser
+            .parse(source_text, None)
+            .ok_or_else(|| anyhow!("Failed to parse file"))
+    })
+}
 
-    let mut symbols = driver.extract_skeleton(&abs, source, root, language.clone())?;
-    let mut imports = driver.find_imports(&abs, source, root, language.clone())?;
-    let mut exports = driver.find_exports(&abs, source, root, language)?;
+/// Everything `analyze_file` derives from a parsed tree; shared with [`Inspector`].
+fn analyze_tree(
+    path: &Path,
+    abs: &Path,
+    driver: &dyn LanguageDriver,
+    source: &[u8],
+    root: Node,
+    notebook: Option<&notebook::NotebookCode>,
+) -> Result<FileSymbols> {
+    let language = driver.language_for_path(abs);
+    let mut symbols = driver.extract_skeleton(abs, source, root, language.clone())?;
+    apply_query_pack(abs, driver.name(), source, root, &language, &mut symbols);
+    let mut imports = driver.find_imports(abs, source, root, language.clone())?;
+    let mut exports = driver.find_exports(abs, source, root, language)?;
+
+    annotate_calls(source, root, &mut symbols);
+    annotate_metrics(source, root, &mut symbols);
+    annotate_signature_flags(source, root, &mut symbols);
+    annotate_visibility(driver.name(), source, root, &mut symbols, &exports);
 
     // Stable ordering: by line then name.
     symbols.sort_by(|a, b| a.line.cmp(&b.line).then_with(|| a.name.cmp(&b.name)));
+    if let Some(code) = notebook {
+        for sym in &mut symbols {
+            sym.cell = code.cell_for_line(sym.line);
+        }
+    }
 
     imports = dedup_sorted(imports);
     exports = dedup_sorted(exports);
+    let markers = extract_markers(source, root);
+    fill_columns(source, &mut symbols);
 
     Ok(FileSymbols {
         file: normalize_path_for_output(path),
         imports,
         exports,
         symbols,
+        markers,
     })
 }
 
+/// A text replacement in a file held by an [`Inspector`]: bytes `start_byte..old_end_byte`
+/// of the current source become `new_text`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TextEdit {
+    pub start_byte: usize,
+    pub old_end_byte: usize,
+    pub new_text: String,
+}
+
+struct ParsedFile {
+    source: String,
+    tree: Tree,
+}
+
+/// Keeps parsed trees between calls so small edits are re-parsed incrementally (watch mode,
+/// the MCP daemon). Config outlines and notebooks have no single syntax tree to edit; use
+/// [`analyze_file`] for those.
+#[derive(Default)]
+pub struct Inspector {
+    files: HashMap<PathBuf, ParsedFile>,
+}
+
+impl Inspector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse `path` from disk (replacing any held tree) and analyse it.
+    pub fn open(&mut self, path: &Path) -> Result<FileSymbols> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        self.files.remove(path);
+        self.reparse(path, source, None)
+    }
+
+    /// Apply `edits` in order (each against the source left by the previous one), reparse
+    /// incrementally and analyse. Files not yet opened are opened first. All or nothing: on
+    /// an invalid edit or a failed parse, the held source and tree stay as they were.
+    pub fn update(&mut self, path: &Path, edits: &[TextEdit]) -> Result<FileSymbols> {
+        if !self.files.contains_key(path) {
+            self.open(path)?;
+        }
+        let file = &self.files[path];
+        let mut source = file.source.clone();
+        let mut tree = file.tree.clone();
+        for edit in edits {
+            let valid = edit.start_byte <= edit.old_end_byte
+                && edit.old_end_byte <= source.len()
+                && source.is_char_boundary(edit.start_byte)
+                && source.is_char_boundary(edit.old_end_byte);
+            if !valid {
+                return Err(anyhow!(
+                    "Invalid edit {}..{} for {} ({} bytes)",
+                    edit.start_byte,
+                    edit.old_end_byte,
+                    path.display(),
+                    source.len()
+                ));
+            }
+            let start_position = point_at(source.as_bytes(), edit.start_byte);
+            let old_end_position = point_at(source.as_bytes(), edit.old_end_byte);
+            source.replace_range(edit.start_byte..edit.old_end_byte, &edit.new_text);
+            let new_end_byte = edit.start_byte + edit.new_text.len();
+            tree.edit(&InputEdit {
+                start_byte: edit.start_byte,
+                old_end_byte: edit.old_end_byte,
+                new_end_byte,
+                start_position,
+                old_end_position,
+                new_end_position: point_at(source.as_bytes(), new_end_byte),
+            });
+        }
+        self.reparse(path, source, Some(tree))
+    }
+
+    /// Current source of an open file.
+    pub fn source(&self, path: &Path) -> Option<&str> {
+        self.files.get(path).map(|f| f.source.as_str())
+    }
+
+    /// Drop the held tree for `path`.
+    pub fn close(&mut self, path: &Path) {
+        self.files.remove(path);
+    }
+
+    /// Parse `source` (incrementally from `old_tree`, already edited to match) and analyse
+    /// it; the file's entry is only replaced once both succeed.
+    fn reparse(
+        &mut self,
+        path: &Path,
+        source: String,
+        old_tree: Option<Tree>,
+    ) -> Result<FileSymbols> {
+        if config_outline::is_config_outline_path(path) || notebook::is_notebook_path(path) {
+            return Err(anyhow!(
+                "{} has no incremental syntax tree; use analyze_file",
+                path.display()
+            ));
+        }
+        let cfg = language_config().read().unwrap();
+        let driver = cfg
+            .driver_for_path(path)
+            .ok_or_else(|| anyhow!("Unsupported file extension: {}", path.display()))?;
+        let mut parser = driver.make_parser(path)?;
+        let tree = parser
+            .parse(source.as_str(), old_tree.as_ref())
+            .ok_or_else(|| anyhow!("Failed to parse file"))?;
+
+        let out = analyze_tree(path, path, driver, source.as_bytes(), tree.root_node(), None)?;
+        self.files
+            .insert(path.to_path_buf(), ParsedFile { source, tree });
+        Ok(out)
+    }
+}
+
+/// Fill `column`/`column_end` (children included) from the symbols' byte offsets.
+fn fill_columns(source: &[u8], syms: &mut [Symbol]) {
+    for sym in syms {
+        sym.column = point_at(source, sym.start_byte).column as u32;
+        sym.column_end = point_at(source, sym.end_byte).column as u32;
+        fill_columns(source, &mut sym.children);
+    }
+}
+
+/// Row/column (in bytes) of `byte` in `source`.
+fn point_at(source: &[u8], byte: usize) -> Point {
+    let before = &source[..byte.min(source.len())];
+    let row = before.iter().filter(|&&b| b == b'\n').count();
+    let line_start = before
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1);
+    Point::new(row, byte - line_start)
+}
+
 /// Extract all top-level symbols from source text without a disk read.
 ///
 /// Used by the vector store for:
@@ -1774,6 +4059,10 @@ pub fn extract_symbols_from_source(path: &Path, source_text: &str) -> Vec<Symbol
             Err(_) => return vec![],
         }
     };
+    if let Some(mut symbols) = config_outline::outline_symbols(&abs, source_text) {
+        fill_columns(source_text.as_bytes(), &mut symbols);
+        return symbols;
+    }
 
     let cfg = language_config().read().unwrap();
     let Some(driver) = cfg.driver_for_path(&abs) else {
@@ -1793,13 +4082,14 @@ pub fn extract_symbols_from_source(path: &Path, source_text: &str) -> Vec<Symbol
 
     let root = tree.root_node();
 
-    match driver.extract_skeleton(&abs, source, root, language) {
-        Ok(mut syms) => {
-            syms.sort_by(|a, b| a.line.cmp(&b.line));
-            syms
-        }
-        Err(_) => vec![],
-    }
+    let mut syms = match driver.extract_skeleton(&abs, source, root, language.clone()) {
+        Ok(syms) => syms,
+        Err(_) => return vec![],
+    };
+    apply_query_pack(&abs, driver.name(), source, root, &language, &mut syms);
+    syms.sort_by(|a, b| a.line.cmp(&b.line));
+    fill_columns(source, &mut syms);
+    syms
 }
 
 // ---------------------------------------------------------------------------
@@ -1841,19 +4131,29 @@ pub fn read_symbol_with_options(
     if raw.contains(&0u8) {
         return Err(anyhow!("Binary file — cannot extract symbol"));
     }
-    let source_text = String::from_utf8_lossy(&raw).into_owned();
+    let mut source_text = String::from_utf8_lossy(&raw).into_owned();
+    // Notebook symbols are sliced from the code view that `analyze_file` reports lines for.
+    let parse_path = if notebook::is_notebook_path(&abs) {
+        source_text = notebook::extract_code_cells(&source_text)?.source;
+        notebook::code_view_path(&abs)
+    } else {
+        abs.clone()
+    };
 
     let cfg = language_config().read().unwrap();
-    let Some(driver) = cfg.driver_for_path(&abs) else {
+    let Some(driver) = cfg.driver_for_path(&parse_path) else {
         return Err(anyhow!(
             "Unsupported file type: {}",
-            abs.extension().and_then(|e| e.to_str()).unwrap_or("?")
+            parse_path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("?")
         ));
     };
-    let language = driver.language_for_path(&abs);
+    let language = driver.language_for_path(&parse_path);
     let source = source_text.as_bytes();
 
-    let mut parser = driver.make_parser(&abs)?;
+    let mut parser = driver.make_parser(&parse_path)?;
     let tree = parser
         .parse(&source_text, None)
         .ok_or_else(|| anyhow!("Tree-sitter parse failed for {}", abs.display()))?;
@@ -1864,7 +4164,7 @@ pub fn read_symbol_with_options(
     let mut candidates: Vec<(String, String, usize, usize)> = Vec::new(); // (name, kind, start, end)
 
     // Standard symbols from the driver (fn, struct, enum, trait, class, method…)
-    if let Ok(syms) = driver.extract_skeleton(&abs, source, root, language.clone()) {
+    if let Ok(syms) = driver.extract_skeleton(&parse_path, source, root, language.clone()) {
         for sym in &syms {
             let start = offsets.get(sym.line as usize).copied().unwrap_or(0);
             let end = if (sym.line_end as usize + 1) < offsets.len() {
@@ -1882,7 +4182,7 @@ pub fn read_symbol_with_options(
         candidates.extend(impl_blocks);
     }
 
-    // ── Step 2: find best match (exact → case-insensitive), collect ALL instances ──
+    // ── Step 2: find best match (exact → case-insensitive → qualified suffix), collect ALL instances ──
     let mut all_matches: Vec<&(String, String, usize, usize)> = candidates
         .iter()
         .filter(|(name, _, _, _)| name == symbol_name)
@@ -1895,6 +4195,16 @@ pub fn read_symbol_with_options(
             .collect();
     }
 
+    // Qualified names (`ns::Class::method`, `Class.method`) also match on their trailing segments.
+    if all_matches.is_empty() {
+        let path_suffix = format!("::{}", symbol_name.trim_start_matches("::"));
+        let dot_suffix = format!(".{}", symbol_name.trim_start_matches('.'));
+        all_matches = candidates
+            .iter()
+            .filter(|(name, _, _, _)| name.ends_with(&path_suffix) || name.ends_with(&dot_suffix))
+            .collect();
+    }
+
     let total_matches = all_matches.len();
 
     if total_matches == 0 {
@@ -1967,7 +4277,7 @@ Consider using find_usages to inspect all occurrences across the codebase.\n",
         // Reuse the same pruning logic as render_skeleton(), but apply only the
         // replacements that fall within this symbol's byte range.
         let mut ranges =
-            driver.body_prune_ranges(&abs, &source_text, source, root, language.clone())?;
+            driver.body_prune_ranges(&parse_path, &source_text, source, root, language.clone())?;
         ranges.retain(|(s, e, _)| {
             // Keep only ranges that overlap with the extracted symbol region.
             *e > *start_byte && *s < *end_byte
@@ -1984,7 +4294,7 @@ Consider using find_usages to inspect all occurrences across the codebase.\n",
             .collect::<Vec<_>>();
 
         let skeleton = apply_replacements(body, adjusted);
-        clean_skeleton_text(&abs, &skeleton)
+        clean_skeleton_text(&parse_path, &skeleton)
     } else {
         body.to_string()
     };
@@ -2029,7 +4339,7 @@ fn find_named_decls_raw(
     language: &Language,
     query_src: &str,
 ) -> Vec<(String, usize, usize)> {
-    let Ok(query) = Query::new(language, query_src) else {
+    let Ok(query) = cached_query(language, query_src) else {
         return vec![];
     };
     let mut cursor = QueryCursor::new();
@@ -2854,7 +5164,7 @@ pub fn find_implementations(target_dir: &Path, trait_or_interface: &str) -> Resu
                 ];
 
                 for qsrc in queries {
-                    let Ok(query) = Query::new(&language, qsrc) else {
+                    let Ok(query) = cached_query(&language, qsrc) else {
                         continue;
                     };
                     let mut cursor = QueryCursor::new();
@@ -3208,7 +5518,7 @@ fn extract_context_lines(lines: &[&str], target_0: usize, ctx: usize) -> String
 ///       [struct  ] User
 /// ```
 pub fn repo_map(target_dir: &Path) -> Result<String> {
-    repo_map_with_filter(target_dir, None, None, false, &[])
+    repo_map_with_filter(target_dir, None, None, false, &[], false)
 }
 
 pub fn repo_map_with_filter(
@@ -3217,6 +5527,7 @@ pub fn repo_map_with_filter(
     max_chars: Option<usize>,
     ignore_gitignore: bool,
     exclude_dirs: &[String],
+    exclude_tests: bool,
 ) -> Result<String> {
     use ignore::WalkBuilder;
     use std::collections::{BTreeMap, BTreeSet, HashSet};
@@ -3286,6 +5597,7 @@ pub fn repo_map_with_filter(
     let mut kept_source_files: usize = 0;
     let mut dropped_by_unsupported_lang: usize = 0;
     let mut dropped_by_search_filter: usize = 0;
+    let mut dropped_by_exclude_tests: usize = 0;
 
     let mut sample_dropped: Vec<String> = Vec::new();
     let mut sample_unsupported: Vec<String> = Vec::new();
@@ -3318,6 +5630,10 @@ pub fn repo_map_with_filter(
             Err(_) => continue,
         };
         let rel_path = rel_from_target.to_string_lossy().replace('\\', "/");
+        if exclude_tests && crate::scanner::is_test_path(&rel_path) {
+            dropped_by_exclude_tests += 1;
+            continue;
+        }
         filtered_paths.insert(rel_path.clone());
 
         let filename = path
@@ -3567,10 +5883,11 @@ Supported extensions include: rs, ts, tsx, js, jsx, py, go.",
 
     let dropped_total = dropped_by_gitignore_or_error
         .saturating_add(dropped_by_unsupported_lang)
-        .saturating_add(dropped_by_search_filter);
+        .saturating_add(dropped_by_search_filter)
+        .saturating_add(dropped_by_exclude_tests);
     push(&format!("{root_name}/   ({kept_source_files} files)\n"));
     push(&format!(
-        "> 📊 Scanned: {scanned_total} items | Kept Source Files: {kept_source_files} | Dropped: {dropped_total} (ignored/errors: {dropped_by_gitignore_or_error}, unsupported: {dropped_by_unsupported_lang}, filtered_out: {dropped_by_search_filter})\n"
+        "> 📊 Scanned: {scanned_total} items | Kept Source Files: {kept_source_files} | Dropped: {dropped_total} (ignored/errors: {dropped_by_gitignore_or_error}, unsupported: {dropped_by_unsupported_lang}, filtered_out: {dropped_by_search_filter}, tests: {dropped_by_exclude_tests})\n"
     ));
     if !sample_dropped.is_empty() {
         let joined = sample_dropped
@@ -4422,3 +6739,1288 @@ fn diagnostics_parse_tsc(stdout: &str, stderr: &str) -> Result<String> {
 
     Ok(out)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serializes tests that clear the global query cache.
+    fn cache_guard() -> std::sync::MutexGuard<'static, ()> {
+        static GUARD: Mutex<()> = Mutex::new(());
+        GUARD.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn clear_query_cache() {
+        query_cache()
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    fn symbol_kinds(out: &FileSymbols) -> Vec<(&str, &str)> {
+        out.symbols
+            .iter()
+            .map(|s| (s.name.as_str(), s.kind.as_str()))
+            .collect()
+    }
+
+    /// Golden `analyze_file` output for a fixture per built-in language, checked against a
+    /// cold and a warm query cache.
+    #[test]
+    fn analyze_output_is_pinned_per_language() {
+        let _g = cache_guard();
+        let tmp = tempfile::tempdir().unwrap();
+        let cases = [
+            (
+                "lib.rs",
+                concat!(
+                    "use std::collections::HashMap;\n",
+                    "\n",
+                    "/// A keyed store.\n",
+                    "#[derive(Debug)]\n",
+                    "pub struct Store {\n",
+                    "    items: HashMap<String, u32>,\n",
+                    "}\n",
+                    "\n",
+                    "impl Store {\n",
+                    "    pub fn get(&self, key: &str) -> Option<u32> {\n",
+                    "        self.items.get(key).copied()\n",
+                    "    }\n",
+                    "}\n",
+                    "\n",
+                    "fn helper() {}\n",
+                ),
+                serde_json::json!({
+                    "imports": ["std::collections::HashMap"], "exports": ["Store", "get"],
+                    "symbols": [
+                        {
+                            "name": "Store", "kind": "struct", "line": 4, "line_end": 6,
+                            "start_byte": 68, "end_byte": 121, "column": 0, "column_end": 1,
+                            "derives": ["Debug"],
+                            "children": [
+                                {
+                                    "name": "items", "kind": "field", "line": 5, "line_end": 5,
+                                    "start_byte": 91, "end_byte": 118, "column": 4,
+                                    "column_end": 31,
+                                    "signature": "items: HashMap<String, u32>",
+                                    "visibility": "private"
+                                }
+                            ],
+                            "visibility": "pub"
+                        },
+                        {
+                            "name": "Store::get", "kind": "method", "line": 9, "line_end": 11,
+                            "start_byte": 140, "end_byte": 228, "column": 4, "column_end": 5,
+                            "signature": "pub fn get(&self, key: &str) -> Option<u32>",
+                            "calls": ["copied", "self.items.get"], "parent": "Store",
+                            "visibility": "pub",
+                            "metrics": {"loc": 3, "branches": 0, "complexity": 1}
+                        },
+                        {
+                            "name": "helper", "kind": "function", "line": 14, "line_end": 14,
+                            "start_byte": 232, "end_byte": 246, "column": 0, "column_end": 14,
+                            "signature": "fn helper()", "visibility": "private",
+                            "metrics": {"loc": 1, "branches": 0, "complexity": 1}
+                        }
+                    ]
+                }),
+            ),
+            (
+                "api.ts",
+                concat!(
+                    "import { fetchJson } from './http';\n",
+                    "\n",
+                    "export interface User {\n",
+                    "  id: string;\n",
+                    "}\n",
+                    "\n",
+                    "export async function loadUser(id: string): Promise<User> {\n",
+                    "  return fetchJson(`/users/${id}`);\n",
+                    "}\n",
+                    "\n",
+                    "class Cache {}\n",
+                ),
+                serde_json::json!({
+                    "imports": ["./http"], "exports": ["User", "loadUser"],
+                    "symbols": [
+                        {
+                            "name": "User", "kind": "interface", "line": 2, "line_end": 4,
+                            "start_byte": 44, "end_byte": 76, "column": 7, "column_end": 1,
+                            "signature": "interface User",
+                            "children": [
+                                {
+                                    "name": "id", "kind": "property", "line": 3, "line_end": 3,
+                                    "start_byte": 63, "end_byte": 73, "column": 2,
+                                    "column_end": 12, "signature": "id: string",
+                                    "visibility": "public"
+                                }
+                            ],
+                            "visibility": "exported"
+                        },
+                        {
+                            "name": "loadUser", "kind": "function", "line": 6, "line_end": 8,
+                            "start_byte": 85, "end_byte": 175, "column": 7, "column_end": 1,
+                            "signature": "async function loadUser(id: string): Promise<User>",
+                            "calls": ["fetchJson"], "visibility": "exported", "is_async": true,
+                            "metrics": {"loc": 3, "branches": 0, "complexity": 1}
+                        },
+                        {
+                            "name": "Cache", "kind": "class", "line": 10, "line_end": 10,
+                            "start_byte": 177, "end_byte": 191, "column": 0, "column_end": 14,
+                            "visibility": "internal"
+                        }
+                    ]
+                }),
+            ),
+            (
+                "util.js",
+                concat!(
+                    "const path = require('path');\n",
+                    "\n",
+                    "function join(a, b) {\n",
+                    "  return path.join(a, b);\n",
+                    "}\n",
+                    "\n",
+                    "module.exports = { join };\n",
+                ),
+                serde_json::json!({
+                    "imports": ["path"], "exports": ["join"],
+                    "symbols": [
+                        {
+                            "name": "path", "kind": "const", "line": 0, "line_end": 0,
+                            "start_byte": 0, "end_byte": 29, "column": 0, "column_end": 29,
+                            "signature": "const path = require('path');",
+                            "visibility": "internal"
+                        },
+                        {
+                            "name": "join", "kind": "function", "line": 2, "line_end": 4,
+                            "start_byte": 31, "end_byte": 80, "column": 0, "column_end": 1,
+                            "signature": "function join(a, b)", "calls": ["path.join"],
+                            "visibility": "exported",
+                            "metrics": {"loc": 3, "branches": 0, "complexity": 1}
+                        }
+                    ]
+                }),
+            ),
+            (
+                "app.py",
+                concat!(
+                    "import os\n",
+                    "from typing import Optional\n",
+                    "\n",
+                    "MAX_USERS = 10\n",
+                    "\n",
+                    "\n",
+                    "class Repo:\n",
+                    "    def find(self, name: str) -> Optional[str]:\n",
+                    "        return os.environ.get(name)\n",
+                    "\n",
+                    "\n",
+                    "def main():\n",
+                    "    Repo().find(\"HOME\")\n",
+                ),
+                serde_json::json!({
+                    "imports": ["os", "typing"], "exports": ["MAX_USERS", "Repo", "main"],
+                    "symbols": [
+                        {
+                            "name": "MAX_USERS", "kind": "const", "line": 3, "line_end": 3,
+                            "start_byte": 39, "end_byte": 53, "column": 0, "column_end": 14,
+                            "signature": "MAX_USERS = 10", "visibility": "exported"
+                        },
+                        {
+                            "name": "Repo", "kind": "class", "line": 6, "line_end": 8,
+                            "start_byte": 56, "end_byte": 151, "column": 0, "column_end": 35,
+                            "visibility": "exported"
+                        },
+                        {
+                            "name": "Repo.find", "kind": "method", "line": 7, "line_end": 8,
+                            "start_byte": 72, "end_byte": 151, "column": 4, "column_end": 35,
+                            "signature": "def find(self, name: str) -> Optional[str]:",
+                            "calls": ["os.environ.get"], "parent": "Repo",
+                            "visibility": "public",
+                            "metrics": {"loc": 2, "branches": 0, "complexity": 1}
+                        },
+                        {
+                            "name": "main", "kind": "function", "line": 11, "line_end": 12,
+                            "start_byte": 154, "end_byte": 189, "column": 0, "column_end": 23,
+                            "signature": "def main():", "calls": ["find", "Repo"],
+                            "visibility": "exported",
+                            "metrics": {"loc": 2, "branches": 0, "complexity": 1}
+                        }
+                    ]
+                }),
+            ),
+        ];
+        for (name, source, expected) in cases {
+            let path = tmp.path().join(name);
+            std::fs::write(&path, source).unwrap();
+            clear_query_cache();
+            for _ in 0..2 {
+                let mut out = serde_json::to_value(analyze_file(&path).unwrap()).unwrap();
+                out.as_object_mut().unwrap().remove("file");
+                assert_eq!(out, expected, "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn repeated_inspection_reuses_compiled_queries() {
+        let _g = cache_guard();
+        let rust: Language = tree_sitter_rust::language();
+        let q = "(function_item name: (identifier) @name)";
+        let first = cached_query(&rust, q).unwrap();
+        assert!(Arc::ptr_eq(&first, &cached_query(&rust, q).unwrap()));
+
+        // Every query a Rust inspection compiled is handed out again, not recompiled.
+        let rust_queries = || -> Vec<(String, Arc<Query>)> {
+            query_cache().read().unwrap()[&rust]
+                .iter()
+                .filter_map(|(src, q)| Some((src.clone(), q.clone().ok()?)))
+                .collect()
+        };
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/lib.rs");
+        clear_query_cache();
+        analyze_file(&path).unwrap();
+        let cold = rust_queries();
+        assert!(!cold.is_empty());
+        analyze_file(&path).unwrap();
+        for (src, query) in cold {
+            let warm = cached_query(&rust, &src).unwrap();
+            assert!(Arc::ptr_eq(&query, &warm), "recompiled {src}");
+        }
+    }
+
+    #[test]
+    fn strip_template_args_keeps_operators() {
+        assert_eq!(strip_template_args("Box<T>::set"), "Box::set");
+        assert_eq!(strip_template_args("Map<K, std::vector<V>>::get"), "Map::get");
+        assert_eq!(strip_template_args("operator<<"), "operator<<");
+        assert_eq!(strip_template_args("Foo::operator<=>"), "Foo::operator<=>");
+        assert_eq!(strip_template_args("ns :: f"), "ns::f");
+    }
+
+    #[test]
+    fn csharp_using_target_strips_modifiers_and_aliases() {
+        for (directive, target) in [
+            ("using System.Text;", "System.Text"),
+            ("using static System.Math;", "System.Math"),
+            ("global using Xunit;", "Xunit"),
+            ("using Json = Newtonsoft.Json;", "Newtonsoft.Json"),
+        ] {
+            assert_eq!(csharp_using_target(directive).as_deref(), Some(target));
+        }
+    }
+
+    #[test]
+    fn php_use_and_include_targets() {
+        assert_eq!(php_use_targets(r"use App\Models\User;"), vec![r"App\Models\User"]);
+        assert_eq!(
+            php_use_targets(r"use App\Models\{Post, Comment as C};"),
+            vec![r"App\Models\Post", r"App\Models\Comment"]
+        );
+        assert_eq!(
+            php_use_targets(r"use function \App\helpers\fmt;"),
+            vec![r"App\helpers\fmt"]
+        );
+        assert_eq!(
+            php_include_target("require_once __DIR__ . '/bootstrap.php'").as_deref(),
+            Some("./bootstrap.php")
+        );
+        assert_eq!(
+            php_include_target(r#"include_once("config.php")"#).as_deref(),
+            Some("config.php")
+        );
+    }
+
+    #[test]
+    fn c_types_prototypes_and_includes() {
+        if !wasm_grammar("c") {
+            return;
+        }
+        let tmp = tempfile::tempdir().unwrap();
+        let c = tmp.path().join("shapes.c");
+        std::fs::write(
+            &c,
+            "#include <stdio.h>\n#include \"util.h\"\n\n\
+             enum Color { RED, GREEN };\n\
+             union Value { int i; float f; };\n\
+             typedef struct Point { int x; int y; } Point;\n\
+             typedef unsigned long size_type;\n\n\
+             int add(int a, int b);\n\
+             char *name_of(enum Color c);\n\n\
+             int add(int a, int b) {\n    return a + b;\n}\n",
+        )
+        .unwrap();
+
+        let out = analyze_file(&c).unwrap();
+        // Project headers lose their quotes; system headers keep their brackets.
+        assert_eq!(out.imports, vec!["<stdio.h>", "util.h"]);
+        let kinds = symbol_kinds(&out);
+        for expected in [
+            ("Color", "enum"),
+            ("Value", "struct"),
+            ("Point", "struct"),
+            ("Point", "type"),
+            ("size_type", "type"),
+            ("name_of", "function"),
+        ] {
+            assert!(kinds.contains(&expected), "{expected:?} in {kinds:?}");
+        }
+        // The prototype and the definition; `enum Color c` is a use, not a definition.
+        assert_eq!(kinds.iter().filter(|k| **k == ("add", "function")).count(), 2);
+        assert_eq!(kinds.iter().filter(|(n, _)| *n == "Color").count(), 1);
+    }
+
+    #[test]
+    fn kotlin_import_targets_drop_aliases() {
+        assert_eq!(
+            kotlin_import_target("import kotlin.math.max").as_deref(),
+            Some("kotlin.math.max")
+        );
+        assert_eq!(
+            kotlin_import_target("import app.util.*").as_deref(),
+            Some("app.util.*")
+        );
+        assert_eq!(
+            kotlin_import_target("import app.model.User as Member").as_deref(),
+            Some("app.model.User")
+        );
+        assert_eq!(kotlin_import_target("import"), None);
+    }
+
+    #[test]
+    fn shell_source_paths_and_functions() {
+        if !wasm_grammar("bash") {
+            return;
+        }
+        let tmp = tempfile::tempdir().unwrap();
+        let script = tmp.path().join("deploy.sh");
+        std::fs::write(
+            &script,
+            "#!/usr/bin/env bash\n\
+             source ./lib/common.sh\n\
+             . \"config/env.sh\"\n\
+             source 'lib/retry.sh'\n\
+             source \"$HOME/.deployrc\"\n\
+             . `pwd`/local.sh\n\
+             echo source not-an-import.sh\n\
+             deploy() {\n  echo deploying\n}\n\
+             function rollback {\n  echo rolling back\n}\n",
+        )
+        .unwrap();
+        let out = analyze_file(&script).unwrap();
+        for expected in ["./lib/common.sh", "config/env.sh", "lib/retry.sh"] {
+            assert!(out.imports.iter().any(|i| i == expected), "{expected} in {:?}", out.imports);
+        }
+        // Paths built from variables or command substitution are kept as written; the
+        // mapper declines to resolve them.
+        assert!(out.imports.iter().any(|i| i == "$HOME/.deployrc"), "{:?}", out.imports);
+        assert!(out.imports.iter().any(|i| i == "`pwd`/local.sh"), "{:?}", out.imports);
+        assert!(!out.imports.iter().any(|i| i.contains("not-an-import")));
+        let kinds = symbol_kinds(&out);
+        assert!(kinds.contains(&("deploy", "function")), "{kinds:?}");
+        assert!(kinds.contains(&("rollback", "function")), "{kinds:?}");
+    }
+
+    #[test]
+    fn kotlin_classes_objects_and_extension_functions() {
+        if !wasm_grammar("kotlin") {
+            return;
+        }
+        let tmp = tempfile::tempdir().unwrap();
+        let kt = tmp.path().join("Items.kt");
+        std::fs::write(
+            &kt,
+            "package app\n\n\
+             import kotlin.math.max\nimport app.util.*\nimport app.model.User as Member\n\n\
+             interface Repo\n\
+             data class Item(val id: Int)\n\
+             enum class Color { RED }\n\
+             object Registry\n\n\
+             fun load(): Item = Item(max(1, 2))\n\
+             fun String.shout(): String = uppercase()\n\
+             typealias Items = List<Item>\n",
+        )
+        .unwrap();
+
+        let out = analyze_file(&kt).unwrap();
+        assert_eq!(
+            out.imports,
+            vec!["app.model.User", "app.util.*", "kotlin.math.max"]
+        );
+        let kinds = symbol_kinds(&out);
+        for expected in [
+            ("Repo", "interface"),
+            ("Item", "class"),
+            ("Color", "enum"),
+            ("Registry", "object"),
+            ("load", "function"),
+            ("shout", "extension"),
+            ("Items", "type"),
+        ] {
+            assert!(kinds.contains(&expected), "{expected:?} in {kinds:?}");
+        }
+        // An extension function is listed once, not also as a plain function.
+        assert!(!kinds.contains(&("shout", "function")));
+    }
+
+    #[test]
+    fn graphql_schema_types_and_named_operations() {
+        if !wasm_grammar("graphql") {
+            return;
+        }
+        let tmp = tempfile::tempdir().unwrap();
+        let schema = tmp.path().join("schema.graphql");
+        std::fs::write(
+            &schema,
+            "scalar DateTime\n\
+             directive @auth(role: String) on FIELD_DEFINITION\n\
+             interface Node { id: ID! }\n\
+             enum Role { ADMIN GUEST }\n\
+             type User implements Node { id: ID! role: Role }\n\
+             type Team { name: String }\n\
+             union Member = User | Team\n\
+             input NewUser { role: Role }\n\
+             extend type Team { size: Int }\n",
+        )
+        .unwrap();
+        let out = analyze_file(&schema).unwrap();
+        assert!(out.imports.is_empty());
+        let kinds = symbol_kinds(&out);
+        for expected in [
+            ("DateTime", "scalar"),
+            ("auth", "directive"),
+            ("Node", "interface"),
+            ("Role", "enum"),
+            ("User", "type"),
+            ("Member", "union"),
+            ("NewUser", "input"),
+        ] {
+            assert!(kinds.contains(&expected), "{expected:?} in {kinds:?}");
+        }
+        // The type and its extension are both listed.
+        assert_eq!(kinds.iter().filter(|(n, _)| *n == "Team").count(), 2);
+
+        let document = tmp.path().join("user.gql");
+        std::fs::write(
+            &document,
+            "fragment UserFields on User { id role }\n\n\
+             query GetUser($id: ID!) { user(id: $id) { ...UserFields } }\n\n\
+             mutation AddUser($input: NewUser!) { addUser(input: $input) { id } }\n\n\
+             { anonymous }\n",
+        )
+        .unwrap();
+        let out = analyze_file(&document).unwrap();
+        // Anonymous operations have no name to list.
+        assert_eq!(
+            symbol_kinds(&out),
+            vec![
+                ("UserFields", "fragment"),
+                ("GetUser", "query"),
+                ("AddUser", "mutation")
+            ]
+        );
+    }
+
+    #[test]
+    fn scala_import_targets_expand_selectors() {
+        assert_eq!(
+            scala_import_targets("import cats.effect.{IO, Resource => Res}"),
+            vec!["cats.effect.IO", "cats.effect.Resource"]
+        );
+        assert_eq!(scala_import_targets("import a.b._, c.D"), vec!["a.b._", "c.D"]);
+    }
+
+    #[test]
+    fn elixir_import_targets_expand_multi_alias() {
+        assert_eq!(
+            elixir_import_targets("MyApp.{Accounts, Billing}"),
+            vec!["MyApp.Accounts", "MyApp.Billing"]
+        );
+        assert_eq!(
+            elixir_import_targets("Ecto.Changeset, only: [cast: 3]"),
+            vec!["Ecto.Changeset"]
+        );
+    }
+
+    #[test]
+    fn sql_procedures_span_until_next_statement() {
+        let src = "CREATE TABLE t (id INT);\n\nCREATE OR REPLACE PROCEDURE app.cleanup()\nLANGUAGE sql AS $$\n  DELETE FROM t;\n$$;\n\ncreate view v as select 1;\n";
+        let procs = sql_procedures(src.as_bytes());
+        assert_eq!(procs.len(), 1);
+        assert_eq!(procs[0].name, "app.cleanup");
+        assert_eq!((procs[0].line, procs[0].line_end), (2, 5));
+        assert!(src[procs[0].start_byte..procs[0].end_byte].ends_with("$$;\n"));
+    }
+
+    #[test]
+    fn notebook_symbols_map_back_to_cells() {
+        let tmp = tempfile::tempdir().unwrap();
+        let nb = tmp.path().join("analysis.ipynb");
+        std::fs::write(
+            &nb,
+            r#"{"cells": [
+                {"cell_type": "code", "source": ["import pandas as pd\n"]},
+                {"cell_type": "markdown", "source": ["Helpers"]},
+                {"cell_type": "code", "source": ["!pip install x\n", "def load(path):\n", "    return pd.read_csv(path)\n"]}
+            ]}"#,
+        )
+        .unwrap();
+
+        let out = analyze_file(&nb).unwrap();
+        assert!(out.file.ends_with("analysis.ipynb"));
+        assert!(out.imports.iter().any(|i| i.contains("pandas")));
+        let load = out.symbols.iter().find(|s| s.name == "load").unwrap();
+        assert_eq!(load.cell, Some(2));
+
+        let body = read_symbol(&nb, "load").unwrap();
+        assert!(body.contains("pd.read_csv"));
+    }
+
+    #[test]
+    fn rust_macros_and_derives_are_reported() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("lib.rs");
+        std::fs::write(
+            &file,
+            "#[macro_export]\nmacro_rules! square {\n    ($x:expr) => { $x * $x };\n}\n\n\
+             /// A point.\n#[derive(Debug, Clone)]\n#[derive(serde::Serialize)]\n#[serde(rename_all = \"camelCase\")]\n\
+             pub struct Point { x: i32 }\n\n#[tokio::main]\nasync fn main() {}\n",
+        )
+        .unwrap();
+
+        let out = analyze_file(&file).unwrap();
+        let square = out.symbols.iter().find(|s| s.name == "square").unwrap();
+        assert_eq!(square.kind, "macro");
+        assert!(out.exports.contains(&"square".to_string()));
+
+        let point = out.symbols.iter().find(|s| s.name == "Point").unwrap();
+        assert_eq!(point.derives, vec!["Debug", "Clone", "serde::Serialize"]);
+        assert_eq!(point.attributes, vec!["serde"]);
+        let main = out.symbols.iter().find(|s| s.name == "main").unwrap();
+        assert_eq!(main.attributes, vec!["tokio::main"]);
+
+        assert!(read_symbol(&file, "square").unwrap().contains("$x * $x"));
+    }
+
+    #[test]
+    fn data_and_type_definitions_are_symbols_and_exports() {
+        let tmp = tempfile::tempdir().unwrap();
+        let kinds = |out: &FileSymbols| -> Vec<(String, String)> {
+            out.symbols
+                .iter()
+                .map(|s| (s.name.clone(), s.kind.clone()))
+                .collect()
+        };
+
+        let rs = tmp.path().join("consts.rs");
+        std::fs::write(
+            &rs,
+            "pub const MAX: usize = 4;\nstatic COUNTER: u32 = 0;\npub type Id = u64;\n",
+        )
+        .unwrap();
+        let out = analyze_file(&rs).unwrap();
+        assert!(kinds(&out).contains(&("COUNTER".into(), "static".into())));
+        assert_eq!(out.exports, vec!["Id", "MAX"]);
+
+        let ts = tmp.path().join("types.ts");
+        std::fs::write(
+            &ts,
+            "export interface User { id: string }\ntype Id = string;\nexport enum Role { Admin, Guest }\n",
+        )
+        .unwrap();
+        let out = analyze_file(&ts).unwrap();
+        let k = kinds(&out);
+        assert!(k.contains(&("User".into(), "interface".into())));
+        assert!(k.contains(&("Id".into(), "type".into())));
+        assert!(k.contains(&("Role".into(), "enum".into())));
+        assert_eq!(out.exports, vec!["Role", "User"]);
+
+        let py = tmp.path().join("settings.py");
+        std::fs::write(
+            &py,
+            "MAX_RETRIES = 3\nAPI_URL: str = \"x\"\n_PRIVATE = 1\nlogger = None\n\ndef get(): pass\n",
+        )
+        .unwrap();
+        let out = analyze_file(&py).unwrap();
+        let k = kinds(&out);
+        assert!(k.contains(&("MAX_RETRIES".into(), "const".into())));
+        assert!(k.contains(&("API_URL".into(), "const".into())));
+        assert!(!k.iter().any(|(n, _)| n == "logger"));
+        assert_eq!(out.exports, vec!["API_URL", "MAX_RETRIES", "get"]);
+
+        std::fs::write(&py, "__all__ = [\"get\"]\nMAX = 1\ndef get(): pass\n").unwrap();
+        assert_eq!(analyze_file(&py).unwrap().exports, vec!["get"]);
+    }
+
+    #[test]
+    fn rust_exports_are_the_pub_items() {
+        let tmp = tempfile::tempdir().unwrap();
+        let rs = tmp.path().join("items.rs");
+        std::fs::write(
+            &rs,
+            "pub fn open() {}\nfn close() {}\npub struct Conn;\nstruct Pool;\n\
+             pub enum Mode { A }\nenum Hidden { B }\npub trait Driver {}\ntrait Inner {}\n\
+             pub(crate) fn shared() {}\n",
+        )
+        .unwrap();
+        let out = analyze_file(&rs).unwrap();
+        assert_eq!(out.exports, vec!["Conn", "Driver", "Mode", "open", "shared"]);
+    }
+
+    #[test]
+    fn fields_variants_and_members_nest_under_their_parent() {
+        let tmp = tempfile::tempdir().unwrap();
+        let child_names = |out: &FileSymbols, parent: &str| -> Vec<(String, String)> {
+            out.symbols
+                .iter()
+                .find(|s| s.name == parent)
+                .unwrap()
+                .children
+                .iter()
+                .map(|c| (c.name.clone(), c.kind.clone()))
+                .collect()
+        };
+
+        let rs = tmp.path().join("config.rs");
+        std::fs::write(
+            &rs,
+            "pub struct Config {\n    pub name: String,\n    #[serde(default)]\n    retries: u32,\n}\n\
+             enum Mode { Fast, Slow { delay: u64 } }\n",
+        )
+        .unwrap();
+        let out = analyze_file(&rs).unwrap();
+        assert_eq!(
+            child_names(&out, "Config"),
+            vec![
+                ("name".into(), "field".into()),
+                ("retries".into(), "field".into())
+            ]
+        );
+        let config = out.symbols.iter().find(|s| s.name == "Config").unwrap();
+        assert_eq!(
+            config.children[0].signature.as_deref(),
+            Some("pub name: String")
+        );
+        assert_eq!(config.children[1].attributes, vec!["serde"]);
+        assert_eq!(
+            child_names(&out, "Mode"),
+            vec![
+                ("Fast".into(), "variant".into()),
+                ("Slow".into(), "variant".into()),
+                ("delay".into(), "field".into())
+            ]
+        );
+
+        let ts = tmp.path().join("api.ts");
+        std::fs::write(
+            &ts,
+            "interface Api { url: string; fetch(id: string): Promise<void>; }\n\
+             enum Color { Red, Green = 2 }\nfunction f(o: { x: number }) {}\n",
+        )
+        .unwrap();
+        let out = analyze_file(&ts).unwrap();
+        assert_eq!(
+            child_names(&out, "Api"),
+            vec![
+                ("url".into(), "property".into()),
+                ("fetch".into(), "method".into())
+            ]
+        );
+        assert_eq!(
+            child_names(&out, "Color"),
+            vec![
+                ("Red".into(), "variant".into()),
+                ("Green".into(), "variant".into())
+            ]
+        );
+        assert!(child_names(&out, "f").is_empty());
+    }
+
+    #[test]
+    fn tsx_components_are_classified_with_props() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("ui.tsx");
+        std::fs::write(
+            &file,
+            r#"import React from "react";
+export function Button({ label }: ButtonProps) {
+  return <button>{label}</button>;
+}
+export const Card: React.FC<CardProps> = ({ title }) => <div>{title}</div>;
+const Input = React.forwardRef<HTMLInputElement, InputProps>((props, ref) => <input ref={ref} />);
+export default function App() {
+  return <><Button label="x" /></>;
+}
+class Legacy extends React.Component<LegacyProps> {
+  render() { return <span />; }
+}
+function formatDate(d: Date) { return d.toISOString(); }
+const Theme = { dark: true };
+"#,
+        )
+        .unwrap();
+
+        let out = analyze_file(&file).unwrap();
+        let get = |name: &str| out.symbols.iter().find(|s| s.name == name).unwrap();
+        for (name, sig) in [
+            ("Button", "Button(props: ButtonProps)"),
+            ("Card", "Card(props: CardProps)"),
+            ("Input", "Input(props: InputProps)"),
+            ("App", "App()"),
+            ("Legacy", "Legacy(props: LegacyProps)"),
+        ] {
+            assert_eq!(get(name).kind, "component", "{name}");
+            assert_eq!(get(name).signature.as_deref(), Some(sig), "{name}");
+        }
+        assert_eq!(get("formatDate").kind, "function");
+        assert_eq!(get("Theme").kind, "const");
+    }
+
+    #[test]
+    fn commonjs_require_and_exports() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("legacy.js");
+        std::fs::write(
+            &file,
+            r#"const fs = require("fs");
+const { helper } = require('./lib/helper');
+const dynamic = require(name);
+exports.parse = function parse() {};
+module.exports.format = format;
+module.exports = { run, stop: halt, "quoted": 1, start() {} };
+"#,
+        )
+        .unwrap();
+
+        let out = analyze_file(&file).unwrap();
+        assert_eq!(out.imports, vec!["./lib/helper", "fs"]);
+        assert_eq!(
+            out.exports,
+            vec!["format", "parse", "quoted", "run", "start", "stop"]
+        );
+
+        std::fs::write(&file, "class Store {}\nmodule.exports = Store;\n").unwrap();
+        assert_eq!(analyze_file(&file).unwrap().exports, vec!["Store"]);
+    }
+
+    #[test]
+    fn dynamic_imports_are_captured() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("routes.tsx");
+        std::fs::write(
+            &file,
+            r#"import React from "react";
+const Settings = React.lazy(() => import("./pages/Settings"));
+const Admin = lazy(() => import(`./pages/Admin`));
+async function load(locale: string) {
+  await import("./polyfills");
+  return import(`./locales/${locale}.json`);
+}
+"#,
+        )
+        .unwrap();
+
+        let out = analyze_file(&file).unwrap();
+        assert_eq!(
+            out.imports,
+            vec!["./pages/Admin", "./pages/Settings", "./polyfills", "react"]
+        );
+    }
+
+    #[test]
+    fn outgoing_calls_are_recorded_per_function() {
+        let tmp = tempfile::tempdir().unwrap();
+        let rs = tmp.path().join("calls.rs");
+        std::fs::write(
+            &rs,
+            "fn run(cfg: &Config) -> Result<()> {\n    let text = std::fs::read_to_string(&cfg.path)?;\n    \
+             let n = text.trim().parse::<u32>()?;\n    helper(n);\n    helper(n + 1);\n    \
+             cfg.store.save(n);\n    Ok(())\n}\n\nstruct Config { path: String }\n",
+        )
+        .unwrap();
+        let out = analyze_file(&rs).unwrap();
+        let run = out.symbols.iter().find(|s| s.name == "run").unwrap();
+        assert_eq!(
+            run.calls,
+            vec![
+                "std::fs::read_to_string",
+                "parse",
+                "text.trim",
+                "helper",
+                "cfg.store.save",
+                "Ok"
+            ]
+        );
+        let config = out.symbols.iter().find(|s| s.name == "Config").unwrap();
+        assert!(config.calls.is_empty());
+
+        let py = tmp.path().join("calls.py");
+        std::fs::write(
+            &py,
+            "class Job:\n    def run(self):\n        self.prepare()\n        print(len(self.items))\n",
+        )
+        .unwrap();
+        let out = analyze_file(&py).unwrap();
+        let run = out.symbols.iter().find(|s| s.name == "Job.run").unwrap();
+        assert_eq!(run.calls, vec!["self.prepare", "print", "len"]);
+    }
+
+    #[test]
+    fn nested_symbols_get_qualified_names_and_parents() {
+        let tmp = tempfile::tempdir().unwrap();
+        let names = |out: &FileSymbols| -> Vec<(String, String, Option<String>)> {
+            out.symbols
+                .iter()
+                .map(|s| (s.name.clone(), s.kind.clone(), s.parent.clone()))
+                .collect()
+        };
+
+        let rs = tmp.path().join("net.rs");
+        std::fs::write(
+            &rs,
+            "mod net {\n    pub struct Client;\n    impl<T> Client {\n        pub fn send(&self) {}\n    }\n}\n\
+             trait Codec {\n    fn encode(&self) {}\n}\nfn main() {}\n",
+        )
+        .unwrap();
+        let out = analyze_file(&rs).unwrap();
+        let n = names(&out);
+        assert!(n.contains(&("net::Client".into(), "struct".into(), Some("net".into()))));
+        assert!(n.contains(&(
+            "net::Client::send".into(),
+            "method".into(),
+            Some("net::Client".into())
+        )));
+        assert!(n.contains(&("Codec::encode".into(), "method".into(), Some("Codec".into()))));
+        assert!(n.contains(&("main".into(), "function".into(), None)));
+        assert!(read_symbol(&rs, "send").unwrap().contains("pub fn send"));
+
+        let ts = tmp.path().join("api.ts");
+        std::fs::write(
+            &ts,
+            "namespace Api {\n  export class Client {\n    send() {}\n  }\n}\n",
+        )
+        .unwrap();
+        let n = names(&analyze_file(&ts).unwrap());
+        assert!(n.contains(&("Api.Client".into(), "class".into(), Some("Api".into()))));
+        assert!(n.contains(&(
+            "Api.Client.send".into(),
+            "method".into(),
+            Some("Api.Client".into())
+        )));
+
+        let py = tmp.path().join("jobs.py");
+        std::fs::write(
+            &py,
+            "class Job:\n    class Config:\n        pass\n    def run(self):\n        def step():\n            pass\n",
+        )
+        .unwrap();
+        let n = names(&analyze_file(&py).unwrap());
+        assert!(n.contains(&("Job.Config".into(), "class".into(), Some("Job".into()))));
+        assert!(n.contains(&("Job.run".into(), "method".into(), Some("Job".into()))));
+        assert!(n.contains(&(
+            "Job.run.step".into(),
+            "function".into(),
+            Some("Job.run".into())
+        )));
+        assert!(read_symbol(&py, "run").unwrap().contains("def run"));
+    }
+
+    #[test]
+    fn query_packs_extend_and_override_builtin_symbols() {
+        let tmp = tempfile::tempdir().unwrap();
+        let packs = tmp.path().join(".context-slicer/queries");
+        std::fs::create_dir_all(packs.join("typescript")).unwrap();
+        std::fs::create_dir_all(packs.join("python")).unwrap();
+        std::fs::write(
+            packs.join("typescript/symbols.scm"),
+            r#"(class_declaration
+                 decorator: (decorator (call_expression function: (identifier) @dec))
+                 name: (type_identifier) @name
+                 (#eq? @dec "Injectable")) @definition.provider"#,
+        )
+        .unwrap();
+        std::fs::write(
+            packs.join("python/symbols.scm"),
+            "; mode: override\n(class_definition name: (identifier) @name) @definition.model\n",
+        )
+        .unwrap();
+
+        let src_dir = tmp.path().join("src");
+        std::fs::create_dir_all(&src_dir).unwrap();
+        let ts = src_dir.join("users.service.ts");
+        std::fs::write(
+            &ts,
+            "@Injectable()\nclass UsersService {\n  find() {}\n}\nclass Plain {}\n",
+        )
+        .unwrap();
+        let out = analyze_file(&ts).unwrap();
+        let kinds: Vec<(&str, &str)> = out
+            .symbols
+            .iter()
+            .map(|s| (s.name.as_str(), s.kind.as_str()))
+            .collect();
+        assert!(kinds.contains(&("UsersService", "provider")));
+        assert!(!kinds.contains(&("UsersService", "class")));
+        assert!(kinds.contains(&("Plain", "class")));
+        assert!(kinds.contains(&("UsersService.find", "method")));
+
+        let py = src_dir.join("models.py");
+        std::fs::write(&py, "class User:\n    pass\n\ndef helper():\n    pass\n").unwrap();
+        let out = analyze_file(&py).unwrap();
+        let kinds: Vec<(&str, &str)> = out
+            .symbols
+            .iter()
+            .map(|s| (s.name.as_str(), s.kind.as_str()))
+            .collect();
+        assert_eq!(kinds, vec![("User", "model")]);
+    }
+
+    #[test]
+    fn broken_query_pack_falls_back_to_builtin_symbols() {
+        let tmp = tempfile::tempdir().unwrap();
+        let packs = tmp.path().join(".context-slicer/queries/rust");
+        std::fs::create_dir_all(&packs).unwrap();
+        std::fs::write(packs.join("symbols.scm"), "(function_item name: @name").unwrap();
+
+        let rs = tmp.path().join("lib.rs");
+        let source = "pub fn alpha() {}\npub struct Beta;\n";
+        std::fs::write(&rs, source).unwrap();
+        let out = analyze_file(&rs).unwrap();
+        let names: Vec<&str> = out.symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["alpha", "Beta"]);
+        assert_eq!(extract_symbols_from_source(&rs, source).len(), 2);
+    }
+
+    #[test]
+    fn function_metrics_count_lines_and_branches() {
+        let tmp = tempfile::tempdir().unwrap();
+        let rs = tmp.path().join("lib.rs");
+        std::fs::write(
+            &rs,
+            "pub struct S;\n\npub fn simple() -> u32 {\n    1\n}\n\n\
+             pub fn busy(xs: &[u32]) -> u32 {\n    let mut n = 0;\n\n    for x in xs {\n\
+             \x20       if *x > 1 && *x < 9 {\n            n += 1;\n        } else if *x == 0 {\n\
+             \x20           n += 2;\n        }\n    }\n    match n {\n        0 => 1,\n        _ => n,\n    }\n}\n",
+        )
+        .unwrap();
+        let out = analyze_file(&rs).unwrap();
+        let metrics = |name: &str| {
+            out.symbols
+                .iter()
+                .find(|s| s.name == name)
+                .and_then(|s| s.metrics)
+        };
+        assert_eq!(metrics("S"), None);
+        assert_eq!(
+            metrics("simple"),
+            Some(SymbolMetrics {
+                loc: 3,
+                branches: 0,
+                complexity: 1
+            })
+        );
+        // for, if, &&, else-if, two match arms.
+        assert_eq!(
+            metrics("busy"),
+            Some(SymbolMetrics {
+                loc: 14,
+                branches: 6,
+                complexity: 7
+            })
+        );
+
+        let py = tmp.path().join("m.py");
+        std::fs::write(
+            &py,
+            "def pick(x):\n    if x or not x:\n        return [y for y in x if y]\n    elif x is None:\n        return 1\n    try:\n        pass\n    except ValueError:\n        pass\n",
+        )
+        .unwrap();
+        let out = analyze_file(&py).unwrap();
+        let pick = out.symbols.iter().find(|s| s.name == "pick").unwrap();
+        // if, or, comprehension for + if, elif, except.
+        assert_eq!(pick.metrics.unwrap().branches, 6);
+    }
+
+    #[test]
+    fn todo_markers_are_collected_from_comments() {
+        let tmp = tempfile::tempdir().unwrap();
+        let rs = tmp.path().join("lib.rs");
+        std::fs::write(
+            &rs,
+            "// TODO: split this module\nfn a() {\n    let s = \"TODO not a comment\";\n}\n\
+             /* FIXME(ana) handle overflow */\n/// Docs. HACKY is not a tag.\n/// HACK: temporary\nfn b() {}\n",
+        )
+        .unwrap();
+        let out = analyze_file(&rs).unwrap();
+        let got: Vec<(&str, u32, &str)> = out
+            .markers
+            .iter()
+            .map(|m| (m.kind.as_str(), m.line, m.text.as_str()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                ("TODO", 0, "split this module"),
+                ("FIXME", 4, "handle overflow"),
+                ("HACK", 6, "temporary"),
+            ]
+        );
+
+        let py = tmp.path().join("m.py");
+        std::fs::write(&py, "x = 1  # todo lowercase\n# FIXME: wrong\n").unwrap();
+        let out = analyze_file(&py).unwrap();
+        assert_eq!(out.markers.len(), 1);
+        assert_eq!(out.markers[0].line, 1);
+    }
+
+    #[test]
+    fn symbols_record_their_visibility() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vis = |out: &FileSymbols, name: &str| {
+            out.symbols
+                .iter()
+                .find(|s| s.name == name)
+                .and_then(|s| s.visibility.clone())
+        };
+
+        let rs = tmp.path().join("lib.rs");
+        std::fs::write(
+            &rs,
+            "pub struct A {\n    pub x: u8,\n    y: u8,\n}\npub(crate) fn b() {}\nfn c() {}\n\
+             pub trait T {\n    fn t(&self);\n}\nimpl T for A {\n    fn t(&self) {}\n}\n\
+             impl A {\n    pub fn new() {}\n    fn helper() {}\n}\n",
+        )
+        .unwrap();
+        let out = analyze_file(&rs).unwrap();
+        assert_eq!(vis(&out, "A").as_deref(), Some("pub"));
+        assert_eq!(vis(&out, "b").as_deref(), Some("pub(crate)"));
+        assert_eq!(vis(&out, "c").as_deref(), Some("private"));
+        assert_eq!(vis(&out, "A::new").as_deref(), Some("pub"));
+        assert_eq!(vis(&out, "A::helper").as_deref(), Some("private"));
+        let a = out.symbols.iter().find(|s| s.name == "A").unwrap();
+        let fields: Vec<Option<&str>> = a
+            .children
+            .iter()
+            .map(|f| f.visibility.as_deref())
+            .collect();
+        assert_eq!(fields, vec![Some("pub"), Some("private")]);
+        for t in out.symbols.iter().filter(|s| s.name == "T::t" || s.name == "A::t") {
+            assert_eq!(t.visibility.as_deref(), Some("pub"));
+        }
+
+        let ts = tmp.path().join("m.ts");
+        std::fs::write(
+            &ts,
+            "export function a() {}\nexport default class B {\n  private x() {}\n  y() {}\n}\n\
+             function c() {}\nfunction d() {}\nexport { d };\n",
+        )
+        .unwrap();
+        let out = analyze_file(&ts).unwrap();
+        assert_eq!(vis(&out, "a").as_deref(), Some("exported"));
+        assert_eq!(vis(&out, "B").as_deref(), Some("default"));
+        assert_eq!(vis(&out, "B.x").as_deref(), Some("private"));
+        assert_eq!(vis(&out, "B.y").as_deref(), Some("public"));
+        assert_eq!(vis(&out, "c").as_deref(), Some("internal"));
+        assert_eq!(vis(&out, "d").as_deref(), Some("exported"));
+
+        let py = tmp.path().join("m.py");
+        std::fs::write(
+            &py,
+            "__all__ = ['api']\ndef api():\n    pass\ndef util():\n    pass\ndef _hidden():\n    pass\n\
+             class K:\n    def __init__(self):\n        pass\n    def _p(self):\n        pass\n    def __m(self):\n        pass\n",
+        )
+        .unwrap();
+        let out = analyze_file(&py).unwrap();
+        assert_eq!(vis(&out, "api").as_deref(), Some("exported"));
+        assert_eq!(vis(&out, "util").as_deref(), Some("internal"));
+        assert_eq!(vis(&out, "_hidden").as_deref(), Some("private"));
+        assert_eq!(vis(&out, "K.__init__").as_deref(), Some("public"));
+        assert_eq!(vis(&out, "K._p").as_deref(), Some("protected"));
+        assert_eq!(vis(&out, "K.__m").as_deref(), Some("private"));
+    }
+
+    #[test]
+    fn async_unsafe_and_generic_flags_are_reported() {
+        let tmp = tempfile::tempdir().unwrap();
+        let flags = |out: &FileSymbols, name: &str| {
+            let s = out.symbols.iter().find(|s| s.name == name).unwrap();
+            (s.is_async, s.is_unsafe, s.generics.clone())
+        };
+
+        let rs = tmp.path().join("lib.rs");
+        std::fs::write(
+            &rs,
+            "pub async fn fetch<'a, T: Clone>(x: &'a T) {}\npub unsafe fn raw() {}\n\
+             pub async unsafe fn both() {}\npub struct Wrap<T> { v: T }\nfn plain() {}\n",
+        )
+        .unwrap();
+        let out = analyze_file(&rs).unwrap();
+        assert_eq!(
+            flags(&out, "fetch"),
+            (true, false, vec!["'a".to_string(), "T: Clone".to_string()])
+        );
+        assert_eq!(flags(&out, "raw"), (false, true, vec![]));
+        assert_eq!(flags(&out, "both"), (true, true, vec![]));
+        assert_eq!(flags(&out, "Wrap"), (false, false, vec!["T".to_string()]));
+        assert_eq!(flags(&out, "plain"), (false, false, vec![]));
+
+        let ts = tmp.path().join("m.ts");
+        std::fs::write(
+            &ts,
+            "export async function load<K extends string>(k: K) {}\n\
+             export const handler = async () => {};\nclass Svc {\n  async run() {}\n}\n",
+        )
+        .unwrap();
+        let out = analyze_file(&ts).unwrap();
+        assert_eq!(
+            flags(&out, "load"),
+            (true, false, vec!["K extends string".to_string()])
+        );
+        assert!(flags(&out, "handler").0);
+        assert!(flags(&out, "Svc.run").0);
+
+        let py = tmp.path().join("m.py");
+        std::fs::write(&py, "async def go():\n    pass\ndef stay():\n    pass\n").unwrap();
+        let out = analyze_file(&py).unwrap();
+        assert!(flags(&out, "go").0);
+        assert!(!flags(&out, "stay").0);
+    }
+
+    #[test]
+    fn inspector_applies_edits_incrementally() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("lib.rs");
+        std::fs::write(&path, "pub fn a() {}\n").unwrap();
+
+        let mut inspector = Inspector::new();
+        let first = inspector.open(&path).unwrap();
+        assert_eq!(first.symbols.len(), 1);
+
+        let edits = [
+            TextEdit {
+                start_byte: 7,
+                old_end_byte: 8,
+                new_text: "renamed".to_string(),
+            },
+            TextEdit {
+                start_byte: 20,
+                old_end_byte: 20,
+                new_text: "\n// TODO: more\nfn helper(x: u8) -> u8 {\n    x\n}\n".to_string(),
+            },
+        ];
+        let updated = inspector.update(&path, &edits).unwrap();
+        let expected = "pub fn renamed() {}\n\n// TODO: more\nfn helper(x: u8) -> u8 {\n    x\n}\n";
+        assert_eq!(inspector.source(&path), Some(expected));
+
+        // The incremental result matches a from-scratch analysis of the same text.
+        std::fs::write(&path, expected).unwrap();
+        let fresh = analyze_file(&path).unwrap();
+        assert_eq!(
+            serde_json::to_string(&updated).unwrap(),
+            serde_json::to_string(&fresh).unwrap()
+        );
+
+        let bad = TextEdit {
+            start_byte: 5,
+            old_end_byte: 500,
+            new_text: String::new(),
+        };
+        assert!(inspector.update(&path, &[bad]).is_err());
+        assert_eq!(inspector.source(&path), Some(expected));
+    }
+
+    #[test]
+    fn inspector_update_is_all_or_nothing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("lib.rs");
+        std::fs::write(&path, "pub fn a() {}\n").unwrap();
+
+        let mut inspector = Inspector::new();
+        inspector.open(&path).unwrap();
+        let valid = TextEdit {
+            start_byte: 7,
+            old_end_byte: 8,
+            new_text: "renamed".to_string(),
+        };
+        let invalid = TextEdit {
+            start_byte: 5,
+            old_end_byte: 500,
+            new_text: String::new(),
+        };
+        assert!(inspector.update(&path, &[valid, invalid]).is_err());
+        assert_eq!(inspector.source(&path), Some("pub fn a() {}\n"));
+
+        // The held tree wasn't edited either: a later update still matches a fresh parse.
+        let append = TextEdit {
+            start_byte: 14,
+            old_end_byte: 14,
+            new_text: "fn b() {}\n".to_string(),
+        };
+        let updated = inspector.update(&path, &[append]).unwrap();
+        std::fs::write(&path, "pub fn a() {}\nfn b() {}\n").unwrap();
+        assert_eq!(
+            serde_json::to_string(&updated).unwrap(),
+            serde_json::to_string(&analyze_file(&path).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn batch_analysis_matches_serial_results_in_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut paths = Vec::new();
+        for i in 0..24 {
+            let (name, src) = match i % 3 {
+                0 => (format!("m{i}.rs"), format!("pub fn f{i}() {{}}\n")),
+                1 => (format!("m{i}.ts"), format!("export function f{i}() {{}}\n")),
+                _ => (format!("m{i}.py"), format!("def f{i}():\n    pass\n")),
+            };
+            let p = tmp.path().join(name);
+            std::fs::write(&p, src).unwrap();
+            paths.push(p);
+        }
+        paths.push(tmp.path().join("missing.rs"));
+
+        let results = analyze_files(&paths);
+        assert_eq!(results.len(), paths.len());
+        for (i, (path, res)) in paths.iter().zip(&results).enumerate().take(24) {
+            let got = res.as_ref().unwrap();
+            assert_eq!(got.symbols[0].name, format!("f{i}"));
+            assert_eq!(
+                serde_json::to_string(got).unwrap(),
+                serde_json::to_string(&analyze_file(path).unwrap()).unwrap()
+            );
+        }
+        assert!(results[24].is_err());
+    }
+
+    #[test]
+    fn symbols_carry_columns_matching_their_byte_range() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("lib.rs");
+        let src = "mod m {\n    pub struct P {\n        pub x: u8,\n    }\n}\n";
+        std::fs::write(&path, src).unwrap();
+        let out = analyze_file(&path).unwrap();
+        let p = out.symbols.iter().find(|s| s.name == "m::P").unwrap();
+        assert_eq!((p.line, p.column), (1, 4));
+        assert_eq!((p.line_end, p.column_end), (3, 5));
+        assert!(src[p.start_byte..p.end_byte].starts_with("pub struct P"));
+        let x = &p.children[0];
+        assert_eq!((x.line, x.column, x.column_end), (2, 8, 17));
+        assert_eq!(&src[x.start_byte..x.end_byte], "pub x: u8");
+    }
+
+    #[test]
+    fn strip_comments_drops_comments_and_blank_lines_but_not_strings() {
+        let src = "// Copyright (c) Example Corp.\n// SPDX-License-Identifier: MIT\n\n\
+                   /// Adds one.\npub fn inc(x: u32) -> u32 {   \n    /* inline */ x + 1 // trailing\n}\n\n\
+                   const HELP: &str = \"usage:\n\n  // not a comment\n\";\n";
+        let out = strip_comments(Path::new("lib.rs"), src).unwrap().unwrap();
+        assert_eq!(
+            out,
+            "pub fn inc(x: u32) -> u32 {\n     x + 1\n}\n\
+             const HELP: &str = \"usage:\n\n  // not a comment\n\";\n"
+        );
+        assert!(strip_comments(Path::new("notes.txt"), "# hi\n").unwrap().is_none());
+    }
+}
@@ -0,0 +1,138 @@
+use crate::analyze_cache::analyze_file_cached;
+use crate::markdown_builder::fence_language;
+use crate::token_estimator::{lang_hint, TokenEstimator};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// JSON slice document: the repository map plus one entry per file.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonSlice {
+    /// 1-based position of this document in a split slice.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parts: Option<usize>,
+    /// Preamble sections (e.g. `memory`), each a top-level string field.
+    #[serde(flatten)]
+    pub preamble: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repository_map: Option<String>,
+    pub files: Vec<JsonSliceFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonSliceFile {
+    pub path: String,
+    /// Fence-style language name (`rust`, `typescript`, ...); empty when unknown.
+    pub language: String,
+    /// Estimated tokens of `content`.
+    pub tokens: usize,
+    pub content: String,
+    /// Top-level symbols of the file on disk (lines refer to it, not to a skeleton `content`).
+    pub symbols: Vec<JsonSliceSymbol>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonSliceSymbol {
+    pub name: String,
+    pub kind: String,
+    /// 0-indexed, like the inspector's symbols.
+    pub line: u32,
+    pub line_end: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// JSON counterpart of [`crate::xml_builder::build_context_xml`], for programmatic consumers.
+pub fn build_context_json(
+    repo_root: &Path,
+    estimator: &dyn TokenEstimator,
+    preamble: &[(String, String)],
+    repository_map: Option<&str>,
+    files: &[(String, String)],
+    part: Option<(usize, usize)>,
+) -> Result<String> {
+    let files = files
+        .iter()
+        .map(|(path, content)| {
+            let symbols = analyze_file_cached(repo_root, Path::new(path))
+                .map(|analyzed| {
+                    analyzed
+                        .symbols
+                        .into_iter()
+                        .map(|s| JsonSliceSymbol {
+                            name: s.name,
+                            kind: s.kind,
+                            line: s.line,
+                            line_end: s.line_end,
+                            signature: s.signature,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            JsonSliceFile {
+                path: path.clone(),
+                language: fence_language(path).to_string(),
+                tokens: estimator.estimate_text(content, lang_hint(Path::new(path))),
+                content: content.clone(),
+                symbols,
+            }
+        })
+        .collect();
+    let slice = JsonSlice {
+        part: part.map(|(part, _)| part),
+        parts: part.map(|(_, parts)| parts),
+        preamble: preamble.iter().cloned().collect(),
+        repository_map: repository_map.map(str::to_string),
+        files,
+    };
+    Ok(serde_json::to_string_pretty(&slice)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token_estimator::HeuristicEstimator;
+
+    #[test]
+    fn files_carry_language_tokens_and_symbols() {
+        let tmp = tempfile::tempdir().unwrap();
+        let source = "pub fn alpha() {}\n\npub struct Beta;\n";
+        std::fs::write(tmp.path().join("lib.rs"), source).unwrap();
+        let files = vec![
+            ("lib.rs".to_string(), source.to_string()),
+            ("notes.txt".to_string(), "hello".to_string()),
+        ];
+        let json = build_context_json(
+            tmp.path(),
+            &HeuristicEstimator::default(),
+            &[],
+            Some("lib.rs"),
+            &files,
+            None,
+        )
+        .unwrap();
+        let v: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(v["repository_map"], "lib.rs");
+        let lib = &v["files"][0];
+        assert_eq!(lib["path"], "lib.rs");
+        assert_eq!(lib["language"], "rust");
+        assert_eq!(lib["tokens"], source.len().div_ceil(4));
+        assert_eq!(lib["content"], source);
+        let names: Vec<&str> = lib["symbols"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| s["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["alpha", "Beta"]);
+        assert_eq!(lib["symbols"][1]["line"], 2);
+
+        let notes = &v["files"][1];
+        assert_eq!(notes["language"], "");
+        assert_eq!(notes["symbols"], serde_json::json!([]));
+    }
+}
@@ -1,3 +1,5 @@
+#![recursion_limit = "256"]
+
 #[macro_export]
 macro_rules! debug_log {
 	($($arg:tt)*) => {{
@@ -9,18 +11,35 @@ macro_rules! debug_log {
 }
 
 pub mod act;
+pub mod analyze_cache;
+pub mod bundle;
 pub mod chronos;
+pub mod codeowners;
 pub mod config;
+pub mod config_outline;
 pub mod data_engine;
+pub mod gitattributes;
 pub mod grammar_manager;
+pub mod graph_export;
 pub mod inspector;
+pub mod json_builder;
 pub mod mapper;
+pub mod markdown_builder;
 pub mod memory;
+pub mod notebook;
+pub mod paths;
+pub mod redact;
 pub mod rules;
 pub mod scanner;
 pub mod server;
+pub mod slice_cache;
+pub mod slice_diff;
 pub mod slicer;
+pub mod sqlite_export;
+pub mod text_builder;
+pub mod token_estimator;
 pub mod universal;
 pub mod vector_store;
+pub mod watch;
 pub mod workspace;
 pub mod xml_builder;
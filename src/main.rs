@@ -1,19 +1,35 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use cortexast::config::load_config;
+use clap::{Parser, Subcommand, ValueEnum};
+use cortexast::bundle::write_context_bundle;
+use cortexast::config::{load_config, PackingStrategy, SliceFormat};
+use cortexast::graph_export::{
+    to_dot, to_graphml, to_html_report, to_mermaid, write_csv, ExportGraph,
+};
 use cortexast::inspector::analyze_file;
 use cortexast::inspector::render_skeleton;
 use cortexast::mapper::{
-    build_map_from_manifests, build_module_graph, build_repo_map, build_repo_map_scoped,
+    build_call_graph, build_map_from_manifests, build_module_graph, build_module_graph_at_rev,
+    build_repo_map, build_repo_map_multi, build_repo_map_scoped, check_layers, collect_markers,
+    diff_module_graphs, file_records, find_module_cycles, git_changed_files, impact_of,
+    import_closure, render_ranked_map, shortest_module_paths, stream_repo_map_multi,
+    with_direct_dependents, RepoMap,
 };
-use cortexast::scanner::{scan_workspace, ScanOptions};
-use cortexast::server::run_stdio_server;
-use cortexast::slicer::{slice_paths_to_xml, slice_to_xml};
+use cortexast::paths::relative_to;
+use cortexast::rules::get_merged_rules;
+use cortexast::scanner::{scan_workspace_with_stats, ScanOptions};
+use cortexast::server::{run_stdio_server, run_ws_server, WsAccess};
+use cortexast::slice_diff::{diff_slices, parse_slice, ParsedSlice};
+use cortexast::slicer::{build_scan_options, slice_paths_to_xml, slice_to_parts, slice_to_xml};
+use cortexast::sqlite_export::export_sqlite;
+use cortexast::token_estimator::{HeuristicEstimator, TokenEstimator};
 use cortexast::vector_store::CodebaseIndex;
+use cortexast::watch::{watch_slice, write_active_slice, WatchSpec};
 use cortexast::workspace::{discover_workspace_members, WorkspaceDiscoveryOptions};
 use indicatif::{ProgressBar, ProgressStyle};
 use serde_json::json;
-use std::path::PathBuf;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
 
 #[derive(Debug, Parser)]
 #[command(name = "cortexast")]
@@ -28,6 +44,69 @@ struct Cli {
     #[arg(long, value_name = "ROOT", num_args = 0..=1, default_missing_value = ".")]
     graph_modules: Option<PathBuf>,
 
+    /// Output a ranked, signature-only text map (file → public symbols) within --budget-tokens.
+    /// Optional ROOT scopes scanning.
+    #[arg(long, value_name = "ROOT", num_args = 0..=1, default_missing_value = ".")]
+    map_text: Option<PathBuf>,
+
+    /// Output the import cycles of the module graph (members + edge weights per cycle). Optional ROOT scopes scanning.
+    #[arg(long, value_name = "ROOT", num_args = 0..=1, default_missing_value = ".")]
+    graph_cycles: Option<PathBuf>,
+
+    /// Output added/removed/reweighted module graph nodes and edges between two git revisions.
+    /// HEAD defaults to the working tree. Example: --graph-diff main HEAD
+    #[arg(long, num_args = 1..=2, value_names = ["BASE", "HEAD"])]
+    graph_diff: Option<Vec<String>>,
+
+    /// Output the files and sections (rules, memory, repository map) added, removed or changed
+    /// between two slice documents of any format. Without NEW, OLD is compared with a fresh
+    /// slice of --target (default: the repo root). Example: --slice-diff old.xml
+    #[arg(long, num_args = 1..=2, value_names = ["OLD", "NEW"])]
+    slice_diff: Option<Vec<PathBuf>>,
+
+    /// Output the cheapest module graph path(s) from module FROM to module TO, where an edge
+    /// costs 1/import-count. Example: --graph-path apps/web packages/db
+    #[arg(long, num_args = 2, value_names = ["FROM", "TO"])]
+    graph_path: Option<Vec<String>>,
+
+    /// Output a function-level call graph (nodes=functions/methods, edges=calls). Optional ROOT scopes scanning.
+    #[arg(long, value_name = "ROOT", num_args = 0..=1, default_missing_value = ".")]
+    call_graph: Option<PathBuf>,
+
+    /// Slice --target (a file) together with the files it transitively imports, nearest
+    /// first, following imports at most DEPTH hops (default 3).
+    #[arg(
+        long,
+        value_name = "DEPTH",
+        num_args = 0..=1,
+        default_missing_value = "3",
+        requires = "target"
+    )]
+    closure: Option<u32>,
+
+    /// Slice only the files changed since REF (a ref, or an `A..B` range) plus the files
+    /// importing them directly. Example: --since main
+    #[arg(long, value_name = "REF", conflicts_with_all = ["query", "closure"])]
+    since: Option<String>,
+
+    /// Slice only the staged files (or, with --since, those staged relative to REF) plus the
+    /// files importing them directly
+    #[arg(long, conflicts_with_all = ["query", "closure"])]
+    staged: bool,
+
+    /// Output every file (and module) that transitively imports FILE, as JSON.
+    #[arg(long, value_name = "FILE")]
+    impact: Option<PathBuf>,
+
+    /// Check imports against the `architecture` layers in config and output violating edges
+    /// as JSON; exits non-zero when any are found. Optional ROOT scopes scanning.
+    #[arg(long, value_name = "ROOT", num_args = 0..=1, default_missing_value = ".")]
+    check_layers: Option<PathBuf>,
+
+    /// Output TODO/FIXME/HACK comments for every source file as JSON. Optional ROOT scopes scanning.
+    #[arg(long, value_name = "ROOT", num_args = 0..=1, default_missing_value = ".")]
+    markers: Option<PathBuf>,
+
     /// Build a module graph strictly from the directories containing these manifest files.
     /// Example: --manifests apps/a/package.json libs/b/Cargo.toml
     #[arg(long, num_args = 1.., value_name = "MANIFEST_PATHS")]
@@ -49,6 +128,11 @@ struct Cli {
     #[arg(long, short = 't')]
     target: Option<PathBuf>,
 
+    /// Another root to slice (or --map) in the same pass, e.g. `--target src --extra-target
+    /// proto`; repeatable, overlaps are deduplicated
+    #[arg(long, value_name = "PATH")]
+    extra_target: Vec<PathBuf>,
+
     /// Vector search query; when present, runs local hybrid search and slices only the most relevant files.
     #[arg(long, value_name = "TEXT")]
     query: Option<String>,
@@ -66,14 +150,70 @@ struct Cli {
     /// Override snippet size (lines per file) when building the vector index.
     #[arg(long, value_name = "N")]
     chunk_lines: Option<usize>,
-    /// Output XML to stdout (also writes {output_dir}/active_context.xml)
+    /// Output the slice to stdout (also writes {output_dir}/active_context.xml, or .md with
+    /// --format markdown)
     #[arg(long)]
     xml: bool,
 
+    /// Split --target into as many budget-sized parts as it takes instead of dropping files,
+    /// written as {output_dir}/active_context.part-N.xml (N = 1..parts)
+    #[arg(
+        long,
+        requires = "target",
+        conflicts_with_all = ["query", "closure", "since", "staged", "bundle"]
+    )]
+    split: bool,
+
+    /// Keep running and rewrite {output_dir}/active_context.* whenever files under --target
+    /// change
+    #[arg(
+        long,
+        requires = "target",
+        conflicts_with_all = ["query", "closure", "since", "staged", "bundle", "split", "xml"]
+    )]
+    watch: bool,
+
+    /// Also pack the slice, repo map JSON, merged rules and meta JSON into a .tar.gz at PATH.
+    #[arg(long, value_name = "PATH")]
+    bundle: Option<PathBuf>,
+
     /// Disable skeleton mode (emit full file contents into XML)
     #[arg(long)]
     full: bool,
 
+    /// Slice files as an API outline: doc comments, signatures and type definitions only
+    #[arg(long, conflicts_with = "full")]
+    outline: bool,
+
+    /// Strip comments, license headers and blank lines from sliced source files
+    #[arg(long)]
+    compress: bool,
+
+    /// Leave test files out of slices and maps (`*_test.rs`, `*.spec.ts`, `tests/` dirs, ...)
+    #[arg(long)]
+    no_tests: bool,
+
+    /// Slice files marked `linguist-generated` or `linguist-vendored` in .gitattributes too
+    #[arg(long)]
+    include_generated: bool,
+
+    /// Print scan statistics (files seen, included, and skipped by size, ignore rules or
+    /// binary content) to stderr, to debug files missing from a slice
+    #[arg(long)]
+    verbose: bool,
+
+    /// Embed related memory journal entries (past intents and decisions) in the slice
+    #[arg(long)]
+    memory: bool,
+
+    /// Open the slice with the merged team/project rules (`.cortex_rules.yml` tiers)
+    #[arg(long)]
+    rules: bool,
+
+    /// Pack READMEs, architecture notes and docs/ pages of the target before source files
+    #[arg(long)]
+    docs_first: bool,
+
     /// Force huge-codebase mode: distribute budget across all workspace members
     /// (auto-detected for repos with ≥5 declared workspace members).
     #[arg(long)]
@@ -83,17 +223,93 @@ struct Cli {
     #[arg(long)]
     list_members: bool,
 
-    /// Token budget override
-    #[arg(long, default_value_t = 32_000)]
-    budget_tokens: usize,
+    /// Token budget override (default: the --profile's budget, else 32000)
+    #[arg(long)]
+    budget_tokens: Option<usize>,
+
+    /// Model budget profile (`claude-200k`, `gpt-4o-128k`, `local-8k` or one from
+    /// `profiles` in config): sets the budget, chars-per-token and slice format defaults
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Output format: json (default), mermaid, dot, graphml or html for --graph-modules and
+    /// --map, plus jsonl for --map; xml, markdown, json or text for slices (default:
+    /// `slice_format` from config).
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
 
     #[command(subcommand)]
     cmd: Option<Command>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Json,
+    /// Mermaid `graph TD` diagram (edge labels = import counts).
+    Mermaid,
+    /// Graphviz DOT, clustered by directory (edge thickness = import count).
+    Dot,
+    /// GraphML with node metrics as typed attributes (Gephi, yEd).
+    Graphml,
+    /// Slice as the `<cortexast>` XML document.
+    Xml,
+    /// Slice as `## path` sections with fenced code blocks.
+    Markdown,
+    /// Slice as raw contents separated by `===== path =====` lines.
+    Text,
+    /// One JSON record per line (`"type": "node" | "edge" | "importance"`), streamed as the
+    /// map is built; --map only.
+    Jsonl,
+    /// Self-contained HTML report bundling the repo map, module graph and last slice's
+    /// metadata with an embedded viewer.
+    Html,
+}
+
+/// Print the HTML report for `map` plus the module graph under `graph_root`, including the
+/// metadata of the last slice written to the output dir, if any.
+fn print_html_report(repo_root: &Path, map: &RepoMap, graph_root: &Path) -> Result<()> {
+    let graph = build_module_graph(repo_root, graph_root)?;
+    let cfg = load_config(repo_root);
+    let slice_meta: Option<serde_json::Value> = std::fs::read(
+        repo_root
+            .join(&cfg.output_dir)
+            .join("active_context.meta.json"),
+    )
+    .ok()
+    .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+    let title = repo_root
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "repository".to_string());
+    print!(
+        "{}",
+        to_html_report(&title, map, &graph, slice_meta.as_ref())?
+    );
+    Ok(())
+}
+
+/// Print a graph as JSON or as the diagram `format` asks for.
+fn print_graph<T: serde::Serialize>(graph: &T, format: Option<OutputFormat>) -> Result<()>
+where
+    for<'a> ExportGraph: From<&'a T>,
+{
+    match format.unwrap_or(OutputFormat::Json) {
+        OutputFormat::Json => println!("{}", serde_json::to_string(graph)?),
+        OutputFormat::Mermaid => print!("{}", to_mermaid(&ExportGraph::from(graph))),
+        OutputFormat::Dot => print!("{}", to_dot(&ExportGraph::from(graph))),
+        OutputFormat::Graphml => print!("{}", to_graphml(&ExportGraph::from(graph))),
+        OutputFormat::Xml | OutputFormat::Markdown | OutputFormat::Text => {
+            anyhow::bail!("--format xml/markdown/text only applies to slices")
+        }
+        OutputFormat::Jsonl => anyhow::bail!("--format jsonl only applies to --map"),
+        OutputFormat::Html => unreachable!("handled by print_html_report"),
+    }
+    Ok(())
+}
+
 #[derive(Debug, Subcommand)]
 enum Command {
-    /// Start MCP stdio server
+    /// Start MCP server (stdio, or WebSocket with --ws)
     Mcp {
         /// Workspace root used as the default repoPath for all tool calls.
         /// Set this in your VS Code / Claude Desktop MCP config:
@@ -101,7 +317,56 @@ enum Command {
         /// Also accepted via the CORTEXAST_ROOT environment variable.
         #[arg(long, value_name = "PATH")]
         root: Option<PathBuf>,
+        /// Serve over WebSocket on ADDR (e.g. 127.0.0.1:7331) instead of stdio, for
+        /// browser-based clients that keep a connection open for notifications.
+        #[arg(long, value_name = "ADDR")]
+        ws: Option<String>,
+        /// Allow --ws to bind a non-loopback address (requires --token).
+        #[arg(long, requires = "ws")]
+        allow_remote: bool,
+        /// Bearer token WebSocket sessions must present (`Authorization: Bearer <TOKEN>`, or
+        /// `{"token": "<TOKEN>"}` as the first message). Also read from CORTEXAST_WS_TOKEN.
+        #[arg(long, value_name = "TOKEN", requires = "ws")]
+        token: Option<String>,
+    },
+    /// Export the codebase structure to another tool's format.
+    Export {
+        #[command(subcommand)]
+        target: ExportTarget,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ExportTarget {
+    /// Write modules, module edges, files, imports and symbols as SQLite tables.
+    Sqlite {
+        /// Database file to create (replaced if it exists).
+        #[arg(value_name = "OUT")]
+        out: PathBuf,
+        /// Directory to export (default: the current directory).
+        #[arg(long, value_name = "ROOT", default_value = ".")]
+        root: PathBuf,
     },
+    /// Write nodes.csv and edges.csv for the module graph or the repo map.
+    Csv {
+        /// Directory to write the two files into (created if missing).
+        #[arg(value_name = "OUT_DIR")]
+        out_dir: PathBuf,
+        /// Directory to export (default: the current directory).
+        #[arg(long, value_name = "ROOT", default_value = ".")]
+        root: PathBuf,
+        /// Which graph to export.
+        #[arg(long, value_enum, default_value = "modules")]
+        graph: CsvGraph,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CsvGraph {
+    /// Module graph (as --graph-modules).
+    Modules,
+    /// Repo map of ROOT's immediate children (as --map).
+    Map,
 }
 
 fn auto_query_limit(budget_tokens: usize, entry_count: usize, configured_default: usize) -> usize {
@@ -115,15 +380,59 @@ fn auto_query_limit(budget_tokens: usize, entry_count: usize, configured_default
     out.max(1)
 }
 
+/// Slice budget when neither `--budget-tokens` nor a profile sets one.
+const DEFAULT_BUDGET_TOKENS: usize = 32_000;
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    if let Some(Command::Mcp { root }) = cli.cmd {
-        return run_stdio_server(root);
+    if let Some(Command::Mcp {
+        root,
+        ws,
+        allow_remote,
+        token,
+    }) = cli.cmd
+    {
+        return match ws {
+            Some(addr) => run_ws_server(
+                &addr,
+                root,
+                WsAccess {
+                    allow_remote,
+                    token: token
+                        .or_else(|| std::env::var("CORTEXAST_WS_TOKEN").ok())
+                        .filter(|t| !t.is_empty()),
+                },
+            ),
+            None => run_stdio_server(root),
+        };
     }
 
     let repo_root = std::env::current_dir().context("Failed to get current dir")?;
 
+    if let Some(Command::Export { target }) = &cli.cmd {
+        match target {
+            ExportTarget::Sqlite { out, root } => {
+                let graph = build_module_graph(&repo_root, root)?;
+                let files = file_records(&repo_root, root, &graph)?;
+                let stats = export_sqlite(&graph, &files, out)?;
+                println!("{}", serde_json::to_string(&stats)?);
+            }
+            ExportTarget::Csv {
+                out_dir,
+                root,
+                graph,
+            } => {
+                let graph = match graph {
+                    CsvGraph::Modules => ExportGraph::from(&build_module_graph(&repo_root, root)?),
+                    CsvGraph::Map => ExportGraph::from(&build_repo_map_scoped(&repo_root, root)?),
+                };
+                write_csv(&graph, out_dir)?;
+            }
+        }
+        return Ok(());
+    }
+
     if let Some(manifests) = cli.manifests.as_ref() {
         let graph = build_map_from_manifests(&repo_root, manifests)?;
         println!("{}", serde_json::to_string(&graph)?);
@@ -131,11 +440,87 @@ fn main() -> Result<()> {
     }
 
     if let Some(root) = cli.graph_modules.as_ref() {
+        if cli.format == Some(OutputFormat::Html) {
+            let map = build_repo_map_scoped(&repo_root, root)?;
+            return print_html_report(&repo_root, &map, root);
+        }
+        let graph = build_module_graph(&repo_root, root)?;
+        return print_graph(&graph, cli.format);
+    }
+
+    if let Some(root) = cli.map_text.as_ref() {
+        print!(
+            "{}",
+            render_ranked_map(
+                &repo_root,
+                root,
+                cli.budget_tokens.unwrap_or(DEFAULT_BUDGET_TOKENS)
+            )?
+        );
+        return Ok(());
+    }
+
+    if let Some(root) = cli.graph_cycles.as_ref() {
         let graph = build_module_graph(&repo_root, root)?;
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&find_module_cycles(&graph))?
+        );
+        return Ok(());
+    }
+
+    if let Some(revs) = cli.graph_diff.as_ref() {
+        let root = PathBuf::from(".");
+        let before = build_module_graph_at_rev(&repo_root, &revs[0], &root)?;
+        let after = match revs.get(1) {
+            Some(head) => build_module_graph_at_rev(&repo_root, head, &root)?,
+            None => build_module_graph(&repo_root, &root)?,
+        };
+        let diff = diff_module_graphs(&before, &after);
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+
+    if let Some(ends) = cli.graph_path.as_ref() {
+        let graph = build_module_graph(&repo_root, &PathBuf::from("."))?;
+        let paths = shortest_module_paths(&graph, &ends[0], &ends[1], 10)?;
+        println!("{}", serde_json::to_string_pretty(&paths)?);
+        return Ok(());
+    }
+
+    if let Some(root) = cli.call_graph.as_ref() {
+        let graph = build_call_graph(&repo_root, root)?;
         println!("{}", serde_json::to_string(&graph)?);
         return Ok(());
     }
 
+    if let Some(path) = cli.impact.as_ref() {
+        let report = impact_of(&repo_root, path)?;
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    if let Some(root) = cli.check_layers.as_ref() {
+        let architecture = load_config(&repo_root).architecture;
+        if architecture.layers.is_empty() {
+            anyhow::bail!(
+                "No architecture layers configured (set `architecture.layers` in config)"
+            );
+        }
+        let violations = check_layers(&repo_root, root, &architecture)?;
+        println!("{}", serde_json::to_string_pretty(&violations)?);
+        if !violations.is_empty() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(root) = cli.markers.as_ref() {
+        let markers = collect_markers(&repo_root, root)?;
+        println!("{}", serde_json::to_string_pretty(&markers)?);
+        return Ok(());
+    }
+
     if let Some(p) = cli.inspect {
         let abs = if p.is_absolute() {
             p
@@ -144,7 +529,7 @@ fn main() -> Result<()> {
         };
         let mut out = analyze_file(&abs)?;
         // Prefer repo-relative file path in JSON output.
-        if let Ok(rel) = abs.strip_prefix(&repo_root) {
+        if let Some(rel) = relative_to(&abs, &repo_root) {
             out.file = rel.to_string_lossy().replace('\\', "/");
         } else {
             out.file = abs.to_string_lossy().replace('\\', "/");
@@ -164,23 +549,83 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
-    if cli.map {
-        let map = if let Some(scope) = cli.map_target.as_ref() {
-            build_repo_map_scoped(&repo_root, scope)?
-        } else {
-            build_repo_map(&repo_root)?
-        };
-        println!("{}", serde_json::to_string(&map)?);
+    let map_exclude_tests = cli.no_tests || load_config(&repo_root).scan.exclude_tests;
+    let map_scopes: Vec<PathBuf> =
+        std::iter::once(cli.map_target.clone().unwrap_or_else(|| repo_root.clone()))
+            .chain(load_config(&repo_root).scan.extra_targets)
+            .chain(cli.extra_target.iter().cloned())
+            .collect();
+    if cli.map && cli.format == Some(OutputFormat::Jsonl) {
+        let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+        stream_repo_map_multi(&repo_root, &map_scopes, map_exclude_tests, &mut |record| {
+            serde_json::to_writer(&mut out, &record)?;
+            out.write_all(b"\n")?;
+            Ok(())
+        })?;
+        out.flush()?;
         return Ok(());
     }
 
+    if cli.map {
+        let map = build_repo_map_multi(&repo_root, &map_scopes, map_exclude_tests)?;
+        if cli.format == Some(OutputFormat::Html) {
+            let root = cli.map_target.clone().unwrap_or_else(|| PathBuf::from("."));
+            return print_html_report(&repo_root, &map, &root);
+        }
+        return print_graph(&map, cli.format);
+    }
+
     let mut cfg = load_config(&repo_root);
+    let profile_budget = match cli.profile.as_deref() {
+        Some(name) => cfg.apply_profile(name)?,
+        None => None,
+    };
+    let budget_tokens = cli
+        .budget_tokens
+        .or(profile_budget)
+        .unwrap_or(DEFAULT_BUDGET_TOKENS);
     if cli.full {
         cfg.skeleton_mode = false;
+        cfg.outline_mode = false;
+    }
+    if cli.outline {
+        cfg.outline_mode = true;
+    }
+    if cli.compress {
+        cfg.compress = true;
+    }
+    if cli.no_tests {
+        cfg.scan.exclude_tests = true;
+    }
+    if cli.include_generated {
+        cfg.scan.include_generated = true;
+    }
+    cfg.scan
+        .extra_targets
+        .extend(cli.extra_target.iter().cloned());
+    if cli.memory {
+        cfg.memory.enabled = true;
+    }
+    if cli.rules {
+        cfg.slice_rules = true;
+    }
+    if cli.docs_first {
+        cfg.packing = PackingStrategy::DocsFirst;
     }
     if cli.huge {
         cfg.huge_codebase.enabled = true;
     }
+    match cli.format {
+        None => {}
+        Some(OutputFormat::Xml) => cfg.slice_format = SliceFormat::Xml,
+        Some(OutputFormat::Markdown) => cfg.slice_format = SliceFormat::Markdown,
+        Some(OutputFormat::Json) => cfg.slice_format = SliceFormat::Json,
+        Some(OutputFormat::Text) => cfg.slice_format = SliceFormat::Text,
+        Some(other) => anyhow::bail!(
+            "--format {} only applies to --graph-modules and --map",
+            format!("{other:?}").to_lowercase()
+        ),
+    }
 
     // ── --list-members: inspect workspace without slicing ─────────────────
     if cli.list_members {
@@ -195,8 +640,98 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    if let Some(docs) = cli.slice_diff.as_ref() {
+        let read = |path: &PathBuf| -> Result<ParsedSlice> {
+            let doc = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            parse_slice(&doc).with_context(|| format!("Failed to parse {}", path.display()))
+        };
+        let old = read(&docs[0])?;
+        let new = match docs.get(1) {
+            Some(path) => read(path)?,
+            None => {
+                let target = cli.target.clone().unwrap_or_else(|| PathBuf::from("."));
+                let (doc, _meta) = slice_to_xml(&repo_root, &target, budget_tokens, &cfg, false)?;
+                parse_slice(&doc)?
+            }
+        };
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&diff_slices(&old, &new))?
+        );
+        return Ok(());
+    }
+
+    if cli.watch {
+        let spec = WatchSpec {
+            repo_root: repo_root.clone(),
+            target: cli.target.clone().context("--watch requires --target")?,
+            budget_tokens,
+            cfg,
+            skeleton_only: false,
+        };
+        let written = write_active_slice(&spec)?;
+        eprintln!(
+            "Wrote {} bytes to {}; watching {} for changes (Ctrl-C to stop)",
+            written.doc.len(),
+            written.path.display(),
+            spec.target.display()
+        );
+        let stop = AtomicBool::new(false);
+        return watch_slice(&spec, &stop, &mut |update| match update {
+            Ok(written) => eprintln!(
+                "Re-sliced: wrote {} bytes to {}",
+                written.doc.len(),
+                written.path.display()
+            ),
+            Err(e) => eprintln!("Re-slice failed: {e:#}"),
+        });
+    }
+
+    if cli.split {
+        let target = cli.target.clone().context("--split requires --target")?;
+        let parts = slice_to_parts(&repo_root, &target, budget_tokens, &cfg, false)?;
+        let out_dir = repo_root.join(&cfg.output_dir);
+        std::fs::create_dir_all(&out_dir)?;
+        let ext = cfg.slice_format.extension();
+        for (i, (doc, _meta)) in parts.iter().enumerate() {
+            let out_file = out_dir.join(format!("active_context.part-{}.{ext}", i + 1));
+            std::fs::write(&out_file, doc)?;
+            if cli.xml {
+                print!("{doc}");
+            } else {
+                eprintln!("Wrote {} bytes to {}", doc.len(), out_file.display());
+            }
+        }
+        let meta_json = json!({
+            "repoRoot": repo_root.to_string_lossy(),
+            "target": target.to_string_lossy(),
+            "budgetTokens": budget_tokens,
+            "parts": parts.len(),
+            "totalTokens": parts.iter().map(|(_, m)| m.total_tokens).sum::<usize>(),
+            "totalChars": parts.iter().map(|(doc, _)| doc.len()).sum::<usize>(),
+            "dropped": parts.first().map(|(_, m)| m.dropped.clone()).unwrap_or_default(),
+            "redactions": parts.first().map(|(_, m)| m.redactions.clone()).unwrap_or_default(),
+            "fingerprints": parts
+                .iter()
+                .flat_map(|(_, m)| m.fingerprints.clone())
+                .collect::<std::collections::BTreeMap<_, _>>(),
+            "scan": parts.first().map(|(_, m)| m.scan).unwrap_or_default()
+        });
+        if cli.verbose {
+            if let Some((_, meta)) = parts.first() {
+                eprintln!("scan: {}", meta.scan);
+            }
+        }
+        std::fs::write(
+            out_dir.join("active_context.meta.json"),
+            serde_json::to_vec_pretty(&meta_json)?,
+        )?;
+        return Ok(());
+    }
+
     // Hybrid search mode: build/update local vector index, retrieve relevant files, then slice only those.
-    let (xml, target_label) = if let Some(q) = cli.query.as_ref() {
+    let (xml, meta, target_label) = if let Some(q) = cli.query.as_ref() {
         let index_target = cli.target.clone().unwrap_or_else(|| PathBuf::from("."));
         let mut exclude_dir_names = vec![
             ".git".into(),
@@ -207,10 +742,9 @@ fn main() -> Result<()> {
         ];
         exclude_dir_names.extend(cfg.scan.exclude_dir_names.iter().cloned());
         let opts = ScanOptions {
-            repo_root: repo_root.clone(),
-            target: index_target.clone(),
-            max_file_bytes: cfg.token_estimator.max_file_bytes,
             exclude_dir_names,
+            keep_oversized: false,
+            ..build_scan_options(&repo_root, &index_target, &cfg)
         };
 
         let scan_spinner = ProgressBar::new_spinner();
@@ -220,7 +754,7 @@ fn main() -> Result<()> {
                 .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]),
         );
         scan_spinner.enable_steady_tick(std::time::Duration::from_millis(80));
-        let entries = scan_workspace(&opts)?;
+        let (entries, scan) = scan_workspace_with_stats(&opts)?;
         scan_spinner.finish_with_message(format!("scanned {} files", entries.len()));
 
         let db_dir = cfg.output_dir.join("db");
@@ -269,7 +803,7 @@ fn main() -> Result<()> {
         let q_owned = q.clone();
         let limit = cli.query_limit.unwrap_or_else(|| {
             auto_query_limit(
-                cli.budget_tokens,
+                budget_tokens,
                 entries.len(),
                 cfg.vector_search.default_query_limit,
             )
@@ -278,49 +812,98 @@ fn main() -> Result<()> {
         let rel_paths: Vec<String> =
             rt.block_on(async move { (index.search(&q_owned, limit).await).unwrap_or_default() });
 
-        let (xml, _meta) = if rel_paths.is_empty() {
-            slice_to_xml(&repo_root, &index_target, cli.budget_tokens, &cfg, false)?
+        let (xml, meta) = if rel_paths.is_empty() {
+            slice_to_xml(&repo_root, &index_target, budget_tokens, &cfg, false)?
         } else {
-            slice_paths_to_xml(&repo_root, &rel_paths, cli.budget_tokens, &cfg, false)?
+            // The search only sees what the index scan kept, so report that scan.
+            let (xml, mut meta) =
+                slice_paths_to_xml(&repo_root, &rel_paths, budget_tokens, &cfg, false)?;
+            meta.scan = scan;
+            (xml, meta)
+        };
+        (xml, meta, format!("query:{}", q))
+    } else if cli.since.is_some() || cli.staged {
+        let changed = git_changed_files(&repo_root, cli.since.as_deref(), cli.staged)?;
+        if changed.is_empty() {
+            anyhow::bail!("No changed files to slice");
+        }
+        let rel_paths: Vec<String> = with_direct_dependents(&repo_root, &changed)
+            .into_iter()
+            .map(|(file, _)| file)
+            .collect();
+        let (xml, meta) = slice_paths_to_xml(&repo_root, &rel_paths, budget_tokens, &cfg, false)?;
+        let label = match (cli.since.as_deref(), cli.staged) {
+            (Some(since), true) => format!("diff:{since}:staged"),
+            (Some(since), false) => format!("diff:{since}"),
+            (None, _) => "diff:staged".to_string(),
         };
-        (xml, format!("query:{}", q))
+        (xml, meta, label)
+    } else if let Some(depth) = cli.closure {
+        let target = cli.target.clone().context("--closure requires --target")?;
+        let rel_paths: Vec<String> = import_closure(&repo_root, &target, depth)?
+            .into_iter()
+            .map(|(file, _)| file)
+            .collect();
+        let (xml, meta) = slice_paths_to_xml(&repo_root, &rel_paths, budget_tokens, &cfg, false)?;
+        (xml, meta, format!("closure:{}", target.to_string_lossy()))
     } else {
         let target = cli
             .target
             .clone()
             .context("Missing --target (or provide --query)")?;
-        let (xml, _meta) = slice_to_xml(&repo_root, &target, cli.budget_tokens, &cfg, false)?;
-        (xml, target.to_string_lossy().to_string())
+        let (xml, meta) = slice_to_xml(&repo_root, &target, budget_tokens, &cfg, false)?;
+        (xml, meta, target.to_string_lossy().to_string())
     };
 
     // Ensure output dir exists and write file.
     let out_dir = repo_root.join(&cfg.output_dir);
     std::fs::create_dir_all(&out_dir)?;
-    std::fs::write(out_dir.join("active_context.xml"), &xml)?;
+    let out_file = out_dir.join(format!("active_context.{}", cfg.slice_format.extension()));
+    std::fs::write(&out_file, &xml)?;
 
     // Write a small meta file for UIs.
     // (Keeps format similar to legacy implementations.)
+    let total_tokens =
+        HeuristicEstimator::from_config(&cfg.token_estimator).estimate_text(&xml, None);
     let meta_json = json!({
         "repoRoot": repo_root.to_string_lossy(),
         "target": target_label,
-        "budgetTokens": cli.budget_tokens,
-        "totalTokens": (xml.len() as f64 / 4.0).ceil() as u64,
-        "totalChars": xml.len()
+        "budgetTokens": budget_tokens,
+        "totalTokens": total_tokens,
+        "totalChars": xml.len(),
+        "dropped": meta.dropped,
+        "redactions": meta.redactions,
+        "fingerprints": meta.fingerprints,
+        "scan": meta.scan
     });
-    let _ = std::fs::write(
-        out_dir.join("active_context.meta.json"),
-        serde_json::to_vec_pretty(&meta_json)?,
-    );
+    if cli.verbose {
+        eprintln!("scan: {}", meta.scan);
+    }
+    let meta_bytes = serde_json::to_vec_pretty(&meta_json)?;
+    let _ = std::fs::write(out_dir.join("active_context.meta.json"), &meta_bytes);
+
+    if let Some(bundle) = cli.bundle.as_ref() {
+        let slice_name = format!("active_context.{}", cfg.slice_format.extension());
+        let repo_map = serde_json::to_vec_pretty(&build_repo_map(&repo_root)?)?;
+        let rules =
+            serde_json::to_vec_pretty(&get_merged_rules(&repo_root.to_string_lossy(), None)?)?;
+        write_context_bundle(
+            bundle,
+            &[
+                (slice_name.as_str(), xml.as_bytes()),
+                ("repo_map.json", &repo_map),
+                ("rules.json", &rules),
+                ("active_context.meta.json", &meta_bytes),
+            ],
+        )?;
+        eprintln!("Wrote context bundle to {}", bundle.display());
+    }
 
     if cli.xml {
         print!("{}", xml);
     } else {
         // Default to printing JSON meta later; for now just confirm success.
-        eprintln!(
-            "Wrote {} bytes to {}",
-            xml.len(),
-            out_dir.join("active_context.xml").display()
-        );
+        eprintln!("Wrote {} bytes to {}", xml.len(), out_file.display());
     }
 
     Ok(())
@@ -1,10 +1,25 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use glob::Pattern;
 use ignore::WalkBuilder;
-use serde::Serialize;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-use crate::inspector::analyze_file;
+use crate::analyze_cache::{analyze_file_cached, analyze_files_cached, store_json};
+use crate::codeowners::CodeOwners;
+use crate::config::{load_config, ArchitectureConfig, ABSOLUTE_MAX_FILE_BYTES};
+use crate::config_outline::strip_jsonc;
+use crate::gitattributes::LinguistAttributes;
+use crate::inspector::{Marker, Symbol};
+use crate::paths::{normalize_abs, relative_to};
+use crate::scanner::{
+    fingerprint, is_test_dir_name, is_test_path, SlicerIgnore, SLICERIGNORE_FILE,
+};
+use crate::token_estimator::{lang_hint, HeuristicEstimator, TokenEstimator};
+use crate::workspace::npm_workspace_packages;
+use rayon::prelude::*;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct MapNode {
@@ -15,6 +30,18 @@ pub struct MapNode {
     pub size_class: String,
     pub bytes: u64,
     pub est_tokens: u64,
+    /// PageRank over import edges, scaled so the average node scores 1.0.
+    pub importance: f64,
+    /// Commits touching this path (within the last [`CHURN_MAX_COMMITS`]).
+    pub commits: u64,
+    /// Unix time of the newest of those commits; `None` outside git or when untouched.
+    pub last_changed: Option<i64>,
+    /// `CODEOWNERS` owners of this path.
+    pub owners: Vec<String>,
+    /// Content hash of a file node, as in [`crate::scanner::FileEntry::fingerprint`]; `None`
+    /// for directories and files past [`ABSOLUTE_MAX_FILE_BYTES`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -30,7 +57,7 @@ pub struct RepoMap {
     pub edges: Vec<MapEdge>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModuleNode {
     pub id: String,
     pub label: String,
@@ -38,9 +65,26 @@ pub struct ModuleNode {
     pub file_count: u64,
     pub bytes: u64,
     pub est_tokens: u64,
+    /// Total lines across the module's source files.
+    pub lines: u64,
+    /// Symbols (functions, types, ...) the inspector extracts from those files.
+    pub symbols: u64,
+    pub avg_file_bytes: u64,
+    /// Source file count per (lowercase) extension.
+    pub languages: BTreeMap<String, u64>,
+    /// PageRank over weighted import edges, scaled so the average module scores 1.0.
+    pub importance: f64,
+    /// Louvain community over the (undirected) import edges; `0` is the largest cluster.
+    pub cluster: usize,
+    /// Commits touching files in this module (within the last [`CHURN_MAX_COMMITS`]).
+    pub commits: u64,
+    /// Unix time of the newest of those commits; `None` outside git or when untouched.
+    pub last_changed: Option<i64>,
+    /// `CODEOWNERS` owners of the module's directory.
+    pub owners: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModuleEdge {
     pub id: String,
     pub source: String,
@@ -48,12 +92,107 @@ pub struct ModuleEdge {
     pub weight: u64,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModuleGraph {
     pub nodes: Vec<ModuleNode>,
     pub edges: Vec<ModuleEdge>,
 }
 
+/// What changed between two module graphs (see [`diff_module_graphs`]).
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleGraphDiff {
+    pub added_nodes: Vec<ModuleNode>,
+    pub removed_nodes: Vec<ModuleNode>,
+    pub added_edges: Vec<ModuleEdge>,
+    pub removed_edges: Vec<ModuleEdge>,
+    /// Edges present on both sides whose import count changed.
+    pub reweighted_edges: Vec<EdgeWeightChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EdgeWeightChange {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    pub before: u64,
+    pub after: u64,
+}
+
+/// A strongly connected set of modules: every member reaches every other through
+/// `edges`, the imports among members (with their weights).
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleCycle {
+    pub members: Vec<String>,
+    pub edges: Vec<ModuleEdge>,
+}
+
+/// One route from module `A` to module `B` (see [`shortest_module_paths`]).
+#[derive(Debug, Clone, Serialize)]
+pub struct ModulePath {
+    /// Modules in order, from the source to the target inclusive.
+    pub modules: Vec<String>,
+    pub edges: Vec<ModuleEdge>,
+    /// Sum of `1 / weight` over `edges`: heavily used imports make shorter links.
+    pub cost: f64,
+}
+
+/// An import that crosses architecture layers against the configured `allow` rules.
+#[derive(Debug, Clone, Serialize)]
+pub struct LayerViolation {
+    pub from_layer: String,
+    pub to_layer: String,
+    pub file: String,
+    /// First line of `file` mentioning `import`, when found.
+    pub line: Option<u32>,
+    pub import: String,
+    pub target: String,
+}
+
+/// Result of [`impact_of`]: files ordered by distance from `target`, then path.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImpactReport {
+    pub target: String,
+    pub files: Vec<ImpactedFile>,
+    pub modules: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImpactedFile {
+    pub file: String,
+    pub depth: u32,
+}
+
+/// A callable symbol in a [`CallGraph`]; `id` is `<file>#<qualified name>`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CallNode {
+    pub id: String,
+    pub name: String,
+    pub kind: String,
+    pub file: String,
+    pub line: u32,
+}
+
+/// `source` calls `target`; `weight` counts the distinct call expressions that resolved to it.
+#[derive(Debug, Clone, Serialize)]
+pub struct CallEdge {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    pub weight: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CallGraph {
+    pub nodes: Vec<CallNode>,
+    pub edges: Vec<CallEdge>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMarkers {
+    pub file: String,
+    pub markers: Vec<Marker>,
+}
+
 fn is_known_manifest_file(name: &str) -> bool {
     matches!(
         name,
@@ -235,7 +374,7 @@ pub fn build_map_from_manifests(repo_root: &Path, manifests: &[PathBuf]) -> Resu
         } else {
             repo_root.join(&m_norm)
         };
-        let abs = abs.canonicalize().unwrap_or(abs);
+        let abs = normalize_abs(&abs);
 
         let name = abs.file_name().and_then(|s| s.to_str()).unwrap_or("");
         if !is_known_manifest_file(name) {
@@ -319,10 +458,15 @@ pub fn build_map_from_manifests(repo_root: &Path, manifests: &[PathBuf]) -> Resu
     #[derive(Clone, Default)]
     struct Acc {
         bytes: u64,
+        est_tokens: u64,
         file_count: u64,
+        lines: u64,
+        languages: BTreeMap<String, u64>,
         files: Vec<PathBuf>,
     }
 
+    let estimator = estimator_for(repo_root);
+
     let module_dir_rel_set: BTreeSet<String> = specs.iter().map(|s| s.dir_rel.clone()).collect();
     let repo_root_owned = repo_root.to_path_buf();
     let module_roots_rel: Vec<(String, String)> = specs
@@ -342,6 +486,7 @@ pub fn build_map_from_manifests(repo_root: &Path, manifests: &[PathBuf]) -> Resu
         let module_dir_rel_set = module_dir_rel_set.clone();
         let walker = WalkBuilder::new(d)
             .standard_filters(true)
+            .add_custom_ignore_filename(SLICERIGNORE_FILE)
             .hidden(false)
             .max_depth(Some(25))
             .filter_entry(move |entry| {
@@ -380,7 +525,12 @@ pub fn build_map_from_manifests(repo_root: &Path, manifests: &[PathBuf]) -> Resu
             let sz = ent.metadata().map(|m| m.len()).unwrap_or(0);
             let a = acc_by_dir.get_mut(d).unwrap();
             a.bytes += sz;
+            a.est_tokens += est_tokens_for_file(&estimator, p, sz);
             a.file_count += 1;
+            a.lines += std::fs::read(p).map(|b| count_lines(&b)).unwrap_or(0);
+            if let Some(ext) = lang_hint(p) {
+                *a.languages.entry(ext.to_lowercase()).or_default() += 1;
+            }
             a.files.push(p.to_path_buf());
         }
     }
@@ -395,7 +545,16 @@ pub fn build_map_from_manifests(repo_root: &Path, manifests: &[PathBuf]) -> Resu
             path: s.id.clone(),
             file_count: a.file_count,
             bytes: a.bytes,
-            est_tokens: est_tokens_from_bytes(a.bytes),
+            est_tokens: a.est_tokens,
+            lines: a.lines,
+            symbols: 0,
+            avg_file_bytes: a.bytes.checked_div(a.file_count).unwrap_or(0),
+            languages: a.languages,
+            importance: 0.0,
+            cluster: 0,
+            commits: 0,
+            last_changed: None,
+            owners: Vec::new(),
         });
     }
     nodes.sort_by(|a, b| a.id.cmp(&b.id));
@@ -440,7 +599,7 @@ pub fn build_map_from_manifests(repo_root: &Path, manifests: &[PathBuf]) -> Resu
         for (_dep_name, dep_path) in deps {
             // Resolve the relative path from this module's directory
             let dep_abs = s.dir_abs.join(&dep_path);
-            let dep_abs = dep_abs.canonicalize().unwrap_or(dep_abs);
+            let dep_abs = normalize_abs(&dep_abs);
 
             // Convert to repo-relative path
             let dep_rel = match rel_str(repo_root, &dep_abs) {
@@ -467,14 +626,18 @@ pub fn build_map_from_manifests(repo_root: &Path, manifests: &[PathBuf]) -> Resu
         .map(|s| (s.dir_abs.clone(), s.id.clone()))
         .collect();
 
+    let mut import_cache = ImportCache::default();
+    let mut symbols_by_module: BTreeMap<String, u64> = BTreeMap::new();
     for (dir, src_mod_id) in &module_ids {
         let a = acc_by_dir.get(dir).cloned().unwrap_or_default();
 
         for file_abs in &a.files {
-            let analyzed = match analyze_file(file_abs) {
+            let analyzed = match analyze_file_cached(repo_root, file_abs) {
                 Ok(v) => v,
                 Err(_) => continue,
             };
+            *symbols_by_module.entry(src_mod_id.clone()).or_default() +=
+                analyzed.symbols.len() as u64;
 
             let ext = file_abs
                 .extension()
@@ -500,25 +663,34 @@ pub fn build_map_from_manifests(repo_root: &Path, manifests: &[PathBuf]) -> Resu
                     continue;
                 }
 
-                // TS/JS: resolve relative import to a file, then map to a selected module by prefix.
-                let Some(dst_file_abs) = resolve_ts_import(repo_root, file_abs, &imp) else {
+                // TS/JS: resolve relative or aliased import to a file, then map to a selected module by prefix.
+                let dst = resolve_ts_import(repo_root, file_abs, &imp).or_else(|| {
+                    resolve_ts_alias(repo_root, file_abs, &imp, &mut import_cache.tsconfig)
+                });
+                let Some(dst_file_abs) = dst else {
                     continue;
                 };
-                let dst_file_abs = dst_file_abs.canonicalize().unwrap_or(dst_file_abs);
+                let dst_file_abs = normalize_abs(&dst_file_abs);
 
-                // Compare using repo-relative forward-slash paths to avoid OS separator mismatches.
-                let Some(dst_rel) = rel_str(repo_root, &dst_file_abs) else {
-                    continue;
-                };
-                let Some(dst_mod_id) = module_id_for_rel_path(&dst_rel, &module_roots_rel) else {
-                    continue;
-                };
-                if dst_mod_id != *src_mod_id {
-                    *weights.entry((src_mod_id.clone(), dst_mod_id)).or_insert(0) += 1;
+                // Barrels re-exporting other modules count as imports of those modules.
+                let dst_mod_ids: BTreeSet<String> =
+                    follow_barrels(repo_root, dst_file_abs, &mut import_cache)
+                        .iter()
+                        // Compare using repo-relative forward-slash paths to avoid OS separator mismatches.
+                        .filter_map(|f| rel_str(repo_root, f))
+                        .filter_map(|rel| module_id_for_rel_path(&rel, &module_roots_rel))
+                        .collect();
+                for dst_mod_id in dst_mod_ids {
+                    if dst_mod_id != *src_mod_id {
+                        *weights.entry((src_mod_id.clone(), dst_mod_id)).or_insert(0) += 1;
+                    }
                 }
             }
         }
     }
+    for node in &mut nodes {
+        node.symbols = symbols_by_module.get(&node.id).copied().unwrap_or(0);
+    }
 
     let mut edges: Vec<ModuleEdge> = Vec::new();
     for ((s, t), w) in weights {
@@ -530,8 +702,280 @@ pub fn build_map_from_manifests(repo_root: &Path, manifests: &[PathBuf]) -> Resu
         });
     }
     edges.sort_by(|a, b| a.id.cmp(&b.id));
+    rank_module_nodes(&mut nodes, &edges);
+    let mut graph = ModuleGraph { nodes, edges };
+    annotate_module_nodes(repo_root, &mut graph);
+
+    Ok(graph)
+}
+
+/// Newline-terminated lines, plus a trailing unterminated one.
+fn count_lines(bytes: &[u8]) -> u64 {
+    let newlines = bytes.iter().filter(|&&b| b == b'\n').count() as u64;
+    newlines + u64::from(bytes.last().is_some_and(|&b| b != b'\n'))
+}
+
+/// How much history [`git_churn`] reads; keeps huge repos fast.
+pub const CHURN_MAX_COMMITS: usize = 5000;
+
+/// [`git_churn`] as of one commit, for one directory of the checkout.
+#[derive(Debug, Serialize, Deserialize)]
+struct ChurnCache {
+    head: String,
+    churn: BTreeMap<String, (u64, i64)>,
+}
 
-    Ok(ModuleGraph { nodes, edges })
+/// Commit count and newest commit time (unix seconds) per repo-relative path under
+/// `repo_root`, from `git log --numstat`. Directories (and `.`) count each commit that
+/// touched anything below them once. Empty outside a git checkout.
+///
+/// History only moves with `HEAD`, so the result is cached under [`GRAPH_CACHE_DIR`] per
+/// `HEAD` and `repo_root`'s path within the checkout; maps, graphs and watch ticks at the
+/// same commit then skip `git log`.
+fn git_churn(repo_root: &Path) -> BTreeMap<String, (u64, i64)> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args(["rev-parse", "HEAD", "--show-prefix"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success());
+    let Some(output) = output else {
+        return BTreeMap::new();
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    let head = lines.next().unwrap_or_default().trim().to_string();
+    let prefix = lines.next().unwrap_or_default().trim();
+
+    let key = format!("{}\0{prefix}", env!("CARGO_PKG_VERSION"));
+    let path = repo_root.join(GRAPH_CACHE_DIR).join(format!(
+        "churn-{}.json",
+        &blake3::hash(key.as_bytes()).to_hex()[..16]
+    ));
+    let cached = std::fs::read(&path)
+        .ok()
+        .and_then(|raw| serde_json::from_slice::<ChurnCache>(&raw).ok());
+    if let Some(cached) = cached.filter(|c| c.head == head) {
+        return cached.churn;
+    }
+    let cache = ChurnCache {
+        head,
+        churn: read_git_churn(repo_root),
+    };
+    if let Err(e) = store_json(&path, &cache) {
+        crate::debug_log!("[cortexast] churn cache not saved: {e:#}");
+    }
+    cache.churn
+}
+
+fn read_git_churn(repo_root: &Path) -> BTreeMap<String, (u64, i64)> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args([
+            "log",
+            "--no-renames",
+            "--relative",
+            "--numstat",
+            "--format=%x00%ct",
+        ])
+        .arg(format!("--max-count={CHURN_MAX_COMMITS}"))
+        .output()
+        .ok()
+        .filter(|o| o.status.success());
+    let Some(output) = output else {
+        return BTreeMap::new();
+    };
+
+    let mut churn: BTreeMap<String, (u64, i64)> = BTreeMap::new();
+    for commit in String::from_utf8_lossy(&output.stdout).split('\0') {
+        let mut lines = commit.lines();
+        let Some(time) = lines.next().and_then(|l| l.trim().parse::<i64>().ok()) else {
+            continue;
+        };
+        let mut touched: BTreeSet<&str> = BTreeSet::new();
+        for path in lines.filter_map(|l| l.splitn(3, '\t').nth(2)) {
+            touched.insert(".");
+            let mut prefix = path;
+            touched.insert(prefix);
+            while let Some((dir, _)) = prefix.rsplit_once('/') {
+                touched.insert(dir);
+                prefix = dir;
+            }
+        }
+        for path in touched {
+            let entry = churn.entry(path.to_string()).or_insert((0, time));
+            entry.0 += 1;
+            entry.1 = entry.1.max(time);
+        }
+    }
+    churn
+}
+
+fn churn_for(churn: &BTreeMap<String, (u64, i64)>, path: &str) -> (u64, Option<i64>) {
+    churn
+        .get(path)
+        .map_or((0, None), |&(commits, last)| (commits, Some(last)))
+}
+
+/// Git churn and `CODEOWNERS` owners for every module node.
+fn annotate_module_nodes(repo_root: &Path, graph: &mut ModuleGraph) {
+    let churn = git_churn(repo_root);
+    let owners = CodeOwners::load(repo_root);
+    for node in &mut graph.nodes {
+        (node.commits, node.last_changed) = churn_for(&churn, &node.path);
+        node.owners = owners.owners_of(&node.path, true);
+    }
+}
+
+fn rank_module_nodes(nodes: &mut [ModuleNode], edges: &[ModuleEdge]) {
+    let index: BTreeMap<&str, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.id.as_str(), i))
+        .collect();
+    let links: Vec<(usize, usize, f64)> = edges
+        .iter()
+        .filter_map(|e| {
+            Some((
+                *index.get(e.source.as_str())?,
+                *index.get(e.target.as_str())?,
+                e.weight as f64,
+            ))
+        })
+        .collect();
+    let ranks = page_rank(nodes.len(), &links);
+    let clusters = louvain(nodes.len(), &links);
+    for ((node, rank), cluster) in nodes.iter_mut().zip(ranks).zip(clusters) {
+        node.importance = rank;
+        node.cluster = cluster;
+    }
+}
+
+/// Louvain community detection over `n` nodes, treating edges as undirected. Returns a
+/// cluster id per node, numbered by cluster size (largest first, ties by lowest member).
+fn louvain(n: usize, edges: &[(usize, usize, f64)]) -> Vec<usize> {
+    let mut adj: Vec<BTreeMap<usize, f64>> = vec![BTreeMap::new(); n];
+    for &(s, t, w) in edges {
+        *adj[s].entry(t).or_default() += w;
+        *adj[t].entry(s).or_default() += w;
+    }
+    // Community of each original node, refined one aggregation level at a time.
+    let mut membership: Vec<usize> = (0..n).collect();
+
+    loop {
+        let size = adj.len();
+        let degree: Vec<f64> = adj.iter().map(|a| a.values().sum()).collect();
+        let total: f64 = degree.iter().sum();
+        if total == 0.0 {
+            break;
+        }
+        let mut community: Vec<usize> = (0..size).collect();
+        let mut community_degree = degree.clone();
+
+        // Local moving: greedily move nodes to the neighbouring community with the best
+        // modularity gain until nothing moves.
+        for _ in 0..100 {
+            let mut moved = false;
+            for i in 0..size {
+                let own = community[i];
+                community_degree[own] -= degree[i];
+                let mut links: BTreeMap<usize, f64> = BTreeMap::new();
+                for (&j, &w) in &adj[i] {
+                    if j != i {
+                        *links.entry(community[j]).or_default() += w;
+                    }
+                }
+                let gain = |c: usize, w: f64| w - community_degree[c] * degree[i] / total;
+                let mut best = (own, gain(own, links.get(&own).copied().unwrap_or(0.0)));
+                for (&c, &w) in &links {
+                    let g = gain(c, w);
+                    if g > best.1 + 1e-12 {
+                        best = (c, g);
+                    }
+                }
+                community_degree[best.0] += degree[i];
+                if best.0 != own {
+                    community[i] = best.0;
+                    moved = true;
+                }
+            }
+            if !moved {
+                break;
+            }
+        }
+
+        let mut renumber: BTreeMap<usize, usize> = BTreeMap::new();
+        for &c in &community {
+            let next = renumber.len();
+            renumber.entry(c).or_insert(next);
+        }
+        if renumber.len() == size {
+            break;
+        }
+        for m in &mut membership {
+            *m = renumber[&community[*m]];
+        }
+        // Aggregate: one node per community, keeping internal weight as a self-loop.
+        let mut next: Vec<BTreeMap<usize, f64>> = vec![BTreeMap::new(); renumber.len()];
+        for (i, links) in adj.iter().enumerate() {
+            let ci = renumber[&community[i]];
+            for (&j, &w) in links {
+                *next[ci].entry(renumber[&community[j]]).or_default() += w;
+            }
+        }
+        adj = next;
+    }
+
+    let mut sizes: BTreeMap<usize, (usize, usize)> = BTreeMap::new();
+    for (i, &c) in membership.iter().enumerate() {
+        let entry = sizes.entry(c).or_insert((0, i));
+        entry.0 += 1;
+    }
+    let mut order: Vec<(usize, (usize, usize))> = sizes.into_iter().collect();
+    order.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.1 .1.cmp(&b.1 .1)));
+    let label: BTreeMap<usize, usize> = order
+        .iter()
+        .enumerate()
+        .map(|(label, (c, _))| (*c, label))
+        .collect();
+    membership.iter().map(|c| label[c]).collect()
+}
+
+/// Weighted PageRank (damping 0.85) over `n` nodes, scaled so the scores average 1.0 and
+/// rounded to 4 decimals. Mass from nodes without outgoing edges is spread evenly.
+fn page_rank(n: usize, edges: &[(usize, usize, f64)]) -> Vec<f64> {
+    const DAMPING: f64 = 0.85;
+    if n == 0 {
+        return vec![];
+    }
+    let mut out_weight = vec![0.0f64; n];
+    for &(s, _, w) in edges {
+        out_weight[s] += w;
+    }
+
+    let uniform = 1.0 / n as f64;
+    let mut rank = vec![uniform; n];
+    for _ in 0..100 {
+        let dangling: f64 = (0..n)
+            .filter(|&i| out_weight[i] == 0.0)
+            .map(|i| rank[i])
+            .sum();
+        let base = (1.0 - DAMPING) * uniform + DAMPING * dangling * uniform;
+        let mut next = vec![base; n];
+        for &(s, t, w) in edges {
+            next[t] += DAMPING * rank[s] * w / out_weight[s];
+        }
+        let delta: f64 = rank.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+        rank = next;
+        if delta < 1e-10 {
+            break;
+        }
+    }
+    rank.iter()
+        .map(|r| (r * n as f64 * 1e4).round() / 1e4)
+        .collect()
 }
 
 // Backward-compatible alias (older clients may still reference this name).
@@ -549,9 +993,14 @@ fn size_class_from_bytes(bytes: u64) -> String {
     }
 }
 
-fn est_tokens_from_bytes(bytes: u64) -> u64 {
-    // Match the simple heuristic used elsewhere: ~4 chars per token.
-    ((bytes as f64) / 4.0).ceil() as u64
+/// Map token estimates come from the repo's `token_estimator` config, so they agree with
+/// the numbers reported by slices.
+fn estimator_for(repo_root: &Path) -> HeuristicEstimator {
+    HeuristicEstimator::from_config(&load_config(repo_root).token_estimator)
+}
+
+fn est_tokens_for_file(estimator: &dyn TokenEstimator, path: &Path, bytes: u64) -> u64 {
+    estimator.estimate_bytes(bytes, lang_hint(path)) as u64
 }
 
 fn is_module_marker_file(name: &str) -> bool {
@@ -589,27 +1038,33 @@ fn resolve_ts_import(repo_root: &Path, from_file_abs: &Path, imp: &str) -> Optio
         return None;
     }
 
-    let base_dir = from_file_abs.parent()?;
+    resolve_ts_candidate(repo_root, &from_file_abs.parent()?.join(imp))
+}
 
+/// The file a JS-style specifier path names: `base` itself, `base.<ext>` or
+/// `base/index.<ext>`, restricted to the repo.
+fn resolve_ts_candidate(repo_root: &Path, base: &Path) -> Option<PathBuf> {
     let exts = [
         "ts", "tsx", "js", "jsx", "json", "md", "toml", "css", "html",
     ];
     let mut candidates: Vec<PathBuf> = Vec::new();
 
-    candidates.push(base_dir.join(imp));
+    candidates.push(base.to_path_buf());
+    let base_str = base.as_os_str().to_string_lossy();
     for e in exts {
-        candidates.push(base_dir.join(format!("{}.{}", imp, e)));
+        candidates.push(PathBuf::from(format!("{}.{}", base_str, e)));
     }
     for e in ["ts", "tsx", "js", "jsx"] {
-        candidates.push(base_dir.join(imp).join(format!("index.{}", e)));
+        candidates.push(base.join(format!("index.{}", e)));
     }
 
     for cand in candidates {
-        if !cand.exists() {
+        // Directories are never targets (`from . import x` would otherwise match the package dir).
+        if !cand.is_file() {
             continue;
         }
-        let cand_abs = cand.canonicalize().unwrap_or(cand);
-        if cand_abs.strip_prefix(repo_root).is_ok() {
+        let cand_abs = normalize_abs(&cand);
+        if relative_to(&cand_abs, repo_root).is_some() {
             return Some(cand_abs);
         }
     }
@@ -617,114 +1072,881 @@ fn resolve_ts_import(repo_root: &Path, from_file_abs: &Path, imp: &str) -> Optio
     None
 }
 
-fn find_owner_module(
-    mut dir: &Path,
-    stop_at: &Path,
-    module_roots: &BTreeSet<PathBuf>,
-) -> Option<PathBuf> {
-    loop {
-        if module_roots.contains(dir) {
-            return Some(dir.to_path_buf());
+/// Parsed project files memoized across [`resolve_native_import`] calls.
+#[derive(Default)]
+struct ImportCache {
+    /// `composer.json` → PSR-4 roots.
+    psr4: BTreeMap<PathBuf, Vec<(String, Vec<PathBuf>)>>,
+    /// `tsconfig.json`/`jsconfig.json` → path aliases (`None` when unreadable).
+    tsconfig: BTreeMap<PathBuf, Option<TsPathAliases>>,
+    /// npm/yarn/pnpm workspace packages of the repo root, longest name first.
+    npm_packages: Option<Vec<(String, PathBuf)>>,
+    /// Barrel file → the files it re-exports (`None` when it isn't a pure barrel).
+    barrels: BTreeMap<PathBuf, Option<Vec<PathBuf>>>,
+}
+
+/// `export * from "x"`, `export * as ns from "x"`, `export { a, b as c } from "x"` (and
+/// their `export type` forms); group 1 is the specifier.
+fn reexport_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r#"export\s+(?:type\s+)?(?:\*(?:\s+as\s+[\w$]+)?|\{[^}]*\})\s*from\s*["']([^"']+)["']\s*;?"#,
+        )
+        .unwrap()
+    })
+}
+
+/// The files a JS/TS barrel re-exports, when `file_abs` consists of nothing but
+/// `export ... from` statements (and comments). Memoized in `cache`.
+fn barrel_reexports(
+    repo_root: &Path,
+    file_abs: &Path,
+    cache: &mut ImportCache,
+) -> Option<Vec<PathBuf>> {
+    if let Some(known) = cache.barrels.get(file_abs) {
+        return known.clone();
+    }
+    let ext = file_abs
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let is_script = matches!(
+        ext.as_str(),
+        "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs" | "mts" | "cts"
+    );
+    let targets = is_script
+        .then(|| std::fs::read_to_string(file_abs).ok())
+        .flatten()
+        .and_then(|text| {
+            let code = strip_jsonc(&text);
+            let re = reexport_regex();
+            if !re.replace_all(&code, "").trim().is_empty() {
+                return None;
+            }
+            let targets: Vec<PathBuf> = re
+                .captures_iter(&code)
+                .filter_map(|c| {
+                    let spec = c.get(1)?.as_str();
+                    resolve_native_import(repo_root, file_abs, spec, cache)
+                        .or_else(|| resolve_ts_import(repo_root, file_abs, spec))
+                        .map(|p| normalize_abs(&p))
+                })
+                .collect();
+            (!targets.is_empty()).then_some(targets)
+        });
+    cache
+        .barrels
+        .insert(file_abs.to_path_buf(), targets.clone());
+    targets
+}
+
+/// Follow barrel re-export chains from a resolved import to the files that implement it.
+/// Non-barrels (and barrels whose re-exports don't resolve) come back unchanged.
+fn follow_barrels(repo_root: &Path, file_abs: PathBuf, cache: &mut ImportCache) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    let mut seen: BTreeSet<PathBuf> = BTreeSet::new();
+    let mut work = vec![file_abs];
+    while let Some(file) = work.pop() {
+        if !seen.insert(file.clone()) {
+            continue;
         }
-        if dir == stop_at {
-            return None;
+        match barrel_reexports(repo_root, &file, cache) {
+            Some(targets) => work.extend(targets.into_iter().rev()),
+            None => out.push(file),
         }
-        dir = dir.parent()?;
     }
+    out
 }
 
-/// High-level architecture graph: nodes are module roots; edges are weighted imports between modules.
-pub fn build_module_graph(repo_root: &Path, root: &Path) -> Result<ModuleGraph> {
-    let root_abs = if root.is_absolute() {
-        root.to_path_buf()
-    } else {
-        repo_root.join(root)
+/// Resolve imports that follow their language's own rules rather than JS-style
+/// relative specifiers (PHP `use`/`require`, tsconfig `paths`/`baseUrl` aliases, Rust
+/// `use`/`mod`, Python modules, Zig `@import`, Lua `require`, Dart URIs, Elixir module
+/// names, shell `source`, protobuf `import`, OCaml `open`).
+/// Returns `None` for everything else so callers can fall back to [`resolve_ts_import`].
+fn resolve_native_import(
+    repo_root: &Path,
+    from_file_abs: &Path,
+    imp: &str,
+    cache: &mut ImportCache,
+) -> Option<PathBuf> {
+    let imp = imp.trim();
+    match from_file_abs.extension().and_then(|e| e.to_str())? {
+        "php" if !imp.starts_with('.') => {
+            resolve_php_import(repo_root, from_file_abs, imp, &mut cache.psr4)
+        }
+        "ts" | "tsx" | "mts" | "cts" | "js" | "jsx" | "mjs" | "cjs" if !imp.starts_with('.') => {
+            resolve_ts_alias(repo_root, from_file_abs, imp, &mut cache.tsconfig).or_else(|| {
+                let packages = cache.npm_packages.get_or_insert_with(|| {
+                    let mut p = npm_workspace_packages(repo_root);
+                    p.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+                    p
+                });
+                resolve_workspace_package_import(repo_root, packages, imp)
+            })
+        }
+        "zig" => resolve_zig_import(repo_root, from_file_abs, imp),
+        "lua" => resolve_lua_import(repo_root, from_file_abs, imp),
+        "dart" => resolve_dart_import(repo_root, from_file_abs, imp),
+        "ex" | "exs" => resolve_elixir_import(repo_root, from_file_abs, imp),
+        "sh" | "bash" | "zsh" => resolve_shell_include(repo_root, from_file_abs, imp),
+        "proto" => resolve_proto_import(repo_root, from_file_abs, imp),
+        "ml" => resolve_ocaml_import(repo_root, from_file_abs, imp),
+        "rs" => resolve_rust_import(repo_root, from_file_abs, imp),
+        "py" | "ipynb" => resolve_python_import(repo_root, from_file_abs, imp),
+        _ => None,
     }
-    .canonicalize()
-    .unwrap_or_else(|_| repo_root.join(root));
+}
 
-    if !root_abs.exists() {
-        anyhow::bail!("Graph root not found: {}", root_abs.display());
-    }
-    if !root_abs.is_dir() {
-        anyhow::bail!("Graph root is not a directory: {}", root_abs.display());
+/// Resolve `@org/pkg` or `@org/pkg/sub/path` to a file inside the workspace package of
+/// that name: the subpath (also tried under `src/`), or for the bare name the manifest
+/// entry point, `index.*` or `src/index.*`. Falls back to the package's `package.json`
+/// so the edge still lands in the owning module when entries point at build output.
+fn resolve_workspace_package_import(
+    repo_root: &Path,
+    packages: &[(String, PathBuf)],
+    imp: &str,
+) -> Option<PathBuf> {
+    let (name, dir) = packages.iter().find(|(name, _)| {
+        imp == name
+            || imp
+                .strip_prefix(name.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+    })?;
+    let subpath = imp[name.len()..].trim_start_matches('/');
+    if !subpath.is_empty() {
+        return resolve_ts_candidate(repo_root, &dir.join(subpath))
+            .or_else(|| resolve_ts_candidate(repo_root, &dir.join("src").join(subpath)));
     }
 
-    // 1) Discover module roots (directories containing marker files).
-    let mut module_roots: BTreeSet<PathBuf> = BTreeSet::new();
-    module_roots.insert(root_abs.clone());
+    let manifest = dir.join("package.json");
+    let pkg: serde_json::Value = std::fs::read_to_string(&manifest)
+        .ok()
+        .and_then(|t| serde_json::from_str(&t).ok())
+        .unwrap_or_default();
+    let exports_main = match pkg.get("exports") {
+        Some(serde_json::Value::String(s)) => Some(s.as_str()),
+        Some(e) => e.get(".").and_then(|d| {
+            d.as_str()
+                .or_else(|| d.get("import").and_then(|v| v.as_str()))
+                .or_else(|| d.get("default").and_then(|v| v.as_str()))
+        }),
+        None => None,
+    };
+    let entry = ["source", "types", "module", "main"]
+        .iter()
+        .filter_map(|k| pkg.get(*k).and_then(|v| v.as_str()))
+        .chain(exports_main)
+        .chain(["index", "src/index"])
+        .find_map(|entry| resolve_ts_candidate(repo_root, &dir.join(entry)))
+        .or_else(|| {
+            let m = normalize_abs(&manifest);
+            relative_to(&m, repo_root).map(|_| m)
+        });
+    entry
+}
 
-    let walker = WalkBuilder::new(&root_abs)
-        .standard_filters(true)
-        .hidden(false)
-        .max_depth(Some(25))
-        .filter_entry(|entry| {
-            let name = entry.file_name().to_str().unwrap_or("");
-            if should_skip_dir_name(name) {
-                return false;
-            }
-            if path_has_forbidden_component(entry.path()) {
-                return false;
-            }
-            true
+/// Module resolution settings from a `tsconfig.json` (after following `extends`).
+#[derive(Debug, Clone, Default)]
+struct TsPathAliases {
+    /// Directory `paths` targets are relative to (`baseUrl`, else the declaring config's dir).
+    paths_base: PathBuf,
+    /// `compilerOptions.paths` patterns with their targets.
+    paths: Vec<(String, Vec<String>)>,
+    /// `compilerOptions.baseUrl`, against which bare specifiers also resolve.
+    base_url: Option<PathBuf>,
+}
+
+/// Resolve a bare TS/JS specifier (`@app/utils`) through the nearest tsconfig/jsconfig:
+/// the longest matching `paths` pattern first, then `baseUrl`. Package imports that
+/// match neither have no in-repo target.
+fn resolve_ts_alias(
+    repo_root: &Path,
+    from_file_abs: &Path,
+    imp: &str,
+    cache: &mut BTreeMap<PathBuf, Option<TsPathAliases>>,
+) -> Option<PathBuf> {
+    let root = normalize_abs(repo_root);
+    let config = from_file_abs
+        .ancestors()
+        .skip(1)
+        .take_while(|d| d.starts_with(&root))
+        .flat_map(|d| [d.join("tsconfig.json"), d.join("jsconfig.json")])
+        .find(|p| p.is_file())?;
+    let aliases = cache
+        .entry(config.clone())
+        .or_insert_with(|| read_tsconfig_aliases(&config, 0))
+        .as_ref()?;
+
+    let mut matches: Vec<(usize, &str, &Vec<String>)> = aliases
+        .paths
+        .iter()
+        .filter_map(|(pattern, targets)| {
+            let (prefix, suffix) = pattern.split_once('*').unwrap_or((pattern.as_str(), ""));
+            let captured = if pattern.contains('*') {
+                imp.strip_prefix(prefix)?.strip_suffix(suffix)?
+            } else if imp == pattern {
+                ""
+            } else {
+                return None;
+            };
+            Some((prefix.len(), captured, targets))
         })
-        .build();
+        .collect();
+    matches.sort_by_key(|m| std::cmp::Reverse(m.0));
+    for (_, captured, targets) in matches {
+        for target in targets {
+            let path = aliases.paths_base.join(target.replacen('*', captured, 1));
+            if let Some(found) = resolve_ts_candidate(repo_root, &path) {
+                return Some(found);
+            }
+        }
+    }
 
-    for ent in walker {
-        let Ok(ent) = ent else { continue };
-        if !ent.file_type().map(|t| t.is_file()).unwrap_or(false) {
-            continue;
+    resolve_ts_candidate(repo_root, &aliases.base_url.as_ref()?.join(imp))
+}
+
+/// Read `compilerOptions.baseUrl`/`paths` from a tsconfig, inheriting from relative
+/// `extends` configs (the extending file's settings win).
+fn read_tsconfig_aliases(config: &Path, depth: usize) -> Option<TsPathAliases> {
+    let text = std::fs::read_to_string(config).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&strip_jsonc(&text)).ok()?;
+    let dir = config.parent()?;
+
+    let mut out = match json.get("extends").and_then(|v| v.as_str()) {
+        Some(parent) if parent.starts_with('.') && depth < 8 => {
+            let mut parent_path = dir.join(parent);
+            if !parent_path.is_file() {
+                parent_path = dir.join(format!("{parent}.json"));
+            }
+            read_tsconfig_aliases(&parent_path, depth + 1).unwrap_or_default()
         }
-        let p = ent.path();
-        let Some(name) = p.file_name().and_then(|s| s.to_str()) else {
-            continue;
-        };
-        if !is_module_marker_file(name) {
-            continue;
+        _ => TsPathAliases::default(),
+    };
+
+    let options = json.get("compilerOptions");
+    if let Some(base) = options
+        .and_then(|o| o.get("baseUrl"))
+        .and_then(|v| v.as_str())
+    {
+        out.base_url = Some(dir.join(base));
+        out.paths_base = dir.join(base);
+    }
+    if let Some(paths) = options
+        .and_then(|o| o.get("paths"))
+        .and_then(|v| v.as_object())
+    {
+        out.paths = paths
+            .iter()
+            .map(|(pattern, targets)| {
+                let targets = targets
+                    .as_array()
+                    .map(|a| {
+                        a.iter()
+                            .filter_map(|t| t.as_str().map(String::from))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (pattern.clone(), targets)
+            })
+            .collect();
+        if out.base_url.is_none() {
+            out.paths_base = dir.to_path_buf();
         }
-        let Some(parent) = p.parent() else { continue };
-        module_roots.insert(parent.to_path_buf());
     }
+    Some(out)
+}
 
-    // 2) Assign files to their owning module (nearest ancestor module root).
-    #[derive(Default)]
-    struct ModuleAcc {
-        bytes: u64,
-        file_count: u64,
-        files: Vec<PathBuf>,
+/// Zig `@import("path.zig")` is relative to the importing file; package imports
+/// (`std`, `build.zig.zon` dependencies) have no in-repo target.
+fn resolve_zig_import(repo_root: &Path, from_file_abs: &Path, imp: &str) -> Option<PathBuf> {
+    if !imp.ends_with(".zig") {
+        return None;
     }
-
-    let mut modules: BTreeMap<PathBuf, ModuleAcc> = BTreeMap::new();
-    for r in &module_roots {
-        modules.entry(r.clone()).or_default();
+    let cand = from_file_abs.parent()?.join(imp);
+    if !cand.is_file() {
+        return None;
     }
+    let abs = normalize_abs(&cand);
+    relative_to(&abs, repo_root).is_some().then_some(abs)
+}
 
-    let walker2 = WalkBuilder::new(&root_abs)
-        .standard_filters(true)
-        .hidden(false)
-        .max_depth(Some(25))
-        .filter_entry(|entry| {
-            let name = entry.file_name().to_str().unwrap_or("");
-            if should_skip_dir_name(name) {
-                return false;
-            }
-            if path_has_forbidden_component(entry.path()) {
-                return false;
-            }
-            true
-        })
-        .build();
+/// Lua `require("a.b")` follows the default `package.path` (`./?.lua;./?/init.lua`)
+/// from the repo root, plus the requiring file's directory and a Neovim-style `lua/` root.
+fn resolve_lua_import(repo_root: &Path, from_file_abs: &Path, imp: &str) -> Option<PathBuf> {
+    if imp.is_empty() || imp.starts_with('.') || imp.contains('/') {
+        return None;
+    }
+    let rel = imp.replace('.', "/");
+    let bases = [
+        Some(repo_root.to_path_buf()),
+        from_file_abs.parent().map(Path::to_path_buf),
+        Some(repo_root.join("lua")),
+    ];
+    bases
+        .into_iter()
+        .flatten()
+        .flat_map(|b| [b.join(format!("{rel}.lua")), b.join(&rel).join("init.lua")])
+        .filter(|c| c.is_file())
+        .map(|c| normalize_abs(&c))
+        .find(|c| relative_to(c, repo_root).is_some())
+}
 
-    for ent in walker2 {
-        let Ok(ent) = ent else { continue };
+/// Dart URIs: `package:<name>/x.dart` maps to `lib/x.dart` of the nearest `pubspec.yaml`
+/// declaring `<name>`; other non-`dart:` URIs are relative to the importing file.
+fn resolve_dart_import(repo_root: &Path, from_file_abs: &Path, imp: &str) -> Option<PathBuf> {
+    if imp.starts_with("dart:") {
+        return None;
+    }
+    let base_dir = from_file_abs.parent()?;
+    let cand = match imp.strip_prefix("package:") {
+        Some(rest) => {
+            let (package, path) = rest.split_once('/')?;
+            let pkg_root = base_dir
+                .ancestors()
+                .take_while(|d| d.starts_with(repo_root))
+                .find(|d| read_pubspec_name(&d.join("pubspec.yaml")).as_deref() == Some(package))?;
+            pkg_root.join("lib").join(path)
+        }
+        None => base_dir.join(imp),
+    };
+    if !cand.is_file() {
+        return None;
+    }
+    let abs = normalize_abs(&cand);
+    relative_to(&abs, repo_root).is_some().then_some(abs)
+}
+
+/// Elixir modules follow the Mix layout convention: `MyApp.HTTPClient` lives in
+/// `lib/my_app/http_client.ex` under the nearest `mix.exs` project.
+fn resolve_elixir_import(repo_root: &Path, from_file_abs: &Path, imp: &str) -> Option<PathBuf> {
+    let rel = imp
+        .split('.')
+        .map(elixir_underscore)
+        .collect::<Vec<_>>()
+        .join("/");
+    if rel.is_empty() {
+        return None;
+    }
+    let project = from_file_abs
+        .parent()?
+        .ancestors()
+        .take_while(|d| d.starts_with(repo_root))
+        .find(|d| d.join("mix.exs").is_file())?;
+    let cand = project.join("lib").join(format!("{rel}.ex"));
+    if !cand.is_file() {
+        return None;
+    }
+    let abs = normalize_abs(&cand);
+    relative_to(&abs, repo_root).is_some().then_some(abs)
+}
+
+/// `Macro.underscore` for a single alias segment (`HTTPClient` → `http_client`).
+fn elixir_underscore(segment: &str) -> String {
+    let chars: Vec<char> = segment.chars().collect();
+    let mut out = String::with_capacity(segment.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_lower)
+            {
+                out.push('_');
+            }
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
+
+/// Shell `source`/`.` paths: relative to the script, then to the repo root (scripts are
+/// usually run from there). Paths built from variables (`$HOME/...`) are skipped.
+fn resolve_shell_include(repo_root: &Path, from_file_abs: &Path, imp: &str) -> Option<PathBuf> {
+    if imp.is_empty() || imp.contains('$') || imp.contains('`') {
+        return None;
+    }
+    [from_file_abs.parent()?.join(imp), repo_root.join(imp)]
+        .into_iter()
+        .filter(|c| c.is_file())
+        .map(|c| normalize_abs(&c))
+        .find(|c| relative_to(c, repo_root).is_some())
+}
+
+/// Protobuf imports are relative to an include root (`protoc -I`). Without the build
+/// config, every directory from the importing file up to the repo root is tried as one.
+fn resolve_proto_import(repo_root: &Path, from_file_abs: &Path, imp: &str) -> Option<PathBuf> {
+    from_file_abs
+        .parent()?
+        .ancestors()
+        .take_while(|d| d.starts_with(repo_root))
+        .map(|d| d.join(imp))
+        .filter(|c| c.is_file())
+        .map(|c| normalize_abs(&c))
+        .find(|c| relative_to(c, repo_root).is_some())
+}
+
+/// OCaml `open Foo.Bar` names compilation unit `Foo` (`foo.ml`). Dune compiles every
+/// module of a library from one directory, so only the opening file's directory is searched;
+/// modules from other libraries and opam packages have no in-repo target.
+fn resolve_ocaml_import(repo_root: &Path, from_file_abs: &Path, imp: &str) -> Option<PathBuf> {
+    let unit = imp.split('.').next()?.trim();
+    let mut chars = unit.chars();
+    let first = chars.next()?;
+    if !first.is_ascii_uppercase() {
+        return None;
+    }
+    let dir = from_file_abs.parent()?;
+    let uncapitalized = format!("{}{}", first.to_ascii_lowercase(), chars.as_str());
+    [uncapitalized.as_str(), unit]
+        .iter()
+        .map(|name| dir.join(format!("{name}.ml")))
+        .filter(|c| c.is_file())
+        .map(|c| normalize_abs(&c))
+        .find(|c| c != &normalize_abs(from_file_abs) && relative_to(c, repo_root).is_some())
+}
+
+/// Python `import a.b` / `from ..pkg import x` to the module file: `b.py` or
+/// `b/__init__.py`. Relative imports climb one package per extra dot; absolute ones are
+/// tried from the importing file's top-level package root, the nearest project dir
+/// (`pyproject.toml`/`setup.py`/`setup.cfg`) and its `src/`, and the repo root and its
+/// `src/`. Trailing segments that name objects rather than modules are dropped.
+fn resolve_python_import(repo_root: &Path, from_file_abs: &Path, imp: &str) -> Option<PathBuf> {
+    let root = normalize_abs(repo_root);
+    let from_file_abs = normalize_abs(from_file_abs);
+    let dots = imp.len() - imp.trim_start_matches('.').len();
+    let segments: Vec<&str> = imp[dots..].split('.').filter(|s| !s.is_empty()).collect();
+
+    let bases: Vec<PathBuf> = if dots > 0 {
+        let mut base = from_file_abs.parent()?.to_path_buf();
+        for _ in 1..dots {
+            base = base.parent()?.to_path_buf();
+        }
+        vec![base]
+    } else {
+        let dir = from_file_abs.parent()?;
+        let package_root = dir
+            .ancestors()
+            .find(|d| !d.join("__init__.py").is_file())
+            .map(Path::to_path_buf);
+        let project = dir
+            .ancestors()
+            .take_while(|d| d.starts_with(&root))
+            .find(|d| {
+                ["pyproject.toml", "setup.py", "setup.cfg"]
+                    .iter()
+                    .any(|m| d.join(m).is_file())
+            });
+        let mut bases: Vec<PathBuf> = package_root.into_iter().collect();
+        if let Some(project) = project {
+            bases.extend([project.to_path_buf(), project.join("src")]);
+        }
+        bases.extend([root.clone(), root.join("src")]);
+        bases
+    };
+
+    for base in bases {
+        if !base.starts_with(&root) {
+            continue;
+        }
+        if segments.is_empty() {
+            let init = base.join("__init__.py");
+            if init.is_file() {
+                return Some(init);
+            }
+            continue;
+        }
+        for n in (1..=segments.len()).rev() {
+            let module = segments[..n].iter().fold(base.clone(), |p, s| p.join(s));
+            let file = module.with_extension("py");
+            let candidate = [file, module.join("__init__.py")]
+                .into_iter()
+                .find(|c| c.is_file());
+            if let Some(found) = candidate {
+                return (found != from_file_abs).then_some(found);
+            }
+            // A relative import's parent package is a valid fallback (`from . import name`).
+            if dots > 0 && n == 1 && base.join("__init__.py").is_file() {
+                let init = base.join("__init__.py");
+                return (init != from_file_abs).then_some(init);
+            }
+        }
+    }
+    None
+}
+
+/// Rust `use crate::/self::/super::...` paths and `mod foo;` declarations, resolved to the
+/// deepest module file they name (`foo.rs` or `foo/mod.rs`); trailing item segments are
+/// ignored and `{...}` groups resolve by their common prefix. Paths into other crates
+/// have no in-repo target.
+fn resolve_rust_import(repo_root: &Path, from_file_abs: &Path, imp: &str) -> Option<PathBuf> {
+    let from_file_abs = normalize_abs(from_file_abs);
+    let stem = from_file_abs.file_stem()?.to_str()?;
+    // Directory holding the current module's child modules.
+    let self_dir = if matches!(stem, "mod" | "lib" | "main") {
+        from_file_abs.parent()?.to_path_buf()
+    } else {
+        from_file_abs.parent()?.join(stem)
+    };
+
+    if let Some(name) = imp.strip_prefix("mod ") {
+        return rust_module_file(&self_dir, name.trim());
+    }
+
+    let path = imp.split(" as ").next()?.trim().trim_start_matches("::");
+    let path = path.split("::{").next()?.trim_end_matches("::*");
+    let mut segments = path.split("::").map(str::trim).peekable();
+
+    let (mut dir, mut target) = match segments.peek().copied()? {
+        "crate" => {
+            segments.next();
+            let src = rust_crate_src_dir(repo_root, &from_file_abs)?;
+            let root_file = ["lib.rs", "main.rs"]
+                .iter()
+                .map(|f| src.join(f))
+                .find(|f| f.is_file())?;
+            (src, root_file)
+        }
+        "self" | "super" => {
+            let mut dir = self_dir;
+            let mut target = from_file_abs.clone();
+            while segments.peek() == Some(&"super") || segments.peek() == Some(&"self") {
+                if segments.next() == Some("super") {
+                    dir = dir.parent()?.to_path_buf();
+                    target = rust_dir_module_file(repo_root, &from_file_abs, &dir)?;
+                }
+            }
+            (dir, target)
+        }
+        // Edition-2018 paths may start at a child module of the current one.
+        first => {
+            let child = rust_module_file(&self_dir, first)?;
+            segments.next();
+            (self_dir.join(first), child)
+        }
+    };
+
+    for seg in segments {
+        let Some(file) = rust_module_file(&dir, seg) else {
+            break;
+        };
+        target = file;
+        dir = dir.join(seg);
+    }
+    let target = normalize_abs(&target);
+    (target != from_file_abs && relative_to(&target, repo_root).is_some()).then_some(target)
+}
+
+/// `dir/name.rs` or `dir/name/mod.rs`, whichever exists.
+fn rust_module_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    [
+        dir.join(format!("{name}.rs")),
+        dir.join(name).join("mod.rs"),
+    ]
+    .into_iter()
+    .find(|p| p.is_file())
+}
+
+/// The file defining the module whose children live in `dir` (the crate root for `src/`).
+fn rust_dir_module_file(repo_root: &Path, from_file_abs: &Path, dir: &Path) -> Option<PathBuf> {
+    if rust_crate_src_dir(repo_root, from_file_abs).as_deref() == Some(dir) {
+        return ["lib.rs", "main.rs"]
+            .iter()
+            .map(|f| dir.join(f))
+            .find(|f| f.is_file());
+    }
+    rust_module_file(dir.parent()?, dir.file_name()?.to_str()?)
+}
+
+/// `src/` of the nearest enclosing Cargo package (within the repo).
+fn rust_crate_src_dir(repo_root: &Path, from_file_abs: &Path) -> Option<PathBuf> {
+    let repo_root = normalize_abs(repo_root);
+    from_file_abs
+        .ancestors()
+        .skip(1)
+        .take_while(|d| d.starts_with(&repo_root))
+        .find(|d| d.join("Cargo.toml").is_file())
+        .map(|d| d.join("src"))
+}
+
+/// PSR-4 autoload roots declared in a `composer.json` (`autoload` and `autoload-dev`),
+/// as (namespace prefix, directories) pairs with the longest prefix first.
+fn read_composer_psr4(composer_json: &Path) -> Vec<(String, Vec<PathBuf>)> {
+    let Ok(text) = std::fs::read_to_string(composer_json) else {
+        return Vec::new();
+    };
+    let Ok(v) = serde_json::from_str::<serde_json::Value>(&text) else {
+        return Vec::new();
+    };
+    let base = composer_json.parent().unwrap_or(Path::new("."));
+
+    let mut out: Vec<(String, Vec<PathBuf>)> = Vec::new();
+    for section in ["autoload", "autoload-dev"] {
+        let Some(map) = v
+            .get(section)
+            .and_then(|a| a.get("psr-4"))
+            .and_then(|m| m.as_object())
+        else {
+            continue;
+        };
+        for (prefix, dirs) in map {
+            let dirs: Vec<PathBuf> = match dirs {
+                serde_json::Value::String(d) => vec![base.join(d)],
+                serde_json::Value::Array(a) => a
+                    .iter()
+                    .filter_map(|d| d.as_str())
+                    .map(|d| base.join(d))
+                    .collect(),
+                _ => continue,
+            };
+            out.push((prefix.trim_start_matches('\\').to_string(), dirs));
+        }
+    }
+    out.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+    out
+}
+
+/// Resolve a PHP import: `require`/`include` paths against the including file (then the
+/// repo root), and `use` names through the PSR-4 map of the nearest `composer.json`.
+///
+/// `psr4_cache` memoizes parsed composer files across calls.
+fn resolve_php_import(
+    repo_root: &Path,
+    from_file_abs: &Path,
+    imp: &str,
+    psr4_cache: &mut BTreeMap<PathBuf, Vec<(String, Vec<PathBuf>)>>,
+) -> Option<PathBuf> {
+    let imp = imp.trim();
+    let base_dir = from_file_abs.parent()?;
+
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    if imp.ends_with(".php") {
+        candidates.push(base_dir.join(imp));
+        candidates.push(repo_root.join(imp));
+    } else {
+        let composer = base_dir
+            .ancestors()
+            .take_while(|d| d.starts_with(repo_root))
+            .map(|d| d.join("composer.json"))
+            .find(|p| p.is_file())?;
+        let roots = psr4_cache
+            .entry(composer.clone())
+            .or_insert_with(|| read_composer_psr4(&composer));
+        let name = imp.trim_start_matches('\\');
+        for (prefix, dirs) in roots.iter() {
+            let Some(rest) = name.strip_prefix(prefix.as_str()) else {
+                continue;
+            };
+            let rel = format!("{}.php", rest.replace('\\', "/"));
+            candidates.extend(dirs.iter().map(|d| d.join(&rel)));
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter(|c| c.is_file())
+        .map(|c| normalize_abs(&c))
+        .find(|c| relative_to(c, repo_root).is_some())
+}
+
+fn find_owner_module(
+    mut dir: &Path,
+    stop_at: &Path,
+    module_roots: &BTreeSet<PathBuf>,
+) -> Option<PathBuf> {
+    loop {
+        if module_roots.contains(dir) {
+            return Some(dir.to_path_buf());
+        }
+        if dir == stop_at {
+            return None;
+        }
+        dir = dir.parent()?;
+    }
+}
+
+/// Directory holding one [`ModuleGraphCache`] per graph root, and one [`ChurnCache`] per
+/// directory churn was read for.
+pub const GRAPH_CACHE_DIR: &str = ".context-slicer/graph";
+
+/// The last module graph built for a root, plus each source file's imports keyed by its
+/// content hash, so rebuilds only re-analyze files that changed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ModuleGraphCache {
+    version: String,
+    /// Hash over every source file's path + content hash, every other file's path, size
+    /// and mtime under the root, and those of the files in each directory above the root up
+    /// to the repo root.
+    fingerprint: String,
+    files: BTreeMap<String, CachedImports>,
+    graph: Option<ModuleGraph>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedImports {
+    hash: String,
+    imports: Vec<String>,
+    symbols: u64,
+    lines: u64,
+}
+
+/// Feed a file's path, size and mtime into a [`ModuleGraphCache`] fingerprint.
+fn hash_file_stat(fingerprint: &mut blake3::Hasher, rel: &str, meta: Option<std::fs::Metadata>) {
+    let mtime = meta
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    fingerprint.update(rel.as_bytes());
+    fingerprint.update(&meta.map(|m| m.len()).unwrap_or(0).to_le_bytes());
+    fingerprint.update(&mtime.to_le_bytes());
+}
+
+fn module_graph_cache_path(repo_root: &Path, root_abs: &Path) -> PathBuf {
+    let key = rel_str(repo_root, root_abs).unwrap_or_else(|| normalize_slash(root_abs));
+    repo_root.join(GRAPH_CACHE_DIR).join(format!(
+        "{}.json",
+        &blake3::hash(key.as_bytes()).to_hex()[..16]
+    ))
+}
+
+/// High-level architecture graph: nodes are module roots; edges are weighted imports between modules.
+///
+/// Results are cached under [`GRAPH_CACHE_DIR`]; see [`ModuleGraphCache`].
+pub fn build_module_graph(repo_root: &Path, root: &Path) -> Result<ModuleGraph> {
+    let root_abs = if root.is_absolute() {
+        root.to_path_buf()
+    } else {
+        repo_root.join(root)
+    };
+    let root_abs = normalize_abs(&root_abs);
+
+    if !root_abs.exists() {
+        anyhow::bail!("Graph root not found: {}", root_abs.display());
+    }
+    if !root_abs.is_dir() {
+        anyhow::bail!("Graph root is not a directory: {}", root_abs.display());
+    }
+
+    // 1) Discover module roots (directories containing marker files).
+    let mut module_roots: BTreeSet<PathBuf> = BTreeSet::new();
+    module_roots.insert(root_abs.clone());
+    let mut crate_dirs: Vec<PathBuf> = Vec::new();
+
+    // Entries are judged by name (every directory below the root gets checked), never by
+    // absolute path, so repos checked out under e.g. `/tmp` or `~/build` still map.
+    let walker = WalkBuilder::new(&root_abs)
+        .standard_filters(true)
+        .add_custom_ignore_filename(SLICERIGNORE_FILE)
+        .hidden(false)
+        .max_depth(Some(25))
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_str().unwrap_or("");
+            if should_skip_dir_name(name) {
+                return false;
+            }
+            true
+        })
+        .build();
+
+    for ent in walker {
+        let Ok(ent) = ent else { continue };
+        if !ent.file_type().map(|t| t.is_file()).unwrap_or(false) {
+            continue;
+        }
+        let p = ent.path();
+        let Some(name) = p.file_name().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let Some(parent) = p.parent() else { continue };
+        if name == "Cargo.toml" {
+            crate_dirs.push(parent.to_path_buf());
+        }
+        if !is_module_marker_file(name) {
+            continue;
+        }
+        module_roots.insert(parent.to_path_buf());
+    }
+
+    // 2) Assign files to their owning module (nearest ancestor module root).
+    #[derive(Default)]
+    struct ModuleAcc {
+        bytes: u64,
+        est_tokens: u64,
+        file_count: u64,
+        languages: BTreeMap<String, u64>,
+        files: Vec<PathBuf>,
+    }
+
+    let estimator = estimator_for(repo_root);
+
+    let mut modules: BTreeMap<PathBuf, ModuleAcc> = BTreeMap::new();
+    for r in &module_roots {
+        modules.entry(r.clone()).or_default();
+    }
+
+    let cache_path = module_graph_cache_path(repo_root, &root_abs);
+    let mut cache: ModuleGraphCache = std::fs::read(&cache_path)
+        .ok()
+        .and_then(|raw| serde_json::from_slice(&raw).ok())
+        .filter(|c: &ModuleGraphCache| c.version == env!("CARGO_PKG_VERSION"))
+        .unwrap_or_default();
+    let mut fingerprint = blake3::Hasher::new();
+
+    // Resolution also looks upwards for the nearest tsconfig, composer.json, Cargo manifest,
+    // ... so the files beside each directory between the root and the repo root count too.
+    let repo_abs = normalize_abs(repo_root);
+    for dir in root_abs
+        .ancestors()
+        .skip(1)
+        .take_while(|d| d.starts_with(&repo_abs))
+    {
+        let Ok(read) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let mut files: Vec<(PathBuf, std::fs::Metadata)> = read
+            .flatten()
+            .filter_map(|e| Some((e.path(), e.metadata().ok()?)))
+            .filter(|(_, m)| m.is_file())
+            .collect();
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        for (p, meta) in files {
+            let rel = normalize_slash(p.strip_prefix(&repo_abs).unwrap_or(&p));
+            hash_file_stat(&mut fingerprint, &rel, Some(meta));
+        }
+    }
+
+    let walker2 = WalkBuilder::new(&root_abs)
+        .standard_filters(true)
+        .add_custom_ignore_filename(SLICERIGNORE_FILE)
+        .hidden(false)
+        .max_depth(Some(25))
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_str().unwrap_or("");
+            if should_skip_dir_name(name) {
+                return false;
+            }
+            true
+        })
+        .build();
+
+    for ent in walker2 {
+        let Ok(ent) = ent else { continue };
         if !ent.file_type().map(|t| t.is_file()).unwrap_or(false) {
             continue;
         }
         let p = ent.path();
-        if path_has_forbidden_component(p) {
+        if path_has_forbidden_component(p.strip_prefix(&root_abs).unwrap_or(p)) {
             continue;
         }
         if !is_allowed_ext(p) {
+            // Manifests and configs (package.json, tsconfig, Cargo.toml, ...) steer import
+            // resolution, so any change to them invalidates the cached graph.
+            let rel = normalize_slash(p.strip_prefix(&root_abs).unwrap_or(p));
+            hash_file_stat(&mut fingerprint, &rel, ent.metadata().ok());
             continue;
         }
         let Some(parent) = p.parent() else { continue };
@@ -733,7 +1955,11 @@ pub fn build_module_graph(repo_root: &Path, root: &Path) -> Result<ModuleGraph>
         let acc = modules.entry(owner).or_default();
         let sz = ent.metadata().map(|m| m.len()).unwrap_or(0);
         acc.bytes += sz;
+        acc.est_tokens += est_tokens_for_file(&estimator, p, sz);
         acc.file_count += 1;
+        if let Some(ext) = lang_hint(p) {
+            *acc.languages.entry(ext.to_lowercase()).or_default() += 1;
+        }
         acc.files.push(p.to_path_buf());
     }
 
@@ -742,10 +1968,7 @@ pub fn build_module_graph(repo_root: &Path, root: &Path) -> Result<ModuleGraph>
     let mut module_id_by_abs: BTreeMap<PathBuf, String> = BTreeMap::new();
 
     for (abs, acc) in &modules {
-        let rel = abs
-            .strip_prefix(repo_root)
-            .ok()
-            .map(|r| r.to_string_lossy().replace('\\', "/"));
+        let rel = relative_to(abs, repo_root).map(|r| normalize_slash(&r));
         let id = normalize_module_id(rel.as_deref().unwrap_or("."));
         module_id_by_abs.insert(abs.clone(), id.clone());
         nodes.push(ModuleNode {
@@ -754,7 +1977,16 @@ pub fn build_module_graph(repo_root: &Path, root: &Path) -> Result<ModuleGraph>
             path: id,
             file_count: acc.file_count,
             bytes: acc.bytes,
-            est_tokens: est_tokens_from_bytes(acc.bytes),
+            est_tokens: acc.est_tokens,
+            lines: 0,
+            symbols: 0,
+            avg_file_bytes: acc.bytes.checked_div(acc.file_count).unwrap_or(0),
+            languages: acc.languages.clone(),
+            importance: 0.0,
+            cluster: 0,
+            commits: 0,
+            last_changed: None,
+            owners: Vec::new(),
         });
     }
 
@@ -762,30 +1994,105 @@ pub fn build_module_graph(repo_root: &Path, root: &Path) -> Result<ModuleGraph>
 
     // 4) Edges: file imports -> module imports, weighted.
     let mut weights: BTreeMap<(String, String), u64> = BTreeMap::new();
+    let mut import_cache = ImportCache::default();
+
+    // Parse every file up front in parallel; import resolution below stays serial.
+    let sources: Vec<(String, PathBuf)> = modules
+        .iter()
+        .filter_map(|(module_abs, acc)| {
+            let src_mod_id = module_id_by_abs.get(module_abs)?;
+            Some(acc.files.iter().map(|f| (src_mod_id.clone(), f.clone())))
+        })
+        .flatten()
+        .collect();
+    let files: Vec<PathBuf> = sources.iter().map(|(_, f)| f.clone()).collect();
+
+    // Unchanged tree: reuse the last graph. Otherwise only files whose content hash moved
+    // are re-analyzed; the rest reuse their cached import lists.
+    let rels: Vec<String> = files
+        .iter()
+        .map(|f| rel_str(repo_root, f).unwrap_or_else(|| normalize_slash(f)))
+        .collect();
+    let hashes: Vec<Option<String>> = files
+        .par_iter()
+        .map(|f| crate::scanner::fingerprint(f))
+        .collect();
+    for (rel, hash) in rels.iter().zip(&hashes) {
+        fingerprint.update(rel.as_bytes());
+        fingerprint.update(hash.as_deref().unwrap_or("").as_bytes());
+    }
+    let fingerprint = fingerprint.finalize().to_hex().to_string();
+    if cache.fingerprint == fingerprint {
+        if let Some(mut graph) = cache.graph {
+            annotate_module_nodes(repo_root, &mut graph);
+            return Ok(graph);
+        }
+    }
 
-    for (module_abs, acc) in &modules {
-        let Some(src_mod_id) = module_id_by_abs.get(module_abs).cloned() else {
+    let stale: Vec<usize> = (0..files.len())
+        .filter(|&i| cache.files.get(&rels[i]).map(|c| Some(&c.hash)) != Some(hashes[i].as_ref()))
+        .collect();
+    let stale_files: Vec<PathBuf> = stale.iter().map(|&i| files[i].clone()).collect();
+    for (&i, analyzed) in stale
+        .iter()
+        .zip(analyze_files_cached(repo_root, &stale_files))
+    {
+        let Some(hash) = &hashes[i] else {
+            cache.files.remove(&rels[i]);
             continue;
         };
-        for file_abs in &acc.files {
-            let analyzed = match analyze_file(file_abs) {
-                Ok(v) => v,
-                Err(_) => continue,
-            };
+        // Files the analyzer can't handle (e.g. Markdown) still count towards `lines`.
+        let (imports, symbols) =
+            analyzed.map_or_else(|_| (Vec::new(), 0), |a| (a.imports, a.symbols.len() as u64));
+        let entry = CachedImports {
+            hash: hash.clone(),
+            imports,
+            symbols,
+            lines: std::fs::read(&files[i]).map_or(0, |b| count_lines(&b)),
+        };
+        cache.files.insert(rels[i].clone(), entry);
+    }
 
-            for imp in analyzed.imports {
-                let Some(dst_file_abs) = resolve_ts_import(repo_root, file_abs, &imp) else {
-                    continue;
-                };
+    let mut counts_by_module: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+    for (i, (src_mod_id, _)) in sources.iter().enumerate() {
+        let counts = counts_by_module.entry(src_mod_id.as_str()).or_default();
+        if let Some(cached) = cache.files.get(&rels[i]) {
+            counts.0 += cached.lines;
+            counts.1 += cached.symbols;
+        }
+    }
+    for node in &mut nodes {
+        (node.lines, node.symbols) = counts_by_module
+            .get(node.id.as_str())
+            .copied()
+            .unwrap_or_default();
+    }
+
+    for ((src_mod_id, file_abs), rel) in sources.iter().zip(&rels) {
+        let Some(cached) = cache.files.get(rel) else {
+            continue;
+        };
+
+        for imp in &cached.imports {
+            let dst = resolve_native_import(repo_root, file_abs, imp, &mut import_cache)
+                .or_else(|| resolve_ts_import(repo_root, file_abs, imp));
+            let Some(dst_file_abs) = dst else {
+                continue;
+            };
+            // Barrels re-exporting other modules count as imports of those modules.
+            let mut dst_mod_ids: BTreeSet<String> = BTreeSet::new();
+            for dst_file_abs in follow_barrels(repo_root, dst_file_abs, &mut import_cache) {
                 let Some(dst_parent) = dst_file_abs.parent() else {
                     continue;
                 };
                 let dst_owner = find_owner_module(dst_parent, &root_abs, &module_roots)
                     .unwrap_or_else(|| root_abs.clone());
-                let Some(dst_mod_id) = module_id_by_abs.get(&dst_owner).cloned() else {
-                    continue;
-                };
-                if dst_mod_id == src_mod_id {
+                if let Some(dst_mod_id) = module_id_by_abs.get(&dst_owner) {
+                    dst_mod_ids.insert(dst_mod_id.clone());
+                }
+            }
+            for dst_mod_id in dst_mod_ids {
+                if &dst_mod_id == src_mod_id {
                     continue;
                 }
                 *weights.entry((src_mod_id.clone(), dst_mod_id)).or_insert(0) += 1;
@@ -793,18 +2100,355 @@ pub fn build_module_graph(repo_root: &Path, root: &Path) -> Result<ModuleGraph>
         }
     }
 
-    let mut edges: Vec<ModuleEdge> = Vec::new();
-    for ((s, t), w) in weights {
-        edges.push(ModuleEdge {
-            id: format!("{}->{}", s, t),
-            source: s,
-            target: t,
-            weight: w,
-        });
+    // Crate-level edges from `path` dependencies, so workspaces show their structure even
+    // where no `use` resolves to a file. A crate is represented by the module owning its
+    // `src/lib.rs` (or `src/main.rs`).
+    let crate_module = |crate_dir: &Path| {
+        let crate_dir = normalize_abs(crate_dir);
+        let anchor = ["src/lib.rs", "src/main.rs"]
+            .iter()
+            .map(|f| crate_dir.join(f))
+            .find(|f| f.is_file())?;
+        let owner = find_owner_module(anchor.parent()?, &root_abs, &module_roots)?;
+        module_id_by_abs.get(&owner).cloned()
+    };
+    for crate_dir in &crate_dirs {
+        let Some(src_mod_id) = crate_module(crate_dir) else {
+            continue;
+        };
+        for dep_dir in crate::workspace::cargo_path_dependencies(crate_dir) {
+            let Some(dst_mod_id) = crate_module(&dep_dir) else {
+                continue;
+            };
+            if dst_mod_id != src_mod_id {
+                *weights.entry((src_mod_id.clone(), dst_mod_id)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut edges: Vec<ModuleEdge> = Vec::new();
+    for ((s, t), w) in weights {
+        edges.push(ModuleEdge {
+            id: format!("{}->{}", s, t),
+            source: s,
+            target: t,
+            weight: w,
+        });
+    }
+    edges.sort_by(|a, b| a.id.cmp(&b.id));
+    rank_module_nodes(&mut nodes, &edges);
+
+    let mut graph = ModuleGraph { nodes, edges };
+    // Not part of the fingerprint (new commits needn't touch the tree), so refreshed on hits.
+    annotate_module_nodes(repo_root, &mut graph);
+    let live: BTreeSet<&String> = rels.iter().collect();
+    cache.files.retain(|rel, _| live.contains(rel));
+    cache.version = env!("CARGO_PKG_VERSION").to_string();
+    cache.fingerprint = fingerprint;
+    cache.graph = Some(graph.clone());
+    let _ = store_json(&cache_path, &cache);
+
+    Ok(graph)
+}
+
+/// Nodes and edges added, removed or reweighted going from `before` to `after`, matched by id.
+pub fn diff_module_graphs(before: &ModuleGraph, after: &ModuleGraph) -> ModuleGraphDiff {
+    let before_nodes: BTreeSet<&str> = before.nodes.iter().map(|n| n.id.as_str()).collect();
+    let after_nodes: BTreeSet<&str> = after.nodes.iter().map(|n| n.id.as_str()).collect();
+    let before_edges: BTreeMap<&str, &ModuleEdge> =
+        before.edges.iter().map(|e| (e.id.as_str(), e)).collect();
+    let after_edges: BTreeMap<&str, &ModuleEdge> =
+        after.edges.iter().map(|e| (e.id.as_str(), e)).collect();
+
+    ModuleGraphDiff {
+        added_nodes: after
+            .nodes
+            .iter()
+            .filter(|n| !before_nodes.contains(n.id.as_str()))
+            .cloned()
+            .collect(),
+        removed_nodes: before
+            .nodes
+            .iter()
+            .filter(|n| !after_nodes.contains(n.id.as_str()))
+            .cloned()
+            .collect(),
+        added_edges: after
+            .edges
+            .iter()
+            .filter(|e| !before_edges.contains_key(e.id.as_str()))
+            .cloned()
+            .collect(),
+        removed_edges: before
+            .edges
+            .iter()
+            .filter(|e| !after_edges.contains_key(e.id.as_str()))
+            .cloned()
+            .collect(),
+        reweighted_edges: after
+            .edges
+            .iter()
+            .filter_map(|e| {
+                let old = before_edges.get(e.id.as_str())?;
+                (old.weight != e.weight).then(|| EdgeWeightChange {
+                    id: e.id.clone(),
+                    source: e.source.clone(),
+                    target: e.target.clone(),
+                    before: old.weight,
+                    after: e.weight,
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Module graph of `repo_root` as of git revision `rev`, built from a temporary detached
+/// worktree so the working copy is untouched. Ids match [`build_module_graph`] on the
+/// live tree, even when `repo_root` is a subdirectory of the git checkout.
+pub fn build_module_graph_at_rev(repo_root: &Path, rev: &str, root: &Path) -> Result<ModuleGraph> {
+    let git = |args: &[&str]| -> Result<String> {
+        let output = std::process::Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(args)
+            .output()
+            .context("Failed to run `git` — is it installed?")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let commit = git(&["rev-parse", "--verify", &format!("{rev}^{{commit}}")])?;
+    let prefix = git(&["rev-parse", "--show-prefix"])?;
+    let dir = std::env::temp_dir().join(format!(
+        "cortexast-graph-{}-{}",
+        std::process::id(),
+        &commit[..commit.len().min(12)]
+    ));
+    let dir_str = dir.to_string_lossy().to_string();
+    git(&["worktree", "add", "--detach", "--force", &dir_str, &commit])?;
+
+    // Removes the worktree however the build below ends.
+    struct Worktree<'a> {
+        repo_root: &'a Path,
+        dir: PathBuf,
+    }
+    impl Drop for Worktree<'_> {
+        fn drop(&mut self) {
+            let _ = std::process::Command::new("git")
+                .arg("-C")
+                .arg(self.repo_root)
+                .args(["worktree", "remove", "--force"])
+                .arg(&self.dir)
+                .output();
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+    let worktree = Worktree { repo_root, dir };
+
+    let rev_root = worktree.dir.join(&prefix);
+    let rev_scope = if root.is_absolute() {
+        root.strip_prefix(repo_root).unwrap_or(root).to_path_buf()
+    } else {
+        root.to_path_buf()
+    };
+    build_module_graph(&rev_root, &rev_scope)
+}
+
+/// Import cycles in `graph`: its strongly connected components with more than one module,
+/// largest first. Members and edges are sorted by id.
+pub fn find_module_cycles(graph: &ModuleGraph) -> Vec<ModuleCycle> {
+    let index: BTreeMap<&str, usize> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.id.as_str(), i))
+        .collect();
+    let mut adj: Vec<Vec<usize>> = vec![Vec::new(); graph.nodes.len()];
+    for e in &graph.edges {
+        if let (Some(&s), Some(&t)) = (index.get(e.source.as_str()), index.get(e.target.as_str())) {
+            adj[s].push(t);
+        }
+    }
+
+    // Tarjan's algorithm, iterative so deep import chains can't overflow the stack.
+    let n = adj.len();
+    let mut order = vec![usize::MAX; n];
+    let mut low = vec![0usize; n];
+    let mut on_stack = vec![false; n];
+    let mut stack: Vec<usize> = Vec::new();
+    let mut next = 0usize;
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    for start in 0..n {
+        if order[start] != usize::MAX {
+            continue;
+        }
+        let mut work: Vec<(usize, usize)> = vec![(start, 0)];
+        while let Some(&mut (v, ref mut child)) = work.last_mut() {
+            if *child == 0 {
+                order[v] = next;
+                low[v] = next;
+                next += 1;
+                stack.push(v);
+                on_stack[v] = true;
+            }
+            if let Some(&w) = adj[v].get(*child) {
+                *child += 1;
+                if order[w] == usize::MAX {
+                    work.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = low[v].min(order[w]);
+                }
+                continue;
+            }
+            work.pop();
+            if let Some(&(parent, _)) = work.last() {
+                low[parent] = low[parent].min(low[v]);
+            }
+            if low[v] == order[v] {
+                let mut component = Vec::new();
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                if component.len() > 1 {
+                    components.push(component);
+                }
+            }
+        }
+    }
+
+    let mut cycles: Vec<ModuleCycle> = components
+        .into_iter()
+        .map(|component| {
+            let mut members: Vec<String> = component
+                .iter()
+                .map(|&i| graph.nodes[i].id.clone())
+                .collect();
+            members.sort();
+            let edges: Vec<ModuleEdge> = graph
+                .edges
+                .iter()
+                .filter(|e| {
+                    members.binary_search(&e.source).is_ok()
+                        && members.binary_search(&e.target).is_ok()
+                })
+                .cloned()
+                .collect();
+            ModuleCycle { members, edges }
+        })
+        .collect();
+    cycles.sort_by(|a, b| {
+        b.members
+            .len()
+            .cmp(&a.members.len())
+            .then_with(|| a.members.cmp(&b.members))
+    });
+    cycles
+}
+
+/// How module `from` reaches module `to`: every cheapest path (up to `max_paths`), where an
+/// edge costs `1 / weight` so routes through heavily used imports win. Empty when `to` is
+/// unreachable; an error when either id isn't a node of `graph`.
+pub fn shortest_module_paths(
+    graph: &ModuleGraph,
+    from: &str,
+    to: &str,
+    max_paths: usize,
+) -> Result<Vec<ModulePath>> {
+    const EPS: f64 = 1e-9;
+    let index: BTreeMap<&str, usize> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.id.as_str(), i))
+        .collect();
+    let lookup = |id: &str| {
+        let id = normalize_module_id(id.trim_matches('/'));
+        index
+            .get(id.as_str())
+            .copied()
+            .with_context(|| format!("Unknown module: {id}"))
+    };
+    let (source, target) = (lookup(from)?, lookup(to)?);
+
+    let n = graph.nodes.len();
+    let mut adj: Vec<Vec<(usize, usize)>> = vec![Vec::new(); n];
+    for (ei, e) in graph.edges.iter().enumerate() {
+        if e.weight == 0 {
+            continue;
+        }
+        if let (Some(&s), Some(&t)) = (index.get(e.source.as_str()), index.get(e.target.as_str())) {
+            adj[s].push((t, ei));
+        }
+    }
+    let cost = |ei: usize| 1.0 / graph.edges[ei].weight as f64;
+
+    // Dijkstra, keeping every predecessor edge that ties for the best distance.
+    let mut dist = vec![f64::INFINITY; n];
+    let mut done = vec![false; n];
+    let mut preds: Vec<Vec<(usize, usize)>> = vec![Vec::new(); n];
+    dist[source] = 0.0;
+    while let Some(u) = (0..n)
+        .filter(|&i| !done[i] && dist[i].is_finite())
+        .min_by(|&a, &b| dist[a].total_cmp(&dist[b]))
+    {
+        done[u] = true;
+        if u == target {
+            break;
+        }
+        for &(v, ei) in &adj[u] {
+            let d = dist[u] + cost(ei);
+            if d < dist[v] - EPS {
+                dist[v] = d;
+                preds[v] = vec![(u, ei)];
+            } else if (d - dist[v]).abs() <= EPS && !done[v] {
+                preds[v].push((u, ei));
+            }
+        }
+    }
+    if source == target || !dist[target].is_finite() {
+        return Ok(Vec::new());
     }
-    edges.sort_by(|a, b| a.id.cmp(&b.id));
 
-    Ok(ModuleGraph { nodes, edges })
+    // Walk the predecessor DAG back from the target.
+    let mut paths = Vec::new();
+    let mut work: Vec<(usize, Vec<usize>)> = vec![(target, Vec::new())];
+    while let Some((v, suffix)) = work.pop() {
+        if paths.len() >= max_paths {
+            break;
+        }
+        if v == source {
+            let edges: Vec<ModuleEdge> = suffix
+                .iter()
+                .rev()
+                .map(|&ei| graph.edges[ei].clone())
+                .collect();
+            let mut modules = vec![graph.nodes[source].id.clone()];
+            modules.extend(edges.iter().map(|e| e.target.clone()));
+            paths.push(ModulePath {
+                modules,
+                edges,
+                cost: (dist[target] * 1e4).round() / 1e4,
+            });
+            continue;
+        }
+        for &(u, ei) in preds[v].iter().rev() {
+            let mut next = suffix.clone();
+            next.push(ei);
+            work.push((u, next));
+        }
+    }
+    paths.sort_by(|a, b| a.modules.cmp(&b.modules));
+    Ok(paths)
 }
 
 /// Core path normalization helper: ALWAYS converts backslashes to forward slashes.
@@ -814,7 +2458,7 @@ fn normalize_slash(p: &Path) -> String {
 }
 
 fn rel_str(repo_root: &Path, p: &Path) -> Option<String> {
-    p.strip_prefix(repo_root).ok().map(normalize_slash)
+    relative_to(p, repo_root).map(|r| normalize_slash(&r))
 }
 
 fn normalize_module_id(rel: &str) -> String {
@@ -859,7 +2503,7 @@ fn should_skip_dir_name(name: &str) -> bool {
         // Infra
         | ".terraform" | ".serverless"
         // Generic junk
-        | "tmp" | "temp" | "logs" | ".cache" | ".cortexast"
+        | "tmp" | "temp" | "logs" | ".cache" | ".cortexast" | ".context-slicer"
     )
 }
 
@@ -886,6 +2530,14 @@ fn is_allowed_ext(path: &Path) -> bool {
         ext,
         // Rust / JS / TS source
         "rs" | "ts" | "tsx" | "js" | "jsx" |
+        // C / C++ / C# sources and headers
+        "c" | "h" | "cpp" | "cc" | "cxx" | "hpp" | "hxx" | "cs" |
+        // PHP / Kotlin / Scala / Zig / Lua / Dart / Elixir / OCaml
+        "php" | "kt" | "kts" | "scala" | "sc" | "zig" | "lua" | "dart" | "ex" | "exs" | "ml" |
+        // Shell / schemas
+        "sh" | "bash" | "zsh" | "sql" | "graphql" | "gql" | "proto" |
+        // Notebooks (analysed through their Python code cells)
+        "ipynb" |
         // Config / docs
         "json" | "md" | "toml" |
         // Web / styles (small allowlist, safe to count)
@@ -893,14 +2545,758 @@ fn is_allowed_ext(path: &Path) -> bool {
     )
 }
 
-fn is_allowed_source_ext(path: &Path) -> bool {
-    let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
-        return false;
-    };
-    matches!(
-        ext,
-        "rs" | "ts" | "tsx" | "js" | "jsx" | "py" | "go" | "dart"
-    )
+fn is_allowed_source_ext(path: &Path) -> bool {
+    let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+        return false;
+    };
+    matches!(
+        ext,
+        "rs" | "ts" | "tsx" | "js" | "jsx" | "py" | "go" | "dart" |
+        // C-family, PHP and JVM languages
+        "c" | "h" | "cpp" | "cc" | "cxx" | "hpp" | "hxx" | "cs" | "php" | "kt" | "kts" |
+        "scala" | "sc" |
+        // Systems and scripting
+        "zig" | "lua" | "ex" | "exs" | "sh" | "bash" | "zsh" | "ml" |
+        // Schemas
+        "sql" | "graphql" | "gql" | "proto" |
+        // Notebooks
+        "ipynb"
+    )
+}
+
+/// Aggregate TODO/FIXME/HACK markers for every source file under `root`, sorted by path.
+pub fn collect_markers(repo_root: &Path, root: &Path) -> Result<Vec<FileMarkers>> {
+    let root_abs = if root.is_absolute() {
+        root.to_path_buf()
+    } else {
+        repo_root.join(root)
+    };
+    let root_abs = normalize_abs(&root_abs);
+    if !root_abs.is_dir() {
+        anyhow::bail!("Markers root is not a directory: {}", root_abs.display());
+    }
+
+    let walker = WalkBuilder::new(&root_abs)
+        .standard_filters(true)
+        .add_custom_ignore_filename(SLICERIGNORE_FILE)
+        .hidden(false)
+        .max_depth(Some(25))
+        // Only judge components below the root, so a root under e.g. `/tmp` still scans.
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !should_skip_dir_name(entry.file_name().to_str().unwrap_or(""))
+        })
+        .build();
+
+    let mut out: Vec<FileMarkers> = Vec::new();
+    for ent in walker {
+        let Ok(ent) = ent else { continue };
+        if !ent.file_type().map(|t| t.is_file()).unwrap_or(false) {
+            continue;
+        }
+        let p = ent.path();
+        if !is_allowed_source_ext(p) {
+            continue;
+        }
+        if std::fs::metadata(p).map(|m| m.len()).unwrap_or(0) > ABSOLUTE_MAX_FILE_BYTES {
+            continue;
+        }
+        let Ok(symbols) = analyze_file_cached(repo_root, p) else {
+            continue;
+        };
+        if symbols.markers.is_empty() {
+            continue;
+        }
+        out.push(FileMarkers {
+            file: rel_str(repo_root, p).unwrap_or_else(|| normalize_slash(p)),
+            markers: symbols.markers,
+        });
+    }
+    out.sort_by(|a, b| a.file.cmp(&b.file));
+    Ok(out)
+}
+
+/// Sorted source files under `root_abs` that are small enough to parse. Skipped directory
+/// names are only judged below the root, so a root under e.g. `/tmp` still scans.
+fn source_files_under(root_abs: &Path) -> Vec<PathBuf> {
+    let walker = WalkBuilder::new(root_abs)
+        .standard_filters(true)
+        .add_custom_ignore_filename(SLICERIGNORE_FILE)
+        .hidden(false)
+        .max_depth(Some(25))
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !should_skip_dir_name(entry.file_name().to_str().unwrap_or(""))
+        })
+        .build();
+    let mut files: Vec<PathBuf> = Vec::new();
+    for ent in walker {
+        let Ok(ent) = ent else { continue };
+        if !ent.file_type().map(|t| t.is_file()).unwrap_or(false) {
+            continue;
+        }
+        let p = ent.path();
+        if !is_allowed_source_ext(p)
+            || std::fs::metadata(p).map(|m| m.len()).unwrap_or(0) > ABSOLUTE_MAX_FILE_BYTES
+        {
+            continue;
+        }
+        files.push(p.to_path_buf());
+    }
+    files.sort();
+    files
+}
+
+/// Each file's imports that resolve to another file in the repo, as (import, target).
+fn resolve_file_imports(repo_root: &Path, files: &[PathBuf]) -> Vec<Vec<(String, PathBuf)>> {
+    let mut import_cache = ImportCache::default();
+    files
+        .iter()
+        .zip(analyze_files_cached(repo_root, files))
+        .map(|(file_abs, analyzed)| {
+            let Ok(analyzed) = analyzed else {
+                return Vec::new();
+            };
+            analyzed
+                .imports
+                .into_iter()
+                .filter_map(|imp| {
+                    let dst = resolve_native_import(repo_root, file_abs, &imp, &mut import_cache)
+                        .or_else(|| resolve_ts_import(repo_root, file_abs, &imp))?;
+                    let dst = normalize_abs(&dst);
+                    (&dst != file_abs).then_some((imp, dst))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Imports crossing layers declared in `architecture` that its `allow` chains don't permit,
+/// for every source file under `root`. Files outside all layers are never flagged.
+pub fn check_layers(
+    repo_root: &Path,
+    root: &Path,
+    architecture: &ArchitectureConfig,
+) -> Result<Vec<LayerViolation>> {
+    let repo_abs = normalize_abs(repo_root);
+    let root_abs = normalize_abs(&if root.is_absolute() {
+        root.to_path_buf()
+    } else {
+        repo_root.join(root)
+    });
+    if !root_abs.is_dir() {
+        anyhow::bail!(
+            "Layer check root is not a directory: {}",
+            root_abs.display()
+        );
+    }
+
+    let layers: Vec<(&str, Vec<Pattern>)> = architecture
+        .layers
+        .iter()
+        .map(|l| {
+            let patterns = l
+                .paths
+                .iter()
+                .filter_map(|p| Pattern::new(p.trim_matches('/')).ok())
+                .collect();
+            (l.name.as_str(), patterns)
+        })
+        .collect();
+    let layer_of = |rel: &str| -> Option<&str> {
+        let mut candidates = vec![rel];
+        let mut rest = rel;
+        while let Some((dir, _)) = rest.rsplit_once('/') {
+            candidates.push(dir);
+            rest = dir;
+        }
+        layers
+            .iter()
+            .find(|(_, patterns)| {
+                patterns
+                    .iter()
+                    .any(|p| candidates.iter().any(|c| p.matches(c)))
+            })
+            .map(|(name, _)| *name)
+    };
+    let allowed = architecture.allowed_pairs();
+
+    let rel = |p: &Path| rel_str(&repo_abs, p).unwrap_or_else(|| normalize_slash(p));
+    let files = source_files_under(&root_abs);
+    let mut violations = Vec::new();
+    for (file_abs, imports) in files.iter().zip(resolve_file_imports(repo_root, &files)) {
+        let file = rel(file_abs);
+        let Some(from) = layer_of(&file) else {
+            continue;
+        };
+        let mut source: Option<String> = None;
+        for (import, dst) in imports {
+            let target = rel(&dst);
+            let Some(to) = layer_of(&target) else {
+                continue;
+            };
+            if from == to || allowed.iter().any(|(a, b)| a == from && b == to) {
+                continue;
+            }
+            let text =
+                source.get_or_insert_with(|| std::fs::read_to_string(file_abs).unwrap_or_default());
+            let line = text
+                .lines()
+                .position(|l| l.contains(import.as_str()))
+                .map(|i| i as u32 + 1);
+            violations.push(LayerViolation {
+                from_layer: from.to_string(),
+                to_layer: to.to_string(),
+                file: file.clone(),
+                line,
+                import,
+                target,
+            });
+        }
+    }
+    Ok(violations)
+}
+
+/// Aider-style text map of `scope`: files ranked by PageRank over their imports, each
+/// followed by the signatures of its public symbols, kept within `budget_tokens`.
+/// Files that don't fit are skipped in favour of lower-ranked ones that still do.
+pub fn render_ranked_map(repo_root: &Path, scope: &Path, budget_tokens: usize) -> Result<String> {
+    let repo_abs = normalize_abs(repo_root);
+    let scope_abs = normalize_abs(&if scope.is_absolute() {
+        scope.to_path_buf()
+    } else {
+        repo_root.join(scope)
+    });
+    if !scope_abs.is_dir() {
+        anyhow::bail!("Map scope is not a directory: {}", scope_abs.display());
+    }
+
+    let files = source_files_under(&scope_abs);
+    let index: BTreeMap<&PathBuf, usize> = files.iter().enumerate().map(|(i, f)| (f, i)).collect();
+    let mut links: Vec<(usize, usize, f64)> = Vec::new();
+    for (src, imports) in resolve_file_imports(repo_root, &files)
+        .into_iter()
+        .enumerate()
+    {
+        for (_, dst) in imports {
+            if let Some(&dst) = index.get(&dst) {
+                links.push((src, dst, 1.0));
+            }
+        }
+    }
+    let ranks = page_rank(files.len(), &links);
+    let rels: Vec<String> = files
+        .iter()
+        .map(|f| rel_str(&repo_abs, f).unwrap_or_else(|| normalize_slash(f)))
+        .collect();
+    let mut order: Vec<usize> = (0..files.len()).collect();
+    order.sort_by(|&a, &b| {
+        ranks[b]
+            .total_cmp(&ranks[a])
+            .then_with(|| rels[a].cmp(&rels[b]))
+    });
+
+    let estimator = estimator_for(repo_root);
+    let analyzed = analyze_files_cached(repo_root, &files);
+    let mut out = String::new();
+    let mut used = 0usize;
+    for i in order {
+        let mut block = format!("{}:\n", rels[i]);
+        if let Ok(analyzed) = &analyzed[i] {
+            let mut symbols: Vec<&Symbol> = analyzed
+                .symbols
+                .iter()
+                .filter(|s| !matches!(s.visibility.as_deref(), Some("private" | "internal")))
+                .collect();
+            symbols.sort_by_key(|s| (s.line, s.column));
+            for symbol in symbols {
+                let signature = symbol
+                    .signature
+                    .as_deref()
+                    .map(|sig| sig.split_whitespace().collect::<Vec<_>>().join(" "))
+                    .map(|sig| sig.trim_end_matches('{').trim_end().to_string())
+                    .filter(|sig| !sig.is_empty())
+                    .unwrap_or_else(|| format!("{} {}", symbol.kind, symbol.name));
+                let indent = if symbol.parent.is_some() {
+                    "│   "
+                } else {
+                    "│ "
+                };
+                block.push_str(indent);
+                block.push_str(&signature);
+                block.push('\n');
+            }
+        }
+        let cost = estimator.estimate_text(&block, None);
+        if used + cost > budget_tokens {
+            continue;
+        }
+        used += cost;
+        out.push_str(&block);
+    }
+    Ok(out)
+}
+
+/// A source file with its resolved imports and symbols, for relational exports.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileRecord {
+    pub path: String,
+    /// Id of the deepest node of the accompanying [`ModuleGraph`] containing the file.
+    pub module: String,
+    pub bytes: u64,
+    pub lines: u64,
+    pub est_tokens: u64,
+    /// (import, repo-relative target file) for each import that resolves to a repo file.
+    pub imports: Vec<(String, String)>,
+    pub symbols: Vec<Symbol>,
+}
+
+/// Every source file under `root`, attributed to the modules of `graph` (normally
+/// [`build_module_graph`] for the same root).
+pub fn file_records(repo_root: &Path, root: &Path, graph: &ModuleGraph) -> Result<Vec<FileRecord>> {
+    let repo_abs = normalize_abs(repo_root);
+    let root_abs = normalize_abs(&if root.is_absolute() {
+        root.to_path_buf()
+    } else {
+        repo_root.join(root)
+    });
+    if !root_abs.is_dir() {
+        anyhow::bail!("Export root is not a directory: {}", root_abs.display());
+    }
+
+    let files = source_files_under(&root_abs);
+    let rel = |p: &Path| rel_str(&repo_abs, p).unwrap_or_else(|| normalize_slash(p));
+    let estimator = estimator_for(repo_root);
+    let imports = resolve_file_imports(repo_root, &files);
+    let analyzed = analyze_files_cached(repo_root, &files);
+    let records = files
+        .iter()
+        .zip(imports)
+        .zip(analyzed)
+        .map(|((file, imports), analyzed)| {
+            let path = rel(file);
+            let module = graph
+                .nodes
+                .iter()
+                .filter(|n| n.path == "." || path.starts_with(&format!("{}/", n.path)))
+                .max_by_key(|n| if n.path == "." { 0 } else { n.path.len() })
+                .map(|n| n.id.clone())
+                .unwrap_or_else(|| ".".to_string());
+            let bytes = std::fs::read(file).unwrap_or_default();
+            FileRecord {
+                module,
+                bytes: bytes.len() as u64,
+                lines: count_lines(&bytes),
+                est_tokens: est_tokens_for_file(&estimator, file, bytes.len() as u64),
+                imports: imports
+                    .into_iter()
+                    .map(|(import, target)| (import, rel(&target)))
+                    .collect(),
+                symbols: analyzed.map(|a| a.symbols).unwrap_or_default(),
+                path,
+            }
+        })
+        .collect();
+    Ok(records)
+}
+
+/// Reverse import edges for every source file under `repo_abs`: file -> files importing it.
+fn reverse_imports(repo_root: &Path, repo_abs: &Path) -> BTreeMap<PathBuf, BTreeSet<PathBuf>> {
+    let files = source_files_under(repo_abs);
+    let mut importers: BTreeMap<PathBuf, BTreeSet<PathBuf>> = BTreeMap::new();
+    for (file_abs, imports) in files.iter().zip(resolve_file_imports(repo_root, &files)) {
+        for (_, dst) in imports {
+            importers.entry(dst).or_default().insert(file_abs.clone());
+        }
+    }
+    importers
+}
+
+/// Repo-relative files that `git diff` reports as changed, sorted: against `since` (a ref,
+/// or an `A..B` range), and in the index rather than the working tree when `staged` is set.
+/// Deleted files are left out, since there is nothing left to slice.
+pub fn git_changed_files(
+    repo_root: &Path,
+    since: Option<&str>,
+    staged: bool,
+) -> Result<Vec<String>> {
+    let mut cmd = std::process::Command::new("git");
+    cmd.arg("-C").arg(repo_root).args([
+        "diff",
+        "--name-only",
+        "--relative",
+        "--no-renames",
+        "--diff-filter=d",
+    ]);
+    if staged {
+        cmd.arg("--cached");
+    }
+    if let Some(since) = since {
+        cmd.arg(since);
+    }
+    let output = cmd.arg("--").output().context("Failed to run git diff")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let mut files: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// `changed` (depth 0) followed by every file importing one of them directly (depth 1),
+/// each group sorted by path: a review-sized context for a change.
+pub fn with_direct_dependents(repo_root: &Path, changed: &[String]) -> Vec<(String, u32)> {
+    let repo_abs = normalize_abs(repo_root);
+    let importers = reverse_imports(repo_root, &repo_abs);
+    let changed_abs: BTreeSet<PathBuf> = changed
+        .iter()
+        .map(|f| normalize_abs(&repo_abs.join(f)))
+        .collect();
+    let dependents: BTreeSet<String> = changed_abs
+        .iter()
+        .flat_map(|f| importers.get(f).into_iter().flatten())
+        .filter(|f| !changed_abs.contains(*f))
+        .map(|f| rel_str(&repo_abs, f).unwrap_or_else(|| normalize_slash(f)))
+        .collect();
+
+    let mut files: Vec<(String, u32)> = changed.iter().map(|f| (f.clone(), 0)).collect();
+    files.sort();
+    files.extend(dependents.into_iter().map(|f| (f, 1)));
+    files
+}
+
+/// Everything that transitively imports `path`: importing files with their distance
+/// (1 = imports it directly) and the modules those files belong to.
+pub fn impact_of(repo_root: &Path, path: &Path) -> Result<ImpactReport> {
+    let repo_abs = normalize_abs(repo_root);
+    let target_abs = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        repo_root.join(path)
+    };
+    let target_abs = normalize_abs(&target_abs);
+    if !target_abs.is_file() {
+        anyhow::bail!("Impact target is not a file: {}", target_abs.display());
+    }
+
+    let importers = reverse_imports(repo_root, &repo_abs);
+
+    // Breadth-first, so each file is reported at its shortest distance.
+    let mut seen: BTreeSet<PathBuf> = BTreeSet::from([target_abs.clone()]);
+    let mut frontier = vec![target_abs.clone()];
+    let mut impacted: Vec<(PathBuf, u32)> = Vec::new();
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        depth += 1;
+        let mut next = Vec::new();
+        for file in &frontier {
+            for importer in importers.get(file).into_iter().flatten() {
+                if seen.insert(importer.clone()) {
+                    impacted.push((importer.clone(), depth));
+                    next.push(importer.clone());
+                }
+            }
+        }
+        frontier = next;
+    }
+
+    let rel = |p: &Path| rel_str(&repo_abs, p).unwrap_or_else(|| normalize_slash(p));
+    let mut module_of: BTreeMap<PathBuf, String> = BTreeMap::new();
+    let mut modules: BTreeSet<String> = BTreeSet::new();
+    for (file, _) in &impacted {
+        let Some(dir) = file.parent() else { continue };
+        let module = module_of
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                let owner = dir
+                    .ancestors()
+                    .take_while(|d| d.starts_with(&repo_abs))
+                    .find(|d| dir_has_module_marker(d))
+                    .unwrap_or(&repo_abs);
+                normalize_module_id(&rel(owner))
+            })
+            .clone();
+        modules.insert(module);
+    }
+
+    Ok(ImpactReport {
+        target: rel(&target_abs),
+        files: impacted
+            .iter()
+            .map(|(f, depth)| ImpactedFile {
+                file: rel(f),
+                depth: *depth,
+            })
+            .collect(),
+        modules: modules.into_iter().collect(),
+    })
+}
+
+/// `path` plus every repo file it transitively imports, up to `max_depth` hops, as
+/// repo-relative paths with their distance (`0` = `path` itself), ordered by distance
+/// then path.
+pub fn import_closure(repo_root: &Path, path: &Path, max_depth: u32) -> Result<Vec<(String, u32)>> {
+    let repo_abs = normalize_abs(repo_root);
+    let target_abs = normalize_abs(&if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        repo_root.join(path)
+    });
+    if !target_abs.is_file() {
+        anyhow::bail!("Closure target is not a file: {}", target_abs.display());
+    }
+
+    let mut seen: BTreeSet<PathBuf> = BTreeSet::from([target_abs.clone()]);
+    let mut closure: Vec<(PathBuf, u32)> = vec![(target_abs.clone(), 0)];
+    let mut frontier = vec![target_abs];
+    let mut depth = 0;
+    while !frontier.is_empty() && depth < max_depth {
+        depth += 1;
+        let mut next = Vec::new();
+        for imports in resolve_file_imports(repo_root, &frontier) {
+            for (_, dst) in imports {
+                if dst.starts_with(&repo_abs) && seen.insert(dst.clone()) {
+                    closure.push((dst.clone(), depth));
+                    next.push(dst);
+                }
+            }
+        }
+        next.sort();
+        frontier = next;
+    }
+
+    let mut files: Vec<(String, u32)> = closure
+        .into_iter()
+        .map(|(f, depth)| {
+            (
+                rel_str(&repo_abs, &f).unwrap_or_else(|| normalize_slash(&f)),
+                depth,
+            )
+        })
+        .collect();
+    files.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(files)
+}
+
+/// Ranking signals for one source file (see [`file_ranks`]).
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FileRank {
+    /// PageRank over resolved file-to-file imports; 1.0 is an average file.
+    pub centrality: f64,
+    pub commits: u64,
+    /// Unix seconds of the newest commit touching the file.
+    pub last_changed: Option<i64>,
+}
+
+/// Import centrality and git churn for every source file under `scope` (or just `scope`
+/// when it is a file), keyed by repo-relative path. Imports leaving the scope are ignored.
+pub fn file_ranks(repo_root: &Path, scope: &Path) -> Result<BTreeMap<String, FileRank>> {
+    let repo_abs = normalize_abs(repo_root);
+    let scope_abs = normalize_abs(&if scope.is_absolute() {
+        scope.to_path_buf()
+    } else {
+        repo_root.join(scope)
+    });
+    if !scope_abs.exists() {
+        anyhow::bail!("Scope path not found: {}", scope_abs.display());
+    }
+
+    let files = if scope_abs.is_file() {
+        vec![scope_abs]
+    } else {
+        source_files_under(&scope_abs)
+    };
+    let index: BTreeMap<&Path, usize> = files
+        .iter()
+        .enumerate()
+        .map(|(i, f)| (f.as_path(), i))
+        .collect();
+    let mut weights: BTreeMap<(usize, usize), f64> = BTreeMap::new();
+    for (src, imports) in resolve_file_imports(repo_root, &files)
+        .into_iter()
+        .enumerate()
+    {
+        for (_, dst) in imports {
+            if let Some(&dst) = index.get(dst.as_path()) {
+                if dst != src {
+                    *weights.entry((src, dst)).or_default() += 1.0;
+                }
+            }
+        }
+    }
+    let edges: Vec<(usize, usize, f64)> =
+        weights.into_iter().map(|((s, t), w)| (s, t, w)).collect();
+    let ranks = page_rank(files.len(), &edges);
+
+    let churn = git_churn(repo_root);
+    Ok(files
+        .iter()
+        .zip(ranks)
+        .map(|(f, centrality)| {
+            let path = rel_str(&repo_abs, f).unwrap_or_else(|| normalize_slash(f));
+            let (commits, last_changed) = churn_for(&churn, &path);
+            (
+                path,
+                FileRank {
+                    centrality,
+                    commits,
+                    last_changed,
+                },
+            )
+        })
+        .collect())
+}
+
+fn dir_has_module_marker(dir: &Path) -> bool {
+    std::fs::read_dir(dir)
+        .map(|rd| {
+            rd.flatten()
+                .any(|e| e.file_name().to_str().is_some_and(is_module_marker_file))
+        })
+        .unwrap_or(false)
+}
+
+/// Function-level call graph for every source file under `scope`: nodes are callable
+/// symbols, edges link each one to the symbols its outgoing calls resolve to.
+///
+/// Callees are matched by name, preferring the caller's own file: `self.x()`/`this.x()`
+/// stays within the caller's type, `Type::x()` needs that qualifier, `obj.x()` only
+/// reaches methods and bare `x()` only free functions. Calls that stay ambiguous across
+/// files, or name nothing in scope (stdlib, dependencies), are dropped.
+pub fn build_call_graph(repo_root: &Path, scope: &Path) -> Result<CallGraph> {
+    let root_abs = if scope.is_absolute() {
+        scope.to_path_buf()
+    } else {
+        repo_root.join(scope)
+    };
+    let root_abs = normalize_abs(&root_abs);
+    if !root_abs.exists() {
+        anyhow::bail!("Call graph scope not found: {}", root_abs.display());
+    }
+
+    let files = if root_abs.is_file() {
+        vec![root_abs.clone()]
+    } else {
+        source_files_under(&root_abs)
+    };
+
+    // Nodes, plus each node's (parent, outgoing calls) for resolution.
+    let mut nodes: Vec<CallNode> = Vec::new();
+    let mut callers: Vec<(Option<String>, Vec<String>)> = Vec::new();
+    for (file_abs, analyzed) in files.iter().zip(analyze_files_cached(repo_root, &files)) {
+        let Ok(analyzed) = analyzed else { continue };
+        let file = rel_str(repo_root, file_abs).unwrap_or_else(|| normalize_slash(file_abs));
+        for sym in analyzed.symbols {
+            if !matches!(sym.kind.as_str(), "function" | "method" | "component") {
+                continue;
+            }
+            let id = format!("{}#{}", file, sym.name);
+            // Overloads and re-declarations collapse onto the first definition.
+            if nodes
+                .iter()
+                .rev()
+                .take_while(|n| n.file == file)
+                .any(|n| n.id == id)
+            {
+                continue;
+            }
+            nodes.push(CallNode {
+                id,
+                name: sym.name,
+                kind: sym.kind,
+                file: file.clone(),
+                line: sym.line,
+            });
+            callers.push((sym.parent, sym.calls));
+        }
+    }
+
+    let mut by_short: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, n) in nodes.iter().enumerate() {
+        by_short
+            .entry(last_path_segment(&n.name))
+            .or_default()
+            .push(i);
+    }
+
+    let mut weights: BTreeMap<(usize, usize), u64> = BTreeMap::new();
+    for (src, (parent, calls)) in callers.iter().enumerate() {
+        for callee in calls {
+            if let Some(dst) = resolve_call(&nodes, &callers, &by_short, src, parent, callee) {
+                *weights.entry((src, dst)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let edges: Vec<CallEdge> = weights
+        .into_iter()
+        .map(|((s, t), w)| CallEdge {
+            id: format!("{}->{}", nodes[s].id, nodes[t].id),
+            source: nodes[s].id.clone(),
+            target: nodes[t].id.clone(),
+            weight: w,
+        })
+        .collect();
+
+    Ok(CallGraph { nodes, edges })
+}
+
+fn last_path_segment(name: &str) -> &str {
+    name.rsplit(['.', ':', '>']).next().unwrap_or(name)
+}
+
+/// Resolve one callee string from `nodes[src]` to a node index (see [`build_call_graph`]).
+fn resolve_call(
+    nodes: &[CallNode],
+    callers: &[(Option<String>, Vec<String>)],
+    by_short: &BTreeMap<&str, Vec<usize>>,
+    src: usize,
+    parent: &Option<String>,
+    callee: &str,
+) -> Option<usize> {
+    let short = last_path_segment(callee);
+    let candidates = by_short.get(short)?;
+    let parent_of = |i: usize| callers[i].0.as_deref();
+
+    let qualifier = callee
+        .strip_suffix(short)
+        .map(|q| q.trim_end_matches([':', '.', '-', '>']))
+        .unwrap_or("");
+    let qualifier = qualifier
+        .strip_prefix("crate::")
+        .or_else(|| qualifier.strip_prefix("super::"))
+        .or_else(|| qualifier.strip_prefix("self::"))
+        .unwrap_or(qualifier);
+
+    let matching: Vec<usize> = candidates
+        .iter()
+        .copied()
+        .filter(|&i| {
+            if matches!(qualifier, "self" | "this" | "Self" | "cls") {
+                parent.is_some() && parent_of(i) == parent.as_deref()
+            } else if qualifier.is_empty() {
+                nodes[i].kind != "method"
+            } else if callee.contains("::") {
+                let name = &nodes[i].name;
+                parent_of(i) == Some(qualifier)
+                    || name.ends_with(&format!("{qualifier}::{short}"))
+                    || name.ends_with(&format!("{qualifier}.{short}"))
+            } else {
+                nodes[i].kind == "method"
+            }
+        })
+        .collect();
+
+    matching
+        .iter()
+        .copied()
+        .find(|&i| nodes[i].file == nodes[src].file)
+        .or_else(|| (matching.len() == 1).then(|| matching[0]))
 }
 
 pub fn build_repo_map(repo_root: &Path) -> Result<RepoMap> {
@@ -915,13 +3311,129 @@ pub fn build_repo_map(repo_root: &Path) -> Result<RepoMap> {
 /// - File nodes are only included for allowlisted text/source extensions.
 /// - Edges connect `parent_id -> child_id`.
 pub fn build_repo_map_scoped(repo_root: &Path, scope: &Path) -> Result<RepoMap> {
+    let exclude_tests = load_config(repo_root).scan.exclude_tests;
+    build_repo_map_filtered(repo_root, scope, exclude_tests)
+}
+
+/// [`build_repo_map_scoped`] with an explicit test-file filter instead of the repo's
+/// `scan.exclude_tests` setting.
+pub fn build_repo_map_filtered(
+    repo_root: &Path,
+    scope: &Path,
+    exclude_tests: bool,
+) -> Result<RepoMap> {
+    build_repo_map_multi(repo_root, &[scope.to_path_buf()], exclude_tests)
+}
+
+/// [`build_repo_map_filtered`] over several scopes (e.g. `src` and `proto`), merged into one
+/// map; see [`stream_repo_map_multi`].
+pub fn build_repo_map_multi(
+    repo_root: &Path,
+    scopes: &[PathBuf],
+    exclude_tests: bool,
+) -> Result<RepoMap> {
+    let mut nodes: Vec<MapNode> = Vec::new();
+    let mut edges: Vec<MapEdge> = Vec::new();
+    let mut ranks: BTreeMap<String, f64> = BTreeMap::new();
+    stream_repo_map_multi(repo_root, scopes, exclude_tests, &mut |record| {
+        match record {
+            MapRecord::Node(node) => nodes.push(node),
+            MapRecord::Edge(edge) => edges.push(edge),
+            MapRecord::Importance { id, importance } => {
+                ranks.insert(id, importance);
+            }
+        }
+        Ok(())
+    })?;
+
+    nodes.sort_by(|a, b| a.id.cmp(&b.id));
+    edges.sort_by(|a, b| a.id.cmp(&b.id));
+    for node in &mut nodes {
+        node.importance = ranks.get(&node.id).copied().unwrap_or(0.0);
+    }
+    Ok(RepoMap { nodes, edges })
+}
+
+/// One record of a streamed repo map (see [`stream_repo_map_scoped`]).
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MapRecord {
+    /// A map node; its `importance` is still `0.0` at this point.
+    Node(MapNode),
+    Edge(MapEdge),
+    /// A node's PageRank, only known once every import edge has been seen, so these all
+    /// come last.
+    Importance {
+        id: String,
+        importance: f64,
+    },
+}
+
+/// [`build_repo_map_scoped`] as a stream: each node and edge is handed to `emit` as soon as
+/// it is discovered (in directory order), followed by one [`MapRecord::Importance`] per
+/// node. Only ids and import links are kept in memory.
+pub fn stream_repo_map_scoped(
+    repo_root: &Path,
+    scope: &Path,
+    emit: &mut dyn FnMut(MapRecord) -> Result<()>,
+) -> Result<()> {
+    let exclude_tests = load_config(repo_root).scan.exclude_tests;
+    stream_repo_map_filtered(repo_root, scope, exclude_tests, emit)
+}
+
+/// [`stream_repo_map_filtered`] over several scopes (e.g. `src` and `proto`): every scope
+/// is listed first, then imports are resolved and importance ranked once over all of them,
+/// so an import from one scope into another is an edge too. A node or edge reached from two
+/// scopes (one nested in the other, or the same directory twice) is emitted once.
+pub fn stream_repo_map_multi(
+    repo_root: &Path,
+    scopes: &[PathBuf],
+    exclude_tests: bool,
+    emit: &mut dyn FnMut(MapRecord) -> Result<()>,
+) -> Result<()> {
+    let mut listing = MapListing::default();
+    for scope in scopes {
+        list_map_scope(repo_root, scope, exclude_tests, &mut listing, emit)?;
+    }
+    emit_imports_and_ranks(repo_root, listing, emit)
+}
+
+/// [`stream_repo_map_scoped`] with an explicit test-file filter (see
+/// [`crate::scanner::is_test_path`]).
+pub fn stream_repo_map_filtered(
+    repo_root: &Path,
+    scope: &Path,
+    exclude_tests: bool,
+    emit: &mut dyn FnMut(MapRecord) -> Result<()>,
+) -> Result<()> {
+    stream_repo_map_multi(repo_root, &[scope.to_path_buf()], exclude_tests, emit)
+}
+
+/// Ids of the nodes emitted so far, across every scope of one map.
+#[derive(Default)]
+struct MapListing {
+    ids: Vec<String>,
+    file_ids: Vec<String>,
+    /// Node and containment edge ids already emitted, so overlapping scopes add nothing twice.
+    emitted: BTreeSet<String>,
+}
+
+/// Emit the node of `scope` and of its immediate children with their containment edges,
+/// recording them in `listing`.
+fn list_map_scope(
+    repo_root: &Path,
+    scope: &Path,
+    exclude_tests: bool,
+    listing: &mut MapListing,
+    emit: &mut dyn FnMut(MapRecord) -> Result<()>,
+) -> Result<()> {
     let scope_abs = if scope.is_absolute() {
         scope.to_path_buf()
     } else {
         repo_root.join(scope)
     };
 
-    let scope_abs = scope_abs.canonicalize().unwrap_or(scope_abs);
+    let scope_abs = normalize_abs(&scope_abs);
 
     if !scope_abs.exists() {
         anyhow::bail!("Scope path not found: {}", scope_abs.display());
@@ -950,10 +3462,28 @@ pub fn build_repo_map_scoped(repo_root: &Path, scope: &Path) -> Result<RepoMap>
             .to_string()
     };
 
-    let mut nodes: Vec<MapNode> = Vec::new();
-    let mut edges: Vec<MapEdge> = Vec::new();
+    let estimator = estimator_for(repo_root);
+    let churn = git_churn(repo_root);
+    let owners = CodeOwners::load(repo_root);
+    // Churn and ownership are per path, so they can be filled in before emitting.
+    let MapListing {
+        ids,
+        file_ids,
+        emitted,
+    } = listing;
+    let mut emit_node = |mut node: MapNode| -> Result<Option<String>> {
+        if !emitted.insert(node.id.clone()) {
+            return Ok(None);
+        }
+        (node.commits, node.last_changed) = churn_for(&churn, &node.path);
+        node.owners = owners.owners_of(&node.path, node.kind == "directory");
+        let id = node.id.clone();
+        emit(MapRecord::Node(node))?;
+        Ok(Some(id))
+    };
+    let mut contains: Vec<MapEdge> = Vec::new();
 
-    nodes.push(MapNode {
+    ids.extend(emit_node(MapNode {
         id: parent_id.clone(),
         label: parent_label,
         path: parent_id.clone(),
@@ -961,8 +3491,21 @@ pub fn build_repo_map_scoped(repo_root: &Path, scope: &Path) -> Result<RepoMap>
         size_class: "small".to_string(),
         bytes: 0,
         est_tokens: 0,
-    });
+        importance: 0.0,
+        commits: 0,
+        last_changed: None,
+        owners: Vec::new(),
+        fingerprint: None,
+    })?);
 
+    let slicer_ignore = SlicerIgnore::for_dir(repo_root, &scope_abs);
+    let scan_cfg = load_config(repo_root).scan;
+    let follow_symlinks = scan_cfg.follow_symlinks;
+    let linguist = if scan_cfg.include_generated {
+        LinguistAttributes::default()
+    } else {
+        LinguistAttributes::load(repo_root)
+    };
     let rd = std::fs::read_dir(&scope_abs)?;
     for entry in rd {
         let Ok(entry) = entry else { continue };
@@ -974,23 +3517,37 @@ pub fn build_repo_map_scoped(repo_root: &Path, scope: &Path) -> Result<RepoMap>
             continue;
         }
 
-        // HARD DENY by path component.
-        if path_has_forbidden_component(&path) {
+        // HARD DENY by path component (below the repo root, so checkouts under e.g. `/tmp`
+        // still map).
+        if path_has_forbidden_component(path.strip_prefix(repo_root).unwrap_or(&path)) {
             continue;
         }
 
         let ft = match entry.file_type() {
+            // A linked directory maps like a real one (see `scan.follow_symlinks`).
+            Ok(t) if t.is_symlink() && follow_symlinks => match std::fs::metadata(&path) {
+                Ok(meta) => meta.file_type(),
+                Err(_) => continue,
+            },
             Ok(t) => t,
             Err(_) => continue,
         };
+        if relative_to(&path, repo_root)
+            .is_some_and(|rel| slicer_ignore.is_ignored(repo_root, &rel, ft.is_dir()))
+        {
+            continue;
+        }
 
         if ft.is_dir() {
+            if exclude_tests && is_test_dir_name(&name) {
+                continue;
+            }
             // Include folder nodes.
             let rel = rel_str(repo_root, &path).unwrap_or_else(|| name.clone());
             let id = normalize_module_id(&rel);
             let label = clamp_label(&name);
 
-            nodes.push(MapNode {
+            ids.extend(emit_node(MapNode {
                 id: id.clone(),
                 label,
                 path: id.clone(),
@@ -998,9 +3555,14 @@ pub fn build_repo_map_scoped(repo_root: &Path, scope: &Path) -> Result<RepoMap>
                 size_class: "small".to_string(),
                 bytes: 0,
                 est_tokens: 0,
-            });
+                importance: 0.0,
+                commits: 0,
+                last_changed: None,
+                owners: Vec::new(),
+                fingerprint: None,
+            })?);
 
-            edges.push(MapEdge {
+            contains.push(MapEdge {
                 id: format!("{}->{}", parent_id, id),
                 source: parent_id.clone(),
                 target: id,
@@ -1016,13 +3578,19 @@ pub fn build_repo_map_scoped(repo_root: &Path, scope: &Path) -> Result<RepoMap>
             }
 
             let rel = rel_str(repo_root, &path).unwrap_or_else(|| name.clone());
+            if exclude_tests && is_test_path(&rel) {
+                continue;
+            }
+            if linguist.excluded_by(&rel).is_some() {
+                continue;
+            }
             let id = normalize_module_id(&rel);
             let label = clamp_label(&name);
             let bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
             let size_class = size_class_from_bytes(bytes);
-            let est_tokens = est_tokens_from_bytes(bytes);
+            let est_tokens = est_tokens_for_file(&estimator, &path, bytes);
 
-            nodes.push(MapNode {
+            let emitted = emit_node(MapNode {
                 id: id.clone(),
                 label,
                 path: id.clone(),
@@ -1030,56 +3598,88 @@ pub fn build_repo_map_scoped(repo_root: &Path, scope: &Path) -> Result<RepoMap>
                 size_class,
                 bytes,
                 est_tokens,
-            });
+                importance: 0.0,
+                commits: 0,
+                last_changed: None,
+                owners: Vec::new(),
+                // Files past the hard cap are never parsed for imports, so don't read them
+                // just to hash them either.
+                fingerprint: (bytes <= ABSOLUTE_MAX_FILE_BYTES)
+                    .then(|| fingerprint(&path))
+                    .flatten(),
+            })?;
+            if emitted.is_some() {
+                ids.push(id.clone());
+                file_ids.push(id.clone());
+            }
 
-            edges.push(MapEdge {
+            contains.push(MapEdge {
                 id: format!("{}->{}", parent_id, id),
                 source: parent_id.clone(),
                 target: id,
             });
         }
     }
-
-    // Smart edges: resolve file-to-file imports (relative imports for TS/JS).
-    let mut id_set: BTreeSet<String> = BTreeSet::new();
-    for n in &nodes {
-        id_set.insert(n.id.clone());
-    }
-
-    // Build a quick lookup of existing file ids.
-    let mut file_ids: Vec<String> = Vec::new();
-    for n in &nodes {
-        if n.kind == "file" {
-            file_ids.push(n.id.clone());
+    for edge in contains {
+        if emitted.insert(edge.id.clone()) {
+            emit(MapRecord::Edge(edge))?;
         }
     }
+    Ok(())
+}
+
+/// Emit the import edges between listed files, then every listed node's importance.
+fn emit_imports_and_ranks(
+    repo_root: &Path,
+    listing: MapListing,
+    emit: &mut dyn FnMut(MapRecord) -> Result<()>,
+) -> Result<()> {
+    let MapListing {
+        mut ids, file_ids, ..
+    } = listing;
+
+    // Smart edges: resolve file-to-file imports (relative imports for TS/JS, plus each
+    // language's own lookup rules via `resolve_native_import`).
+    let id_set: BTreeSet<String> = ids.iter().cloned().collect();
+    let mut imports: Vec<(String, String, String)> = Vec::new();
 
     // Attempt to resolve relative imports within the repo.
     let exts = ["ts", "tsx", "js", "jsx", "json", "md"];
+    let mut import_cache = ImportCache::default();
     for src_id in &file_ids {
         let src_abs = repo_root.join(src_id);
-        let analyzed = match analyze_file(&src_abs) {
+        // Never parse files past the hard cap (e.g. generated clients kept by oversize_policy).
+        let too_big = std::fs::metadata(&src_abs)
+            .map(|m| m.len() > ABSOLUTE_MAX_FILE_BYTES)
+            .unwrap_or(true);
+        if too_big {
+            continue;
+        }
+        let analyzed = match analyze_file_cached(repo_root, &src_abs) {
             Ok(v) => v,
             Err(_) => continue,
         };
 
         for imp in analyzed.imports {
             let imp = imp.trim();
-            if !imp.starts_with('.') {
-                continue;
-            }
-
-            let base_dir = src_abs.parent().unwrap_or(repo_root);
             let mut candidates: Vec<PathBuf> = Vec::new();
 
-            let raw = base_dir.join(imp);
-            candidates.push(raw.clone());
-            for e in exts {
-                candidates.push(base_dir.join(format!("{}.{}", imp, e)));
-            }
-            // Directory-style imports: ./foo -> ./foo/index.ts
-            for e in ["ts", "tsx", "js", "jsx"] {
-                candidates.push(base_dir.join(imp).join(format!("index.{}", e)));
+            if let Some(dst) = resolve_native_import(repo_root, &src_abs, imp, &mut import_cache) {
+                candidates.push(dst);
+            } else if imp.starts_with('.') {
+                let base_dir = src_abs.parent().unwrap_or(repo_root);
+
+                let raw = base_dir.join(imp);
+                candidates.push(raw.clone());
+                for e in exts {
+                    candidates.push(base_dir.join(format!("{}.{}", imp, e)));
+                }
+                // Directory-style imports: ./foo -> ./foo/index.ts
+                for e in ["ts", "tsx", "js", "jsx"] {
+                    candidates.push(base_dir.join(imp).join(format!("index.{}", e)));
+                }
+            } else {
+                continue;
             }
 
             let mut resolved: Option<String> = None;
@@ -1087,8 +3687,8 @@ pub fn build_repo_map_scoped(repo_root: &Path, scope: &Path) -> Result<RepoMap>
                 if !cand.exists() {
                     continue;
                 }
-                let cand_abs = cand.canonicalize().unwrap_or(cand);
-                if let Ok(rel) = cand_abs.strip_prefix(repo_root) {
+                let cand_abs = normalize_abs(&cand);
+                if let Some(rel) = relative_to(&cand_abs, repo_root) {
                     let rel_str = rel.to_string_lossy().replace('\\', "/");
                     let id = normalize_module_id(&rel_str);
                     if id_set.contains(&id) {
@@ -1103,16 +3703,1416 @@ pub fn build_repo_map_scoped(repo_root: &Path, scope: &Path) -> Result<RepoMap>
                 continue;
             }
 
-            edges.push(MapEdge {
+            let edge = MapEdge {
                 id: format!("import:{}->{}", src_id, dst_id),
                 source: src_id.clone(),
                 target: dst_id,
-            });
+            };
+            imports.push((edge.id.clone(), edge.source.clone(), edge.target.clone()));
+            emit(MapRecord::Edge(edge))?;
         }
     }
 
-    nodes.sort_by(|a, b| a.id.cmp(&b.id));
-    edges.sort_by(|a, b| a.id.cmp(&b.id));
+    // Rank by imports only; containment edges would just favour shallow entries. Ids and
+    // links are sorted so ranks don't depend on directory order.
+    ids.sort();
+    imports.sort();
+    let index: BTreeMap<&str, usize> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), i))
+        .collect();
+    let links: Vec<(usize, usize, f64)> = imports
+        .iter()
+        .filter_map(|(_, source, target)| {
+            Some((
+                *index.get(source.as_str())?,
+                *index.get(target.as_str())?,
+                1.0,
+            ))
+        })
+        .collect();
+    let ranks = page_rank(ids.len(), &links);
+    for (id, importance) in ids.iter().zip(ranks) {
+        emit(MapRecord::Importance {
+            id: id.clone(),
+            importance,
+        })?;
+    }
+    Ok(())
+}
 
-    Ok(RepoMap { nodes, edges })
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn php_use_resolves_through_composer_psr4() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::write(
+            root.join("composer.json"),
+            r#"{"autoload": {"psr-4": {"App\\": "src/", "App\\Legacy\\": ["legacy/"]}}}"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("src/Models")).unwrap();
+        std::fs::create_dir_all(root.join("legacy")).unwrap();
+        std::fs::write(root.join("src/Models/User.php"), "<?php\n").unwrap();
+        std::fs::write(root.join("legacy/Mailer.php"), "<?php\n").unwrap();
+        std::fs::write(root.join("src/helpers.php"), "<?php\n").unwrap();
+        let from = root.join("src/Http.php");
+
+        let mut cache = BTreeMap::new();
+        let resolve = |imp: &str, cache: &mut BTreeMap<_, _>| {
+            resolve_php_import(&root, &from, imp, cache).and_then(|p| rel_str(&root, &p))
+        };
+        assert_eq!(
+            resolve(r"App\Models\User", &mut cache).as_deref(),
+            Some("src/Models/User.php")
+        );
+        // Longest prefix wins over the broader `App\` root.
+        assert_eq!(
+            resolve(r"\App\Legacy\Mailer", &mut cache).as_deref(),
+            Some("legacy/Mailer.php")
+        );
+        assert_eq!(
+            resolve("helpers.php", &mut cache).as_deref(),
+            Some("src/helpers.php")
+        );
+        assert_eq!(resolve(r"Vendor\Pkg\Thing", &mut cache), None);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn zig_import_resolves_relative_to_importing_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::create_dir_all(root.join("src/net")).unwrap();
+        std::fs::write(root.join("src/net/client.zig"), "pub fn get() void {}\n").unwrap();
+        let from = root.join("src/main.zig");
+
+        let mut cache = ImportCache::default();
+        let dst = resolve_native_import(&root, &from, "net/client.zig", &mut cache);
+        assert_eq!(
+            dst.and_then(|p| rel_str(&root, &p)).as_deref(),
+            Some("src/net/client.zig")
+        );
+        assert_eq!(resolve_native_import(&root, &from, "std", &mut cache), None);
+    }
+
+    #[test]
+    fn lua_require_resolves_modules_and_init_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::create_dir_all(root.join("lib/json")).unwrap();
+        std::fs::write(root.join("lib/json/init.lua"), "return {}\n").unwrap();
+        std::fs::write(root.join("lib/util.lua"), "return {}\n").unwrap();
+        let from = root.join("main.lua");
+
+        let mut cache = ImportCache::default();
+        let mut resolve = |imp: &str| {
+            resolve_native_import(&root, &from, imp, &mut cache).and_then(|p| rel_str(&root, &p))
+        };
+        assert_eq!(resolve("lib.util").as_deref(), Some("lib/util.lua"));
+        assert_eq!(resolve("lib.json").as_deref(), Some("lib/json/init.lua"));
+        assert_eq!(resolve("socket.http"), None);
+    }
+
+    #[test]
+    fn dart_package_uri_resolves_against_pubspec_lib() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::write(root.join("pubspec.yaml"), "name: my_app\n").unwrap();
+        std::fs::create_dir_all(root.join("lib/src")).unwrap();
+        std::fs::write(root.join("lib/src/util.dart"), "int x = 1;\n").unwrap();
+        let from = root.join("lib/main.dart");
+
+        let mut cache = ImportCache::default();
+        let mut resolve = |imp: &str| {
+            resolve_native_import(&root, &from, imp, &mut cache).and_then(|p| rel_str(&root, &p))
+        };
+        assert_eq!(
+            resolve("package:my_app/src/util.dart").as_deref(),
+            Some("lib/src/util.dart")
+        );
+        assert_eq!(
+            resolve("src/util.dart").as_deref(),
+            Some("lib/src/util.dart")
+        );
+        assert_eq!(resolve("package:flutter/material.dart"), None);
+        assert_eq!(resolve("dart:async"), None);
+    }
+
+    #[test]
+    fn elixir_alias_resolves_through_mix_layout() {
+        assert_eq!(elixir_underscore("HTTPClient"), "http_client");
+        assert_eq!(elixir_underscore("MyApp"), "my_app");
+
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::write(root.join("mix.exs"), "defmodule MyApp.MixProject do\nend\n").unwrap();
+        std::fs::create_dir_all(root.join("lib/my_app/accounts")).unwrap();
+        std::fs::write(root.join("lib/my_app/accounts/user.ex"), "").unwrap();
+        let from = root.join("lib/my_app.ex");
+
+        let mut cache = ImportCache::default();
+        let dst = resolve_native_import(&root, &from, "MyApp.Accounts.User", &mut cache);
+        assert_eq!(
+            dst.and_then(|p| rel_str(&root, &p)).as_deref(),
+            Some("lib/my_app/accounts/user.ex")
+        );
+        assert_eq!(
+            resolve_native_import(&root, &from, "Ecto.Schema", &mut cache),
+            None
+        );
+    }
+
+    #[test]
+    fn proto_import_resolves_against_ancestor_include_roots() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::create_dir_all(root.join("proto/acme/common")).unwrap();
+        std::fs::create_dir_all(root.join("proto/acme/billing/v1")).unwrap();
+        std::fs::write(root.join("proto/acme/common/money.proto"), "").unwrap();
+        let from = root.join("proto/acme/billing/v1/billing.proto");
+
+        let mut cache = ImportCache::default();
+        let dst = resolve_native_import(&root, &from, "acme/common/money.proto", &mut cache);
+        assert_eq!(
+            dst.and_then(|p| rel_str(&root, &p)).as_deref(),
+            Some("proto/acme/common/money.proto")
+        );
+        let missing = "google/protobuf/timestamp.proto";
+        assert_eq!(
+            resolve_native_import(&root, &from, missing, &mut cache),
+            None
+        );
+    }
+
+    #[test]
+    fn ocaml_open_resolves_to_sibling_compilation_unit() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/http_client.ml"), "").unwrap();
+        let from = root.join("src/main.ml");
+
+        let mut cache = ImportCache::default();
+        let dst = resolve_native_import(&root, &from, "Http_client.Request", &mut cache);
+        assert_eq!(
+            dst.and_then(|p| rel_str(&root, &p)).as_deref(),
+            Some("src/http_client.ml")
+        );
+        assert_eq!(
+            resolve_native_import(&root, &from, "Printf", &mut cache),
+            None
+        );
+    }
+
+    #[test]
+    fn shell_source_resolves_next_to_the_script_then_at_the_root() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::create_dir_all(root.join("scripts/lib")).unwrap();
+        std::fs::create_dir_all(root.join("lib")).unwrap();
+        std::fs::write(root.join("scripts/lib/log.sh"), "log() { echo \"$@\"; }\n").unwrap();
+        std::fs::write(root.join("lib/log.sh"), "log() { :; }\n").unwrap();
+        std::fs::write(root.join("lib/env.sh"), "export STAGE=prod\n").unwrap();
+        std::fs::write(
+            root.join("scripts/deploy.sh"),
+            "source lib/log.sh\n. lib/env.sh\nsource \"$HOME/.deployrc\"\nlog deploying\n",
+        )
+        .unwrap();
+        let from = root.join("scripts/deploy.sh");
+
+        let mut cache = ImportCache::default();
+        let mut resolve = |imp: &str| {
+            resolve_native_import(&root, &from, imp, &mut cache).and_then(|p| rel_str(&root, &p))
+        };
+        // Next to the script wins over the repo root.
+        assert_eq!(resolve("lib/log.sh").as_deref(), Some("scripts/lib/log.sh"));
+        assert_eq!(resolve("lib/env.sh").as_deref(), Some("lib/env.sh"));
+        assert_eq!(resolve("$HOME/.deployrc"), None);
+        assert_eq!(resolve("$(dirname \"$0\")/lib/log.sh"), None);
+        assert_eq!(resolve("`pwd`/lib/env.sh"), None);
+        assert_eq!(resolve("lib/missing.sh"), None);
+
+        if !crate::inspector::wasm_grammar("bash") {
+            return;
+        }
+        let closure = import_closure(&root, Path::new("scripts/deploy.sh"), 1).unwrap();
+        let files: Vec<&str> = closure.iter().map(|(f, _)| f.as_str()).collect();
+        assert_eq!(
+            files,
+            vec!["scripts/deploy.sh", "lib/env.sh", "scripts/lib/log.sh"]
+        );
+    }
+
+    #[test]
+    fn markers_are_aggregated_across_the_repo() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/b.rs"), "// FIXME: b\nfn b() {}\n").unwrap();
+        std::fs::write(root.join("src/a.py"), "# TODO: a\n").unwrap();
+        std::fs::write(root.join("src/clean.rs"), "fn c() {}\n").unwrap();
+        std::fs::write(root.join("notes.md"), "TODO: not source\n").unwrap();
+
+        let all = collect_markers(&root, Path::new(".")).unwrap();
+        let files: Vec<&str> = all.iter().map(|f| f.file.as_str()).collect();
+        assert_eq!(files, vec!["src/a.py", "src/b.rs"]);
+        assert_eq!(all[1].markers[0].kind, "FIXME");
+    }
+
+    #[test]
+    fn rust_use_paths_and_mod_declarations_resolve_to_module_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        std::fs::create_dir_all(root.join("src/net")).unwrap();
+        for (f, src) in [
+            ("src/lib.rs", "mod net;\nmod util;\n"),
+            ("src/util.rs", ""),
+            ("src/net/mod.rs", "pub mod client;\n"),
+            (
+                "src/net/client.rs",
+                "use crate::util::helper;\nuse super::super::util;\n",
+            ),
+        ] {
+            std::fs::write(root.join(f), src).unwrap();
+        }
+        let resolve = |from: &str, imp: &str| {
+            resolve_rust_import(&root, &root.join(from), imp).and_then(|p| rel_str(&root, &p))
+        };
+
+        assert_eq!(
+            resolve("src/lib.rs", "mod net").as_deref(),
+            Some("src/net/mod.rs")
+        );
+        assert_eq!(
+            resolve("src/lib.rs", "crate::net::client::Client").as_deref(),
+            Some("src/net/client.rs")
+        );
+        assert_eq!(
+            resolve("src/net/mod.rs", "mod client").as_deref(),
+            Some("src/net/client.rs")
+        );
+        assert_eq!(
+            resolve("src/net/mod.rs", "super::util").as_deref(),
+            Some("src/util.rs")
+        );
+        assert_eq!(
+            resolve("src/net/client.rs", "super::super::util::helper").as_deref(),
+            Some("src/util.rs")
+        );
+        // `super::Item` in a child module points at the parent module's file.
+        assert_eq!(
+            resolve("src/net/client.rs", "super::Pool").as_deref(),
+            Some("src/net/mod.rs")
+        );
+        assert_eq!(
+            resolve("src/util.rs", "crate::{util, net}").as_deref(),
+            Some("src/lib.rs")
+        );
+        assert_eq!(
+            resolve("src/lib.rs", "net::client").as_deref(),
+            Some("src/net/client.rs")
+        );
+        assert_eq!(resolve("src/lib.rs", "serde::Serialize"), None);
+        assert_eq!(resolve("src/lib.rs", "std::collections::HashMap"), None);
+
+        let graph = build_module_graph(&root, Path::new(".")).unwrap();
+        let edges: Vec<(&str, &str, u64)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.source.as_str(), e.target.as_str(), e.weight))
+            .collect();
+        assert_eq!(edges, vec![("src", "src/net", 1), ("src/net", "src", 2)]);
+    }
+
+    #[test]
+    fn tsconfig_paths_and_base_url_aliases_resolve() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::write(
+            root.join("tsconfig.base.json"),
+            r#"{"compilerOptions": {"baseUrl": ".", "paths": {
+                "@app/*": ["src/app/*"],
+                "@app/special/*": ["src/special/*"],
+                "@shared": ["libs/shared/index.ts"]
+            }}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("tsconfig.json"),
+            "// project config\n{ \"extends\": \"./tsconfig.base\", }\n",
+        )
+        .unwrap();
+        for f in [
+            "src/main.ts",
+            "src/app/utils.ts",
+            "src/app/special/a.ts",
+            "src/special/a.ts",
+            "src/lib/thing.ts",
+            "libs/shared/index.ts",
+        ] {
+            std::fs::create_dir_all(root.join(f).parent().unwrap()).unwrap();
+            std::fs::write(root.join(f), "export const x = 1;\n").unwrap();
+        }
+        let from = root.join("src/main.ts");
+        let mut cache = ImportCache::default();
+        let mut resolve = |imp: &str| {
+            resolve_native_import(&root, &from, imp, &mut cache).and_then(|p| rel_str(&root, &p))
+        };
+        assert_eq!(resolve("@app/utils").as_deref(), Some("src/app/utils.ts"));
+        // The longest matching prefix wins.
+        assert_eq!(
+            resolve("@app/special/a").as_deref(),
+            Some("src/special/a.ts")
+        );
+        assert_eq!(resolve("@shared").as_deref(), Some("libs/shared/index.ts"));
+        assert_eq!(
+            resolve("src/lib/thing").as_deref(),
+            Some("src/lib/thing.ts")
+        );
+        assert_eq!(resolve("react"), None);
+
+        std::fs::write(&from, "import { x } from '@shared';\n").unwrap();
+        let graph = build_module_graph(&root, Path::new(".")).unwrap();
+        assert!(graph
+            .edges
+            .iter()
+            .any(|e| e.source == "." && e.target == "libs/shared"));
+    }
+
+    #[test]
+    fn workspace_package_imports_resolve_to_their_package() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::write(
+            root.join("package.json"),
+            r#"{"name": "mono", "private": true}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("pnpm-workspace.yaml"),
+            "packages:\n  - 'packages/*'\n  - '!packages/ignored'\n",
+        )
+        .unwrap();
+        let pkg = |dir: &str, manifest: &str, files: &[&str]| {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::fs::write(root.join(dir).join("package.json"), manifest).unwrap();
+            for f in files {
+                let p = root.join(dir).join(f);
+                std::fs::create_dir_all(p.parent().unwrap()).unwrap();
+                std::fs::write(p, "export const x = 1;\n").unwrap();
+            }
+        };
+        pkg(
+            "packages/a",
+            r#"{"name": "@org/pkg-a"}"#,
+            &["index.ts", "main.ts"],
+        );
+        pkg(
+            "packages/b",
+            r#"{"name": "@org/pkg-b", "main": "dist/index.js", "source": "lib/entry.ts"}"#,
+            &["lib/entry.ts", "lib/util.ts"],
+        );
+        pkg(
+            "packages/c",
+            r#"{"name": "@org/pkg-c", "main": "dist/index.js"}"#,
+            &[],
+        );
+
+        let from = root.join("packages/a/main.ts");
+        let mut cache = ImportCache::default();
+        let mut resolve = |imp: &str| {
+            resolve_native_import(&root, &from, imp, &mut cache).and_then(|p| rel_str(&root, &p))
+        };
+        assert_eq!(
+            resolve("@org/pkg-b").as_deref(),
+            Some("packages/b/lib/entry.ts")
+        );
+        assert_eq!(
+            resolve("@org/pkg-b/lib/util").as_deref(),
+            Some("packages/b/lib/util.ts")
+        );
+        assert_eq!(
+            resolve("@org/pkg-a").as_deref(),
+            Some("packages/a/index.ts")
+        );
+        // Unbuilt entry points still land in the owning package.
+        assert_eq!(
+            resolve("@org/pkg-c").as_deref(),
+            Some("packages/c/package.json")
+        );
+        assert_eq!(resolve("@org/pkg-bb"), None);
+        assert_eq!(resolve("lodash"), None);
+
+        std::fs::write(
+            &from,
+            "import b from '@org/pkg-b';\nimport c from '@org/pkg-c';\n",
+        )
+        .unwrap();
+        let graph = build_module_graph(&root, Path::new(".")).unwrap();
+        let edges: Vec<(&str, &str)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.source.as_str(), e.target.as_str()))
+            .collect();
+        assert!(edges.contains(&("packages/a", "packages/b")));
+        assert!(edges.contains(&("packages/a", "packages/c")));
+    }
+
+    #[test]
+    fn python_imports_resolve_to_modules_and_packages() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::write(root.join("pyproject.toml"), "[project]\nname = \"app\"\n").unwrap();
+        for f in [
+            "src/app/__init__.py",
+            "src/app/main.py",
+            "src/app/models.py",
+            "src/app/core/__init__.py",
+            "src/app/core/db.py",
+            "scripts/tool.py",
+        ] {
+            std::fs::create_dir_all(root.join(f).parent().unwrap()).unwrap();
+            std::fs::write(root.join(f), "").unwrap();
+        }
+        let resolve = |from: &str, imp: &str| {
+            resolve_python_import(&root, &root.join(from), imp).and_then(|p| rel_str(&root, &p))
+        };
+
+        assert_eq!(
+            resolve("src/app/main.py", "app.core.db").as_deref(),
+            Some("src/app/core/db.py")
+        );
+        assert_eq!(
+            resolve("src/app/main.py", "app.core.db.Session").as_deref(),
+            Some("src/app/core/db.py")
+        );
+        assert_eq!(
+            resolve("src/app/main.py", "app.core").as_deref(),
+            Some("src/app/core/__init__.py")
+        );
+        assert_eq!(
+            resolve("src/app/main.py", ".models").as_deref(),
+            Some("src/app/models.py")
+        );
+        assert_eq!(
+            resolve("src/app/core/db.py", "..models").as_deref(),
+            Some("src/app/models.py")
+        );
+        assert_eq!(
+            resolve("src/app/core/db.py", ".").as_deref(),
+            Some("src/app/core/__init__.py")
+        );
+        // `from . import helper` where `helper` is defined in the package itself.
+        assert_eq!(
+            resolve("src/app/main.py", ".helper").as_deref(),
+            Some("src/app/__init__.py")
+        );
+        // `src/` layouts resolve from scripts outside the package too.
+        assert_eq!(
+            resolve("scripts/tool.py", "app.models").as_deref(),
+            Some("src/app/models.py")
+        );
+        assert_eq!(resolve("src/app/main.py", "requests"), None);
+
+        std::fs::write(
+            root.join("src/app/core/db.py"),
+            "from .. import models\nfrom app.main import run\n",
+        )
+        .unwrap();
+        let imports = crate::inspector::analyze_file(&root.join("src/app/core/db.py"))
+            .unwrap()
+            .imports;
+        assert_eq!(imports, vec!["..models", "app.main"]);
+    }
+
+    #[test]
+    fn module_graph_links_cargo_workspace_path_dependencies() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        let write = |rel: &str, text: &str| {
+            std::fs::create_dir_all(root.join(rel).parent().unwrap()).unwrap();
+            std::fs::write(root.join(rel), text).unwrap();
+        };
+        write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\n\n[workspace.dependencies]\ncore = { path = \"crates/core\" }\n",
+        );
+        write("crates/core/Cargo.toml", "[package]\nname = \"core\"\n");
+        write("crates/core/src/lib.rs", "pub fn id() {}\n");
+        write(
+            "crates/cli/Cargo.toml",
+            "[package]\nname = \"cli\"\n\n[dependencies]\ncore.workspace = true\nserde = \"1\"\n\n[dev-dependencies]\nutil = { path = \"../util\" }\n",
+        );
+        write("crates/cli/src/main.rs", "fn main() {}\n");
+        write("crates/util/Cargo.toml", "[package]\nname = \"util\"\n");
+        write("crates/util/src/lib.rs", "pub fn helper() {}\n");
+
+        let graph = build_module_graph(&root, Path::new(".")).unwrap();
+        let edges: Vec<(&str, &str)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.source.as_str(), e.target.as_str()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("crates/cli/src", "crates/core/src"),
+                ("crates/cli/src", "crates/util/src")
+            ]
+        );
+    }
+
+    #[test]
+    fn call_graph_links_functions_across_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/main.rs"),
+            "fn main() {\n    let job = Job::new();\n    job.run();\n    helper();\n    println!(\"done\");\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/job.rs"),
+            "pub struct Job;\n\nimpl Job {\n    pub fn new() -> Self { Job }\n    pub fn run(&self) { self.step(); helper(); }\n    fn step(&self) { std::thread::yield_now(); }\n}\n\npub fn helper() {}\n",
+        )
+        .unwrap();
+
+        let graph = build_call_graph(&root, Path::new(".")).unwrap();
+        let ids: Vec<&str> = graph.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "src/job.rs#Job::new",
+                "src/job.rs#Job::run",
+                "src/job.rs#Job::step",
+                "src/job.rs#helper",
+                "src/main.rs#main"
+            ]
+        );
+        let edges: Vec<(&str, &str)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.source.as_str(), e.target.as_str()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("src/job.rs#Job::run", "src/job.rs#Job::step"),
+                ("src/job.rs#Job::run", "src/job.rs#helper"),
+                ("src/main.rs#main", "src/job.rs#Job::new"),
+                ("src/main.rs#main", "src/job.rs#Job::run"),
+                ("src/main.rs#main", "src/job.rs#helper"),
+            ]
+        );
+    }
+
+    #[test]
+    fn module_cycles_are_reported_as_strongly_connected_components() {
+        let node = |id: &str| ModuleNode {
+            id: id.to_string(),
+            label: id.to_string(),
+            path: id.to_string(),
+            file_count: 1,
+            bytes: 0,
+            est_tokens: 0,
+            lines: 0,
+            symbols: 0,
+            avg_file_bytes: 0,
+            languages: BTreeMap::new(),
+            importance: 0.0,
+            cluster: 0,
+            commits: 0,
+            last_changed: None,
+            owners: Vec::new(),
+        };
+        let edge = |s: &str, t: &str, weight: u64| ModuleEdge {
+            id: format!("{s}->{t}"),
+            source: s.to_string(),
+            target: t.to_string(),
+            weight,
+        };
+        let graph = ModuleGraph {
+            nodes: ["a", "b", "c", "d", "e", "f"].map(node).to_vec(),
+            edges: vec![
+                edge("a", "b", 2),
+                edge("b", "c", 1),
+                edge("c", "a", 4),
+                edge("c", "d", 1),
+                edge("d", "e", 1),
+                edge("e", "d", 3),
+                edge("e", "f", 1),
+            ],
+        };
+
+        let cycles = find_module_cycles(&graph);
+        let members: Vec<Vec<&str>> = cycles
+            .iter()
+            .map(|c| c.members.iter().map(String::as_str).collect())
+            .collect();
+        assert_eq!(members, vec![vec!["a", "b", "c"], vec!["d", "e"]]);
+        let weights: Vec<Vec<(&str, u64)>> = cycles
+            .iter()
+            .map(|c| c.edges.iter().map(|e| (e.id.as_str(), e.weight)).collect())
+            .collect();
+        assert_eq!(
+            weights,
+            vec![
+                vec![("a->b", 2), ("b->c", 1), ("c->a", 4)],
+                vec![("d->e", 1), ("e->d", 3)]
+            ]
+        );
+    }
+
+    #[test]
+    fn page_rank_favours_widely_imported_nodes() {
+        // 0, 1 and 2 all import 3; 3 imports 4; 5 is isolated.
+        let edges = [(0, 3, 1.0), (1, 3, 1.0), (2, 3, 1.0), (3, 4, 1.0)];
+        let ranks = page_rank(6, &edges);
+        let total: f64 = ranks.iter().sum();
+        assert!((total - 6.0).abs() < 1e-3, "{ranks:?}");
+        assert!(ranks[3] > ranks[0] && ranks[4] > ranks[0], "{ranks:?}");
+        assert_eq!(ranks[0], ranks[5]);
+        assert_eq!(page_rank(3, &[]), vec![1.0, 1.0, 1.0]);
+
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::write(root.join("util.ts"), "export const x = 1;\n").unwrap();
+        std::fs::write(root.join("a.ts"), "import { x } from './util';\n").unwrap();
+        std::fs::write(root.join("b.ts"), "import { x } from './util';\n").unwrap();
+        let map = build_repo_map_scoped(&root, Path::new(".")).unwrap();
+        let importance = |id: &str| map.nodes.iter().find(|n| n.id == id).unwrap().importance;
+        assert!(importance("util.ts") > importance("a.ts"));
+    }
+
+    #[test]
+    fn impact_lists_transitive_importers_by_distance() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        let write = |rel: &str, text: &str| {
+            std::fs::create_dir_all(root.join(rel).parent().unwrap()).unwrap();
+            std::fs::write(root.join(rel), text).unwrap();
+        };
+        write("core/util.ts", "export const x = 1;\n");
+        write(
+            "core/index.ts",
+            "import { x } from './util';\nexport { x };\n",
+        );
+        write("app/main.ts", "import { x } from '../core';\n");
+        write("app/direct.ts", "import { x } from '../core/util';\n");
+        write("app/unrelated.ts", "export const y = 2;\n");
+        write("app/package.json", "{\"name\": \"app\"}\n");
+
+        let report = impact_of(&root, Path::new("core/util.ts")).unwrap();
+        assert_eq!(report.target, "core/util.ts");
+        let files: Vec<(&str, u32)> = report
+            .files
+            .iter()
+            .map(|f| (f.file.as_str(), f.depth))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("app/direct.ts", 1),
+                ("core/index.ts", 1),
+                ("app/main.ts", 2)
+            ]
+        );
+        assert_eq!(report.modules, vec!["app", "core"]);
+        assert!(impact_of(&root, Path::new("missing.ts")).is_err());
+    }
+
+    #[test]
+    fn module_graph_diff_between_git_revisions() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        let git = |args: &[&str]| {
+            let out = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&root)
+                .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(
+                out.status.success(),
+                "{}",
+                String::from_utf8_lossy(&out.stderr)
+            );
+        };
+        let write = |rel: &str, text: &str| {
+            std::fs::create_dir_all(root.join(rel).parent().unwrap()).unwrap();
+            std::fs::write(root.join(rel), text).unwrap();
+        };
+        write("core/index.ts", "export const x = 1;\n");
+        write("app/index.ts", "import { x } from '../core';\n");
+        write("legacy/index.ts", "import { x } from '../core';\n");
+        git(&["init", "-q"]);
+        git(&["add", "-A"]);
+        git(&["commit", "-q", "-m", "base"]);
+
+        std::fs::remove_dir_all(root.join("legacy")).unwrap();
+        write("api/index.ts", "import { x } from '../core';\n");
+        write(
+            "app/index.ts",
+            "import { x } from '../core';\nimport { y } from '../api';\n",
+        );
+        git(&["add", "-A"]);
+        git(&["commit", "-q", "-m", "head"]);
+
+        let before = build_module_graph_at_rev(&root, "HEAD~1", Path::new(".")).unwrap();
+        let after = build_module_graph_at_rev(&root, "HEAD", Path::new(".")).unwrap();
+        let diff = diff_module_graphs(&before, &after);
+        let ids = |nodes: &[ModuleNode]| nodes.iter().map(|n| n.id.clone()).collect::<Vec<_>>();
+        let edge_ids =
+            |edges: &[ModuleEdge]| edges.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&diff.added_nodes), vec!["api"]);
+        assert_eq!(ids(&diff.removed_nodes), vec!["legacy"]);
+        assert_eq!(edge_ids(&diff.added_edges), vec!["api->core", "app->api"]);
+        assert_eq!(edge_ids(&diff.removed_edges), vec!["legacy->core"]);
+        assert!(diff.reweighted_edges.is_empty());
+
+        // Temporary worktrees are cleaned up again.
+        let list = std::process::Command::new("git")
+            .arg("-C")
+            .arg(&root)
+            .args(["worktree", "list"])
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&list.stdout).lines().count(), 1);
+        assert!(build_module_graph_at_rev(&root, "no-such-rev", Path::new(".")).is_err());
+    }
+
+    #[test]
+    fn module_graph_is_cached_and_rebuilt_incrementally() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        let write = |rel: &str, text: &str| {
+            std::fs::create_dir_all(root.join(rel).parent().unwrap()).unwrap();
+            std::fs::write(root.join(rel), text).unwrap();
+        };
+        write("core/index.ts", "export const x = 1;\n");
+        write("app/index.ts", "import { x } from '../core';\n");
+        write("web/index.ts", "export const y = 2;\n");
+
+        let edges = |g: &ModuleGraph| g.edges.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+        let cold = build_module_graph(&root, Path::new(".")).unwrap();
+        assert_eq!(edges(&cold), vec!["app->core"]);
+        let cache_path = module_graph_cache_path(&root, &root);
+
+        // Tamper with the stored graph: an unchanged tree must be served from it.
+        let mut cache: ModuleGraphCache =
+            serde_json::from_slice(&std::fs::read(&cache_path).unwrap()).unwrap();
+        cache.graph.as_mut().unwrap().edges[0].weight = 42;
+        // Tamper with app's cached imports too: unchanged files must not be re-analyzed.
+        cache.files.get_mut("app/index.ts").unwrap().imports = vec!["../web".to_string()];
+        std::fs::write(&cache_path, serde_json::to_vec(&cache).unwrap()).unwrap();
+        let warm = build_module_graph(&root, Path::new(".")).unwrap();
+        assert_eq!(warm.edges[0].weight, 42);
+
+        // Editing one file rebuilds the graph, re-analyzing only that file.
+        write("web/index.ts", "import { x } from '../core';\n");
+        let rebuilt = build_module_graph(&root, Path::new(".")).unwrap();
+        assert_eq!(edges(&rebuilt), vec!["app->web", "web->core"]);
+
+        // Non-source files that steer resolution invalidate the graph as well.
+        write("app/package.json", "{\"name\": \"app\"}\n");
+        let cache: ModuleGraphCache =
+            serde_json::from_slice(&std::fs::read(&cache_path).unwrap()).unwrap();
+        let before = cache.fingerprint;
+        build_module_graph(&root, Path::new(".")).unwrap();
+        let cache: ModuleGraphCache =
+            serde_json::from_slice(&std::fs::read(&cache_path).unwrap()).unwrap();
+        assert_ne!(cache.fingerprint, before);
+    }
+
+    #[test]
+    fn resolution_configs_above_the_graph_root_invalidate_the_cache() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        let write = |rel: &str, text: &str| {
+            std::fs::create_dir_all(root.join(rel).parent().unwrap()).unwrap();
+            std::fs::write(root.join(rel), text).unwrap();
+        };
+        write("packages/core/index.ts", "export const x = 1;\n");
+        write("packages/app/index.ts", "import { x } from '@core';\n");
+
+        let edges = |g: &ModuleGraph| g.edges.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+        let graph = build_module_graph(&root, Path::new("packages")).unwrap();
+        assert!(edges(&graph).is_empty());
+
+        // The alias lives in the repo-root tsconfig, outside the graph root.
+        write(
+            "tsconfig.json",
+            r#"{"compilerOptions": {"paths": {"@core": ["packages/core/index.ts"]}}}"#,
+        );
+        let graph = build_module_graph(&root, Path::new("packages")).unwrap();
+        assert_eq!(edges(&graph), vec!["packages/app->packages/core"]);
+    }
+
+    #[test]
+    fn map_and_module_nodes_carry_git_churn() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        let commit = |rel: &str, text: &str, time: &str| {
+            std::fs::create_dir_all(root.join(rel).parent().unwrap()).unwrap();
+            std::fs::write(root.join(rel), text).unwrap();
+            for args in [&["add", "-A"][..], &["commit", "-q", "-m", "c"][..]] {
+                let out = std::process::Command::new("git")
+                    .arg("-C")
+                    .arg(&root)
+                    .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+                    .args(args)
+                    .env("GIT_AUTHOR_DATE", format!("@{time} +0000"))
+                    .env("GIT_COMMITTER_DATE", format!("@{time} +0000"))
+                    .output()
+                    .unwrap();
+                assert!(
+                    out.status.success(),
+                    "{}",
+                    String::from_utf8_lossy(&out.stderr)
+                );
+            }
+        };
+        let init = std::process::Command::new("git")
+            .arg("-C")
+            .arg(&root)
+            .args(["init", "-q"])
+            .status()
+            .unwrap();
+        assert!(init.success());
+        commit("core/index.ts", "export const x = 1;\n", "1700000000");
+        commit("core/index.ts", "export const x = 2;\n", "1700000100");
+        commit(
+            "app/index.ts",
+            "import { x } from '../core';\n",
+            "1700000200",
+        );
+        std::fs::write(root.join("app/new.ts"), "export {};\n").unwrap();
+
+        let map = build_repo_map_scoped(&root, Path::new("core")).unwrap();
+        let node = |id: &str| map.nodes.iter().find(|n| n.id == id).unwrap().clone();
+        assert_eq!(node("core").commits, 2);
+        assert_eq!(node("core/index.ts").last_changed, Some(1_700_000_100));
+
+        let graph = build_module_graph(&root, Path::new(".")).unwrap();
+        let module = |id: &str| graph.nodes.iter().find(|n| n.id == id).unwrap().clone();
+        assert_eq!(
+            (module(".").commits, module(".").last_changed),
+            (3, Some(1_700_000_200))
+        );
+        assert_eq!(
+            (module("app").commits, module("app").last_changed),
+            (1, Some(1_700_000_200))
+        );
+        // Untracked paths have no history.
+        let app = build_repo_map_scoped(&root, Path::new("app")).unwrap();
+        let new = app.nodes.iter().find(|n| n.id == "app/new.ts").unwrap();
+        assert_eq!((new.commits, new.last_changed), (0, None));
+    }
+
+    #[test]
+    fn git_churn_is_cached_until_head_moves() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        let git = |args: &[&str]| {
+            let out = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&root)
+                .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(
+                out.status.success(),
+                "{}",
+                String::from_utf8_lossy(&out.stderr)
+            );
+        };
+        git(&["init", "-q"]);
+        std::fs::write(root.join(".gitignore"), ".context-slicer/\n").unwrap();
+        std::fs::write(root.join("a.ts"), "export const a = 1;\n").unwrap();
+        git(&["add", "-A"]);
+        git(&["commit", "-q", "-m", "a"]);
+
+        assert_eq!(git_churn(&root)["a.ts"].0, 1);
+        let cached: Vec<PathBuf> = std::fs::read_dir(root.join(GRAPH_CACHE_DIR))
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        assert_eq!(cached.len(), 1);
+
+        // Same HEAD: served from the cache, as a tampered entry shows.
+        let mut cache: ChurnCache =
+            serde_json::from_slice(&std::fs::read(&cached[0]).unwrap()).unwrap();
+        cache.churn.get_mut("a.ts").unwrap().0 = 42;
+        store_json(&cached[0], &cache).unwrap();
+        assert_eq!(git_churn(&root)["a.ts"].0, 42);
+
+        std::fs::write(root.join("a.ts"), "export const a = 2;\n").unwrap();
+        git(&["commit", "-q", "-am", "a2"]);
+        assert_eq!(git_churn(&root)["a.ts"].0, 2);
+    }
+
+    #[test]
+    fn map_and_module_nodes_carry_codeowners() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        let write = |rel: &str, text: &str| {
+            std::fs::create_dir_all(root.join(rel).parent().unwrap()).unwrap();
+            std::fs::write(root.join(rel), text).unwrap();
+        };
+        write(
+            ".github/CODEOWNERS",
+            "* @org/core\n/app/ @org/app\n*.test.ts @org/qa\n",
+        );
+        write("app/index.ts", "export const x = 1;\n");
+        write("app/index.test.ts", "import { x } from './index';\n");
+        write("lib/index.ts", "export const y = 1;\n");
+
+        let map = build_repo_map_scoped(&root, Path::new("app")).unwrap();
+        let owners = |id: &str| {
+            map.nodes
+                .iter()
+                .find(|n| n.id == id)
+                .unwrap()
+                .owners
+                .clone()
+        };
+        assert_eq!(owners("app"), vec!["@org/app"]);
+        assert_eq!(owners("app/index.ts"), vec!["@org/app"]);
+        assert_eq!(owners("app/index.test.ts"), vec!["@org/qa"]);
+
+        let graph = build_module_graph(&root, Path::new(".")).unwrap();
+        let owners = |id: &str| {
+            graph
+                .nodes
+                .iter()
+                .find(|n| n.id == id)
+                .unwrap()
+                .owners
+                .clone()
+        };
+        assert_eq!(owners("."), vec!["@org/core"]);
+        assert_eq!(owners("app"), vec!["@org/app"]);
+        assert_eq!(owners("lib"), vec!["@org/core"]);
+    }
+
+    #[test]
+    fn layer_check_reports_disallowed_imports_with_evidence() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        let write = |rel: &str, text: &str| {
+            std::fs::create_dir_all(root.join(rel).parent().unwrap()).unwrap();
+            std::fs::write(root.join(rel), text).unwrap();
+        };
+        write("data/db.ts", "export const db = 1;\n");
+        write("core/service.ts", "import { db } from '../data/db';\n");
+        write("core/helpers.ts", "import { db } from '../data/db';\n");
+        write(
+            "ui/page.ts",
+            "import { s } from '../core/service';\n// direct access\nimport { db } from '../data/db';\n",
+        );
+        write("scripts/seed.ts", "import { db } from '../data/db';\n");
+        write("data/audit.ts", "import { s } from '../core/service';\n");
+
+        let architecture: ArchitectureConfig = serde_json::from_str(
+            r#"{
+                "layers": [
+                    {"name": "ui", "paths": ["ui"]},
+                    {"name": "core", "paths": ["core/**"]},
+                    {"name": "data", "paths": ["data"]}
+                ],
+                "allow": ["ui -> core -> data"]
+            }"#,
+        )
+        .unwrap();
+        let violations = check_layers(&root, Path::new("."), &architecture).unwrap();
+        let found: Vec<(&str, &str, &str, Option<u32>, &str)> = violations
+            .iter()
+            .map(|v| {
+                (
+                    v.file.as_str(),
+                    v.from_layer.as_str(),
+                    v.to_layer.as_str(),
+                    v.line,
+                    v.target.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("data/audit.ts", "data", "core", Some(1), "core/service.ts"),
+                ("ui/page.ts", "ui", "data", Some(3), "data/db.ts"),
+            ]
+        );
+    }
+
+    #[test]
+    fn louvain_separates_loosely_coupled_groups() {
+        // Two dense triangles joined by one light edge, plus an isolated node.
+        let edges = [
+            (0, 1, 3.0),
+            (1, 2, 3.0),
+            (2, 0, 3.0),
+            (3, 4, 2.0),
+            (4, 5, 2.0),
+            (5, 3, 2.0),
+            (6, 7, 1.0),
+            (2, 3, 1.0),
+        ];
+        let clusters = louvain(9, &edges);
+        assert_eq!(clusters[0], clusters[1]);
+        assert_eq!(clusters[1], clusters[2]);
+        assert_eq!(clusters[3], clusters[4]);
+        assert_eq!(clusters[4], clusters[5]);
+        assert_ne!(clusters[0], clusters[3]);
+        assert_eq!(clusters[6], clusters[7]);
+        assert_ne!(clusters[6], clusters[0]);
+        assert_ne!(clusters[6], clusters[3]);
+        // Largest clusters first, ties broken by the lowest member; singletons last.
+        assert_eq!(
+            (clusters[0], clusters[3], clusters[6], clusters[8]),
+            (0, 1, 2, 3)
+        );
+        assert!(louvain(3, &[]).iter().copied().eq(0..3));
+    }
+
+    #[test]
+    fn shortest_module_paths_prefer_heavily_used_imports() {
+        let node = |id: &str| ModuleNode {
+            id: id.to_string(),
+            label: id.to_string(),
+            path: id.to_string(),
+            file_count: 1,
+            bytes: 0,
+            est_tokens: 0,
+            lines: 0,
+            symbols: 0,
+            avg_file_bytes: 0,
+            languages: BTreeMap::new(),
+            importance: 0.0,
+            cluster: 0,
+            commits: 0,
+            last_changed: None,
+            owners: Vec::new(),
+        };
+        let edge = |s: &str, t: &str, weight: u64| ModuleEdge {
+            id: format!("{s}->{t}"),
+            source: s.to_string(),
+            target: t.to_string(),
+            weight,
+        };
+        let graph = ModuleGraph {
+            nodes: ["app", "api", "db", "legacy", "web", "orphan"]
+                .map(node)
+                .to_vec(),
+            edges: vec![
+                // app -> db directly (one import) vs. via api (many imports each way).
+                edge("app", "db", 1),
+                edge("app", "api", 4),
+                edge("api", "db", 4),
+                edge("app", "web", 4),
+                edge("web", "db", 4),
+                edge("app", "legacy", 1),
+                edge("legacy", "db", 1),
+            ],
+        };
+        let paths = shortest_module_paths(&graph, "app", "db", 10).unwrap();
+        let routes: Vec<Vec<&str>> = paths
+            .iter()
+            .map(|p| p.modules.iter().map(String::as_str).collect())
+            .collect();
+        assert_eq!(
+            routes,
+            vec![vec!["app", "api", "db"], vec!["app", "web", "db"]]
+        );
+        assert_eq!(paths[0].cost, 0.5);
+        assert_eq!(paths[0].edges[1].id, "api->db");
+
+        assert_eq!(
+            shortest_module_paths(&graph, "app", "db", 1).unwrap().len(),
+            1
+        );
+        assert!(shortest_module_paths(&graph, "db", "app", 10)
+            .unwrap()
+            .is_empty());
+        assert!(shortest_module_paths(&graph, "app", "orphan", 10)
+            .unwrap()
+            .is_empty());
+        assert!(shortest_module_paths(&graph, "app", "missing", 10).is_err());
+    }
+
+    #[test]
+    fn module_graph_follows_barrel_reexports_to_implementing_modules() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        let write = |rel: &str, text: &str| {
+            std::fs::create_dir_all(root.join(rel).parent().unwrap()).unwrap();
+            std::fs::write(root.join(rel), text).unwrap();
+        };
+        for pkg in [
+            "packages/theme",
+            "packages/ui",
+            "packages/shared",
+            "packages/mixed",
+            "apps/web",
+        ] {
+            write(&format!("{pkg}/package.json"), "{}\n");
+        }
+        write("packages/theme/colors.ts", "export const colors = {};\n");
+        write("packages/ui/button.ts", "export const Button = 1;\n");
+        write(
+            "packages/ui/index.ts",
+            "// Public API\nexport * from './button';\nexport { colors as palette } from \"../theme/colors\";\n",
+        );
+        // A barrel of a barrel.
+        write("packages/shared/index.ts", "export * from '../ui';\n");
+        // Not a pure barrel: has its own code, so the edge stays on it.
+        write(
+            "packages/mixed/index.ts",
+            "export * from '../ui/button';\nexport const local = 1;\n",
+        );
+        write(
+            "apps/web/main.ts",
+            "import { Button, palette } from '../../packages/shared';\nimport { local } from '../../packages/mixed';\n",
+        );
+
+        let graph = build_module_graph(&root, Path::new(".")).unwrap();
+        let edges: Vec<(&str, u64)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.id.as_str(), e.weight))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("apps/web->packages/mixed", 1),
+                ("apps/web->packages/theme", 1),
+                ("apps/web->packages/ui", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn module_nodes_carry_line_symbol_and_language_metrics() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        let write = |rel: &str, text: &str| {
+            std::fs::create_dir_all(root.join(rel).parent().unwrap()).unwrap();
+            std::fs::write(root.join(rel), text).unwrap();
+        };
+        write("web/package.json", "{}\n");
+        write(
+            "web/app.ts",
+            "export function a() {}\nexport function b() {}\n",
+        );
+        write("web/legacy.js", "function c() {}");
+        write("web/README.md", "# web\n");
+
+        for _ in 0..2 {
+            // The second build is served from the graph cache.
+            let graph = build_module_graph(&root, Path::new(".")).unwrap();
+            let web = graph.nodes.iter().find(|n| n.id == "web").unwrap();
+            assert_eq!(web.file_count, 4);
+            assert_eq!(web.lines, 5);
+            assert_eq!(web.symbols, 3);
+            assert_eq!(web.avg_file_bytes, web.bytes / 4);
+            assert_eq!(
+                web.languages,
+                BTreeMap::from([
+                    ("js".to_string(), 1),
+                    ("json".to_string(), 1),
+                    ("md".to_string(), 1),
+                    ("ts".to_string(), 1)
+                ])
+            );
+        }
+    }
+
+    #[test]
+    fn ranked_map_lists_signatures_by_import_rank_within_budget() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        let write = |rel: &str, text: &str| {
+            std::fs::create_dir_all(root.join(rel).parent().unwrap()).unwrap();
+            std::fs::write(root.join(rel), text).unwrap();
+        };
+        write(
+            "core.ts",
+            "export function load(id: string,   opts?: object): Item {\n  return x;\n}\nfunction hidden() {}\n",
+        );
+        write(
+            "a.ts",
+            "import { load } from './core';\nexport const a = 1;\n",
+        );
+        write(
+            "b.ts",
+            "import { load } from './core';\nexport function b() {}\n",
+        );
+
+        let map = render_ranked_map(&root, Path::new("."), 10_000).unwrap();
+        let lines: Vec<&str> = map.lines().collect();
+        // The widely imported file ranks first; signature whitespace is collapsed.
+        assert_eq!(lines[0], "core.ts:");
+        assert_eq!(lines[1], "│ function load(id: string, opts?: object): Item");
+        assert!(map.contains("b.ts:\n│ function b()\n"));
+        assert!(!map.contains("hidden"));
+
+        let small = render_ranked_map(&root, Path::new("."), 20).unwrap();
+        assert!(small.starts_with("core.ts:"));
+        assert!(small.len() < map.len());
+    }
+
+    #[test]
+    fn streamed_map_matches_the_built_map() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::create_dir_all(root.join("lib")).unwrap();
+        std::fs::write(root.join("a.ts"), "import { b } from './b';\n").unwrap();
+        std::fs::write(root.join("b.ts"), "export const b = 1;\n").unwrap();
+
+        let mut records = Vec::new();
+        stream_repo_map_scoped(&root, &root, &mut |record| {
+            records.push(record);
+            Ok(())
+        })
+        .unwrap();
+        let map = build_repo_map_scoped(&root, &root).unwrap();
+
+        assert!(matches!(&records[0], MapRecord::Node(n) if n.id == "."));
+        let first_rank = records
+            .iter()
+            .position(|r| matches!(r, MapRecord::Importance { .. }))
+            .unwrap();
+        assert_eq!(first_rank, records.len() - map.nodes.len());
+        let mut edges: Vec<String> = records
+            .iter()
+            .filter_map(|r| match r {
+                MapRecord::Edge(e) => Some(e.id.clone()),
+                _ => None,
+            })
+            .collect();
+        edges.sort();
+        assert_eq!(
+            edges,
+            map.edges.iter().map(|e| e.id.clone()).collect::<Vec<_>>()
+        );
+        for record in &records[first_rank..] {
+            let MapRecord::Importance { id, importance } = record else {
+                panic!("node or edge after the importance records");
+            };
+            let node = map.nodes.iter().find(|n| &n.id == id).unwrap();
+            assert_eq!(node.importance, *importance);
+        }
+    }
+
+    #[test]
+    fn import_closure_follows_imports_breadth_first_up_to_depth() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::write(
+            root.join("main.ts"),
+            "import { a } from './a';\nimport { b } from './b';\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("a.ts"), "import { c } from './c';\n").unwrap();
+        std::fs::write(root.join("b.ts"), "import { a } from './a';\n").unwrap();
+        std::fs::write(root.join("c.ts"), "import { d } from './d';\n").unwrap();
+        std::fs::write(root.join("d.ts"), "export const d = 1;\n").unwrap();
+        std::fs::write(root.join("unused.ts"), "export const u = 1;\n").unwrap();
+
+        let closure = |depth| import_closure(&root, Path::new("main.ts"), depth).unwrap();
+        let files = |v: Vec<(String, u32)>| {
+            v.into_iter()
+                .map(|(f, d)| format!("{f}@{d}"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(files(closure(0)), vec!["main.ts@0"]);
+        assert_eq!(
+            files(closure(2)),
+            vec!["main.ts@0", "a.ts@1", "b.ts@1", "c.ts@2"]
+        );
+        assert_eq!(
+            files(closure(10)),
+            vec!["main.ts@0", "a.ts@1", "b.ts@1", "c.ts@2", "d.ts@3"]
+        );
+    }
+
+    #[test]
+    fn file_ranks_favour_widely_imported_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        std::fs::write(root.join("core.ts"), "export const c = 1;\n").unwrap();
+        for name in ["a", "b", "c"] {
+            std::fs::write(
+                root.join(format!("{name}.ts")),
+                "import { c } from './core';\n",
+            )
+            .unwrap();
+        }
+        std::fs::write(root.join("lonely.ts"), "export const l = 1;\n").unwrap();
+
+        let ranks = file_ranks(&root, Path::new(".")).unwrap();
+        assert_eq!(ranks.len(), 5);
+        assert!(ranks["core.ts"].centrality > ranks["a.ts"].centrality);
+        assert!(ranks["core.ts"].centrality > ranks["lonely.ts"].centrality);
+        assert_eq!(ranks["core.ts"].commits, 0);
+    }
+
+    #[test]
+    fn changed_files_come_with_their_direct_dependents() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = normalize_abs(tmp.path());
+        let git = |args: &[&str]| {
+            let out = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&root)
+                .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(
+                out.status.success(),
+                "{}",
+                String::from_utf8_lossy(&out.stderr)
+            );
+        };
+        std::fs::write(root.join("core.ts"), "export const x = 1;\n").unwrap();
+        std::fs::write(root.join("app.ts"), "import { x } from './core';\n").unwrap();
+        std::fs::write(root.join("main.ts"), "import { a } from './app';\n").unwrap();
+        std::fs::write(root.join("other.ts"), "export const o = 1;\n").unwrap();
+        git(&["init", "-q"]);
+        git(&["add", "-A"]);
+        git(&["commit", "-q", "-m", "base"]);
+
+        std::fs::write(root.join("core.ts"), "export const x = 2;\n").unwrap();
+        std::fs::write(root.join("other.ts"), "export const o = 2;\n").unwrap();
+        git(&["add", "other.ts"]);
+
+        assert_eq!(
+            git_changed_files(&root, Some("HEAD"), false).unwrap(),
+            vec!["core.ts", "other.ts"]
+        );
+        let staged = git_changed_files(&root, None, true).unwrap();
+        assert_eq!(staged, vec!["other.ts"]);
+        assert!(git_changed_files(&root, Some("no-such-ref"), false).is_err());
+
+        let changed = git_changed_files(&root, Some("HEAD"), false).unwrap();
+        assert_eq!(
+            with_direct_dependents(&root, &changed),
+            vec![
+                ("core.ts".to_string(), 0),
+                ("other.ts".to_string(), 0),
+                ("app.ts".to_string(), 1)
+            ]
+        );
+    }
 }
@@ -0,0 +1,131 @@
+use anyhow::Result;
+use std::path::Path;
+
+/// Fence info string for a file, from its extension (empty when unknown).
+pub fn fence_language(path: &str) -> &'static str {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "rs" => "rust",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "tsx",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "jsx",
+        "py" | "pyi" => "python",
+        "go" => "go",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "swift" => "swift",
+        "cs" => "csharp",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" => "cpp",
+        "rb" => "ruby",
+        "php" => "php",
+        "dart" => "dart",
+        "lua" => "lua",
+        "zig" => "zig",
+        "ex" | "exs" => "elixir",
+        "ml" | "mli" => "ocaml",
+        "sql" => "sql",
+        "sh" | "bash" | "zsh" => "bash",
+        "proto" => "protobuf",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "toml" => "toml",
+        "md" | "markdown" => "markdown",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "vue" => "vue",
+        "svelte" => "svelte",
+        _ => "",
+    }
+}
+
+/// A backtick fence longer than any backtick run inside `content`, so embedded fences
+/// (e.g. in Markdown files) can't close the block early.
+fn fence_for(content: &str) -> String {
+    let mut longest = 0usize;
+    let mut run = 0usize;
+    for ch in content.chars() {
+        if ch == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    "`".repeat((longest + 1).max(3))
+}
+
+fn push_block(out: &mut String, language: &str, content: &str) {
+    let fence = fence_for(content);
+    out.push_str(&fence);
+    out.push_str(language);
+    out.push('\n');
+    out.push_str(content.trim_end());
+    out.push('\n');
+    out.push_str(&fence);
+    out.push_str("\n\n");
+}
+
+/// Markdown counterpart of [`crate::xml_builder::build_context_xml`]: a `## Name` section
+/// per preamble entry, the repository map, and then one `## path` section per file, each a
+/// fenced block tagged with its language.
+pub fn build_context_markdown(
+    preamble: &[(String, String)],
+    repository_map: Option<&str>,
+    files: &[(String, String)],
+) -> Result<String> {
+    let mut out = String::new();
+
+    for (name, text) in preamble {
+        let mut chars = name.chars();
+        out.push_str("## ");
+        out.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        out.push_str(chars.as_str());
+        out.push_str("\n\n");
+        push_block(&mut out, "text", text);
+    }
+
+    if let Some(map_text) = repository_map {
+        out.push_str("## Repository map\n\n");
+        push_block(&mut out, "text", map_text);
+    }
+
+    for (path, content) in files {
+        out.push_str("## ");
+        out.push_str(path);
+        out.push_str("\n\n");
+        push_block(&mut out, fence_language(path), content);
+    }
+
+    Ok(out.trim_end().to_string() + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_become_headed_fenced_blocks() {
+        let files = vec![
+            ("src/lib.rs".to_string(), "pub fn a() {}\n\n".to_string()),
+            (
+                "README.md".to_string(),
+                "Example:\n```sh\nmake\n```\n".to_string(),
+            ),
+            ("LICENSE".to_string(), "MIT".to_string()),
+        ];
+        let md = build_context_markdown(&[], Some("src/\n  lib.rs"), &files).unwrap();
+        assert_eq!(
+            md,
+            "## Repository map\n\n```text\nsrc/\n  lib.rs\n```\n\n\
+             ## src/lib.rs\n\n```rust\npub fn a() {}\n```\n\n\
+             ## README.md\n\n````markdown\nExample:\n```sh\nmake\n```\n````\n\n\
+             ## LICENSE\n\n```\nMIT\n```\n"
+        );
+    }
+}
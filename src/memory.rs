@@ -297,6 +297,81 @@ pub fn hybrid_search(
     ranked
 }
 
+/// Entries of the project at `repo_root` related to the repo-relative `paths` (files or
+/// directories, `.` for the whole repo), best first.
+///
+/// Scoring: one point per `files_touched` path at or under one of `paths`, plus the
+/// [`keyword_score`] of the paths' file stems and directory names. Unrelated entries
+/// (score `0`) are dropped; ties go to the most recent entry.
+pub fn related_entries(
+    store: &MemoryStore,
+    repo_root: &Path,
+    paths: &[String],
+    top_k: usize,
+) -> Vec<RankedEntry> {
+    let roots: Vec<String> = [Some(repo_root.to_path_buf()), repo_root.canonicalize().ok()]
+        .into_iter()
+        .flatten()
+        .map(|p| p.to_string_lossy().trim_end_matches('/').to_string())
+        .collect();
+    let paths: Vec<&str> = paths
+        .iter()
+        .map(|p| p.trim_start_matches("./").trim_end_matches('/'))
+        .collect();
+
+    let mut tokens: Vec<&str> = paths
+        .iter()
+        .filter_map(|p| p.rsplit('/').next())
+        .map(|name| name.split('.').next().unwrap_or(name))
+        .filter(|t| t.len() >= 3)
+        .collect();
+    tokens.sort_unstable();
+    tokens.dedup();
+
+    let mut ranked: Vec<RankedEntry> = store
+        .entries
+        .iter()
+        .filter(|e| roots.contains(&e.project_path.trim_end_matches('/').to_string()))
+        .filter_map(|e| {
+            let project = e.project_path.trim_end_matches('/');
+            let overlap = e
+                .files_touched
+                .iter()
+                .map(|f| {
+                    let f = f.replace('\\', "/");
+                    let rel = f
+                        .strip_prefix(project)
+                        .map(|r| r.trim_start_matches('/').to_string())
+                        .unwrap_or(f);
+                    rel.trim_start_matches("./").to_string()
+                })
+                .filter(|f| {
+                    paths.iter().any(|p| {
+                        p.is_empty()
+                            || *p == "."
+                            || f == p
+                            || f.strip_prefix(p).is_some_and(|r| r.starts_with('/'))
+                    })
+                })
+                .count();
+            let score = overlap as f32 + keyword_score(e, &tokens);
+            (score > 0.0).then(|| RankedEntry {
+                entry: e.clone(),
+                score,
+            })
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| b.entry.timestamp.cmp(&a.entry.timestamp))
+    });
+    ranked.truncate(top_k);
+    ranked
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -431,4 +506,33 @@ mod tests {
         assert_eq!(results.len(), 1, "only one entry has tag 'bugfix'");
         assert_eq!(results[0].entry.id, "id-tagged");
     }
+
+    /// `related_entries` must rank by touched-file overlap, then keywords, within the project.
+    #[test]
+    fn related_entries_prefer_overlapping_files() {
+        use std::io::Write;
+        let entry = |id: &str, project: &str, intent: &str, files: &str| {
+            format!(
+                r#"{{"schema_version":"1.0","id":"{id}","session_id":"s1","timestamp":"2026-01-01T00:00:00Z","source_ide":"cursor","project_path":"{project}","intent":"{intent}","decision":"ok","tool_calls":[],"files_touched":[{files}],"tags":[]}}"#
+            )
+        };
+
+        let mut tmp = tempfile::NamedTempFile::new().expect("temp file");
+        for line in [
+            entry("touched", "/proj", "tidy up", r#""/proj/src/parser/lexer.rs""#),
+            entry("keyword", "/proj", "speed up the parser", ""),
+            entry("unrelated", "/proj", "add UI button", r#""web/app.ts""#),
+            entry("elsewhere", "/other", "parser rewrite", r#""src/parser/lexer.rs""#),
+        ] {
+            writeln!(tmp, "{line}").unwrap();
+        }
+
+        let store = MemoryStore::load(tmp.path()).expect("store");
+        let ids: Vec<String> =
+            related_entries(&store, Path::new("/proj"), &["src/parser".to_string()], 5)
+                .into_iter()
+                .map(|r| r.entry.id)
+                .collect();
+        assert_eq!(ids, vec!["touched", "keyword"]);
+    }
 }
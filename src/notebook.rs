@@ -0,0 +1,124 @@
+//! Jupyter notebook (`.ipynb`) support.
+//!
+//! Code cells are concatenated into a Python "code view" (Jupytext percent format: each cell
+//! starts with a `# %% [cell N]` marker line) and analysed by the Python driver. Symbol lines
+//! refer to that view; [`NotebookCode::cell_for_line`] maps them back to notebook cells.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+pub fn is_notebook_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("ipynb"))
+}
+
+/// The path whose driver analyses a notebook's code view (`analysis.ipynb` → `analysis.py`).
+pub fn code_view_path(path: &Path) -> PathBuf {
+    path.with_extension("py")
+}
+
+#[derive(Debug, Clone)]
+pub struct NotebookCode {
+    /// Concatenated code cells, each preceded by its `# %% [cell N]` marker.
+    pub source: String,
+    /// (cell index, first line of the cell's code in `source`), in order.
+    cells: Vec<(u32, u32)>,
+}
+
+impl NotebookCode {
+    /// Index (0-based, counting markdown/raw cells too) of the cell containing `line`.
+    pub fn cell_for_line(&self, line: u32) -> Option<u32> {
+        self.cells
+            .iter()
+            .take_while(|(_, start)| *start <= line + 1)
+            .last()
+            .map(|(cell, _)| *cell)
+    }
+}
+
+/// Extract the code view of a notebook. Notebooks whose kernel isn't Python are rejected,
+/// since their cells would be parsed with the wrong grammar.
+pub fn extract_code_cells(notebook_json: &str) -> Result<NotebookCode> {
+    let nb: Value = serde_json::from_str(notebook_json).context("Invalid notebook JSON")?;
+
+    let language = nb
+        .pointer("/metadata/kernelspec/language")
+        .or_else(|| nb.pointer("/metadata/language_info/name"))
+        .and_then(Value::as_str);
+    if let Some(lang) = language {
+        if !lang.eq_ignore_ascii_case("python") {
+            return Err(anyhow!("Unsupported notebook language: {lang}"));
+        }
+    }
+
+    let cells = nb
+        .get("cells")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("Notebook has no cells array"))?;
+
+    let mut source = String::new();
+    let mut line = 0u32;
+    let mut starts = Vec::new();
+    for (idx, cell) in cells.iter().enumerate() {
+        if cell.get("cell_type").and_then(Value::as_str) != Some("code") {
+            continue;
+        }
+        let text = match cell.get("source") {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Array(parts)) => parts.iter().filter_map(Value::as_str).collect(),
+            _ => String::new(),
+        };
+
+        source.push_str(&format!("# %% [cell {idx}]\n"));
+        line += 1;
+        starts.push((idx as u32, line));
+        for l in text.lines() {
+            // IPython magics and shell escapes aren't Python; keep the line count stable.
+            let t = l.trim_start();
+            if t.starts_with('%') || t.starts_with('!') {
+                source.push_str("# ");
+            }
+            source.push_str(l);
+            source.push('\n');
+            line += 1;
+        }
+    }
+
+    Ok(NotebookCode {
+        source,
+        cells: starts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concatenates_code_cells_and_maps_lines_back() {
+        let nb = r##"{
+          "metadata": {"kernelspec": {"language": "python"}},
+          "cells": [
+            {"cell_type": "markdown", "source": ["# Title\n"]},
+            {"cell_type": "code", "source": ["%matplotlib inline\n", "import numpy as np\n"]},
+            {"cell_type": "code", "source": "def f(x):\n    return x"}
+          ]
+        }"##;
+        let code = extract_code_cells(nb).unwrap();
+        assert_eq!(
+            code.source,
+            "# %% [cell 1]\n# %matplotlib inline\nimport numpy as np\n# %% [cell 2]\ndef f(x):\n    return x\n"
+        );
+        assert_eq!(code.cell_for_line(2), Some(1));
+        assert_eq!(code.cell_for_line(3), Some(2));
+        assert_eq!(code.cell_for_line(4), Some(2));
+    }
+
+    #[test]
+    fn rejects_non_python_kernels() {
+        let nb = r#"{"metadata": {"kernelspec": {"language": "R"}}, "cells": []}"#;
+        assert!(extract_code_cells(nb).is_err());
+    }
+}
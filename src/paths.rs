@@ -0,0 +1,145 @@
+use std::path::{Path, PathBuf};
+
+/// Strip Windows extended-length prefixes produced by `canonicalize()`.
+///
+/// `\\?\C:\repo` becomes `C:\repo` and `\\?\UNC\server\share` becomes `\\server\share`
+/// (the same rules as the `dunce` crate). Works on the string form so it behaves the same
+/// on every host OS.
+pub fn strip_unc_prefix(p: &Path) -> PathBuf {
+    let s = p.to_string_lossy();
+    if let Some(rest) = s.strip_prefix(r"\\?\UNC\") {
+        return PathBuf::from(format!(r"\\{}", rest));
+    }
+    if let Some(rest) = s.strip_prefix(r"\\?\") {
+        let b = rest.as_bytes();
+        // Only strip for plain drive paths (`X:\...`); other verbatim forms have no short equivalent.
+        if b.len() >= 2 && b[0].is_ascii_alphabetic() && b[1] == b':' {
+            return PathBuf::from(rest);
+        }
+    }
+    p.to_path_buf()
+}
+
+/// Canonicalize a path when possible (falling back to the input) and strip UNC prefixes.
+pub fn normalize_abs(p: &Path) -> PathBuf {
+    let canon = p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+    strip_unc_prefix(&canon)
+}
+
+/// Repo-relative form of `path`, tolerant of UNC prefixes, separator style and (on
+/// case-insensitive filesystems) casing differences between the two sides.
+pub fn relative_to(path: &Path, base: &Path) -> Option<PathBuf> {
+    // Fast path: identical prefixes need no filesystem access (and keep symlinked entries intact).
+    if let Ok(rel) = path.strip_prefix(base) {
+        return Some(rel.to_path_buf());
+    }
+    relative_to_with(
+        &normalize_abs(path),
+        &normalize_abs(base),
+        cfg!(any(windows, target_os = "macos")),
+    )
+}
+
+/// String-level prefix comparison used by [`relative_to`] once both sides are normalized.
+fn relative_to_with(path: &Path, base: &Path, case_insensitive: bool) -> Option<PathBuf> {
+    let p = comparable(path);
+    let b = comparable(base);
+
+    let matches = |a: &str, b: &str| {
+        if case_insensitive {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
+        }
+    };
+
+    if matches(&p, &b) {
+        return Some(PathBuf::new());
+    }
+    // Prefix must end on a component boundary (`/repo` is not a prefix of `/repo2`).
+    let cut = if b.ends_with('/') {
+        b.len()
+    } else {
+        b.len() + 1
+    };
+    if p.len() < cut || !p.is_char_boundary(b.len()) || !matches(&p[..b.len()], &b) {
+        return None;
+    }
+    if !b.ends_with('/') && p.as_bytes()[b.len()] != b'/' {
+        return None;
+    }
+    Some(PathBuf::from(&p[cut..]))
+}
+
+fn comparable(p: &Path) -> String {
+    let s = strip_unc_prefix(p).to_string_lossy().replace('\\', "/");
+    // Keep a lone root ("/" or "C:/") intact, but drop trailing separators elsewhere.
+    let trimmed = s.trim_end_matches('/');
+    if trimmed.is_empty() || trimmed.ends_with(':') {
+        format!("{}/", trimmed)
+    } else {
+        trimmed.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_extended_length_drive_prefix() {
+        let p = PathBuf::from(r"\\?\C:\work\repo\src\main.rs");
+        assert_eq!(
+            strip_unc_prefix(&p),
+            PathBuf::from(r"C:\work\repo\src\main.rs")
+        );
+    }
+
+    #[test]
+    fn strips_extended_length_unc_share_prefix() {
+        let p = PathBuf::from(r"\\?\UNC\server\share\repo");
+        assert_eq!(strip_unc_prefix(&p), PathBuf::from(r"\\server\share\repo"));
+    }
+
+    #[test]
+    fn leaves_plain_paths_untouched() {
+        let p = PathBuf::from("/home/user/repo");
+        assert_eq!(strip_unc_prefix(&p), p);
+    }
+
+    /// A canonicalized (UNC) file path must still resolve against a non-canonical repo root.
+    #[test]
+    fn relative_to_handles_unc_vs_plain_root() {
+        let file = PathBuf::from(r"\\?\C:\work\repo\src\lib.rs");
+        let root = PathBuf::from(r"C:\work\repo");
+        assert_eq!(
+            relative_to_with(&file, &root, false),
+            Some(PathBuf::from("src/lib.rs"))
+        );
+    }
+
+    #[test]
+    fn relative_to_handles_mixed_case_when_case_insensitive() {
+        let file = PathBuf::from("/Users/Dev/Repo/src/App.tsx");
+        let root = PathBuf::from("/users/dev/repo");
+        assert_eq!(
+            relative_to_with(&file, &root, true),
+            Some(PathBuf::from("src/App.tsx"))
+        );
+        assert_eq!(relative_to_with(&file, &root, false), None);
+    }
+
+    #[test]
+    fn relative_to_respects_component_boundaries() {
+        let file = PathBuf::from("/work/repo2/src/a.rs");
+        let root = PathBuf::from("/work/repo");
+        assert_eq!(relative_to_with(&file, &root, true), None);
+    }
+
+    #[test]
+    fn relative_to_same_path_is_empty() {
+        let root = PathBuf::from(r"\\?\C:\Work\Repo\");
+        let other = PathBuf::from(r"c:\work\repo");
+        assert_eq!(relative_to_with(&root, &other, true), Some(PathBuf::new()));
+    }
+}
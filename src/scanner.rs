@@ -1,10 +1,67 @@
 use anyhow::{Context, Result};
+use ignore::gitignore::Gitignore;
 use ignore::overrides::{Override, OverrideBuilder};
-use ignore::WalkBuilder;
+use ignore::{Match, WalkBuilder};
+use serde::Serialize;
 use std::collections::HashSet;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::config::{TokenEstimatorConfig, ABSOLUTE_MAX_FILE_BYTES};
+use crate::gitattributes::LinguistAttributes;
+use crate::paths::{normalize_abs, relative_to};
+
+/// Ignore file (gitignore syntax) for files that should stay out of slices and maps without
+/// being git-ignored, such as fixtures and golden files. Like `.gitignore`, it applies to its
+/// own directory and below; the repo-level one is the usual place.
+pub const SLICERIGNORE_FILE: &str = ".slicerignore";
+
+/// The [`SLICERIGNORE_FILE`]s that apply inside one directory, for listings that don't go
+/// through a [`WalkBuilder`] (walkers pick them up with `add_custom_ignore_filename`).
+#[derive(Debug, Default)]
+pub struct SlicerIgnore {
+    /// `(directory, its .slicerignore)`, repo root first.
+    matchers: Vec<(PathBuf, Gitignore)>,
+}
 
-use crate::config::ABSOLUTE_MAX_FILE_BYTES;
+impl SlicerIgnore {
+    /// The files in `repo_root` and every directory from there down to `dir`.
+    pub fn for_dir(repo_root: &Path, dir: &Path) -> Self {
+        let mut matchers = Vec::new();
+        let mut push = |base: &Path| {
+            let file = base.join(SLICERIGNORE_FILE);
+            if file.is_file() {
+                matchers.push((base.to_path_buf(), Gitignore::new(&file).0));
+            }
+        };
+        let mut base = repo_root.to_path_buf();
+        push(&base);
+        for component in relative_to(dir, repo_root).unwrap_or_default().components() {
+            if let Component::Normal(name) = component {
+                base.push(name);
+                push(&base);
+            }
+        }
+        Self { matchers }
+    }
+
+    /// Whether `rel_path` (repo-relative) is ignored; the deepest file with a matching
+    /// pattern decides, so it can re-include what a parent ignores.
+    pub fn is_ignored(&self, repo_root: &Path, rel_path: &Path, is_dir: bool) -> bool {
+        let abs = repo_root.join(rel_path);
+        for (base, matcher) in self.matchers.iter().rev() {
+            let Ok(below) = abs.strip_prefix(base) else {
+                continue;
+            };
+            match matcher.matched_path_or_any_parents(below, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+        false
+    }
+}
 
 fn repomix_default_overrides(repo_root: &Path, exclude_dir_names: &[String]) -> Result<Override> {
     let mut ob = OverrideBuilder::new(repo_root);
@@ -96,6 +153,7 @@ fn repomix_default_overrides(repo_root: &Path, exclude_dir_names: &[String]) ->
         ".m2",
         // Misc
         ".cortexast",
+        ".context-slicer",
         ".terraform",
         ".serverless",
         "tmp",
@@ -125,35 +183,191 @@ pub struct FileEntry {
     pub abs_path: PathBuf,
     pub rel_path: PathBuf,
     pub bytes: u64,
+    /// Larger than `ScanOptions::max_file_bytes`; only emitted when `keep_oversized` is set,
+    /// so the slicer can apply its oversize policy instead of reading the whole file.
+    pub oversized: bool,
+    /// Computed on first use by [`FileEntry::fingerprint`], so scans that never look at it
+    /// don't read the file.
+    hash: OnceLock<Option<String>>,
+}
+
+impl FileEntry {
+    pub fn new(abs_path: PathBuf, rel_path: PathBuf, bytes: u64, oversized: bool) -> Self {
+        Self {
+            abs_path,
+            rel_path,
+            bytes,
+            oversized,
+            hash: OnceLock::new(),
+        }
+    }
+
+    /// blake3 of the file on disk (see [`fingerprint`]), the identity map nodes, slice
+    /// metadata and the slice cache share; `None` when the file couldn't be read.
+    pub fn fingerprint(&self) -> Option<&str> {
+        self.hash
+            .get_or_init(|| fingerprint(&self.abs_path))
+            .as_deref()
+    }
+}
+
+/// What a scan saw and why files were left out, so a file missing from a slice can be
+/// traced back to the rule that dropped it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanStats {
+    /// Files under the target roots that the walk reached, or that ignore rules pruned from
+    /// a directory it reached. Files inside a pruned directory are not counted.
+    pub files_seen: usize,
+    pub included: usize,
+    /// Over `max_file_bytes` (or the hard cap) without `keep_oversized`.
+    pub skipped_by_size: usize,
+    /// Left out by `.gitignore`/`.ignore`/`.slicerignore`, hidden-file filtering, default
+    /// overrides, excluded directory names, a test-path filter or a Linguist marker.
+    pub skipped_by_ignore: usize,
+    pub skipped_binary: usize,
+    /// Directories left out whole by ignore rules or excluded names (VCS metadata aside).
+    pub pruned_dirs: usize,
+}
+
+impl ScanStats {
+    pub fn merge(&mut self, other: &ScanStats) {
+        self.files_seen += other.files_seen;
+        self.included += other.included;
+        self.skipped_by_size += other.skipped_by_size;
+        self.skipped_by_ignore += other.skipped_by_ignore;
+        self.skipped_binary += other.skipped_binary;
+        self.pruned_dirs += other.pruned_dirs;
+    }
+}
+
+impl std::fmt::Display for ScanStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} files seen, {} included, {} skipped by size, {} skipped by ignore rules, {} binary, \
+             {} directories pruned",
+            self.files_seen,
+            self.included,
+            self.skipped_by_size,
+            self.skipped_by_ignore,
+            self.skipped_binary,
+            self.pruned_dirs
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct ScanOptions {
     pub repo_root: PathBuf,
     pub target: PathBuf,
+    /// More roots scanned in the same pass (e.g. `proto/` next to `src/`). Roots inside
+    /// another root are skipped, and each file is listed once.
+    pub extra_targets: Vec<PathBuf>,
     pub max_file_bytes: u64,
     pub exclude_dir_names: Vec<String>,
+    /// Tag files over `max_file_bytes` as `oversized` instead of dropping them.
+    pub keep_oversized: bool,
+    /// Skip test files and test directories (see [`is_test_path`]).
+    pub exclude_tests: bool,
+    /// Descend into symlinked directories, visiting each directory once (see
+    /// [`VisitedDirs`]).
+    pub follow_symlinks: bool,
+    /// Keep files marked `linguist-generated` or `linguist-vendored` in `.gitattributes`.
+    pub include_generated: bool,
 }
 
 impl ScanOptions {
+    /// Scan `target` under `repo_root` with the default size limit and no other filters;
+    /// override the rest with struct-update syntax.
+    pub fn new(repo_root: impl Into<PathBuf>, target: impl Into<PathBuf>) -> Self {
+        Self {
+            repo_root: repo_root.into(),
+            target: target.into(),
+            extra_targets: Vec::new(),
+            max_file_bytes: TokenEstimatorConfig::default().max_file_bytes,
+            exclude_dir_names: Vec::new(),
+            keep_oversized: false,
+            exclude_tests: false,
+            follow_symlinks: false,
+            include_generated: false,
+        }
+    }
+
     pub fn target_root(&self) -> PathBuf {
-        if self.target.is_absolute() {
-            self.target.clone()
+        self.resolve(&self.target)
+    }
+
+    /// `target` and the extra targets, without roots nested in (or equal to) an earlier one.
+    pub fn target_roots(&self) -> Vec<PathBuf> {
+        // (as given, normalized for the overlap check)
+        let mut roots: Vec<(PathBuf, PathBuf)> = Vec::new();
+        for target in std::iter::once(&self.target).chain(&self.extra_targets) {
+            let root = self.resolve(target);
+            let key = normalize_abs(&root);
+            if roots.iter().any(|(_, k)| key.starts_with(k)) {
+                continue;
+            }
+            roots.retain(|(_, k)| !k.starts_with(&key));
+            roots.push((root, key));
+        }
+        roots.into_iter().map(|(root, _)| root).collect()
+    }
+
+    fn resolve(&self, target: &Path) -> PathBuf {
+        if target.is_absolute() {
+            target.to_path_buf()
         } else {
-            self.repo_root.join(&self.target)
+            self.repo_root.join(target)
         }
     }
 }
 
 pub fn scan_workspace(opts: &ScanOptions) -> Result<Vec<FileEntry>> {
-    let target_root = opts.target_root();
+    scan_workspace_counted(opts, false).map(|(entries, _)| entries)
+}
+
+/// [`scan_workspace`], also reporting how many files were seen and why the rest were skipped.
+///
+/// Entries pruned by ignore rules are never yielded by the walk, so they are counted by
+/// listing each walked directory once more; pruned directories count as one entry each and
+/// are not descended into.
+pub fn scan_workspace_with_stats(opts: &ScanOptions) -> Result<(Vec<FileEntry>, ScanStats)> {
+    scan_workspace_counted(opts, true)
+}
+
+fn scan_workspace_counted(
+    opts: &ScanOptions,
+    count_pruned: bool,
+) -> Result<(Vec<FileEntry>, ScanStats)> {
+    let mut stats = ScanStats::default();
+    if opts.extra_targets.is_empty() {
+        let entries = scan_root(opts, &opts.target_root(), &mut stats, count_pruned)?;
+        stats.included = entries.len();
+        return Ok((entries, stats));
+    }
+    let mut entries = Vec::new();
+    for root in opts.target_roots() {
+        entries.extend(scan_root(opts, &root, &mut stats, count_pruned)?);
+    }
+    // Symlinks can still bring one file in under two roots.
+    entries.sort_by(|a, b| a.rel_path.cmp(&b.rel_path));
+    entries.dedup_by(|a, b| a.rel_path == b.rel_path);
+    stats.included = entries.len();
+    Ok((entries, stats))
+}
 
-    let meta = std::fs::metadata(&target_root)
+fn scan_root(
+    opts: &ScanOptions,
+    target_root: &Path,
+    stats: &mut ScanStats,
+    count_pruned: bool,
+) -> Result<Vec<FileEntry>> {
+    let meta = std::fs::metadata(target_root)
         .with_context(|| format!("Target does not exist: {}", target_root.display()))?;
 
     if meta.is_file() {
-        return scan_single_file(&opts.repo_root, &target_root, opts.max_file_bytes)
-            .map(|v| v.into_iter().collect());
+        return scan_single_file(opts, target_root, stats);
     }
 
     let mut entries = Vec::new();
@@ -170,9 +384,20 @@ pub fn scan_workspace(opts: &ScanOptions) -> Result<Vec<FileEntry>> {
         }
     }
 
-    let walker = WalkBuilder::new(&target_root)
+    let linguist = linguist_attributes(opts);
+    let visited = Arc::new(VisitedDirs::default());
+    let follow_symlinks = opts.follow_symlinks;
+    if follow_symlinks {
+        // The walker never filters its root, so a link back to it must find it recorded.
+        visited.first_visit(target_root);
+    }
+    let walk_visited = Arc::clone(&visited);
+    let mut builder = WalkBuilder::new(target_root);
+    builder
         .standard_filters(true) // .gitignore, .ignore, hidden, etc.
+        .add_custom_ignore_filename(SLICERIGNORE_FILE)
         .overrides(overrides)
+        .follow_links(follow_symlinks)
         .filter_entry(move |dent| {
             // Skip excluded directories by name (prevents descending).
             if dent.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
@@ -181,20 +406,33 @@ pub fn scan_workspace(opts: &ScanOptions) -> Result<Vec<FileEntry>> {
                         return false;
                     }
                 }
+                if follow_symlinks && !walk_visited.first_visit(dent.path()) {
+                    return false;
+                }
             }
             true
-        })
-        .build();
+        });
+    if follow_symlinks {
+        // Which of several links to one directory wins must not depend on readdir order.
+        builder.sort_by_file_name(|a, b| a.cmp(b));
+    }
+    let walker = builder.build();
 
+    let mut walked_files = 0;
+    let mut walked_dirs = HashSet::new();
     for item in walker {
         let dent = match item {
             Ok(d) => d,
             Err(_) => continue,
         };
 
+        if count_pruned && dent.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
+            walked_dirs.insert(dent.path().to_path_buf());
+        }
         if !dent.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
             continue;
         }
+        walked_files += 1;
 
         let abs_path = dent.into_path();
 
@@ -202,35 +440,153 @@ pub fn scan_workspace(opts: &ScanOptions) -> Result<Vec<FileEntry>> {
             Ok(b) => b,
             Err(_) => continue,
         };
+        stats.files_seen += 1;
 
-        // Hard absolute cap — always skip before any config override can raise it.
-        if bytes > ABSOLUTE_MAX_FILE_BYTES {
-            crate::debug_log!(
-                "[cortexast] skipping large file ({}): {}",
-                humanize_bytes(bytes),
-                abs_path.display()
-            );
+        let Some(oversized) =
+            classify_size(&abs_path, bytes, opts.max_file_bytes, opts.keep_oversized)
+        else {
+            stats.skipped_by_size += 1;
             continue;
-        }
-
-        if bytes == 0 || bytes > opts.max_file_bytes {
+        };
+        if let Some(reason) = binary_reason(&abs_path) {
+            crate::debug_log!("[cortexast] skipping {reason}: {}", abs_path.display());
+            stats.skipped_binary += 1;
             continue;
         }
 
         let rel_path = path_relative_to(&abs_path, &opts.repo_root)
             .with_context(|| format!("Failed to relativize path: {}", abs_path.display()))?;
+        let rel_str = rel_path.to_string_lossy().replace('\\', "/");
+        if opts.exclude_tests && is_test_path(&rel_str) {
+            stats.skipped_by_ignore += 1;
+            continue;
+        }
+        if let Some(marker) = linguist.excluded_by(&rel_str) {
+            crate::debug_log!("[cortexast] skipping {marker}: {rel_str}");
+            stats.skipped_by_ignore += 1;
+            continue;
+        }
 
-        entries.push(FileEntry {
-            abs_path,
-            rel_path,
-            bytes,
-        });
+        entries.push(FileEntry::new(abs_path, rel_path, bytes, oversized));
+    }
+
+    if count_pruned {
+        let (files, dirs) = count_pruned_entries(&walked_dirs, follow_symlinks, &visited);
+        let pruned = files.saturating_sub(walked_files);
+        stats.files_seen += pruned;
+        stats.skipped_by_ignore += pruned;
+        stats.pruned_dirs += dirs;
     }
 
     entries.sort_by(|a, b| a.rel_path.cmp(&b.rel_path));
     Ok(entries)
 }
 
+/// Files directly inside the walked directories, and their subdirectories the walk did not
+/// enter. Pruned directories are not listed, so an ignored `node_modules/` costs one entry.
+fn count_pruned_entries(
+    walked_dirs: &HashSet<PathBuf>,
+    follow_symlinks: bool,
+    visited: &VisitedDirs,
+) -> (usize, usize) {
+    let (mut files, mut dirs) = (0, 0);
+    for dir in walked_dirs {
+        let Ok(children) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for child in children.flatten() {
+            let path = child.path();
+            let file_type = if follow_symlinks {
+                std::fs::metadata(&path).map(|m| m.file_type())
+            } else {
+                child.file_type()
+            };
+            let Ok(file_type) = file_type else {
+                continue;
+            };
+            if file_type.is_file() {
+                files += 1;
+            } else if file_type.is_dir()
+                && child.file_name() != ".git"
+                && !walked_dirs.contains(&path)
+                // A second link to a directory already walked was deduplicated, not pruned.
+                && (!follow_symlinks || visited.first_visit(&path))
+            {
+                dirs += 1;
+            }
+        }
+    }
+    (files, dirs)
+}
+
+/// Directories already walked, by identity (device and inode on Unix, canonical path
+/// elsewhere), so a walk that follows symlinks enters each directory once: a second link to
+/// the same package is skipped and a link back to an ancestor can't loop.
+#[derive(Debug, Default)]
+pub struct VisitedDirs(Mutex<HashSet<DirId>>);
+
+#[cfg(unix)]
+type DirId = (u64, u64);
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+impl VisitedDirs {
+    /// Record `dir` (following symlinks); false when it was seen before or can't be read.
+    pub fn first_visit(&self, dir: &Path) -> bool {
+        let Some(id) = dir_id(dir) else {
+            return false;
+        };
+        self.0
+            .lock()
+            .map(|mut seen| seen.insert(id))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(unix)]
+fn dir_id(dir: &Path) -> Option<DirId> {
+    use std::os::unix::fs::MetadataExt as _;
+    let meta = std::fs::metadata(dir).ok()?;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_id(dir: &Path) -> Option<DirId> {
+    dir.canonicalize().ok()
+}
+
+/// Whether a directory's contents are treated as tests by [`is_test_path`].
+pub fn is_test_dir_name(name: &str) -> bool {
+    matches!(name, "test" | "tests" | "__tests__" | "spec")
+}
+
+/// Whether a `/`-separated relative path looks like a test file: anything under a
+/// `test`/`tests`/`__tests__`/`spec` directory, `*_test.*` / `*_spec.*`, `*.test.*` /
+/// `*.spec.*`, Python's `test_*.py`, and JVM/.NET `*Test(s).{java,kt,cs}`.
+pub fn is_test_path(rel_path: &str) -> bool {
+    let mut components: Vec<&str> = rel_path.split('/').filter(|c| !c.is_empty()).collect();
+    let Some(name) = components.pop() else {
+        return false;
+    };
+    if components.iter().any(|c| is_test_dir_name(c)) {
+        return true;
+    }
+
+    let (stem, ext) = name.rsplit_once('.').unwrap_or((name, ""));
+    if stem.ends_with("_test")
+        || stem.ends_with("_spec")
+        || stem.ends_with(".test")
+        || stem.ends_with(".spec")
+    {
+        return true;
+    }
+    match ext {
+        "py" => stem.starts_with("test_"),
+        "java" | "kt" | "cs" => stem.ends_with("Test") || stem.ends_with("Tests"),
+        _ => false,
+    }
+}
+
 #[cfg(debug_assertions)]
 fn humanize_bytes(bytes: u64) -> String {
     if bytes >= 1_048_576 {
@@ -242,42 +598,159 @@ fn humanize_bytes(bytes: u64) -> String {
     }
 }
 
+/// `.gitattributes` markers to honor; empty when `include_generated` is set.
+fn linguist_attributes(opts: &ScanOptions) -> LinguistAttributes {
+    if opts.include_generated {
+        LinguistAttributes::default()
+    } else {
+        LinguistAttributes::load(&opts.repo_root)
+    }
+}
+
 fn scan_single_file(
-    repo_root: &Path,
+    opts: &ScanOptions,
     abs_path: &Path,
-    max_file_bytes: u64,
+    stats: &mut ScanStats,
 ) -> Result<Vec<FileEntry>> {
+    let repo_root = opts.repo_root.as_path();
+    let bytes = std::fs::metadata(abs_path)?.len();
+    stats.files_seen += 1;
+
     // Apply the same default overrides for consistency.
     let ov = repomix_default_overrides(repo_root, &[])?;
 
     let rel_path = path_relative_to(abs_path, repo_root)?;
     if ov.matched(&rel_path, /* is_dir */ false).is_ignore() {
+        stats.skipped_by_ignore += 1;
+        return Ok(vec![]);
+    }
+    let parent = abs_path.parent().unwrap_or(repo_root);
+    if SlicerIgnore::for_dir(repo_root, parent).is_ignored(repo_root, &rel_path, false) {
+        stats.skipped_by_ignore += 1;
+        return Ok(vec![]);
+    }
+    let rel_str = rel_path.to_string_lossy().replace('\\', "/");
+    if let Some(marker) = linguist_attributes(opts).excluded_by(&rel_str) {
+        crate::debug_log!("[cortexast] skipping {marker}: {rel_str}");
+        stats.skipped_by_ignore += 1;
         return Ok(vec![]);
     }
 
-    let bytes = std::fs::metadata(abs_path)?.len();
+    let Some(oversized) = classify_size(abs_path, bytes, opts.max_file_bytes, opts.keep_oversized)
+    else {
+        stats.skipped_by_size += 1;
+        return Ok(vec![]);
+    };
+    if let Some(reason) = binary_reason(abs_path) {
+        crate::debug_log!("[cortexast] skipping {reason}: {}", abs_path.display());
+        stats.skipped_binary += 1;
+        return Ok(vec![]);
+    }
+
+    Ok(vec![FileEntry::new(
+        abs_path.to_path_buf(),
+        rel_path,
+        bytes,
+        oversized,
+    )])
+}
+
+/// Hex blake3 of a file's contents, streamed so oversized files are never loaded whole.
+pub fn fingerprint(abs_path: &Path) -> Option<String> {
+    let mut file = std::fs::File::open(abs_path).ok()?;
+    let mut hasher = blake3::Hasher::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(hasher.finalize().to_hex().to_string())
+}
+
+/// Size gate shared by the walker and single-file scans.
+///
+/// Returns `None` to skip the file, otherwise whether it exceeds `max_file_bytes`.
+/// Without `keep_oversized`, the hard absolute cap always applies before any config override.
+/// With it, oversized files are kept (tagged) because the slicer never reads more than
+/// `max_file_bytes` of them.
+#[cfg_attr(not(debug_assertions), allow(unused_variables))]
+fn classify_size(
+    abs_path: &Path,
+    bytes: u64,
+    max_file_bytes: u64,
+    keep_oversized: bool,
+) -> Option<bool> {
+    if bytes == 0 {
+        return None;
+    }
+    if bytes <= max_file_bytes.min(ABSOLUTE_MAX_FILE_BYTES) {
+        return Some(false);
+    }
+    if keep_oversized {
+        return Some(true);
+    }
     if bytes > ABSOLUTE_MAX_FILE_BYTES {
         crate::debug_log!(
             "[cortexast] skipping large file ({}): {}",
             humanize_bytes(bytes),
             abs_path.display()
         );
-        return Ok(vec![]);
     }
-    if bytes == 0 || bytes > max_file_bytes {
-        return Ok(vec![]);
+    None
+}
+
+/// Bytes sniffed from the start of each file by [`binary_reason`].
+const SNIFF_BYTES: usize = 8 * 1024;
+
+/// Why a file looks binary rather than text, judged from its first [`SNIFF_BYTES`]: NUL
+/// bytes, mostly control bytes, near-random bytes (compressed or encrypted data, embedded
+/// blobs), or a full sample without a line break (minified or generated one-liners).
+/// `None` for text and for unreadable files, which are left to the reader.
+pub fn binary_reason(abs_path: &Path) -> Option<&'static str> {
+    use std::io::Read as _;
+
+    let mut sample = Vec::with_capacity(SNIFF_BYTES);
+    std::fs::File::open(abs_path)
+        .ok()?
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut sample)
+        .ok()?;
+    if sample.is_empty() {
+        return None;
+    }
+    if sample.contains(&0) {
+        return Some("binary file (NUL bytes)");
     }
 
-    Ok(vec![FileEntry {
-        abs_path: abs_path.to_path_buf(),
-        rel_path,
-        bytes,
-    }])
+    let control = sample
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c))
+        .count();
+    if control * 10 > sample.len() {
+        return Some("binary file (control bytes)");
+    }
+
+    // Text stays well under 6 bits per byte; compressed data sits near 8.
+    let mut counts = [0usize; 256];
+    for &b in &sample {
+        counts[b as usize] += 1;
+    }
+    let len = sample.len() as f64;
+    let entropy: f64 = counts
+        .iter()
+        .filter(|&&n| n > 0)
+        .map(|&n| {
+            let p = n as f64 / len;
+            -p * p.log2()
+        })
+        .sum();
+    if sample.len() >= 1024 && entropy > 7.2 {
+        return Some("binary file (high entropy)");
+    }
+
+    if sample.len() == SNIFF_BYTES && !sample.contains(&b'\n') {
+        return Some("generated file (no line breaks)");
+    }
+    None
 }
 
 fn path_relative_to(path: &Path, base: &Path) -> Result<PathBuf> {
-    let rel = path
-        .strip_prefix(base)
-        .with_context(|| format!("{} is not under {}", path.display(), base.display()))?;
-    Ok(rel.to_path_buf())
+    relative_to(path, base)
+        .with_context(|| format!("{} is not under {}", path.display(), base.display()))
 }
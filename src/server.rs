@@ -1,22 +1,28 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use model2vec_rs::model::StaticModel;
 use serde_json::json;
 use std::io::{BufRead, Write};
 use std::path::PathBuf;
 
 use crate::chronos::{checkpoint_symbol, compare_symbol, list_checkpoints};
-use crate::config::load_config;
+use crate::config::{load_config, PackingStrategy};
+use crate::graph_export::{to_mermaid, to_mermaid_within, ExportGraph};
 use crate::inspector::{
     call_hierarchy, extract_symbols_from_source, find_implementations, find_usages,
     propagation_checklist, read_symbol_with_options, render_skeleton, repo_map_with_filter,
     run_diagnostics,
 };
-use crate::memory::{hybrid_search, MemoryStore};
+use crate::mapper::{build_call_graph, build_module_graph, impact_of, render_ranked_map};
+use crate::memory::{hybrid_search, MemoryStore, RankedEntry};
 use crate::rules::get_merged_rules;
 use crate::scanner::{scan_workspace, ScanOptions};
 use crate::slicer::{slice_paths_to_xml, slice_to_xml};
 use crate::vector_store::{CodebaseIndex, IndexJob};
+use crate::watch::{watch_slice, write_active_slice, WatchSpec};
 use rayon::prelude::*;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 #[derive(Default)]
 pub struct ServerState {
@@ -28,6 +34,54 @@ pub struct ServerState {
     ///   5. Find-up heuristic on tool args (`path` / `target_dir` / `target`).
     ///   6. `cwd` — last resort; refused if it equals $HOME or OS root.
     repo_root: Option<PathBuf>,
+    /// Slice kept fresh by `deep_slice` with `watch: true`; replacing it stops the old watch.
+    watch: Option<ActiveWatch>,
+    /// Where server-initiated notifications go; stdout when unset (the stdio transport).
+    notify: Option<Notifier>,
+    /// Resource URIs the client asked `notifications/resources/updated` for
+    /// (`resources/subscribe`); shared with the watch thread.
+    subscriptions: Arc<Mutex<HashSet<String>>>,
+}
+
+/// Sends one JSON-RPC notification to the client from any thread.
+type Notifier = Arc<dyn Fn(serde_json::Value) + Send + Sync>;
+
+/// A running `deep_slice` watch; its slice and repo map are exposed to the client as resources.
+struct ActiveWatch {
+    slice_path: PathBuf,
+    mime_type: &'static str,
+    map_path: PathBuf,
+    stop: Arc<AtomicBool>,
+}
+
+impl ActiveWatch {
+    /// `(uri, path, mimeType, description)` of each watched resource.
+    fn files(&self) -> [(String, &PathBuf, &'static str, &'static str); 2] {
+        [
+            (
+                file_uri(&self.slice_path),
+                &self.slice_path,
+                self.mime_type,
+                "Slice kept up to date by deep_slice(watch: true)",
+            ),
+            (
+                file_uri(&self.map_path),
+                &self.map_path,
+                "application/json",
+                "Repo map of the watched target, kept up to date with the slice",
+            ),
+        ]
+    }
+}
+
+fn file_uri(path: &std::path::Path) -> String {
+    format!("file://{}", path.to_string_lossy().replace('\\', "/"))
+}
+
+impl Drop for ActiveWatch {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
 }
 
 /// Returns `true` for "useless" roots that indicate the server started with the
@@ -116,6 +170,79 @@ fn get_network_map() -> Result<serde_json::Value, String> {
 }
 
 impl ServerState {
+    /// Write the slice for `spec` and keep rewriting it on a background thread as files change.
+    /// Each re-slice sends `notifications/resources/updated` for the slice and the repo map
+    /// when they changed and are subscribed, plus `notifications/tools/list_changed` when
+    /// files or imports came or went, so clients drop stale tool results.
+    fn start_watch(&mut self, spec: WatchSpec) -> Result<String> {
+        let written = write_active_slice(&spec)?;
+        let stop = Arc::new(AtomicBool::new(false));
+        self.watch = Some(ActiveWatch {
+            slice_path: written.path,
+            mime_type: spec.cfg.slice_format.mime_type(),
+            map_path: written.map_path,
+            stop: Arc::clone(&stop),
+        });
+        let notify = self.notifier();
+        notify(json!({ "jsonrpc": "2.0", "method": "notifications/resources/list_changed" }));
+        let subscriptions = Arc::clone(&self.subscriptions);
+        std::thread::spawn(move || {
+            let updated = |path: &std::path::Path| {
+                let uri = file_uri(path);
+                let subscribed = subscriptions.lock().map(|s| s.contains(&uri)).unwrap_or(false);
+                if subscribed {
+                    notify(json!({ "jsonrpc": "2.0", "method": "notifications/resources/updated", "params": { "uri": uri } }));
+                }
+            };
+            let mut on_update = |update: Result<crate::watch::WrittenSlice>| match update {
+                Ok(written) => {
+                    if written.changed {
+                        updated(&written.path);
+                    }
+                    if written.map_changed {
+                        updated(&written.map_path);
+                    }
+                    if written.structure_changed {
+                        notify(json!({ "jsonrpc": "2.0", "method": "notifications/tools/list_changed" }));
+                    }
+                }
+                Err(e) => crate::debug_log!("[cortexast] watch re-slice failed: {e:#}"),
+            };
+            if let Err(e) = watch_slice(&spec, &stop, &mut on_update) {
+                crate::debug_log!("[cortexast] watch stopped: {e:#}");
+            }
+        });
+        Ok(written.doc)
+    }
+
+    /// The transport's notification sink, defaulting to stdout.
+    fn notifier(&self) -> Notifier {
+        self.notify.clone().unwrap_or_else(|| {
+            Arc::new(|msg| {
+                // Stdout is shared with the request loop; one locked write keeps lines whole.
+                let mut out = std::io::stdout().lock();
+                let _ = writeln!(out, "{}", msg);
+                let _ = out.flush();
+            })
+        })
+    }
+
+    /// The watched slice and repo map as MCP resource descriptors, if a watch is running.
+    fn watched_resources(&self) -> Vec<serde_json::Value> {
+        self.watch
+            .iter()
+            .flat_map(|w| w.files())
+            .map(|(uri, path, mime_type, description)| {
+                json!({
+                    "uri": uri,
+                    "name": path.file_name().map(|n| n.to_string_lossy().to_string()),
+                    "description": description,
+                    "mimeType": mime_type
+                })
+            })
+            .collect()
+    }
+
     /// Called once when the MCP `initialize` request is received.
     /// Extracts the workspace root from standard LSP/MCP protocol fields and
     /// writes it directly into `self.repo_root` — making the protocol signal
@@ -299,25 +426,37 @@ impl ServerState {
                 "tools": [
                     {
                         "name": "cortex_code_explorer",
-                        "description": "Codebase explorer. Use INSTEAD of ls/tree/find/cat. Two modes: `map_overview` (fast symbol map, near-zero tokens — run first on any repo) and `deep_slice` (token-budgeted XML with function bodies, vector-ranked by query). Use map_overview to orient; deep_slice to get code for editing.",
+                        "description": "Codebase explorer. Use INSTEAD of ls/tree/find/cat. Modes: `map_overview` (fast symbol map, near-zero tokens — run first on any repo), `deep_slice` (token-budgeted XML with function bodies, vector-ranked by query), `call_graph` (function-level caller→callee JSON graph for tracing execution paths), `impact` (files/modules transitively importing a file — check before editing shared code), `ranked_map` (most-imported files first with their public signatures, within budget_tokens) and `graph_modules` (module-level import graph as JSON or Mermaid, for reasoning about architecture). Use map_overview to orient; deep_slice to get code for editing.",
                         "inputSchema": {
                             "type": "object",
                             "properties": {
                                 "action": {
                                     "type": "string",
-                                    "enum": ["map_overview", "deep_slice"],
-                                    "description": "map_overview: bird's-eye symbol map of a dir (requires target_dir='.'). deep_slice: token-budgeted XML with bodies (requires target file/dir; use single_file=true for a specific file, query for semantic ranking)."
+                                    "enum": ["map_overview", "deep_slice", "call_graph", "impact", "ranked_map", "graph_modules"],
+                                    "description": "map_overview: bird's-eye symbol map of a dir (requires target_dir='.'). deep_slice: token-budgeted XML with bodies (requires target file/dir; use single_file=true for a specific file, query for semantic ranking). call_graph: functions + resolved calls as JSON (requires target_dir). impact: transitive importers of a file as JSON (requires target file). ranked_map: import-ranked files with public signatures as text (requires target_dir; budget_tokens defaults to 2048). graph_modules: modules and weighted import edges (requires target_dir; format='mermaid' for a diagram)."
                                 },
                                 "repoPath": { "type": "string", "description": "Abs path to repo root. Default: cwd." },
                                 "target_project": { "type": "string", "description": "Cross-project: ID or abs path from network map. Overrides repoPath." },
-                                "target_dir": { "type": "string", "description": "(map_overview) Dir to map. Use '.' for repo root." },
+                                "target_dir": { "type": "string", "description": "(map_overview, call_graph, ranked_map, graph_modules) Dir to map. Use '.' for repo root." },
                                 "search_filter": { "type": "string", "description": "(map_overview) Case-insensitive substring filter. OR via 'foo|bar'." },
                                 "max_chars": { "type": "integer", "description": "Max output chars. Default 8000." },
+                                "cursor": { "type": "string", "description": "(map_overview, call_graph, ranked_map, graph_modules) Continuation token from the previous page's nextCursor (also named in its footer). Pass with the same other arguments." },
+                                "page_size": { "type": "integer", "exclusiveMinimum": 0, "description": "(map_overview, call_graph, ranked_map, graph_modules) Return output in pages of this many chars (default: max_chars) with a nextCursor, instead of truncating the tail." },
+                                "format": { "type": "string", "enum": ["json", "mermaid"], "description": "(graph_modules) 'json' (default) or 'mermaid'. A Mermaid diagram over max_chars keeps the heaviest import edges and notes what was left out." },
                                 "ignore_gitignore": { "type": "boolean", "description": "(map_overview) Include git-ignored files." },
                                 "exclude": { "type": "array", "items": { "type": "string" }, "description": "Dir names to skip (e.g. ['node_modules','build'])." },
-                                "target": { "type": "string", "description": "(deep_slice) Relative path to file or dir." },
-                                "budget_tokens": { "type": "integer", "exclusiveMinimum": 0, "description": "(deep_slice) Token budget. Default 32000." },
+                                "exclude_tests": { "type": "boolean", "description": "(map_overview, deep_slice) Leave out test files (*_test.rs, *.spec.ts, tests/ dirs, ...). Default: scan.exclude_tests from .cortexast.json." },
+                                "target": { "type": "string", "description": "(deep_slice) Relative path to file or dir. (impact) Relative path to the file being changed." },
+                                "budget_tokens": { "type": "integer", "exclusiveMinimum": 0, "description": "(deep_slice) Token budget. Default 32000, or the profile's budget. (ranked_map) Default 2048." },
+                                "profile": { "type": "string", "description": "(deep_slice) Model budget profile: 'claude-200k', 'gpt-4o-128k', 'local-8k' or one from `profiles` in .cortexast.json. Sets budget, chars-per-token and output format defaults." },
                                 "skeleton_only": { "type": "boolean", "description": "(deep_slice) Strip function bodies, return signatures only." },
+                                "outline": { "type": "boolean", "description": "(deep_slice) API overview: doc comments, signatures and type definitions only. Fits whole modules in small budgets." },
+                                "compress": { "type": "boolean", "description": "(deep_slice) Strip comments, license headers and blank lines before budget fitting (typically 20-30% fewer tokens)." },
+                                "memory": { "type": "boolean", "description": "(deep_slice) Embed past decisions from the memory journal that touched the target, in a <memory> section." },
+                                "rules": { "type": "boolean", "description": "(deep_slice) Open the slice with the merged global/team/project rules in a <rules> section, instead of a separate cortex_get_rules call." },
+                                "docs_first": { "type": "boolean", "description": "(deep_slice) Pack the target's READMEs, architecture notes and docs/ pages before source files, so tight budgets start with orientation material." },
+                                "extra_targets": { "type": "array", "items": { "type": "string" }, "description": "(deep_slice) More paths sliced together with 'target' in one pass (e.g. target='src', extra_targets=['proto']). Overlaps are deduplicated." },
+                                "watch": { "type": "boolean", "description": "(deep_slice) Keep re-slicing the target as its files change. The slice and the target's repo map are exposed as resources; after a re-slice, notifications/resources/updated is sent for each one that changed (once subscribed), and notifications/tools/list_changed when files or imports were added or removed. A new watch replaces the previous one. Ignored with 'query'." },
                                 "query": { "type": "string", "description": "(deep_slice) Semantic query for vector-ranked file selection." },
                                 "query_limit": { "type": "integer", "description": "(deep_slice) Max files returned in query mode." },
                                 "single_file": { "type": "boolean", "description": "(deep_slice) Skip vector search; return only the exact target file." },
@@ -343,6 +482,8 @@ impl ServerState {
                                 "target_dir": { "type": "string", "description": "Scope dir ('.' = whole repo). Required for find_usages/blast_radius." },
                                 "ignore_gitignore": { "type": "boolean", "description": "(propagation_checklist) Include git-ignored files." },
                                 "max_chars": { "type": "integer", "description": "Max output chars. Default 8000." },
+                                "cursor": { "type": "string", "description": "(find_usages, find_implementations) Continuation token from the previous page's nextCursor (also named in its footer). Pass with the same other arguments." },
+                                "page_size": { "type": "integer", "exclusiveMinimum": 0, "description": "(find_usages, find_implementations) Return output in pages of this many chars (default: max_chars) with a nextCursor, instead of truncating the tail." },
                                 "only_dir": { "type": "string", "description": "(propagation_checklist) Restrict scan to this subdir." },
                                 "aliases": { "type": "array", "items": { "type": "string" }, "description": "(propagation_checklist) Alternative names across language boundaries." },
                                 "path": { "type": "string", "description": "(read_source) Source file. Required." },
@@ -402,7 +543,26 @@ impl ServerState {
                                 "top_k": { "type": "integer", "description": "Max results. Default 5.", "default": 5 },
                                 "tags": { "type": "array", "items": { "type": "string" }, "description": "Filter by tags (case-insensitive)." },
                                 "project_path": { "type": "string", "description": "Filter to entries matching this project path substring." },
-                                "max_chars": { "type": "integer", "description": "Max output chars. Default 8000." }
+                                "max_chars": { "type": "integer", "description": "Max output chars. Default 8000." },
+                                "cursor": { "type": "string", "description": "Continuation token from the previous page's nextCursor (also named in its footer). Pass with the same other arguments." },
+                                "page_size": { "type": "integer", "exclusiveMinimum": 0, "description": "Return output in pages of this many chars (default: max_chars) with a nextCursor, instead of truncating the tail." }
+                            },
+                            "required": ["query"]
+                        }
+                    },
+                    {
+                        "name": "cortex_memory_search",
+                        "description": "Recall past agent decisions mid-session as JSON: the same hybrid (semantic + keyword) search as cortex_memory_retriever over the global memory journal, returning {query, searched, results[{rank, score, id, timestamp, project_path, intent, decision, tags, files_touched}]} best first.",
+                        "inputSchema": {
+                            "type": "object",
+                            "properties": {
+                                "query": { "type": "string", "description": "Natural-language search query." },
+                                "top_k": { "type": "integer", "description": "Max results. Default 5.", "default": 5 },
+                                "tags": { "type": "array", "items": { "type": "string" }, "description": "Only entries with at least one of these tags (case-insensitive)." },
+                                "project_path": { "type": "string", "description": "Only entries whose project path contains this substring." },
+                                "max_chars": { "type": "integer", "description": "Max output chars. Default 8000." },
+                                "cursor": { "type": "string", "description": "Continuation token from the previous page's nextCursor (also named in its footer). Pass with the same other arguments." },
+                                "page_size": { "type": "integer", "exclusiveMinimum": 0, "description": "Return output in pages of this many chars (default: max_chars) with a nextCursor, instead of truncating the tail." }
                             },
                             "required": ["query"]
                         }
@@ -456,7 +616,7 @@ impl ServerState {
                     },
                     {
                         "name": "cortex_manage_ast_languages",
-                        "description": "Manage Wasm grammar parsers for non-core languages. Core (always active): rust, typescript, python. Call status to see active/available languages. Call add with languages[] to download and hot-reload parsers from GitHub tree-sitter releases. Available: go, php, cpp, c, c_sharp, java, ruby, dart.",
+                        "description": "Manage Wasm grammar parsers for non-core languages. Core (always active): rust, typescript, python. Call status to see active/available languages. Call add with languages[] to download and hot-reload parsers from GitHub tree-sitter releases. Available: go, php, cpp, c, c_sharp, java, ruby, dart, kotlin, scala, zig, lua, elixir, bash, sql, graphql, proto, ocaml.",
                         "inputSchema": {
                             "type": "object",
                             "properties": {
@@ -525,13 +685,31 @@ impl ServerState {
         let name = params.get("name").and_then(|n| n.as_str()).unwrap_or("");
         let args = params.get("arguments").cloned().unwrap_or(json!({}));
         let max_chars = negotiated_max_chars(&args);
+        // With `cursor`/`page_size`, output is returned in pages instead of truncated.
+        let paging = negotiated_paging(&args, max_chars);
 
         let ok = |text: String| {
-            let text = force_inline_truncate(text, max_chars);
+            let (text, next_cursor) = match &paging {
+                Some((cursor, page_size)) => match paginate(&text, cursor.as_deref(), *page_size) {
+                    Ok(page) => page,
+                    Err(msg) => {
+                        return json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "result": { "content": [{"type":"text","text": msg }], "isError": true }
+                        })
+                    }
+                },
+                None => (force_inline_truncate(text, max_chars), None),
+            };
+            let mut result = json!({ "content": [{"type":"text","text": text }], "isError": false });
+            if let Some(next) = next_cursor {
+                result["nextCursor"] = json!(next);
+            }
             json!({
                 "jsonrpc": "2.0",
                 "id": id,
-                "result": { "content": [{"type":"text","text": text }], "isError": false }
+                "result": result
             })
         };
 
@@ -555,7 +733,7 @@ impl ServerState {
                 match action {
                     "status" => {
                         let active = crate::inspector::exported_language_config().read().unwrap().active_languages();
-                        let available_to_download = vec!["go", "php", "ruby", "java", "c", "cpp", "c_sharp", "dart"];
+                        let available_to_download = vec!["go", "php", "ruby", "java", "c", "cpp", "c_sharp", "dart", "kotlin", "scala", "zig", "lua", "elixir", "bash", "sql", "graphql", "proto", "ocaml"];
                         ok(serde_json::to_string(&json!({
                             "active": active,
                             "available_to_download": available_to_download
@@ -642,8 +820,13 @@ impl ServerState {
                             .and_then(|v| v.as_str())
                             .map(|s| s.trim())
                             .filter(|s| !s.is_empty());
-                        let max_chars = Some(max_chars);
+                        // Paged output must be complete; the pages take the place of the cap.
+                        let max_chars = paging.is_none().then_some(max_chars);
                         let ignore_gitignore = args.get("ignore_gitignore").and_then(|v| v.as_bool()).unwrap_or(false);
+                        let exclude_tests = args
+                            .get("exclude_tests")
+                            .and_then(|v| v.as_bool())
+                            .unwrap_or_else(|| load_config(&repo_root).scan.exclude_tests);
                         let exclude_dirs: Vec<String> = args
                             .get("exclude")
                             .and_then(|v| v.as_array())
@@ -681,7 +864,7 @@ Please correct your target_dir (or pass repoPath explicitly).",
                             ));
                         }
 
-                        match repo_map_with_filter(&target_dir, search_filter, max_chars, ignore_gitignore, &exclude_dirs) {
+                        match repo_map_with_filter(&target_dir, search_filter, max_chars, ignore_gitignore, &exclude_dirs, exclude_tests) {
                             Ok(s) => ok(s),
                             Err(e) => err(format!("repo_map failed: {e}")),
                         }
@@ -751,9 +934,43 @@ Please correct your target_dir (or pass repoPath explicitly).",
                             }
                         }
 
-                        let budget_tokens = args.get("budget_tokens").and_then(|v| v.as_u64()).unwrap_or(32_000) as usize;
                         let skeleton_only = args.get("skeleton_only").and_then(|v| v.as_bool()).unwrap_or(false);
                         let mut cfg = load_config(&repo_root);
+                        let profile_budget = match args.get("profile").and_then(|v| v.as_str()) {
+                            Some(name) => match cfg.apply_profile(name) {
+                                Ok(budget) => budget,
+                                Err(e) => return err(e.to_string()),
+                            },
+                            None => None,
+                        };
+                        let budget_tokens = args
+                            .get("budget_tokens")
+                            .and_then(|v| v.as_u64())
+                            .map(|n| n as usize)
+                            .or(profile_budget)
+                            .unwrap_or(32_000);
+                        if args.get("outline").and_then(|v| v.as_bool()).unwrap_or(false) {
+                            cfg.outline_mode = true;
+                        }
+                        if args.get("compress").and_then(|v| v.as_bool()).unwrap_or(false) {
+                            cfg.compress = true;
+                        }
+                        if let Some(exclude_tests) = args.get("exclude_tests").and_then(|v| v.as_bool()) {
+                            cfg.scan.exclude_tests = exclude_tests;
+                        }
+                        if let Some(memory) = args.get("memory").and_then(|v| v.as_bool()) {
+                            cfg.memory.enabled = memory;
+                        }
+                        if let Some(rules) = args.get("rules").and_then(|v| v.as_bool()) {
+                            cfg.slice_rules = rules;
+                        }
+                        if let Some(docs_first) = args.get("docs_first").and_then(|v| v.as_bool()) {
+                            cfg.packing = if docs_first {
+                                PackingStrategy::DocsFirst
+                            } else {
+                                PackingStrategy::Relevance
+                            };
+                        }
 
                         // Merge per-call exclude dirs into config so build_scan_options picks them up.
                         if let Some(arr) = args.get("exclude").and_then(|v| v.as_array()) {
@@ -763,6 +980,11 @@ Please correct your target_dir (or pass repoPath explicitly).",
                                 .collect();
                             cfg.scan.exclude_dir_names.extend(extra);
                         }
+                        if let Some(arr) = args.get("extra_targets").and_then(|v| v.as_array()) {
+                            cfg.scan.extra_targets.extend(
+                                arr.iter().filter_map(|x| x.as_str()).map(|s| resolve_path(&repo_root, s)),
+                            );
+                        }
 
                         // `single_file=true` bypasses all vector search — returns exactly the
                         // target file/dir without any semantic cross-file expansion.
@@ -787,14 +1009,90 @@ Please correct your target_dir (or pass repoPath explicitly).",
                             }
                         }
 
+                        if args.get("watch").and_then(|v| v.as_bool()).unwrap_or(false) {
+                            let spec = WatchSpec { repo_root, target, budget_tokens, cfg, skeleton_only };
+                            return match self.start_watch(spec) {
+                                Ok(xml) => ok(xml),
+                                Err(e) => err(format!("watch failed: {e}")),
+                            };
+                        }
+
                         match slice_to_xml(&repo_root, &target, budget_tokens, &cfg, skeleton_only) {
                             Ok((xml, _meta)) => ok(xml),
                             Err(e) => err(format!("slice failed: {e}")),
                         }
                     }
+                    "call_graph" => {
+                        let repo_root = match self.resolve_target_project(&args) { Ok(r) => r, Err(e) => return err(e) };
+                        let Some(target_str) = args.get("target_dir").and_then(|v| v.as_str()) else {
+                            return err(
+                                "Error: action 'call_graph' requires the 'target_dir' parameter (e.g. '.' for the whole repo). \
+                                Please call cortex_code_explorer again with action='call_graph' and target_dir='.'.".to_string()
+                            );
+                        };
+                        let target_dir = resolve_path(&repo_root, target_str);
+                        match build_call_graph(&repo_root, &target_dir) {
+                            Ok(graph) => ok(serde_json::to_string(&graph).unwrap_or_default()),
+                            Err(e) => err(format!("call_graph failed: {e}")),
+                        }
+                    }
+                    "impact" => {
+                        let repo_root = match self.resolve_target_project(&args) { Ok(r) => r, Err(e) => return err(e) };
+                        let Some(target_str) = args.get("target").and_then(|v| v.as_str()) else {
+                            return err(
+                                "Error: action 'impact' requires the 'target' parameter (relative path to the file you plan to change). \
+                                Please call cortex_code_explorer again with action='impact' and target='<file>'.".to_string()
+                            );
+                        };
+                        let target = resolve_path(&repo_root, target_str);
+                        match impact_of(&repo_root, &target) {
+                            Ok(report) => ok(serde_json::to_string(&report).unwrap_or_default()),
+                            Err(e) => err(format!("impact failed: {e}")),
+                        }
+                    }
+                    "ranked_map" => {
+                        let repo_root = match self.resolve_target_project(&args) { Ok(r) => r, Err(e) => return err(e) };
+                        let Some(target_str) = args.get("target_dir").and_then(|v| v.as_str()) else {
+                            return err(
+                                "Error: action 'ranked_map' requires the 'target_dir' parameter (e.g. '.' for the whole repo). \
+                                Please call cortex_code_explorer again with action='ranked_map' and target_dir='.'.".to_string()
+                            );
+                        };
+                        let target_dir = resolve_path(&repo_root, target_str);
+                        let budget_tokens = args.get("budget_tokens").and_then(|v| v.as_u64()).unwrap_or(2_048) as usize;
+                        match render_ranked_map(&repo_root, &target_dir, budget_tokens) {
+                            Ok(map) => ok(map),
+                            Err(e) => err(format!("ranked_map failed: {e}")),
+                        }
+                    }
+                    "graph_modules" => {
+                        let repo_root = match self.resolve_target_project(&args) { Ok(r) => r, Err(e) => return err(e) };
+                        let Some(target_str) = args.get("target_dir").and_then(|v| v.as_str()) else {
+                            return err(
+                                "Error: action 'graph_modules' requires the 'target_dir' parameter (e.g. '.' for the whole repo). \
+                                Please call cortex_code_explorer again with action='graph_modules' and target_dir='.'.".to_string()
+                            );
+                        };
+                        let target_dir = resolve_path(&repo_root, target_str);
+                        let graph = match build_module_graph(&repo_root, &target_dir) {
+                            Ok(graph) => graph,
+                            Err(e) => return err(format!("graph_modules failed: {e}")),
+                        };
+                        match args.get("format").and_then(|v| v.as_str()).unwrap_or("json") {
+                            "json" => ok(serde_json::to_string(&graph).unwrap_or_default()),
+                            // Paged output is never cut, so only trim the diagram for a single reply.
+                            "mermaid" => ok(match paging {
+                                Some(_) => to_mermaid(&ExportGraph::from(&graph)),
+                                None => to_mermaid_within(&ExportGraph::from(&graph), max_chars),
+                            }),
+                            other => err(format!(
+                                "Error: unknown format '{other}' for action 'graph_modules'. Use 'json' or 'mermaid'."
+                            )),
+                        }
+                    }
                     _ => err(format!(
                         "Error: Invalid or missing 'action' for cortex_code_explorer: received '{action}'. \
-                        Choose one of: 'map_overview' (repo structure map) or 'deep_slice' (token-budgeted content slice). \
+                        Choose one of: 'map_overview' (repo structure map), 'deep_slice' (token-budgeted content slice), 'call_graph' (function call graph), 'impact' (reverse-dependency impact), 'ranked_map' (ranked signature map) or 'graph_modules' (module import graph). \
                         Example: cortex_code_explorer with action='map_overview' and target_dir='.'"
                     )),
                 }
@@ -1170,20 +1468,11 @@ Call cortex_chronos with action='list_checkpoints' first to see what exists.".to
             }
 
             "cortex_memory_retriever" => {
-                let query = match args.get("query").and_then(|v| v.as_str()) {
-                    Some(q) if !q.trim().is_empty() => q.trim().to_string(),
-                    _ => return err("cortex_memory_retriever requires a non-empty 'query' parameter.".to_string()),
+                let (query, results, searched) = match search_memory(name, &args) {
+                    Ok(found) => found,
+                    Err(e) => return err(e),
                 };
-                let top_k = args.get("top_k").and_then(|v| v.as_u64()).map(|n| n as usize).unwrap_or(5).max(1);
-                let tag_filter: Vec<String> = args
-                    .get("tags")
-                    .and_then(|v| v.as_array())
-                    .map(|arr| arr.iter().filter_map(|x| x.as_str().map(String::from)).collect())
-                    .unwrap_or_default();
-
-                // Load the memory store from the default journal path.
-                let store = MemoryStore::from_default();
-                if store.entries().is_empty() {
+                if searched == 0 {
                     return ok(format!(
                         "Memory journal is empty or does not exist yet.\n\
                          Expected location: {}\n\n\
@@ -1192,39 +1481,6 @@ Call cortex_chronos with action='list_checkpoints' first to see what exists.".to
                     ));
                 }
 
-                // Embed the query. Load model lazily; graceful fallback to keyword-only on failure.
-                let query_vec: Option<Vec<f32>> = StaticModel::from_pretrained(
-                    "minishlab/potion-retrieval-32M",
-                    None,
-                    None,
-                    None,
-                )
-                .ok()
-                .map(|m| m.encode_single(&format!("query: {}", query)));
-
-                // Tokenise the raw query for keyword scoring.
-                let tokens_owned: Vec<String> = query
-                    .split_whitespace()
-                    .filter(|t| t.len() >= 2)
-                    .map(|t| t.to_lowercase())
-                    .collect();
-                let tokens: Vec<&str> = tokens_owned.iter().map(String::as_str).collect();
-
-                let project_path_filter = args
-                    .get("project_path")
-                    .and_then(|v| v.as_str())
-                    .filter(|s| !s.trim().is_empty())
-                    .map(String::from);
-
-                let results = hybrid_search(
-                    &store,
-                    query_vec.as_deref(),
-                    &tokens,
-                    top_k,
-                    &tag_filter,
-                    project_path_filter.as_deref(),
-                );
-
                 if results.is_empty() {
                     return ok("No relevant memory entries found for the given query/tags.".to_string());
                 }
@@ -1233,7 +1489,7 @@ Call cortex_chronos with action='list_checkpoints' first to see what exists.".to
                 let mut out = format!(
                     "## Memory Search Results\n**Query:** {query}\n**Matches:** {}/{} entries\n\n",
                     results.len(),
-                    store.entries().len()
+                    searched
                 );
                 for (rank, r) in results.iter().enumerate() {
                     let e = &r.entry;
@@ -1264,6 +1520,33 @@ Call cortex_chronos with action='list_checkpoints' first to see what exists.".to
                 ok(out)
             }
 
+            "cortex_memory_search" => {
+                let (query, results, searched) = match search_memory(name, &args) {
+                    Ok(found) => found,
+                    Err(e) => return err(e),
+                };
+                let results: Vec<serde_json::Value> = results
+                    .iter()
+                    .enumerate()
+                    .map(|(rank, r)| {
+                        let e = &r.entry;
+                        json!({
+                            "rank": rank + 1,
+                            "score": r.score,
+                            "id": e.id,
+                            "timestamp": e.timestamp,
+                            "project_path": e.project_path,
+                            "intent": e.intent,
+                            "decision": e.decision,
+                            "tags": e.tags,
+                            "files_touched": e.files_touched
+                        })
+                    })
+                    .collect();
+                let out = json!({ "query": query, "searched": searched, "results": results });
+                ok(serde_json::to_string_pretty(&out).unwrap_or_default())
+            }
+
             "cortex_get_rules" => {
                 let project_path = match args.get("project_path").and_then(|v| v.as_str()) {
                     Some(p) if !p.trim().is_empty() => p.trim().to_string(),
@@ -1571,10 +1854,9 @@ Call cortex_chronos with action='list_checkpoints' first to see what exists.".to
         exclude_dir_names.extend(cfg.scan.exclude_dir_names.iter().cloned());
 
         let opts = ScanOptions {
-            repo_root: repo_root.to_path_buf(),
-            target: target.to_path_buf(),
-            max_file_bytes: cfg.token_estimator.max_file_bytes,
             exclude_dir_names,
+            keep_oversized: false,
+            ..crate::slicer::build_scan_options(repo_root, target, cfg)
         };
         let entries = scan_workspace(&opts)?;
 
@@ -1675,6 +1957,65 @@ Call cortex_chronos with action='list_checkpoints' first to see what exists.".to
     }
 }
 
+/// Shared by `cortex_memory_retriever` and `cortex_memory_search`: run [`hybrid_search`] over
+/// the global journal with the call's `query`, `top_k`, `tags` and `project_path`. Returns
+/// the trimmed query, the ranked entries and how many entries the journal holds.
+fn search_memory(
+    tool: &str,
+    args: &serde_json::Value,
+) -> std::result::Result<(String, Vec<RankedEntry>, usize), String> {
+    let query = match args.get("query").and_then(|v| v.as_str()) {
+        Some(q) if !q.trim().is_empty() => q.trim().to_string(),
+        _ => return Err(format!("{tool} requires a non-empty 'query' parameter.")),
+    };
+    let top_k = args.get("top_k").and_then(|v| v.as_u64()).map(|n| n as usize).unwrap_or(5).max(1);
+    let tag_filter: Vec<String> = args
+        .get("tags")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|x| x.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+
+    // Load the memory store from the default journal path.
+    let store = MemoryStore::from_default();
+    if store.entries().is_empty() {
+        return Ok((query, Vec::new(), 0));
+    }
+
+    // Embed the query. Load model lazily (only when some entry has a vector to compare
+    // against); graceful fallback to keyword-only on failure.
+    let query_vec: Option<Vec<f32>> = if store.entries().iter().any(|e| e.vector.is_some()) {
+        StaticModel::from_pretrained("minishlab/potion-retrieval-32M", None, None, None)
+            .ok()
+            .map(|m| m.encode_single(&format!("query: {}", query)))
+    } else {
+        None
+    };
+
+    // Tokenise the raw query for keyword scoring.
+    let tokens_owned: Vec<String> = query
+        .split_whitespace()
+        .filter(|t| t.len() >= 2)
+        .map(|t| t.to_lowercase())
+        .collect();
+    let tokens: Vec<&str> = tokens_owned.iter().map(String::as_str).collect();
+
+    let project_path_filter = args
+        .get("project_path")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.trim().is_empty())
+        .map(String::from);
+
+    let results = hybrid_search(
+        &store,
+        query_vec.as_deref(),
+        &tokens,
+        top_k,
+        &tag_filter,
+        project_path_filter.as_deref(),
+    );
+    Ok((query, results, store.entries().len()))
+}
+
 /// Resolve a path parameter: if absolute, use as-is; otherwise join to repo_root.
 fn resolve_path(repo_root: &std::path::Path, p: &str) -> PathBuf {
     let pb = PathBuf::from(p);
@@ -1703,7 +2044,216 @@ pub fn run_stdio_server(startup_root: Option<PathBuf>) -> Result<()> {
     let stdin = std::io::stdin();
     let mut stdout = std::io::stdout();
 
-    let mut state = ServerState::default();
+    let mut state = bootstrap_state(startup_root);
+
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else { continue };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let msg: serde_json::Value = match serde_json::from_str(&line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        if let Some(reply) = state.handle_message(&msg) {
+            writeln!(stdout, "{}", reply)?;
+            stdout.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Who may open a WebSocket session.
+#[derive(Debug, Clone, Default)]
+pub struct WsAccess {
+    /// Serve on a non-loopback address (`--allow-remote`); such binds are refused otherwise.
+    pub allow_remote: bool,
+    /// Bearer token a session must present, either as `Authorization: Bearer <token>` on the
+    /// handshake or as a first message `{"token": "<token>"}`. Required off loopback.
+    pub token: Option<String>,
+}
+
+/// Serve MCP over WebSocket on `addr` (e.g. `127.0.0.1:7331`): one JSON-RPC message per text
+/// frame, one session per connection. Unlike stdio, the socket stays open for browser-based
+/// clients and carries server-initiated notifications such as a watch's
+/// `notifications/resources/list_changed`.
+///
+/// Every tool call can read the workspace, so only loopback addresses are served unless
+/// `access.allow_remote` is set, and then only with a token.
+pub fn run_ws_server(addr: &str, startup_root: Option<PathBuf>, access: WsAccess) -> Result<()> {
+    use std::net::ToSocketAddrs as _;
+
+    let resolved: Vec<std::net::SocketAddr> = addr
+        .to_socket_addrs()
+        .with_context(|| format!("Invalid WebSocket address {addr}"))?
+        .collect();
+    if !resolved.iter().all(|a| a.ip().is_loopback()) {
+        if !access.allow_remote {
+            anyhow::bail!(
+                "Refusing to serve MCP on non-loopback address {addr}; pass --allow-remote and --token to expose it"
+            );
+        }
+        if access.token.is_none() {
+            anyhow::bail!("Serving MCP on non-loopback address {addr} requires --token");
+        }
+    }
+
+    let listener = std::net::TcpListener::bind(&resolved[..])
+        .with_context(|| format!("Failed to bind WebSocket listener on {addr}"))?;
+    eprintln!("cortexast MCP listening on ws://{}", listener.local_addr()?);
+    let token: Option<Arc<str>> = access.token.map(Arc::from);
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let startup_root = startup_root.clone();
+        let token = token.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = serve_ws_connection(stream, startup_root, token.as_deref()) {
+                crate::debug_log!("[cortexast] WebSocket session ended: {e:#}");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// How long a session blocks on the socket before flushing queued notifications.
+const WS_POLL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// How long a session without a handshake token has to send it as its first message.
+const WS_AUTH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Compares hashes rather than the strings, so the time taken leaks nothing about the token.
+fn token_matches(expected: &str, given: &str) -> bool {
+    blake3::hash(expected.as_bytes()) == blake3::hash(given.as_bytes())
+}
+
+fn serve_ws_connection(
+    stream: std::net::TcpStream,
+    startup_root: Option<PathBuf>,
+    token: Option<&str>,
+) -> Result<()> {
+    use tungstenite::Message;
+
+    // Without a token every session is trusted; with one, the handshake or the first
+    // message must carry it.
+    let mut authorized = token.is_none();
+    #[allow(clippy::result_large_err)] // signature fixed by tungstenite
+    let mut ws = tungstenite::accept_hdr(stream, |req: &tungstenite::handshake::server::Request, resp| {
+        let resp = check_ws_origin(req, resp)?;
+        if let Some(token) = token {
+            let bearer = req
+                .headers()
+                .get("authorization")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "));
+            match bearer {
+                Some(given) if token_matches(token, given) => authorized = true,
+                Some(_) => return Err(ws_error(tungstenite::http::StatusCode::UNAUTHORIZED, "Invalid token")),
+                None => {}
+            }
+        }
+        Ok(resp)
+    })
+    .map_err(|e| anyhow::anyhow!("WebSocket handshake failed: {e}"))?;
+
+    if !authorized {
+        // Nothing is served (not even notifications) before the token arrives.
+        ws.get_ref().set_read_timeout(Some(WS_AUTH_TIMEOUT))?;
+        let given = match ws.read() {
+            Ok(Message::Text(text)) => serde_json::from_str::<serde_json::Value>(&text)
+                .ok()
+                .and_then(|v| v.get("token").and_then(|t| t.as_str()).map(str::to_string)),
+            _ => None,
+        };
+        if !given.is_some_and(|given| token.is_some_and(|token| token_matches(token, &given))) {
+            let _ = ws.close(Some(tungstenite::protocol::CloseFrame {
+                code: tungstenite::protocol::frame::coding::CloseCode::Policy,
+                reason: "Invalid or missing token".into(),
+            }));
+            let _ = ws.flush();
+            anyhow::bail!("WebSocket session refused: invalid or missing token");
+        }
+    }
+    ws.get_ref().set_read_timeout(Some(WS_POLL))?;
+
+    let (tx, rx) = std::sync::mpsc::channel::<serde_json::Value>();
+    let mut state = bootstrap_state(startup_root);
+    state.notify = Some(Arc::new(move |msg| {
+        let _ = tx.send(msg);
+    }));
+
+    loop {
+        while let Ok(note) = rx.try_recv() {
+            ws.send(Message::Text(note.to_string()))?;
+        }
+        match ws.read() {
+            Ok(Message::Text(text)) => {
+                let Ok(msg) = serde_json::from_str::<serde_json::Value>(&text) else { continue };
+                if let Some(reply) = state.handle_message(&msg) {
+                    ws.send(Message::Text(reply.to_string()))?;
+                }
+            }
+            Ok(Message::Close(_)) => {}
+            // Pings are answered by tungstenite; binary frames aren't part of the protocol.
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+            Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Handshake callback: any page the user has open can reach a localhost socket, so only
+/// local pages (and non-browser clients, which send no `Origin`) may drive the server.
+#[allow(clippy::result_large_err)] // signature fixed by tungstenite
+fn check_ws_origin(
+    req: &tungstenite::handshake::server::Request,
+    resp: tungstenite::handshake::server::Response,
+) -> std::result::Result<
+    tungstenite::handshake::server::Response,
+    tungstenite::handshake::server::ErrorResponse,
+> {
+    let origin = req.headers().get("origin").and_then(|o| o.to_str().ok());
+    if origin.is_none_or(is_local_origin) {
+        return Ok(resp);
+    }
+    Err(ws_error(tungstenite::http::StatusCode::FORBIDDEN, "Origin not allowed"))
+}
+
+/// A refused handshake with `status`.
+fn ws_error(
+    status: tungstenite::http::StatusCode,
+    reason: &str,
+) -> tungstenite::handshake::server::ErrorResponse {
+    let mut denied = tungstenite::handshake::server::ErrorResponse::new(Some(reason.to_string()));
+    *denied.status_mut() = status;
+    denied
+}
+
+/// `Origin` of a page served from this machine (`localhost`, a loopback address or a
+/// `file:` page, which browsers report as `null`).
+fn is_local_origin(origin: &str) -> bool {
+    if origin == "null" {
+        return true;
+    }
+    let Some((_, rest)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = match rest.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or(""),
+        None => rest.split(':').next().unwrap_or(""),
+    };
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// A fresh session state with `repo_root` bootstrapped from `--root` or the environment.
+fn bootstrap_state(startup_root: Option<PathBuf>) -> ServerState {
     // ── Bootstrap repo_root before the first tool call arrives ──────────────
     // Priority (first non-None wins; the MCP initialize handler may overwrite
     // this later with the editor's authoritative root):
@@ -1738,26 +2288,20 @@ pub fn run_stdio_server(startup_root: Option<PathBuf>) -> Result<()> {
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty())
         .map(PathBuf::from);
-    if let Some(r) = startup_root.or(env_root) {
-        state.repo_root = Some(r);
+    ServerState {
+        repo_root: startup_root.or(env_root),
+        ..ServerState::default()
     }
+}
 
-    for line in stdin.lock().lines() {
-        let Ok(line) = line else { continue };
-        if line.trim().is_empty() {
-            continue;
-        }
-
-        let msg: serde_json::Value = match serde_json::from_str(&line) {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
-
+impl ServerState {
+    /// Dispatch one JSON-RPC message; `None` for notifications, which get no reply.
+    fn handle_message(&mut self, msg: &serde_json::Value) -> Option<serde_json::Value> {
         // JSON-RPC notifications have no "id" field — don't respond.
         let has_id = msg.get("id").is_some();
         if !has_id {
             // Side-effect-only notifications (initialize ack, cancel, log, etc.) — ignore.
-            continue;
+            return None;
         }
 
         let id = msg.get("id").cloned().unwrap_or(json!(null));
@@ -1768,14 +2312,17 @@ pub fn run_stdio_server(startup_root: Option<PathBuf>) -> Result<()> {
                 // Capture workspace root from VS Code's initialize params so subsequent
                 // tool calls without repoPath resolve to the correct directory.
                 if let Some(p) = msg.get("params") {
-                    state.capture_init_root(p);
+                    self.capture_init_root(p);
                 }
                 json!({
                     "jsonrpc": "2.0",
                     "id": id,
                     "result": {
                         "protocolVersion": msg.get("params").and_then(|p| p.get("protocolVersion")).cloned().unwrap_or(json!("2024-11-05")),
-                        "capabilities": { "tools": { "listChanged": true } },
+                        "capabilities": {
+                            "tools": { "listChanged": true },
+                            "resources": { "subscribe": true, "listChanged": true }
+                        },
                         "serverInfo": { "name": "cortexast", "version": env!("CARGO_PKG_VERSION") }
                     }
                 })
@@ -1785,17 +2332,70 @@ pub fn run_stdio_server(startup_root: Option<PathBuf>) -> Result<()> {
                 "id": id,
                 "result": {}
             }),
-            "tools/list" => state.tool_list(id),
+            "tools/list" => self.tool_list(id),
             "tools/call" => {
                 let params = msg.get("params").cloned().unwrap_or(json!({}));
-                state.tool_call(id, &params)
+                self.tool_call(id, &params)
             }
-            // Return empty lists for resources/prompts — we don't implement them.
+            // The only resources are the slice and repo map of a running deep_slice watch;
+            // prompts are unimplemented.
             "resources/list" => json!({
                 "jsonrpc": "2.0",
                 "id": id,
-                "result": { "resources": [] }
+                "result": { "resources": self.watched_resources() }
             }),
+            "resources/read" => {
+                let uri = msg
+                    .get("params")
+                    .and_then(|p| p.get("uri"))
+                    .and_then(|u| u.as_str())
+                    .unwrap_or("");
+                let found = self
+                    .watch
+                    .iter()
+                    .flat_map(|w| w.files())
+                    .find(|(u, ..)| u == uri)
+                    .and_then(|(_, path, mime_type, _)| {
+                        std::fs::read_to_string(path).ok().map(|text| (mime_type, text))
+                    });
+                match found {
+                    Some((mime_type, text)) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "result": { "contents": [{
+                            "uri": uri,
+                            "mimeType": mime_type,
+                            "text": text
+                        }] }
+                    }),
+                    None => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": -32002, "message": format!("Resource not found: {uri}") }
+                    }),
+                }
+            }
+            // Subscriptions outlive a watch, so a client can subscribe before deep_slice(watch).
+            "resources/subscribe" | "resources/unsubscribe" => {
+                let uri = msg
+                    .get("params")
+                    .and_then(|p| p.get("uri"))
+                    .and_then(|u| u.as_str())
+                    .unwrap_or("")
+                    .to_string();
+                if let Ok(mut subs) = self.subscriptions.lock() {
+                    if method == "resources/subscribe" {
+                        subs.insert(uri);
+                    } else {
+                        subs.remove(&uri);
+                    }
+                }
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "result": {}
+                })
+            }
             "prompts/list" => json!({
                 "jsonrpc": "2.0",
                 "id": id,
@@ -1807,12 +2407,8 @@ pub fn run_stdio_server(startup_root: Option<PathBuf>) -> Result<()> {
                 "error": { "code": -32601, "message": format!("Method not found: {method}") }
             }),
         };
-
-        writeln!(stdout, "{}", reply)?;
-        stdout.flush()?;
+        Some(reply)
     }
-
-    Ok(())
 }
 
 const DEFAULT_MAX_CHARS: usize = 8_000;
@@ -1825,6 +2421,77 @@ fn negotiated_max_chars(args: &serde_json::Value) -> usize {
         .unwrap_or(DEFAULT_MAX_CHARS)
 }
 
+/// `Some((cursor, page_size))` when the call asks for paged output (`cursor` and/or
+/// `page_size`); the page size defaults to `max_chars`.
+fn negotiated_paging(args: &serde_json::Value, max_chars: usize) -> Option<(Option<String>, usize)> {
+    let cursor = args
+        .get("cursor")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(String::from);
+    let page_size = args
+        .get("page_size")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize)
+        .filter(|n| *n > 0);
+    if cursor.is_none() && page_size.is_none() {
+        return None;
+    }
+    Some((cursor, page_size.unwrap_or(max_chars)))
+}
+
+/// One page of `content`: up to `page_size` chars from the cursor's offset, cut after the
+/// last line break when there is one so entries aren't split across pages. Returns the page
+/// (with a footer naming the next cursor) and that cursor, `None` on the last page.
+///
+/// A cursor is `<offset>:<digest>`, the digest covering the whole output, so paging through
+/// output that changed between calls fails instead of skipping or repeating entries.
+fn paginate(
+    content: &str,
+    cursor: Option<&str>,
+    page_size: usize,
+) -> std::result::Result<(String, Option<String>), String> {
+    let digest = &blake3::hash(content.as_bytes()).to_hex()[..12];
+    let start = match cursor {
+        None => 0,
+        Some(cursor) => {
+            let invalid = || format!("Invalid cursor '{cursor}'. Pass the 'nextCursor' of the previous page, or omit 'cursor' to start over.");
+            let (offset, cursor_digest) = cursor.split_once(':').ok_or_else(invalid)?;
+            if cursor_digest != digest {
+                return Err("Cursor expired: the output changed since the previous page. Call again without 'cursor' to start over.".to_string());
+            }
+            offset
+                .parse::<usize>()
+                .ok()
+                .filter(|&o| o <= content.len() && content.is_char_boundary(o))
+                .ok_or_else(invalid)?
+        }
+    };
+    let rest = &content[start..];
+    if rest.len() <= page_size {
+        return Ok((rest.to_string(), None));
+    }
+    let mut cut = page_size;
+    while cut > 0 && !rest.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    if let Some(nl) = rest[..cut].rfind('\n') {
+        cut = nl + 1;
+    }
+    if cut == 0 {
+        // A page smaller than one character still has to move forward.
+        cut = rest.chars().next().map_or(0, char::len_utf8);
+    }
+    let end = start + cut;
+    let next = format!("{end}:{digest}");
+    let page = format!(
+        "{}\n\n[PAGE: chars {start}-{end} of {}. More results: call again with the same arguments and cursor=\"{next}\"]",
+        &rest[..cut],
+        content.len()
+    );
+    Ok((page, Some(next)))
+}
+
 /// Hard inline cap: always truncates in the response body — never writes to disk.
 /// Safe for any MCP client; the truncation marker makes partial output obvious.
 fn force_inline_truncate(mut content: String, max_chars: usize) -> String {
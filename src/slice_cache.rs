@@ -0,0 +1,223 @@
+//! Per-target cache of the file contents that went into the last slice.
+//!
+//! Each slice of a target (with a given config) keeps `<repo>/.context-slicer/slices/<key>.json`:
+//! per file, its size, modification time, fingerprint (the scanner's content hash) and prepared
+//! (condensed, compressed, redacted) content with the redactions made to it. On the next slice,
+//! files with an unchanged fingerprint skip re-condensing; only changed files go through the
+//! full pipeline. The fingerprint is always hashed from the file, since a rewrite can keep its
+//! size and mtime; those only spare rewriting the cache when nothing moved. Ranking and budget
+//! fitting always run fresh. Deleting the directory is always safe.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::analyze_cache::store_json;
+use crate::redact::Redaction;
+use crate::scanner::FileEntry;
+
+pub const SLICE_CACHE_DIR: &str = ".context-slicer/slices";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedFile {
+    bytes: u64,
+    modified_ns: Option<u64>,
+    /// [`FileEntry::fingerprint`] of the file the content was prepared from.
+    hash: String,
+    #[serde(flatten)]
+    prepared: Prepared,
+}
+
+/// A file's slice content, already redacted so no secret reaches the cache on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Prepared {
+    pub content: String,
+    /// What redaction masked in `content`, replayed into the slice's metadata on hits.
+    pub redactions: Vec<Redaction>,
+}
+
+/// Prepared contents of one target's last slice, loaded by [`SliceCache::open`] and written
+/// back by [`SliceCache::save`].
+#[derive(Debug, Default)]
+pub struct SliceCache {
+    path: PathBuf,
+    files: BTreeMap<String, CachedFile>,
+    /// Files seen in this slice; the rest are pruned on save.
+    seen: BTreeMap<String, CachedFile>,
+    dirty: bool,
+}
+
+impl SliceCache {
+    /// The cache for slicing `target` with settings `key` (anything that changes prepared
+    /// contents, e.g. the config and focus file); empty when missing or unreadable.
+    pub fn open<K: Serialize>(repo_root: &Path, target: &Path, key: &K) -> Self {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+        hasher.update(b"\0");
+        hasher.update(target.to_string_lossy().replace('\\', "/").as_bytes());
+        hasher.update(b"\0");
+        hasher.update(&serde_json::to_vec(key).unwrap_or_default());
+        let path = repo_root
+            .join(SLICE_CACHE_DIR)
+            .join(format!("{}.json", hasher.finalize().to_hex()));
+        let files = std::fs::read(&path)
+            .ok()
+            .and_then(|raw| serde_json::from_slice(&raw).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            files,
+            ..Self::default()
+        }
+    }
+
+    /// Prepared content of `e`, from the cache when the file is unchanged, otherwise from
+    /// `prepare` (whose `None` is passed through uncached).
+    pub fn content(
+        &mut self,
+        e: &FileEntry,
+        rel: &str,
+        prepare: impl FnOnce() -> Option<Prepared>,
+    ) -> Option<Prepared> {
+        let modified_ns = std::fs::metadata(&e.abs_path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos() as u64);
+
+        if let Some(hit) = self.files.remove(rel) {
+            if e.fingerprint() == Some(hit.hash.as_str()) {
+                let prepared = hit.prepared.clone();
+                // Only touched: record the new size and mtime.
+                if hit.bytes != e.bytes || hit.modified_ns != modified_ns {
+                    self.dirty = true;
+                }
+                self.seen.insert(
+                    rel.to_string(),
+                    CachedFile {
+                        bytes: e.bytes,
+                        modified_ns,
+                        ..hit
+                    },
+                );
+                return Some(prepared);
+            }
+            // The file changed: its old entry is dropped on save.
+            self.dirty = true;
+        }
+
+        let prepared = prepare()?;
+        if let Some(hash) = e.fingerprint() {
+            self.seen.insert(
+                rel.to_string(),
+                CachedFile {
+                    bytes: e.bytes,
+                    modified_ns,
+                    hash: hash.to_string(),
+                    prepared: prepared.clone(),
+                },
+            );
+            self.dirty = true;
+        }
+        Some(prepared)
+    }
+
+    /// Write the files seen since [`SliceCache::open`], dropping the rest; a no-op when
+    /// nothing changed.
+    pub fn save(self) -> Result<()> {
+        if !self.dirty && self.files.is_empty() {
+            return Ok(());
+        }
+        store_json(&self.path, &self.seen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(root: &Path, rel: &str) -> FileEntry {
+        let abs_path = root.join(rel);
+        let bytes = std::fs::metadata(&abs_path).unwrap().len();
+        FileEntry::new(abs_path, PathBuf::from(rel), bytes, false)
+    }
+
+    fn prepared(content: &str) -> Option<Prepared> {
+        Some(Prepared {
+            content: content.to_string(),
+            redactions: Vec::new(),
+        })
+    }
+
+    fn text(prepared: Option<Prepared>) -> Option<String> {
+        prepared.map(|p| p.content)
+    }
+
+    #[test]
+    fn unchanged_files_reuse_their_prepared_content() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        std::fs::write(root.join("a.rs"), "fn a() {}\n").unwrap();
+        std::fs::write(root.join("b.rs"), "fn b() {}\n").unwrap();
+        let target = Path::new(".");
+
+        let mut cache = SliceCache::open(root, target, &"cfg");
+        for rel in ["a.rs", "b.rs"] {
+            let content = cache.content(&entry(root, rel), rel, || {
+                prepared(&format!("prepared {rel}"))
+            });
+            assert_eq!(text(content), Some(format!("prepared {rel}")));
+        }
+        cache.save().unwrap();
+
+        // Only the edited file is prepared again.
+        std::fs::write(root.join("b.rs"), "fn b() { 1 }\n").unwrap();
+        let mut cache = SliceCache::open(root, target, &"cfg");
+        let a = cache.content(&entry(root, "a.rs"), "a.rs", || panic!("a.rs is unchanged"));
+        assert_eq!(text(a).as_deref(), Some("prepared a.rs"));
+        let b = cache.content(&entry(root, "b.rs"), "b.rs", || prepared("fresh b"));
+        assert_eq!(text(b).as_deref(), Some("fresh b"));
+        cache.save().unwrap();
+
+        // Another config key starts cold.
+        let mut cache = SliceCache::open(root, target, &"other");
+        let a = cache.content(&entry(root, "a.rs"), "a.rs", || prepared("cold"));
+        assert_eq!(text(a).as_deref(), Some("cold"));
+    }
+
+    #[test]
+    fn rewrites_keeping_size_and_mtime_are_still_detected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let path = root.join("a.rs");
+        std::fs::write(&path, "fn a() {}\n").unwrap();
+        let target = Path::new(".");
+
+        let first = entry(root, "a.rs");
+        let mut cache = SliceCache::open(root, target, &"cfg");
+        cache.content(&first, "a.rs", || prepared("prepared"));
+        cache.save().unwrap();
+        let hash = first.fingerprint().unwrap().to_string();
+
+        // A same-length rewrite with its mtime restored is hashed, not trusted.
+        let mtime = std::fs::metadata(&path).unwrap().modified().unwrap();
+        std::fs::write(&path, "fn b() {}\n").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+        let second = entry(root, "a.rs");
+        let mut cache = SliceCache::open(root, target, &"cfg");
+        let a = cache.content(&second, "a.rs", || prepared("rewritten"));
+        assert_eq!(text(a).as_deref(), Some("rewritten"));
+        assert_ne!(second.fingerprint(), Some(hash.as_str()));
+        assert_eq!(
+            second.fingerprint(),
+            crate::scanner::fingerprint(&path).as_deref()
+        );
+    }
+}
@@ -0,0 +1,337 @@
+//! Comparison of two slice documents, to track how an agent's context drifted.
+//!
+//! Slices in any [`crate::config::SliceFormat`] are parsed back into their sections (rules,
+//! memory, repository map) and files. Contents are compared with trailing whitespace and
+//! blank lines ignored, so a slice compares equal to itself in another format.
+
+use anyhow::{Context, Result};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Sections and files of a slice document, by name and path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedSlice {
+    /// Preamble sections and the repository map (`rules`, `memory`, `repository_map`).
+    pub sections: BTreeMap<String, String>,
+    pub files: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SliceDiff {
+    pub files: EntryDiff,
+    pub sections: EntryDiff,
+}
+
+/// Names added, removed or changed between two slices.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EntryDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<ChangedEntry>,
+}
+
+/// An entry in both slices whose content differs; line counts compare the two as multisets
+/// of lines, so a moved line counts as neither.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangedEntry {
+    pub name: String,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+}
+
+impl SliceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.sections.is_empty()
+    }
+}
+
+impl EntryDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Parse a slice document, detecting its format from its first line.
+pub fn parse_slice(doc: &str) -> Result<ParsedSlice> {
+    let head = doc.trim_start();
+    if head.starts_with('<') {
+        parse_xml(doc)
+    } else if head.starts_with('{') {
+        parse_json(doc)
+    } else if head.starts_with("===== ") {
+        Ok(parse_text(doc))
+    } else {
+        Ok(parse_markdown(doc))
+    }
+}
+
+pub fn diff_slices(old: &ParsedSlice, new: &ParsedSlice) -> SliceDiff {
+    SliceDiff {
+        files: diff_entries(&old.files, &new.files),
+        sections: diff_entries(&old.sections, &new.sections),
+    }
+}
+
+fn diff_entries(old: &BTreeMap<String, String>, new: &BTreeMap<String, String>) -> EntryDiff {
+    let mut diff = EntryDiff {
+        removed: old
+            .keys()
+            .filter(|name| !new.contains_key(*name))
+            .cloned()
+            .collect(),
+        ..EntryDiff::default()
+    };
+    for (name, content) in new {
+        match old.get(name) {
+            None => diff.added.push(name.clone()),
+            Some(before) => {
+                let (lines_added, lines_removed) = line_changes(before, content);
+                if lines_added > 0 || lines_removed > 0 {
+                    diff.changed.push(ChangedEntry {
+                        name: name.clone(),
+                        lines_added,
+                        lines_removed,
+                    });
+                }
+            }
+        }
+    }
+    diff
+}
+
+/// Lines only in `after` and only in `before`, as multisets of normalized lines.
+fn line_changes(before: &str, after: &str) -> (usize, usize) {
+    let mut counts: HashMap<&str, isize> = HashMap::new();
+    for line in normalized_lines(before) {
+        *counts.entry(line).or_default() -= 1;
+    }
+    for line in normalized_lines(after) {
+        *counts.entry(line).or_default() += 1;
+    }
+    counts.values().fold((0, 0), |(added, removed), &n| {
+        if n > 0 {
+            (added + n as usize, removed)
+        } else {
+            (added, removed + n.unsigned_abs())
+        }
+    })
+}
+
+fn normalized_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().map(str::trim_end).filter(|l| !l.is_empty())
+}
+
+fn parse_xml(doc: &str) -> Result<ParsedSlice> {
+    let mut reader = Reader::from_str(doc);
+    let mut slice = ParsedSlice::default();
+    // (section name or file path, is a file, text so far) of the open top-level element.
+    let mut open: Option<(String, bool, String)> = None;
+    let mut depth = 0usize;
+    loop {
+        match reader
+            .read_event()
+            .context("Slice is not well-formed XML")?
+        {
+            Event::Start(el) => {
+                depth += 1;
+                if depth == 2 {
+                    let name = String::from_utf8_lossy(el.name().as_ref()).to_string();
+                    open = Some(if name == "file" {
+                        let path = el
+                            .try_get_attribute("path")?
+                            .context("<file> without a path")?
+                            .unescape_value()?
+                            .to_string();
+                        (path, true, String::new())
+                    } else {
+                        (name, false, String::new())
+                    });
+                }
+            }
+            Event::End(_) => {
+                if depth == 2 {
+                    if let Some((name, is_file, text)) = open.take() {
+                        let map = if is_file {
+                            &mut slice.files
+                        } else {
+                            &mut slice.sections
+                        };
+                        map.insert(name, text);
+                    }
+                }
+                depth = depth.saturating_sub(1);
+            }
+            // Only direct content counts; `<symbols>` and other children are metadata.
+            Event::CData(data) if depth == 2 => {
+                if let Some((_, _, text)) = open.as_mut() {
+                    text.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::Text(data) if depth == 2 => {
+                if let Some((_, _, text)) = open.as_mut() {
+                    text.push_str(&data.unescape()?);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(slice)
+}
+
+fn parse_json(doc: &str) -> Result<ParsedSlice> {
+    let value: serde_json::Value = serde_json::from_str(doc).context("Slice is not valid JSON")?;
+    let object = value.as_object().context("JSON slice is not an object")?;
+    let mut slice = ParsedSlice::default();
+    for (key, field) in object {
+        match (key.as_str(), field) {
+            ("files", serde_json::Value::Array(files)) => {
+                for file in files {
+                    let path = file.get("path").and_then(|p| p.as_str());
+                    let content = file.get("content").and_then(|c| c.as_str());
+                    if let (Some(path), Some(content)) = (path, content) {
+                        slice.files.insert(path.to_string(), content.to_string());
+                    }
+                }
+            }
+            (_, serde_json::Value::String(text)) => {
+                slice.sections.insert(key.clone(), text.clone());
+            }
+            _ => {}
+        }
+    }
+    Ok(slice)
+}
+
+/// `===== title =====` blocks; the preamble and map titles are sections, the rest files.
+fn parse_text(doc: &str) -> ParsedSlice {
+    let mut slice = ParsedSlice::default();
+    let mut open: Option<(String, String)> = None;
+    for line in doc.lines() {
+        let title = line
+            .strip_prefix("===== ")
+            .and_then(|rest| rest.strip_suffix(" ====="));
+        match title {
+            Some(title) => {
+                if let Some((title, body)) = open.take() {
+                    insert_titled(&mut slice, &title, body);
+                }
+                open = Some((title.to_string(), String::new()));
+            }
+            None => {
+                if let Some((_, body)) = open.as_mut() {
+                    body.push_str(line);
+                    body.push('\n');
+                }
+            }
+        }
+    }
+    if let Some((title, body)) = open {
+        insert_titled(&mut slice, &title, body);
+    }
+    slice
+}
+
+/// `## title` headings, each followed by one fenced block.
+fn parse_markdown(doc: &str) -> ParsedSlice {
+    let mut slice = ParsedSlice::default();
+    let mut lines = doc.lines();
+    while let Some(line) = lines.next() {
+        let Some(title) = line.strip_prefix("## ") else {
+            continue;
+        };
+        let Some(opening) = lines.by_ref().find(|l| l.starts_with("```")) else {
+            break;
+        };
+        let fence_len = opening.chars().take_while(|&c| c == '`').count();
+        let fence = &opening[..fence_len];
+        let mut body = String::new();
+        for line in lines.by_ref() {
+            if line == fence {
+                break;
+            }
+            body.push_str(line);
+            body.push('\n');
+        }
+        insert_titled(&mut slice, title, body);
+    }
+    slice
+}
+
+/// File an entry of a text or Markdown slice under its section name or file path.
+fn insert_titled(slice: &mut ParsedSlice, title: &str, body: String) {
+    match title.to_lowercase().as_str() {
+        "repository map" => {
+            slice.sections.insert("repository_map".to_string(), body);
+        }
+        section @ ("rules" | "memory") => {
+            slice.sections.insert(section.to_string(), body);
+        }
+        _ => {
+            slice.files.insert(title.to_string(), body);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown_builder::build_context_markdown;
+    use crate::text_builder::build_context_text;
+
+    fn files(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .map(|(p, c)| (p.to_string(), c.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed_entries_across_formats() {
+        let preamble = vec![("rules".to_string(), "no unwrap\n".to_string())];
+        let old = build_context_markdown(
+            &preamble,
+            Some("a.rs\nb.rs\n"),
+            &files(&[("a.rs", "fn a() {}\n"), ("b.rs", "fn b() {}\n")]),
+        )
+        .unwrap();
+        let new = build_context_text(
+            &preamble,
+            Some("a.rs\nc.rs\n"),
+            &files(&[
+                ("a.rs", "fn a() {}\n\nfn a2() {}\n"),
+                ("c.rs", "fn c() {}\n"),
+            ]),
+        )
+        .unwrap();
+
+        let old = parse_slice(&old).unwrap();
+        assert_eq!(old.files["b.rs"], "fn b() {}\n");
+        let diff = diff_slices(&old, &parse_slice(&new).unwrap());
+        assert_eq!(diff.files.added, ["c.rs"]);
+        assert_eq!(diff.files.removed, ["b.rs"]);
+        assert_eq!(
+            diff.files.changed,
+            [ChangedEntry {
+                name: "a.rs".to_string(),
+                lines_added: 1,
+                lines_removed: 0,
+            }]
+        );
+        assert!(diff.sections.added.is_empty() && diff.sections.removed.is_empty());
+        assert_eq!(diff.sections.changed[0].name, "repository_map");
+    }
+
+    #[test]
+    fn xml_slices_parse_sections_and_file_contents() {
+        let doc = r#"<?xml version="1.0" encoding="utf-8"?><cortexast><memory><![CDATA[- fixed auth]]></memory><repository_map><![CDATA[src/a.rs]]></repository_map><file path="src/a.rs"><symbols><symbol name="a"/></symbols><![CDATA[fn a() {}]]></file></cortexast>"#;
+        let slice = parse_slice(doc).unwrap();
+        assert_eq!(slice.sections["memory"], "- fixed auth");
+        assert_eq!(slice.sections["repository_map"], "src/a.rs");
+        assert_eq!(slice.files["src/a.rs"], "fn a() {}");
+        assert!(diff_slices(&slice, &slice).is_empty());
+    }
+}
@@ -1,11 +1,27 @@
-use crate::config::Config;
-use crate::inspector::try_render_skeleton_from_source;
-use crate::mapper::build_repo_map_scoped;
-use crate::scanner::{scan_workspace, FileEntry, ScanOptions};
+use crate::config::{
+    Config, OverflowPolicy, OversizePolicy, PackingStrategy, SliceFormat, ABSOLUTE_MAX_FILE_BYTES,
+};
+use crate::inspector::{analyze_file, strip_comments, try_render_skeleton_from_source};
+use crate::json_builder::build_context_json;
+use crate::mapper::{file_ranks, FileRank};
+use crate::markdown_builder::build_context_markdown;
+use crate::memory::{related_entries, MemoryStore, RankedEntry};
+use crate::notebook::is_notebook_path;
+use crate::paths::relative_to;
+use crate::redact::{Redaction, Redactor};
+use crate::rules::get_merged_rules;
+use crate::scanner::{
+    scan_workspace, scan_workspace_with_stats, FileEntry, ScanOptions, ScanStats,
+};
+use crate::slice_cache::{Prepared, SliceCache};
+use crate::text_builder::build_context_text;
+use crate::token_estimator::{lang_hint, HeuristicEstimator, TokenEstimator};
 use crate::workspace::{discover_workspace_members, WorkspaceDiscoveryOptions};
 use crate::xml_builder::build_context_xml;
-use anyhow::{Context, Result};
-use std::collections::HashMap;
+use anyhow::Result;
+use glob::{MatchOptions, Pattern};
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone)]
@@ -16,15 +32,182 @@ pub struct SliceMeta {
     pub total_tokens: usize,
     pub total_files: usize,
     pub total_bytes: u64,
+    /// Files that were considered but left out, in the order they were dropped.
+    pub dropped: Vec<DroppedFile>,
+    /// Secrets masked in the files read for the slice (see `Config::redaction`).
+    pub redactions: Vec<Redaction>,
+    /// [`FileEntry::fingerprint`] of each file in the slice, by path.
+    pub fingerprints: BTreeMap<String, String>,
+    /// What the scan behind the slice saw and skipped; zero for explicit path lists.
+    pub scan: ScanStats,
 }
 
-pub fn estimate_tokens_from_bytes(total_bytes: u64, chars_per_token: usize) -> usize {
-    if chars_per_token == 0 {
-        return total_bytes as usize;
+/// A file missing from a slice, with its estimated cost and why it was left out.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DroppedFile {
+    pub path: String,
+    /// Tokens the file would have added (its condensed content when it was read).
+    pub est_tokens: usize,
+    pub reason: DropReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DropReason {
+    /// Did not fit in the remaining budget, even truncated per `token_estimator.budget_overflow`.
+    OverBudget,
+    /// Larger than `token_estimator.max_file_bytes` with nothing to show for it under the
+    /// `oversize_policy`.
+    Oversized,
+    /// Could not be read.
+    Unreadable,
+}
+
+impl DroppedFile {
+    /// A file dropped before budget fitting, estimated from its size on disk.
+    fn unread(estimator: &dyn TokenEstimator, e: &FileEntry, rel_path: &str) -> Self {
+        Self {
+            path: rel_path.to_string(),
+            est_tokens: estimator.estimate_bytes(e.bytes, lang_hint(&e.abs_path)),
+            reason: if e.oversized {
+                DropReason::Oversized
+            } else {
+                DropReason::Unreadable
+            },
+        }
+    }
+
+    fn over_budget(rel_path: &str, est_tokens: usize) -> Self {
+        Self {
+            path: rel_path.to_string(),
+            est_tokens,
+            reason: DropReason::OverBudget,
+        }
+    }
+}
+
+/// The slice document in `cfg.slice_format`; `part` is `(part, parts)` for one part of a
+/// split slice (see [`slice_to_parts`]).
+fn render_slice(
+    cfg: &Config,
+    repo_root: &Path,
+    preamble: &[(String, String)],
+    repository_map: Option<&str>,
+    files: &[(String, String)],
+    part: Option<(usize, usize)>,
+) -> Result<String> {
+    match cfg.slice_format {
+        SliceFormat::Xml => {
+            let estimator = HeuristicEstimator::from_config(&cfg.token_estimator);
+            build_context_xml(
+                repo_root,
+                &estimator,
+                &cfg.xml_metadata,
+                preamble,
+                repository_map,
+                files,
+                part,
+            )
+        }
+        SliceFormat::Markdown => {
+            let doc = build_context_markdown(preamble, repository_map, files)?;
+            Ok(match part {
+                Some((part, parts)) => format!("# Part {part} of {parts}\n\n{doc}"),
+                None => doc,
+            })
+        }
+        SliceFormat::Json => {
+            let estimator = HeuristicEstimator::from_config(&cfg.token_estimator);
+            build_context_json(repo_root, &estimator, preamble, repository_map, files, part)
+        }
+        SliceFormat::Text => {
+            let doc = build_context_text(preamble, repository_map, files)?;
+            Ok(match part {
+                Some((part, parts)) => format!("===== part {part} of {parts} =====\n{doc}"),
+                None => doc,
+            })
+        }
     }
+}
+
+/// Sections rendered ahead of the repository map of a slice covering the repo-relative
+/// `paths`: the merged rules (see `Config::slice_rules`), then the related memory entries
+/// (see `Config::memory`).
+fn slice_preamble(repo_root: &Path, cfg: &Config, paths: &[String]) -> Vec<(String, String)> {
+    let rules = rules_section(repo_root, cfg, paths).map(|text| ("rules".to_string(), text));
+    let memory = memory_section(repo_root, cfg, paths).map(|text| ("memory".to_string(), text));
+    rules.into_iter().chain(memory).collect()
+}
+
+/// The merged rule tiers as YAML, filtered for a single-path slice like `cortex_get_rules`
+/// with a file context; `None` when disabled or no tier has rules.
+fn rules_section(repo_root: &Path, cfg: &Config, paths: &[String]) -> Option<String> {
+    if !cfg.slice_rules {
+        return None;
+    }
+    let context = match paths {
+        [only] => Some(only.as_str()),
+        _ => None,
+    };
+    let rules = get_merged_rules(&repo_root.to_string_lossy(), context).ok()?;
+    if rules.get("status").and_then(|s| s.as_str()) == Some("no_rules_found") {
+        return None;
+    }
+    serde_yaml::to_string(&rules).ok()
+}
+
+/// Journal entries related to `paths`, one `- timestamp intent` item each with its decision
+/// and touched files; `None` when disabled or nothing matches.
+fn memory_section(repo_root: &Path, cfg: &Config, paths: &[String]) -> Option<String> {
+    if !cfg.memory.enabled || cfg.memory.max_entries == 0 {
+        return None;
+    }
+    let store = match &cfg.memory.journal {
+        Some(journal) => MemoryStore::load(&repo_root.join(journal)).ok()?,
+        None => MemoryStore::from_default(),
+    };
+    let related = related_entries(&store, repo_root, paths, cfg.memory.max_entries);
+    if related.is_empty() {
+        return None;
+    }
+    let mut out = String::new();
+    for RankedEntry { entry, .. } in related {
+        out.push_str(&format!("- {} {}\n", entry.timestamp, entry.intent));
+        out.push_str(&format!("  decision: {}\n", entry.decision));
+        if !entry.files_touched.is_empty() {
+            out.push_str(&format!("  files: {}\n", entry.files_touched.join(", ")));
+        }
+    }
+    Some(out)
+}
+
+/// Bytes the preamble adds to a rendered slice (element tags plus CDATA wrapper).
+fn preamble_bytes(preamble: &[(String, String)]) -> u64 {
+    preamble
+        .iter()
+        .map(|(name, text)| (2 * name.len() + 17 + text.len()) as u64)
+        .sum()
+}
+
+/// `target` relative to `repo_root`, `/`-separated.
+fn target_rel(repo_root: &Path, target: &Path) -> String {
+    let rel = if target.is_absolute() {
+        relative_to(target, repo_root).unwrap_or_else(|| target.to_path_buf())
+    } else {
+        target.to_path_buf()
+    };
+    rel.to_string_lossy().replace('\\', "/")
+}
 
-    // Heuristic: ~4 chars per token. We use bytes as a proxy for chars.
-    ((total_bytes as f64) / (chars_per_token as f64)).ceil() as usize
+pub fn estimate_tokens_from_bytes(total_bytes: u64, chars_per_token: usize) -> usize {
+    HeuristicEstimator::new(chars_per_token as f64).estimate_bytes(total_bytes, None)
+}
+
+/// Tokens one `<file>` element adds to a slice: XML overhead plus (language-calibrated) content.
+fn file_tokens(estimator: &dyn TokenEstimator, rel_path: &str, content: &str) -> usize {
+    estimator.estimate_bytes(estimate_xml_file_overhead_bytes(rel_path), None)
+        + estimator.estimate_text(content, lang_hint(Path::new(rel_path)))
 }
 
 /// Slice a specific list of repo-relative file paths into context XML.
@@ -39,9 +222,14 @@ pub fn slice_paths_to_xml(
 ) -> Result<(String, SliceMeta)> {
     let repo_root = repo_root.to_path_buf();
     let target = PathBuf::from(".");
+    let estimator = &HeuristicEstimator::from_config(&cfg.token_estimator);
 
     // Build entries in the provided order (assumed relevance-ranked).
     let mut entries: Vec<crate::scanner::FileEntry> = Vec::new();
+    let mut dropped: Vec<DroppedFile> = Vec::new();
+    let redactor = Redactor::new(&cfg.redaction)?;
+    let mut redactions: Vec<Redaction> = Vec::new();
+    let mut fingerprints: BTreeMap<String, String> = BTreeMap::new();
     for rel in rel_paths {
         let rel_norm = rel.replace('\\', "/");
         let abs = repo_root.join(&rel_norm);
@@ -53,62 +241,109 @@ pub fn slice_paths_to_xml(
             continue;
         }
         let bytes = meta.len();
-        if bytes == 0 || bytes > cfg.token_estimator.max_file_bytes {
+        if bytes == 0 {
             continue;
         }
-        entries.push(crate::scanner::FileEntry {
-            abs_path: abs,
-            rel_path: PathBuf::from(rel_norm),
+        let entry = crate::scanner::FileEntry::new(
+            abs,
+            PathBuf::from(&rel_norm),
             bytes,
-        });
+            bytes > cfg.token_estimator.max_file_bytes,
+        );
+        if entry.oversized && cfg.token_estimator.oversize_policy == OversizePolicy::Skip {
+            dropped.push(DroppedFile::unread(estimator, &entry, &rel_norm));
+            continue;
+        }
+        entries.push(entry);
     }
+    add_always_included(&repo_root, cfg, &mut entries)?;
+    // Pinned files go first; the rest keep the given order.
+    let pins = Pins::new(cfg);
+    entries.sort_by_cached_key(|e| {
+        pins.tier(&e.rel_path.to_string_lossy().replace('\\', "/"))
+            .unwrap_or(usize::MAX)
+    });
 
     let all_paths: Vec<String> = entries
         .iter()
         .map(|e| e.rel_path.to_string_lossy().replace('\\', "/"))
         .collect();
     let repository_map_text = build_repository_map_text(&all_paths);
+    let preamble = slice_preamble(&repo_root, cfg, &all_paths);
 
     let mut files_for_xml: Vec<(String, String)> = Vec::new();
     let mut total_bytes: u64 = 64;
     total_bytes = total_bytes
+        .saturating_add(preamble_bytes(&preamble))
         .saturating_add(estimate_xml_repository_map_overhead_bytes())
         .saturating_add(repository_map_text.len() as u64);
+    let mut total_tokens = estimator.estimate_bytes(total_bytes, None);
 
     for e in entries.iter() {
-        let bytes = match std::fs::read(&e.abs_path) {
-            Ok(b) => b,
-            Err(_) => continue,
-        };
-        let content_full = String::from_utf8(bytes)
-            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).to_string());
         let rel = e.rel_path.to_string_lossy().replace('\\', "/");
-
-        let content = if cfg.skeleton_mode || skeleton_only {
-            match try_render_skeleton_from_source(&e.abs_path, &content_full) {
-                Ok(Some(s)) => s,
-                Ok(None) => truncate_unknown(&rel, &content_full),
-                Err(_) => truncate_unknown(&rel, &content_full),
+        let content = if e.oversized {
+            match oversized_content(e, &rel, cfg) {
+                Some(c) => c,
+                None => {
+                    dropped.push(DroppedFile::unread(estimator, e, &rel));
+                    continue;
+                }
             }
         } else {
-            content_full
+            let bytes = match std::fs::read(&e.abs_path) {
+                Ok(b) => b,
+                Err(_) => {
+                    dropped.push(DroppedFile::unread(estimator, e, &rel));
+                    continue;
+                }
+            };
+            let content_full = String::from_utf8(bytes)
+                .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).to_string());
+            let content_full = compressed(cfg, &e.abs_path, content_full);
+
+            if cfg.skeleton_mode || cfg.outline_mode || skeleton_only {
+                condensed_content(cfg, &e.abs_path, &rel, &content_full)
+            } else {
+                content_full
+            }
         };
 
-        let overhead = estimate_xml_file_overhead_bytes(&rel);
-        let new_total = total_bytes
-            .saturating_add(overhead)
+        let content = redactor.redact(&rel, content, &mut redactions);
+        let remaining = budget_tokens.saturating_sub(total_tokens);
+        let (content, added_tokens) = match fit_content(
+            cfg,
+            estimator,
+            &redactor,
+            &e.abs_path,
+            &rel,
+            content,
+            remaining,
+        ) {
+            Ok(fitted) => fitted,
+            Err(est_tokens) => {
+                dropped.push(DroppedFile::over_budget(&rel, est_tokens));
+                continue;
+            }
+        };
+
+        total_tokens += added_tokens;
+        total_bytes = total_bytes
+            .saturating_add(estimate_xml_file_overhead_bytes(&rel))
             .saturating_add(content.len() as u64);
-        let est = estimate_tokens_from_bytes(new_total, cfg.token_estimator.chars_per_token);
-        if est > budget_tokens {
-            continue;
+        if let Some(fp) = e.fingerprint() {
+            fingerprints.insert(rel.clone(), fp.to_string());
         }
-
-        total_bytes = new_total;
         files_for_xml.push((rel, content));
     }
 
-    let total_tokens = estimate_tokens_from_bytes(total_bytes, cfg.token_estimator.chars_per_token);
-    let xml = build_context_xml(Some(&repository_map_text), &files_for_xml)?;
+    let xml = render_slice(
+        cfg,
+        &repo_root,
+        &preamble,
+        Some(&repository_map_text),
+        &files_for_xml,
+        None,
+    )?;
 
     let meta = SliceMeta {
         repo_root,
@@ -117,6 +352,10 @@ pub fn slice_paths_to_xml(
         total_tokens,
         total_files: files_for_xml.len(),
         total_bytes,
+        dropped,
+        redactions,
+        fingerprints,
+        scan: ScanStats::default(),
     };
 
     Ok((xml, meta))
@@ -182,6 +421,398 @@ fn truncate_unknown(rel_path: &str, content: &str) -> String {
     out
 }
 
+/// `content` without comments and blank lines when `cfg.compress` is set, for languages
+/// with a tree-sitter driver. Outlines keep their doc comments, so `outline_mode` skips it.
+fn compressed(cfg: &Config, abs_path: &Path, content: String) -> String {
+    if !cfg.compress || cfg.outline_mode {
+        return content;
+    }
+    match strip_comments(abs_path, &content) {
+        Ok(Some(stripped)) => stripped,
+        _ => content,
+    }
+}
+
+/// Pruned content for a file: its API outline in `outline_mode` (unless it has no public
+/// symbols), otherwise its skeleton, or a truncated head for unsupported files.
+fn condensed_content(cfg: &Config, abs_path: &Path, rel_path: &str, content: &str) -> String {
+    if cfg.outline_mode && !is_notebook_path(abs_path) {
+        if let Ok(fs) = analyze_file(abs_path) {
+            let outline = render_api_outline(content, &fs);
+            if !outline.is_empty() {
+                return outline;
+            }
+        }
+    }
+    match try_render_skeleton_from_source(abs_path, content) {
+        Ok(Some(s)) => s,
+        Ok(None) | Err(_) => truncate_unknown(rel_path, content),
+    }
+}
+
+/// Kinds whose whole definition (fields, variants, members) is API, not just its first line.
+const TYPE_DEFINITION_KINDS: [&str; 5] = ["struct", "enum", "union", "interface", "type"];
+
+/// API overview of a file: each public symbol's doc comment, then the full source of type
+/// definitions and the signature of everything else, at its original indentation. Private
+/// items and tests (`#[test]` functions, anything in a `tests` module) are left out.
+fn render_api_outline(source: &str, fs: &crate::inspector::FileSymbols) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let in_tests = |s: &crate::inspector::Symbol| {
+        s.attributes.iter().any(|a| a == "test")
+            || std::iter::once(s.name.as_str())
+                .chain(s.parent.as_deref())
+                .flat_map(|path| path.split([':', '.']))
+                .any(|segment| segment == "tests")
+    };
+    let mut symbols: Vec<&crate::inspector::Symbol> = fs
+        .symbols
+        .iter()
+        .filter(|s| !matches!(s.visibility.as_deref(), Some("private" | "internal")))
+        .filter(|s| !in_tests(s))
+        .collect();
+    symbols.sort_by_key(|s| (s.line, s.column));
+
+    let indent_of = |line: &str| line.len() - line.trim_start().len();
+    let mut out = String::new();
+    // Last line already emitted verbatim, so members of a printed type aren't repeated.
+    let mut covered_until: Option<u32> = None;
+    // Declaration lines already printed, so an enclosing `impl`/`class` is shown once.
+    let mut printed: Vec<usize> = Vec::new();
+    for s in symbols {
+        if covered_until.is_some_and(|end| s.line <= end) {
+            continue;
+        }
+        let start = s.line as usize;
+        let end = (s.line_end as usize).min(lines.len().saturating_sub(1));
+        if start >= lines.len() {
+            continue;
+        }
+        // The declaration itself starts below any attributes / decorators.
+        let decl = (start..=end)
+            .find(|&i| !is_attribute_line(lines[i]))
+            .unwrap_or(start);
+        let indent = indent_of(lines[decl]);
+
+        // Nested members get their enclosing block's header (`impl Foo`, `class Bar`),
+        // which may be private itself or not a symbol at all.
+        if indent > 0 {
+            let container = (0..decl).rev().find(|&i| {
+                let t = lines[i].trim_start();
+                !t.is_empty()
+                    && !is_attribute_line(t)
+                    && !["//", "/*", "*", "#"].iter().any(|p| t.starts_with(p))
+                    && indent_of(lines[i]) < indent
+            });
+            if let Some(c) = container.filter(|c| !printed.contains(c)) {
+                if let Some(head) = declaration_head(&lines[c..=decl]) {
+                    out.push_str(&lines[c][..indent_of(lines[c])]);
+                    out.push_str(&head);
+                    out.push('\n');
+                }
+                printed.push(c);
+            }
+        }
+
+        for doc in doc_comment_above(&lines, start) {
+            out.push_str(doc);
+            out.push('\n');
+        }
+        printed.push(decl);
+        if TYPE_DEFINITION_KINDS.contains(&s.kind.as_str()) {
+            for l in &lines[start..=end] {
+                out.push_str(l);
+                out.push('\n');
+            }
+            covered_until = Some(s.line_end);
+            continue;
+        }
+        let signature = declaration_head(&lines[decl..=end])
+            .or_else(|| s.signature.clone())
+            .filter(|sig| !sig.is_empty())
+            .unwrap_or_else(|| format!("{} {}", s.kind, s.name));
+        out.push_str(&lines[decl][..indent]);
+        out.push_str(&signature);
+        out.push('\n');
+    }
+    out
+}
+
+fn is_attribute_line(line: &str) -> bool {
+    let t = line.trim_start();
+    t.starts_with("#[") || t.starts_with("#!") || t.starts_with('@')
+}
+
+/// A declaration up to (not including) its body, on one line: everything before the first
+/// `{` or `;` outside parentheses/brackets, or before a line-ending `:` (Python).
+fn declaration_head(lines: &[&str]) -> Option<String> {
+    let mut head = String::new();
+    let mut depth = 0i32;
+    for line in lines.iter().take(16) {
+        let line = line.trim();
+        let mut cut = None;
+        for (i, ch) in line.char_indices() {
+            match ch {
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth -= 1,
+                '{' | ';' if depth <= 0 => {
+                    cut = Some(i);
+                    break;
+                }
+                ':' if depth <= 0 && i + 1 == line.len() => {
+                    cut = Some(i);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let piece = line[..cut.unwrap_or(line.len())].trim_end();
+        // Rejoin wrapped parameter lists as `f(a, b)`, not `f( a, b, )`.
+        if piece.starts_with([')', ']']) {
+            head.truncate(head.trim_end_matches(',').len());
+        } else if !head.is_empty() && !head.ends_with(['(', '[']) {
+            head.push(' ');
+        }
+        head.push_str(piece);
+        if cut.is_some() {
+            break;
+        }
+    }
+    let head = head.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!head.is_empty()).then_some(head)
+}
+
+/// The comment block directly above `line` (looking past attributes and decorators), in
+/// source order.
+fn doc_comment_above<'a>(lines: &[&'a str], line: usize) -> Vec<&'a str> {
+    let mut docs = Vec::new();
+    for l in lines[..line].iter().rev() {
+        let t = l.trim_start();
+        if is_attribute_line(l) {
+            continue;
+        }
+        let is_comment = ["//", "/*", "*", "#"].iter().any(|p| t.starts_with(p));
+        if !is_comment || t.is_empty() {
+            break;
+        }
+        docs.push(*l);
+    }
+    docs.reverse();
+    docs
+}
+
+/// Content for a file the scanner tagged as oversized, per `token_estimator.oversize_policy`.
+///
+/// Returns `None` when the file should be left out of the slice.
+fn oversized_content(e: &FileEntry, rel_path: &str, cfg: &Config) -> Option<String> {
+    let max_bytes = cfg.token_estimator.max_file_bytes;
+    match cfg.token_estimator.oversize_policy {
+        OversizePolicy::Skip => None,
+        // Parsing is only safe below the hard cap; larger files degrade to "head".
+        OversizePolicy::Outline if e.bytes <= ABSOLUTE_MAX_FILE_BYTES => {
+            match analyze_file(&e.abs_path) {
+                Ok(fs) if !fs.symbols.is_empty() => Some(render_outline(rel_path, e.bytes, &fs)),
+                _ => head_content(e, rel_path, max_bytes),
+            }
+        }
+        OversizePolicy::HeadOutline => {
+            let head_bytes = cfg.token_estimator.oversize_head_bytes.min(max_bytes);
+            let head = read_head(&e.abs_path, head_bytes).ok()?;
+            // Symbols starting on the head's cut-off last line or later; 0-based lines.
+            let head_lines = head.matches('\n').count();
+            let rest: Vec<crate::inspector::Symbol> = if e.bytes <= ABSOLUTE_MAX_FILE_BYTES {
+                analyze_file(&e.abs_path)
+                    .map(|fs| fs.symbols)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|s| s.line as usize >= head_lines)
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            Some(render_head_outline(rel_path, e.bytes, &head, &rest))
+        }
+        OversizePolicy::Head | OversizePolicy::Outline => head_content(e, rel_path, max_bytes),
+    }
+}
+
+/// `content` with its token cost when it fits in `remaining` tokens; otherwise its
+/// stand-in per `token_estimator.budget_overflow`, or the full content's cost as the error
+/// to leave the file out.
+///
+/// `content` is expected to be redacted already; the outline is parsed from the raw file,
+/// so it goes through `redactor` here (its hits were counted with the content).
+fn fit_content(
+    cfg: &Config,
+    estimator: &dyn TokenEstimator,
+    redactor: &Redactor,
+    abs_path: &Path,
+    rel_path: &str,
+    content: String,
+    remaining: usize,
+) -> std::result::Result<(String, usize), usize> {
+    let full_tokens = file_tokens(estimator, rel_path, &content);
+    if full_tokens <= remaining {
+        return Ok((content, full_tokens));
+    }
+    let outline = match cfg.token_estimator.budget_overflow {
+        OverflowPolicy::Skip => return Err(full_tokens),
+        OverflowPolicy::Head => None,
+        OverflowPolicy::OutlineHead => analyze_file(abs_path)
+            .ok()
+            .filter(|fs| !fs.symbols.is_empty())
+            .map(|fs| {
+                let mut outline = String::new();
+                for s in &fs.symbols {
+                    push_outline_line(&mut outline, s, "");
+                }
+                redactor.redact(rel_path, outline, &mut Vec::new())
+            }),
+    };
+
+    // Prefer the outline with as many lines as fit, then fall back to the head alone.
+    let lines: Vec<&str> = content.lines().collect();
+    let max_lines = cfg.token_estimator.overflow_head_lines.min(lines.len());
+    for outline in [outline.as_deref(), None] {
+        for kept in (0..=max_lines).rev() {
+            let out = overflow_head(rel_path, outline, &lines, kept);
+            let tokens = file_tokens(estimator, rel_path, &out);
+            if tokens <= remaining {
+                return Ok((out, tokens));
+            }
+        }
+        if outline.is_none() {
+            break;
+        }
+    }
+    Err(full_tokens)
+}
+
+/// The first `kept` of `lines`, after an optional symbol outline, between truncation markers.
+fn overflow_head(rel_path: &str, outline: Option<&str>, lines: &[&str], kept: usize) -> String {
+    let mut out = String::new();
+    out.push_str(truncation_header_for_path(rel_path));
+    if let Some(outline) = outline {
+        out.push_str("/* outline of over-budget file */\n");
+        out.push_str(outline);
+        out.push('\n');
+    }
+    for line in &lines[..kept] {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.push_str(&format!(
+        "\n/* ... truncated to fit the budget: first {} of {} lines */\n",
+        kept,
+        lines.len()
+    ));
+    out
+}
+
+fn head_content(e: &FileEntry, rel_path: &str, max_bytes: u64) -> Option<String> {
+    let head = read_head(&e.abs_path, max_bytes).ok()?;
+    let mut out = String::with_capacity(head.len() + 96);
+    out.push_str(truncation_header_for_path(rel_path));
+    out.push_str(&head);
+    if !head.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&format!(
+        "\n/* ... truncated: first {} of {} bytes */\n",
+        head.len(),
+        e.bytes
+    ));
+    Some(out)
+}
+
+/// Read at most `max_bytes` from the start of a file, cut back to a UTF-8 char boundary.
+///
+/// Streams through `Read::take`, so the rest of the file is never loaded.
+fn read_head(path: &Path, max_bytes: u64) -> std::io::Result<String> {
+    use std::io::Read;
+
+    let file = std::fs::File::open(path)?;
+    let mut buf = Vec::with_capacity(max_bytes.min(ABSOLUTE_MAX_FILE_BYTES) as usize);
+    file.take(max_bytes).read_to_end(&mut buf)?;
+
+    match String::from_utf8(buf) {
+        Ok(s) => Ok(s),
+        Err(err) => {
+            let utf8 = err.utf8_error();
+            let mut bytes = err.into_bytes();
+            // A multi-byte char split by the cut: drop the partial tail.
+            if utf8.error_len().is_none() {
+                bytes.truncate(utf8.valid_up_to());
+            }
+            Ok(String::from_utf8_lossy(&bytes).into_owned())
+        }
+    }
+}
+
+/// `head` of an oversized file, then the outline of the symbols in the rest of it.
+fn render_head_outline(
+    rel_path: &str,
+    bytes: u64,
+    head: &str,
+    rest: &[crate::inspector::Symbol],
+) -> String {
+    let mut out = String::with_capacity(head.len() + rest.len() * 64 + 128);
+    out.push_str(truncation_header_for_path(rel_path));
+    out.push_str(head);
+    if !head.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&format!(
+        "\n/* ... truncated: first {} of {} bytes",
+        head.len(),
+        bytes
+    ));
+    if rest.is_empty() {
+        out.push_str(" */\n");
+        return out;
+    }
+    out.push_str("; outline of the rest */\n");
+    for s in rest {
+        push_outline_line(&mut out, s, "");
+        for c in &s.children {
+            push_outline_line(&mut out, c, "  ");
+        }
+    }
+    out
+}
+
+fn render_outline(rel_path: &str, bytes: u64, fs: &crate::inspector::FileSymbols) -> String {
+    let mut out = String::new();
+    out.push_str(truncation_header_for_path(rel_path));
+    out.push_str(&format!(
+        "/* outline of oversized file ({} bytes) */\n",
+        bytes
+    ));
+    for s in &fs.symbols {
+        push_outline_line(&mut out, s, "");
+        // Fields / variants / members, indented under their parent.
+        for c in &s.children {
+            push_outline_line(&mut out, c, "  ");
+        }
+    }
+    out
+}
+
+fn push_outline_line(out: &mut String, s: &crate::inspector::Symbol, indent: &str) {
+    match s.signature.as_deref() {
+        Some(sig) if !sig.is_empty() => {
+            out.push_str(&format!("{}L{}: {}\n", indent, s.line + 1, sig));
+        }
+        _ => out.push_str(&format!(
+            "{}L{}: {} {}\n",
+            indent,
+            s.line + 1,
+            s.kind,
+            s.name
+        )),
+    }
+}
+
 fn is_manifest_file(rel_path: &str) -> bool {
     let p = rel_path.to_lowercase();
     p.ends_with("cargo.toml") || p.ends_with("package.json")
@@ -348,34 +979,152 @@ fn importance_score(rel_path: &str) -> i64 {
     score
 }
 
-fn compute_repo_map_indegree(repo_root: &Path, target: &Path) -> HashMap<String, u32> {
-    // Build a best-effort file graph using mapper.rs (polyglot import extraction).
-    // We only need indegree counts for ranking.
-    let scope = if target.as_os_str().is_empty() {
-        PathBuf::from(".")
-    } else {
-        target.to_path_buf()
-    };
+/// Order `entries` so the most useful files are packed first when the budget runs out.
+///
+/// Each file's score combines the path heuristics of [`importance_score`] with its import
+/// centrality (PageRank over file-to-file imports under `scope`), how often and how
+/// recently it changed in git, and a penalty for size, since one huge file can crowd out
+/// several central ones. The focus file always comes first, then the pinned files (see
+/// [`Pins`]), then under `PackingStrategy::DocsFirst` the orientation docs (see
+/// [`orientation_doc_rank`]); ties fall back to path order.
+fn rank_entries(
+    repo_root: &Path,
+    scope: &Path,
+    cfg: &Config,
+    entries: &mut [FileEntry],
+    focus: Option<&str>,
+) {
+    let pins = Pins::new(cfg);
+    let ranks = file_ranks(repo_root, scope).unwrap_or_default();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    let docs_first = cfg.packing == PackingStrategy::DocsFirst;
+
+    entries.sort_by_cached_key(|e| {
+        let rel = e.rel_path.to_string_lossy().replace('\\', "/");
+        let doc_rank = if docs_first {
+            orientation_doc_rank(&rel, scope)
+        } else {
+            None
+        };
+        let tier = if focus == Some(rel.as_str()) {
+            0
+        } else if let Some(t) = pins.tier(&rel) {
+            t + 1
+        } else if doc_rank.is_some() {
+            usize::MAX - 1
+        } else {
+            usize::MAX
+        };
+        let score = importance_score(&rel) + relevance_bonus(ranks.get(&rel), e.bytes, now);
+        (tier, doc_rank, std::cmp::Reverse(score), rel)
+    });
+}
 
-    let map = match build_repo_map_scoped(repo_root, &scope) {
-        Ok(m) => m,
-        Err(_) => return HashMap::new(),
-    };
+/// Packing order of `rel` among the orientation docs of `scope`: READMEs and
+/// architecture/design/overview notes, then other pages under a `docs/` or `doc/`
+/// directory, each ordered by depth below `scope`. `None` for any other file.
+fn orientation_doc_rank(rel: &str, scope: &Path) -> Option<usize> {
+    const DOC_EXTS: &[&str] = &["md", "markdown", "mdx", "rst", "adoc", "txt"];
+    const NAMES: &[&str] = &["readme", "architecture", "design", "overview"];
+
+    let lower = rel.to_lowercase();
+    let (dir, file) = lower.rsplit_once('/').unwrap_or(("", lower.as_str()));
+    let (stem, ext) = file.rsplit_once('.').unwrap_or((file, ""));
+    let named = NAMES.contains(&stem) && (ext.is_empty() || DOC_EXTS.contains(&ext));
+    let in_docs = DOC_EXTS.contains(&ext) && dir.split('/').any(|c| c == "docs" || c == "doc");
+    if !named && !in_docs {
+        return None;
+    }
 
-    let mut id_to_path: HashMap<String, String> = HashMap::new();
-    for n in map.nodes {
-        // mapper emits `path` as a repo-relative path (or best-effort); normalize.
-        id_to_path.insert(n.id.clone(), n.path.replace('\\', "/"));
+    let scope = scope.to_string_lossy().replace('\\', "/");
+    let scope_depth = scope
+        .split('/')
+        .filter(|c| !c.is_empty() && *c != ".")
+        .count();
+    let depth = rel.matches('/').count().saturating_sub(scope_depth);
+    Some(depth * 2 + usize::from(!named))
+}
+
+/// `always_include` and `priority_globs` from config, compiled. Invalid globs are ignored.
+struct Pins {
+    always: Vec<Pattern>,
+    priority: Vec<Pattern>,
+}
+
+impl Pins {
+    fn new(cfg: &Config) -> Self {
+        let compile = |globs: &[String]| -> Vec<Pattern> {
+            globs.iter().filter_map(|g| Pattern::new(g).ok()).collect()
+        };
+        Self {
+            always: compile(&cfg.always_include),
+            priority: compile(&cfg.priority_globs),
+        }
     }
 
-    let mut indegree: HashMap<String, u32> = HashMap::new();
-    for e in map.edges {
-        if let Some(dst_path) = id_to_path.get(&e.target) {
-            *indegree.entry(dst_path.clone()).or_insert(0) += 1;
+    /// Packing tier of a repo-relative path: 0 when always included, `1 + i` when the
+    /// `i`-th priority glob is the first to match, `None` when not pinned.
+    fn tier(&self, rel: &str) -> Option<usize> {
+        if self.always.iter().any(|p| pin_matches(p, rel)) {
+            return Some(0);
         }
+        self.priority
+            .iter()
+            .position(|p| pin_matches(p, rel))
+            .map(|i| i + 1)
+    }
+}
+
+fn pin_matches(pattern: &Pattern, rel: &str) -> bool {
+    const OPTS: MatchOptions = MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    if pattern.as_str().contains('/') {
+        pattern.matches_with(rel, OPTS)
+    } else {
+        pattern.matches_with(rel.rsplit('/').next().unwrap_or(rel), OPTS)
     }
+}
 
-    indegree
+/// Append the repo's `always_include` files that `entries` doesn't already have.
+fn add_always_included(repo_root: &Path, cfg: &Config, entries: &mut Vec<FileEntry>) -> Result<()> {
+    let always = Pins::new(cfg).always;
+    if always.is_empty() {
+        return Ok(());
+    }
+    let have: std::collections::HashSet<PathBuf> =
+        entries.iter().map(|e| e.rel_path.clone()).collect();
+    let repo_wide = scan_workspace(&build_scan_options(repo_root, Path::new("."), cfg))?;
+    entries.extend(repo_wide.into_iter().filter(|e| {
+        let rel = e.rel_path.to_string_lossy().replace('\\', "/");
+        !have.contains(&e.rel_path) && always.iter().any(|p| pin_matches(p, &rel))
+    }));
+    Ok(())
+}
+
+/// Sort bonus from a file's [`FileRank`] and on-disk size (see [`rank_entries`]).
+fn relevance_bonus(rank: Option<&FileRank>, bytes: u64, now: i64) -> i64 {
+    const DAY: i64 = 24 * 3600;
+    let mut bonus = 0;
+    if let Some(rank) = rank {
+        // 1.0 centrality = average file; hubs imported everywhere climb quickly.
+        bonus += (rank.centrality * 20.0).round().min(200.0) as i64;
+        bonus += rank.commits.min(10) as i64;
+        bonus += match rank.last_changed.map(|t| now - t) {
+            Some(age) if age < 7 * DAY => 15,
+            Some(age) if age < 30 * DAY => 10,
+            Some(age) if age < 90 * DAY => 5,
+            _ => 0,
+        };
+    }
+    // Roughly one point per 1k tokens of raw source, capped.
+    bonus - (bytes / 4096).min(50) as i64
 }
 
 fn focus_full_file_rel(repo_root: &Path, target: &Path) -> Option<String> {
@@ -436,6 +1185,51 @@ fn build_repository_map_text_raw(sections_text: &str) -> String {
     out
 }
 
+/// What a scanned file contributes to a slice before budget fitting: its oversize stand-in,
+/// full content for the focus file, compacted manifests, or (condensed) source. `None` when
+/// the file is left out or unreadable.
+fn entry_content(
+    e: &FileEntry,
+    rel: &str,
+    cfg: &Config,
+    focus_full_rel: Option<&str>,
+    skeleton_only: bool,
+) -> Option<String> {
+    if e.oversized {
+        return oversized_content(e, rel, cfg);
+    }
+    let bytes = std::fs::read(&e.abs_path).ok()?;
+    let content_full = String::from_utf8(bytes)
+        .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).to_string());
+    let content_full = compressed(cfg, &e.abs_path, content_full);
+
+    let is_focus_full = focus_full_rel.is_some_and(|f| f == rel.replace('\\', "/"));
+    let skeleton_mode = cfg.skeleton_mode || cfg.outline_mode || skeleton_only;
+    Some(if is_focus_full {
+        content_full
+    } else if rel.to_lowercase().ends_with("cargo.toml") {
+        compact_cargo_toml(&content_full).unwrap_or(content_full)
+    } else if rel.to_lowercase().ends_with("package.json") {
+        compact_package_json(&content_full).unwrap_or(content_full)
+    } else if skeleton_mode {
+        condensed_content(cfg, &e.abs_path, rel, &content_full)
+    } else {
+        content_full
+    })
+}
+
+/// The [`SliceCache`] of a target slice; prepared contents depend on the whole config, the
+/// focus file and `skeleton_only`.
+fn open_slice_cache(
+    repo_root: &Path,
+    target: &Path,
+    cfg: &Config,
+    focus_full_rel: Option<&str>,
+    skeleton_only: bool,
+) -> SliceCache {
+    SliceCache::open(repo_root, target, &(cfg, focus_full_rel, skeleton_only))
+}
+
 /// Shared inner function: convert a ranked list of `FileEntry` into context XML.
 fn build_xml_from_entries(
     entries: Vec<crate::scanner::FileEntry>,
@@ -446,66 +1240,87 @@ fn build_xml_from_entries(
     focus_full_rel: Option<String>,
     skeleton_only: bool,
 ) -> Result<(String, SliceMeta)> {
+    let estimator = &HeuristicEstimator::from_config(&cfg.token_estimator);
     let mut all_paths: Vec<String> = entries
         .iter()
         .map(|e| e.rel_path.to_string_lossy().replace('\\', "/"))
         .collect();
     all_paths.sort();
     let repository_map_text = build_repository_map_text(&all_paths);
+    let preamble = slice_preamble(repo_root, cfg, &[target_rel(repo_root, target)]);
 
     let mut files_for_xml: Vec<(String, String)> = Vec::new();
     let mut total_bytes: u64 = 64;
     total_bytes = total_bytes
+        .saturating_add(preamble_bytes(&preamble))
         .saturating_add(estimate_xml_repository_map_overhead_bytes())
         .saturating_add(repository_map_text.len() as u64);
+    let mut total_tokens = estimator.estimate_bytes(total_bytes, None);
+    let mut dropped: Vec<DroppedFile> = Vec::new();
+    let redactor = Redactor::new(&cfg.redaction)?;
+    let mut redactions: Vec<Redaction> = Vec::new();
+    let mut fingerprints: BTreeMap<String, String> = BTreeMap::new();
+    let mut cache = open_slice_cache(
+        repo_root,
+        target,
+        cfg,
+        focus_full_rel.as_deref(),
+        skeleton_only,
+    );
 
     for e in entries {
-        let bytes = match std::fs::read(&e.abs_path)
-            .with_context(|| format!("Failed to read file: {}", e.abs_path.display()))
-        {
-            Ok(b) => b,
-            Err(_) => continue,
-        };
-
-        let content_full = String::from_utf8(bytes)
-            .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).to_string());
         let rel = e.rel_path.to_string_lossy().to_string();
+        let Some(prepared) = cache.content(&e, &rel, || {
+            let content = entry_content(&e, &rel, cfg, focus_full_rel.as_deref(), skeleton_only)?;
+            let mut redactions = Vec::new();
+            let content = redactor.redact(&rel, content, &mut redactions);
+            Some(Prepared {
+                content,
+                redactions,
+            })
+        }) else {
+            dropped.push(DroppedFile::unread(estimator, &e, &rel));
+            continue;
+        };
+        let content = prepared.content;
 
-        let is_focus_full = focus_full_rel
-            .as_ref()
-            .is_some_and(|f| f == &rel.replace('\\', "/"));
-        let skeleton_mode = cfg.skeleton_mode || skeleton_only;
-        let content = if is_focus_full {
-            content_full
-        } else if rel.to_lowercase().ends_with("cargo.toml") {
-            compact_cargo_toml(&content_full).unwrap_or_else(|| content_full.clone())
-        } else if rel.to_lowercase().ends_with("package.json") {
-            compact_package_json(&content_full).unwrap_or_else(|| content_full.clone())
-        } else if skeleton_mode {
-            match try_render_skeleton_from_source(&e.abs_path, &content_full) {
-                Ok(Some(s)) => s,
-                Ok(None) => truncate_unknown(&rel, &content_full),
-                Err(_) => truncate_unknown(&rel, &content_full),
+        redactions.extend(prepared.redactions);
+        let remaining = budget_tokens.saturating_sub(total_tokens);
+        let (content, added_tokens) = match fit_content(
+            cfg,
+            estimator,
+            &redactor,
+            &e.abs_path,
+            &rel,
+            content,
+            remaining,
+        ) {
+            Ok(fitted) => fitted,
+            Err(est_tokens) => {
+                dropped.push(DroppedFile::over_budget(&rel, est_tokens));
+                continue;
             }
-        } else {
-            content_full
         };
 
-        let overhead = estimate_xml_file_overhead_bytes(&rel);
-        let new_total = total_bytes
-            .saturating_add(overhead)
+        total_tokens += added_tokens;
+        total_bytes = total_bytes
+            .saturating_add(estimate_xml_file_overhead_bytes(&rel))
             .saturating_add(content.len() as u64);
-        let est = estimate_tokens_from_bytes(new_total, cfg.token_estimator.chars_per_token);
-        if est > budget_tokens {
-            continue;
+        if let Some(fp) = e.fingerprint() {
+            fingerprints.insert(rel.clone(), fp.to_string());
         }
-
-        total_bytes = new_total;
         files_for_xml.push((rel, content));
     }
+    let _ = cache.save();
 
-    let total_tokens = estimate_tokens_from_bytes(total_bytes, cfg.token_estimator.chars_per_token);
-    let xml = build_context_xml(Some(&repository_map_text), &files_for_xml)?;
+    let xml = render_slice(
+        cfg,
+        repo_root,
+        &preamble,
+        Some(&repository_map_text),
+        &files_for_xml,
+        None,
+    )?;
 
     let meta = SliceMeta {
         repo_root: repo_root.to_path_buf(),
@@ -514,6 +1329,10 @@ fn build_xml_from_entries(
         total_tokens,
         total_files: files_for_xml.len(),
         total_bytes,
+        dropped,
+        redactions,
+        fingerprints,
+        scan: ScanStats::default(),
     };
 
     Ok((xml, meta))
@@ -539,29 +1358,23 @@ pub fn slice_to_xml(
 
     let opts = build_scan_options(repo_root, target, cfg);
 
-    let mut entries = scan_workspace(&opts)?;
+    let (mut entries, scan) = scan_for_slice(opts, cfg)?;
+    add_always_included(repo_root, cfg, &mut entries)?;
 
     // Task 1: only the exact target file (if target is a file) is allowed to stay FULL.
     // If target is a directory, everything is treated as context and will be skeletonized/truncated.
     let focus_full_rel = focus_full_file_rel(repo_root, target);
 
-    // Task 3: importance-based sorting.
-    // Task 2: Aider-style ranking: score by incoming edges from the repo map.
-    let indegree = compute_repo_map_indegree(repo_root, target);
-    entries.sort_by(|a, b| {
-        let a_rel = a.rel_path.to_string_lossy().replace('\\', "/");
-        let b_rel = b.rel_path.to_string_lossy().replace('\\', "/");
-
-        let mut a_score = importance_score(&a_rel);
-        let mut b_score = importance_score(&b_rel);
-
-        a_score += *indegree.get(&a_rel).unwrap_or(&0) as i64 * 10;
-        b_score += *indegree.get(&b_rel).unwrap_or(&0) as i64 * 10;
-
-        b_score.cmp(&a_score).then_with(|| a_rel.cmp(&b_rel))
-    });
+    // Relevance-ranked budget fitting: central, recently changed, compact files first.
+    rank_entries(
+        repo_root,
+        target,
+        cfg,
+        &mut entries,
+        focus_full_rel.as_deref(),
+    );
 
-    build_xml_from_entries(
+    let (xml, mut meta) = build_xml_from_entries(
         entries,
         repo_root,
         target,
@@ -569,7 +1382,168 @@ pub fn slice_to_xml(
         cfg,
         focus_full_rel,
         skeleton_only,
-    )
+    )?;
+    meta.scan = scan;
+    Ok((xml, meta))
+}
+
+/// [`slice_to_xml`] without dropping files for lack of budget: the ranked files are packed,
+/// in order, into as many sequential documents as needed, each within `budget_tokens` and
+/// tagged with its part number and the part count. Only the first part carries the
+/// repository map. A file too large for a part of its own is still subject to
+/// `token_estimator.budget_overflow`; every part's meta lists all dropped files.
+pub fn slice_to_parts(
+    repo_root: &Path,
+    target: &Path,
+    budget_tokens: usize,
+    cfg: &Config,
+    skeleton_only: bool,
+) -> Result<Vec<(String, SliceMeta)>> {
+    let estimator = &HeuristicEstimator::from_config(&cfg.token_estimator);
+    let opts = build_scan_options(repo_root, target, cfg);
+    let (mut entries, scan) = scan_for_slice(opts, cfg)?;
+    add_always_included(repo_root, cfg, &mut entries)?;
+    let focus_full_rel = focus_full_file_rel(repo_root, target);
+    rank_entries(
+        repo_root,
+        target,
+        cfg,
+        &mut entries,
+        focus_full_rel.as_deref(),
+    );
+
+    let mut all_paths: Vec<String> = entries
+        .iter()
+        .map(|e| e.rel_path.to_string_lossy().replace('\\', "/"))
+        .collect();
+    all_paths.sort();
+    let repository_map_text = build_repository_map_text(&all_paths);
+
+    let preamble = slice_preamble(repo_root, cfg, &[target_rel(repo_root, target)]);
+
+    // Every part pays for the document envelope; the first also for the preamble and map.
+    let envelope_bytes: u64 = 64;
+    let map_bytes = estimate_xml_repository_map_overhead_bytes()
+        .saturating_add(repository_map_text.len() as u64)
+        .saturating_add(preamble_bytes(&preamble));
+    let first_bytes = envelope_bytes + map_bytes;
+    let mut parts = vec![SlicePart::new(estimator, first_bytes)];
+    let mut dropped: Vec<DroppedFile> = Vec::new();
+    let redactor = Redactor::new(&cfg.redaction)?;
+    let mut redactions: Vec<Redaction> = Vec::new();
+    let mut cache = open_slice_cache(
+        repo_root,
+        target,
+        cfg,
+        focus_full_rel.as_deref(),
+        skeleton_only,
+    );
+
+    for e in entries {
+        let rel = e.rel_path.to_string_lossy().replace('\\', "/");
+        let Some(prepared) = cache.content(&e, &rel, || {
+            let content = entry_content(&e, &rel, cfg, focus_full_rel.as_deref(), skeleton_only)?;
+            let mut redactions = Vec::new();
+            let content = redactor.redact(&rel, content, &mut redactions);
+            Some(Prepared {
+                content,
+                redactions,
+            })
+        }) else {
+            dropped.push(DroppedFile::unread(estimator, &e, &rel));
+            continue;
+        };
+        let content = prepared.content;
+        let tokens = file_tokens(estimator, &rel, &content);
+        let current = parts.last().expect("at least one part");
+        if !current.files.is_empty() && current.tokens + tokens > budget_tokens {
+            parts.push(SlicePart::new(estimator, envelope_bytes));
+        }
+
+        let part = parts.last_mut().expect("at least one part");
+        redactions.extend(prepared.redactions);
+        let remaining = budget_tokens.saturating_sub(part.tokens);
+        let (content, added_tokens) = match fit_content(
+            cfg,
+            estimator,
+            &redactor,
+            &e.abs_path,
+            &rel,
+            content,
+            remaining,
+        ) {
+            Ok(fitted) => fitted,
+            Err(est_tokens) => {
+                dropped.push(DroppedFile::over_budget(&rel, est_tokens));
+                continue;
+            }
+        };
+        part.tokens += added_tokens;
+        part.bytes = part
+            .bytes
+            .saturating_add(estimate_xml_file_overhead_bytes(&rel))
+            .saturating_add(content.len() as u64);
+        if let Some(fp) = e.fingerprint() {
+            part.fingerprints.insert(rel.clone(), fp.to_string());
+        }
+        part.files.push((rel, content));
+    }
+
+    let _ = cache.save();
+
+    // A part opened for a file that then didn't fit at all stays empty.
+    if parts.len() > 1 && parts.last().is_some_and(|p| p.files.is_empty()) {
+        parts.pop();
+    }
+    let count = parts.len();
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(i, part)| {
+            let map = (i == 0).then_some(repository_map_text.as_str());
+            let preamble = if i == 0 { preamble.as_slice() } else { &[] };
+            let doc = render_slice(
+                cfg,
+                repo_root,
+                preamble,
+                map,
+                &part.files,
+                Some((i + 1, count)),
+            )?;
+            let meta = SliceMeta {
+                repo_root: repo_root.to_path_buf(),
+                target: target.to_path_buf(),
+                budget_tokens,
+                total_tokens: part.tokens,
+                total_files: part.files.len(),
+                total_bytes: part.bytes,
+                dropped: dropped.clone(),
+                redactions: redactions.clone(),
+                fingerprints: part.fingerprints,
+                scan,
+            };
+            Ok((doc, meta))
+        })
+        .collect()
+}
+
+/// Files packed into one part of a split slice so far, with their running totals.
+struct SlicePart {
+    files: Vec<(String, String)>,
+    fingerprints: BTreeMap<String, String>,
+    tokens: usize,
+    bytes: u64,
+}
+
+impl SlicePart {
+    fn new(estimator: &dyn TokenEstimator, base_bytes: u64) -> Self {
+        Self {
+            files: Vec::new(),
+            fingerprints: BTreeMap::new(),
+            tokens: estimator.estimate_bytes(base_bytes, None),
+            bytes: base_bytes,
+        }
+    }
 }
 
 /// Estimate whether this is a "large workspace" by counting top-level manifests
@@ -604,7 +1578,7 @@ fn is_large_workspace(root: &Path) -> bool {
 /// Build `ScanOptions` for a given repo root and target.
 /// Properly handles the case where `target` is a Rust `target/` *inside* a service
 /// by not over-excluding by name, but instead always excluding the root-level `target/`.
-fn build_scan_options(repo_root: &Path, target: &Path, cfg: &Config) -> ScanOptions {
+pub fn build_scan_options(repo_root: &Path, target: &Path, cfg: &Config) -> ScanOptions {
     let mut exclude_dirs = vec![
         ".git".into(),
         "node_modules".into(),
@@ -634,13 +1608,34 @@ fn build_scan_options(repo_root: &Path, target: &Path, cfg: &Config) -> ScanOpti
     }
 
     ScanOptions {
-        repo_root: repo_root.to_path_buf(),
-        target: target.to_path_buf(),
+        extra_targets: cfg.scan.extra_targets.clone(),
         max_file_bytes: cfg.token_estimator.max_file_bytes,
         exclude_dir_names: exclude_dirs,
+        keep_oversized: cfg.token_estimator.oversize_policy != OversizePolicy::Skip,
+        exclude_tests: cfg.scan.exclude_tests,
+        follow_symlinks: cfg.scan.follow_symlinks,
+        include_generated: cfg.scan.include_generated,
+        ..ScanOptions::new(repo_root, target)
     }
 }
 
+/// [`scan_workspace_with_stats`] for a slice. Oversized files are always kept (tagged), so
+/// under the `skip` oversize policy they reach `dropped` as [`DropReason::Oversized`] with a
+/// size-based estimate instead of vanishing in the scan; they still count as skipped by size.
+fn scan_for_slice(opts: ScanOptions, cfg: &Config) -> Result<(Vec<FileEntry>, ScanStats)> {
+    let opts = ScanOptions {
+        keep_oversized: true,
+        ..opts
+    };
+    let (entries, mut scan) = scan_workspace_with_stats(&opts)?;
+    if cfg.token_estimator.oversize_policy == OversizePolicy::Skip {
+        let oversized = entries.iter().filter(|e| e.oversized).count();
+        scan.included -= oversized;
+        scan.skipped_by_size += oversized;
+    }
+    Ok((entries, scan))
+}
+
 /// Huge-codebase mode: discover all workspace members, distribute the token budget
 /// across them proportionally, slice each one, then merge into a single XML.
 ///
@@ -659,12 +1654,13 @@ pub fn slice_to_xml_huge(
     };
 
     let members = discover_workspace_members(repo_root, &discovery_opts)?;
+    let estimator = &HeuristicEstimator::from_config(&cfg.token_estimator);
 
     if members.is_empty() {
         // No sub-projects found; fall back to plain slice.
         let opts = build_scan_options(repo_root, Path::new("."), cfg);
-        let entries = scan_workspace(&opts)?;
-        return build_xml_from_entries(
+        let (entries, scan) = scan_for_slice(opts, cfg)?;
+        let (xml, mut meta) = build_xml_from_entries(
             entries,
             repo_root,
             Path::new("."),
@@ -672,7 +1668,9 @@ pub fn slice_to_xml_huge(
             cfg,
             None,
             skeleton_only,
-        );
+        )?;
+        meta.scan = scan;
+        return Ok((xml, meta));
     }
 
     // Budget per member: divide equally, but floor at min_member_budget.
@@ -688,13 +1686,18 @@ pub fn slice_to_xml_huge(
     let mut all_files: Vec<(String, String)> = Vec::new();
     let mut repo_map_sections: Vec<String> = Vec::new();
     let mut total_bytes: u64 = 64;
+    let mut total_tokens: usize = 0;
+    let mut dropped: Vec<DroppedFile> = Vec::new();
+    let redactor = Redactor::new(&cfg.redaction)?;
+    let mut redactions: Vec<Redaction> = Vec::new();
+    let mut fingerprints: BTreeMap<String, String> = BTreeMap::new();
+    // Member scans only: the root scan walks the whole tree just to keep its top-level files.
+    let mut scan = ScanStats::default();
 
     // ── Root-level context (workspace manifest + README) ─────────────────
     {
         let root_opts = ScanOptions {
-            repo_root: repo_root.to_path_buf(),
-            target: PathBuf::from("."),
-            max_file_bytes: cfg.token_estimator.max_file_bytes,
+            extra_targets: Vec::new(),
             exclude_dir_names: vec![
                 ".git".into(),
                 "node_modules".into(),
@@ -704,6 +1707,9 @@ pub fn slice_to_xml_huge(
                 // Exclude any sub-directories that are workspace members — avoid duplication.
                 // We include at most the top-level files, not the entire sub-dirs.
             ],
+            // Oversized root files go through the oversize policy like member files.
+            keep_oversized: true,
+            ..build_scan_options(repo_root, Path::new("."), cfg)
         };
 
         // Add user-defined excludes.
@@ -726,30 +1732,48 @@ pub fn slice_to_xml_huge(
             let root_section = "# ROOT (workspace root)\n".to_string();
             repo_map_sections.push(root_section);
 
-            let mut root_used: u64 = 0;
+            let mut root_used: usize = 0;
             for e in root_only {
-                if let Ok(bytes) = std::fs::read(&e.abs_path) {
+                let rel = e.rel_path.to_string_lossy().replace('\\', "/");
+                let content = if e.oversized {
+                    match oversized_content(&e, &rel, cfg) {
+                        Some(c) => c,
+                        None => {
+                            dropped.push(DroppedFile::unread(estimator, &e, &rel));
+                            continue;
+                        }
+                    }
+                } else {
+                    let Ok(bytes) = std::fs::read(&e.abs_path) else {
+                        dropped.push(DroppedFile::unread(estimator, &e, &rel));
+                        continue;
+                    };
                     let content_full = String::from_utf8(bytes)
                         .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).to_string());
-                    let rel = e.rel_path.to_string_lossy().replace('\\', "/");
 
-                    let content = if rel.to_lowercase().ends_with("cargo.toml") {
+                    if rel.to_lowercase().ends_with("cargo.toml") {
                         compact_cargo_toml(&content_full).unwrap_or(content_full)
                     } else if rel.to_lowercase().ends_with("package.json") {
                         compact_package_json(&content_full).unwrap_or(content_full)
                     } else {
                         truncate_unknown(&rel, &content_full)
-                    };
-
-                    let overhead = estimate_xml_file_overhead_bytes(&rel);
-                    let added = overhead + content.len() as u64;
-                    if root_used + added > root_budget as u64 * 4 {
-                        break;
                     }
-                    root_used += added;
-                    total_bytes = total_bytes.saturating_add(added);
-                    all_files.push((rel, content));
+                };
+
+                let content = redactor.redact(&rel, content, &mut redactions);
+                let added_tokens = file_tokens(estimator, &rel, &content);
+                if root_used + added_tokens > root_budget {
+                    // Keep going rather than stop at the first misfit: a smaller root file
+                    // may still fit, and each file left out gets its own `dropped` entry.
+                    dropped.push(DroppedFile::over_budget(&rel, added_tokens));
+                    continue;
                 }
+                root_used += added_tokens;
+                total_tokens += added_tokens;
+                total_bytes = total_bytes
+                    .saturating_add(estimate_xml_file_overhead_bytes(&rel))
+                    .saturating_add(content.len() as u64);
+                all_files.push((rel, content));
             }
         }
     }
@@ -757,8 +1781,11 @@ pub fn slice_to_xml_huge(
     // ── Per-member slices ─────────────────────────────────────────────────
     for member in &members {
         let member_opts = build_scan_options(repo_root, Path::new(&member.rel_path), cfg);
-        let mut entries = match scan_workspace(&member_opts) {
-            Ok(e) => e,
+        let mut entries = match scan_for_slice(member_opts, cfg) {
+            Ok((e, member_scan)) => {
+                scan.merge(&member_scan);
+                e
+            }
             Err(_) => continue,
         };
 
@@ -766,17 +1793,14 @@ pub fn slice_to_xml_huge(
             continue;
         }
 
-        // Sort by importance within this member.
-        let indegree = compute_repo_map_indegree(repo_root, Path::new(&member.rel_path));
-        entries.sort_by(|a, b| {
-            let a_rel = a.rel_path.to_string_lossy().replace('\\', "/");
-            let b_rel = b.rel_path.to_string_lossy().replace('\\', "/");
-            let mut a_s = importance_score(&a_rel);
-            let mut b_s = importance_score(&b_rel);
-            a_s += *indegree.get(&a_rel).unwrap_or(&0) as i64 * 10;
-            b_s += *indegree.get(&b_rel).unwrap_or(&0) as i64 * 10;
-            b_s.cmp(&a_s).then_with(|| a_rel.cmp(&b_rel))
-        });
+        // Rank by relevance within this member.
+        rank_entries(
+            repo_root,
+            Path::new(&member.rel_path),
+            cfg,
+            &mut entries,
+            None,
+        );
 
         let section_header = format!("# {} ({})\n", member.name, member.rel_path);
         let section_paths: Vec<String> = entries
@@ -785,44 +1809,68 @@ pub fn slice_to_xml_huge(
             .collect();
         repo_map_sections.push(format!("{}{}", section_header, section_paths.join("\n")));
 
-        let mut member_bytes: u64 = 0;
+        let mut member_tokens: usize = 0;
         for e in entries {
-            let bytes = match std::fs::read(&e.abs_path) {
-                Ok(b) => b,
-                Err(_) => continue,
-            };
-            let content_full = String::from_utf8(bytes)
-                .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).to_string());
             let rel = e.rel_path.to_string_lossy().replace('\\', "/");
-
-            let skeleton_mode = cfg.skeleton_mode || skeleton_only;
-
-            let content = if rel.to_lowercase().ends_with("cargo.toml") {
-                compact_cargo_toml(&content_full).unwrap_or(content_full)
-            } else if rel.to_lowercase().ends_with("package.json") {
-                compact_package_json(&content_full).unwrap_or(content_full)
-            } else if skeleton_mode {
-                match try_render_skeleton_from_source(&e.abs_path, &content_full) {
-                    Ok(Some(s)) => s,
-                    Ok(None) => truncate_unknown(&rel, &content_full),
-                    Err(_) => truncate_unknown(&rel, &content_full),
+            let content = if e.oversized {
+                match oversized_content(&e, &rel, cfg) {
+                    Some(c) => c,
+                    None => {
+                        dropped.push(DroppedFile::unread(estimator, &e, &rel));
+                        continue;
+                    }
                 }
             } else {
-                content_full
+                let bytes = match std::fs::read(&e.abs_path) {
+                    Ok(b) => b,
+                    Err(_) => {
+                        dropped.push(DroppedFile::unread(estimator, &e, &rel));
+                        continue;
+                    }
+                };
+                let content_full = String::from_utf8(bytes)
+                    .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).to_string());
+                let content_full = compressed(cfg, &e.abs_path, content_full);
+
+                let skeleton_mode = cfg.skeleton_mode || cfg.outline_mode || skeleton_only;
+
+                if rel.to_lowercase().ends_with("cargo.toml") {
+                    compact_cargo_toml(&content_full).unwrap_or(content_full)
+                } else if rel.to_lowercase().ends_with("package.json") {
+                    compact_package_json(&content_full).unwrap_or(content_full)
+                } else if skeleton_mode {
+                    condensed_content(cfg, &e.abs_path, &rel, &content_full)
+                } else {
+                    content_full
+                }
             };
 
-            let overhead = estimate_xml_file_overhead_bytes(&rel);
-            let added = overhead + content.len() as u64;
-            let new_member_est = estimate_tokens_from_bytes(
-                member_bytes + added,
-                cfg.token_estimator.chars_per_token,
-            );
-            if new_member_est > per_member_budget {
-                continue;
-            }
+            let content = redactor.redact(&rel, content, &mut redactions);
+            let remaining = per_member_budget.saturating_sub(member_tokens);
+            let (content, added_tokens) = match fit_content(
+                cfg,
+                estimator,
+                &redactor,
+                &e.abs_path,
+                &rel,
+                content,
+                remaining,
+            ) {
+                Ok(fitted) => fitted,
+                Err(est_tokens) => {
+                    dropped.push(DroppedFile::over_budget(&rel, est_tokens));
+                    continue;
+                }
+            };
 
-            member_bytes = member_bytes.saturating_add(added);
-            total_bytes = total_bytes.saturating_add(added);
+            member_tokens += added_tokens;
+            total_tokens += added_tokens;
+            total_bytes = total_bytes
+                .saturating_add(estimate_xml_file_overhead_bytes(&rel))
+                .saturating_add(content.len() as u64);
+            if let Some(fp) = e.fingerprint() {
+                fingerprints.insert(rel.clone(), fp.to_string());
+            }
             all_files.push((rel, content));
         }
     }
@@ -833,12 +1881,21 @@ pub fn slice_to_xml_huge(
         build_repository_map_text_raw(&combined)
     };
 
-    total_bytes = total_bytes
-        .saturating_add(estimate_xml_repository_map_overhead_bytes())
-        .saturating_add(repo_map_text.len() as u64);
-
-    let total_tokens = estimate_tokens_from_bytes(total_bytes, cfg.token_estimator.chars_per_token);
-    let xml = build_context_xml(Some(&repo_map_text), &all_files)?;
+    let preamble = slice_preamble(repo_root, cfg, &[".".to_string()]);
+    let map_bytes = estimate_xml_repository_map_overhead_bytes()
+        + repo_map_text.len() as u64
+        + preamble_bytes(&preamble);
+    total_bytes = total_bytes.saturating_add(map_bytes);
+    // The 64-byte document envelope is counted with the map, as in the other slicers.
+    total_tokens += estimator.estimate_bytes(64 + map_bytes, None);
+    let xml = render_slice(
+        cfg,
+        repo_root,
+        &preamble,
+        Some(&repo_map_text),
+        &all_files,
+        None,
+    )?;
 
     let meta = SliceMeta {
         repo_root: repo_root.to_path_buf(),
@@ -847,6 +1904,10 @@ pub fn slice_to_xml_huge(
         total_tokens,
         total_files: all_files.len(),
         total_bytes,
+        dropped,
+        redactions,
+        fingerprints,
+        scan,
     };
 
     Ok((xml, meta))
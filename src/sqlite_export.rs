@@ -0,0 +1,250 @@
+//! SQLite dump of a module graph and its source files, so codebase structure can be
+//! queried with SQL and joined against other data.
+//!
+//! Tables: `nodes` (modules) and `edges` (weighted module imports), `files` (each linked
+//! to its module), `imports` (file → file) and `symbols` (each linked to its file). List
+//! columns (`owners`, `languages`) hold JSON text, usable with SQLite's `json_each`.
+
+use crate::inspector::Symbol;
+use crate::mapper::{FileRecord, ModuleGraph};
+use crate::markdown_builder::fence_language;
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, Transaction};
+use serde::Serialize;
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE nodes (
+    id TEXT PRIMARY KEY,
+    label TEXT NOT NULL,
+    path TEXT NOT NULL,
+    file_count INTEGER NOT NULL,
+    bytes INTEGER NOT NULL,
+    est_tokens INTEGER NOT NULL,
+    lines INTEGER NOT NULL,
+    symbols INTEGER NOT NULL,
+    avg_file_bytes INTEGER NOT NULL,
+    languages TEXT NOT NULL,
+    importance REAL NOT NULL,
+    cluster INTEGER NOT NULL,
+    commits INTEGER NOT NULL,
+    last_changed INTEGER,
+    owners TEXT NOT NULL
+);
+CREATE TABLE edges (
+    source TEXT NOT NULL REFERENCES nodes(id),
+    target TEXT NOT NULL REFERENCES nodes(id),
+    weight INTEGER NOT NULL,
+    PRIMARY KEY (source, target)
+);
+CREATE TABLE files (
+    path TEXT PRIMARY KEY,
+    module TEXT NOT NULL,
+    language TEXT NOT NULL,
+    bytes INTEGER NOT NULL,
+    lines INTEGER NOT NULL,
+    est_tokens INTEGER NOT NULL
+);
+CREATE TABLE imports (
+    source TEXT NOT NULL REFERENCES files(path),
+    target TEXT NOT NULL,
+    import TEXT NOT NULL
+);
+CREATE TABLE symbols (
+    id INTEGER PRIMARY KEY,
+    file TEXT NOT NULL REFERENCES files(path),
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    parent TEXT,
+    visibility TEXT,
+    line INTEGER NOT NULL,
+    line_end INTEGER NOT NULL,
+    signature TEXT
+);
+CREATE INDEX files_module ON files(module);
+CREATE INDEX imports_target ON imports(target);
+CREATE INDEX symbols_file ON symbols(file);
+CREATE INDEX symbols_name ON symbols(name);
+";
+
+/// Row counts written by [`export_sqlite`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SqliteExportStats {
+    pub nodes: usize,
+    pub edges: usize,
+    pub files: usize,
+    pub imports: usize,
+    pub symbols: usize,
+}
+
+/// Write `graph` and `files` to a fresh database at `out` (an existing file is replaced).
+/// Symbol lines are 0-indexed, as reported by the inspector; struct fields and enum
+/// variants get their own rows with the owning symbol as `parent`.
+pub fn export_sqlite(
+    graph: &ModuleGraph,
+    files: &[FileRecord],
+    out: &Path,
+) -> Result<SqliteExportStats> {
+    if out.exists() {
+        std::fs::remove_file(out)
+            .with_context(|| format!("Failed to replace {}", out.display()))?;
+    }
+    let mut conn = Connection::open(out)
+        .with_context(|| format!("Failed to create database {}", out.display()))?;
+    conn.execute_batch(SCHEMA)?;
+
+    let tx = conn.transaction()?;
+    let stats = write_rows(&tx, graph, files)?;
+    tx.commit()?;
+    Ok(stats)
+}
+
+fn write_rows(
+    tx: &Transaction,
+    graph: &ModuleGraph,
+    files: &[FileRecord],
+) -> Result<SqliteExportStats> {
+    let mut stats = SqliteExportStats::default();
+
+    let mut insert = tx.prepare(
+        "INSERT INTO nodes VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+    )?;
+    for n in &graph.nodes {
+        insert.execute(params![
+            n.id,
+            n.label,
+            n.path,
+            n.file_count,
+            n.bytes,
+            n.est_tokens,
+            n.lines,
+            n.symbols,
+            n.avg_file_bytes,
+            serde_json::to_string(&n.languages)?,
+            n.importance,
+            n.cluster,
+            n.commits,
+            n.last_changed,
+            serde_json::to_string(&n.owners)?,
+        ])?;
+        stats.nodes += 1;
+    }
+
+    let mut insert = tx.prepare("INSERT INTO edges VALUES (?1, ?2, ?3)")?;
+    for e in &graph.edges {
+        insert.execute(params![e.source, e.target, e.weight])?;
+        stats.edges += 1;
+    }
+
+    let mut insert_file = tx.prepare("INSERT INTO files VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+    let mut insert_import = tx.prepare("INSERT INTO imports VALUES (?1, ?2, ?3)")?;
+    let mut insert_symbol = tx.prepare(
+        "INSERT INTO symbols (file, name, kind, parent, visibility, line, line_end, signature) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+    for f in files {
+        insert_file.execute(params![
+            f.path,
+            f.module,
+            fence_language(&f.path),
+            f.bytes,
+            f.lines,
+            f.est_tokens,
+        ])?;
+        stats.files += 1;
+
+        for (import, target) in &f.imports {
+            insert_import.execute(params![f.path, target, import])?;
+            stats.imports += 1;
+        }
+
+        let mut pending: Vec<(&Symbol, Option<&str>)> =
+            f.symbols.iter().map(|s| (s, None)).collect();
+        while let Some((s, owner)) = pending.pop() {
+            insert_symbol.execute(params![
+                f.path,
+                s.name,
+                s.kind,
+                s.parent.as_deref().or(owner),
+                s.visibility,
+                s.line,
+                s.line_end,
+                s.signature,
+            ])?;
+            stats.symbols += 1;
+            pending.extend(s.children.iter().map(|c| (c, Some(s.name.as_str()))));
+        }
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mapper::{build_module_graph, file_records};
+
+    #[test]
+    fn exports_modules_files_imports_and_symbols() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/main.ts"),
+            "import { Point } from './geo';\nexport function run() {}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/geo.ts"),
+            "export interface Point {\n  x: number;\n}\n",
+        )
+        .unwrap();
+
+        let graph = build_module_graph(root, Path::new(".")).unwrap();
+        let files = file_records(root, Path::new("."), &graph).unwrap();
+        let out = root.join("map.sqlite");
+        std::fs::write(&out, "stale").unwrap();
+        let stats = export_sqlite(&graph, &files, &out).unwrap();
+        assert_eq!((stats.files, stats.imports), (2, 1));
+
+        let conn = Connection::open(&out).unwrap();
+        let import: (String, String, String) = conn
+            .query_row("SELECT source, target, import FROM imports", [], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+            })
+            .unwrap();
+        assert_eq!(
+            import,
+            (
+                "src/main.ts".to_string(),
+                "src/geo.ts".to_string(),
+                "./geo".to_string()
+            )
+        );
+        let language: String = conn
+            .query_row(
+                "SELECT language FROM files WHERE path = 'src/geo.ts'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(language, "typescript");
+        let owner: String = conn
+            .query_row(
+                "SELECT s.file FROM symbols s JOIN files f ON f.path = s.file \
+                 WHERE s.name = 'run'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(owner, "src/main.ts");
+        let modules: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM files f JOIN nodes n ON n.id = f.module",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(modules, 2);
+    }
+}
@@ -0,0 +1,50 @@
+use anyhow::Result;
+
+/// Minimal slice rendering: each file's raw content under a `===== path =====` line, after
+/// the preamble sections and the repository map. No escaping or fencing, so it costs the
+/// fewest tokens.
+pub fn build_context_text(
+    preamble: &[(String, String)],
+    repository_map: Option<&str>,
+    files: &[(String, String)],
+) -> Result<String> {
+    let mut out = String::new();
+    let mut push = |title: &str, content: &str| {
+        out.push_str("===== ");
+        out.push_str(title);
+        out.push_str(" =====\n");
+        out.push_str(content.trim_end());
+        out.push_str("\n\n");
+    };
+
+    for (name, text) in preamble {
+        push(name, text);
+    }
+    if let Some(map_text) = repository_map {
+        push("repository map", map_text);
+    }
+    for (path, content) in files {
+        push(path, content);
+    }
+
+    Ok(out.trim_end().to_string() + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_are_separated_by_path_lines() {
+        let files = vec![
+            ("a.rs".to_string(), "fn a() {}\n\n".to_string()),
+            ("b.txt".to_string(), "<raw & unescaped>".to_string()),
+        ];
+        assert_eq!(
+            build_context_text(&[], Some("a.rs\nb.txt\n"), &files).unwrap(),
+            "===== repository map =====\na.rs\nb.txt\n\n\
+             ===== a.rs =====\nfn a() {}\n\n\
+             ===== b.txt =====\n<raw & unescaped>\n"
+        );
+    }
+}
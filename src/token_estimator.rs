@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::TokenEstimatorConfig;
+
+/// Token counting strategy shared by the slicer, mapper, CLI and MCP surfaces.
+///
+/// `lang_hint` is a file extension (e.g. `"py"`); implementations may use it for
+/// per-language calibration and must fall back to a generic estimate when it is `None`.
+pub trait TokenEstimator: Send + Sync {
+    fn estimate_bytes(&self, bytes: u64, lang_hint: Option<&str>) -> usize;
+
+    fn estimate_text(&self, text: &str, lang_hint: Option<&str>) -> usize {
+        self.estimate_bytes(text.len() as u64, lang_hint)
+    }
+}
+
+/// Bytes-divided-by-chars-per-token heuristic with optional per-extension divisors.
+///
+/// Bytes are used as a proxy for chars.
+#[derive(Debug, Clone)]
+pub struct HeuristicEstimator {
+    chars_per_token: f64,
+    per_extension: HashMap<String, f64>,
+}
+
+impl HeuristicEstimator {
+    pub fn new(chars_per_token: f64) -> Self {
+        Self {
+            chars_per_token,
+            per_extension: HashMap::new(),
+        }
+    }
+
+    pub fn from_config(cfg: &TokenEstimatorConfig) -> Self {
+        let per_extension = cfg
+            .per_extension
+            .iter()
+            .filter(|(_, v)| v.is_finite() && **v > 0.0)
+            .map(|(k, v)| (k.trim_start_matches('.').to_ascii_lowercase(), *v))
+            .collect();
+        Self {
+            chars_per_token: cfg.chars_per_token as f64,
+            per_extension,
+        }
+    }
+
+    fn divisor(&self, lang_hint: Option<&str>) -> f64 {
+        lang_hint
+            .and_then(|ext| self.per_extension.get(&ext.to_ascii_lowercase()))
+            .copied()
+            .unwrap_or(self.chars_per_token)
+    }
+}
+
+impl Default for HeuristicEstimator {
+    fn default() -> Self {
+        Self::from_config(&TokenEstimatorConfig::default())
+    }
+}
+
+impl TokenEstimator for HeuristicEstimator {
+    fn estimate_bytes(&self, bytes: u64, lang_hint: Option<&str>) -> usize {
+        let divisor = self.divisor(lang_hint);
+        if divisor <= 0.0 {
+            return bytes as usize;
+        }
+        ((bytes as f64) / divisor).ceil() as usize
+    }
+}
+
+/// The language hint for a path: its extension, as used by `per_extension` divisors.
+pub fn lang_hint(path: &Path) -> Option<&str> {
+    path.extension().and_then(|e| e.to_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn default_matches_four_chars_per_token() {
+        let est = HeuristicEstimator::default();
+        assert_eq!(est.estimate_bytes(400, None), 100);
+        assert_eq!(est.estimate_bytes(401, Some("rs")), 101);
+        assert_eq!(est.estimate_text("abcd", None), 1);
+    }
+
+    #[test]
+    fn per_extension_divisor_overrides_default() {
+        let cfg = TokenEstimatorConfig {
+            per_extension: BTreeMap::from([(".PY".to_string(), 2.0)]),
+            ..TokenEstimatorConfig::default()
+        };
+        let est = HeuristicEstimator::from_config(&cfg);
+        assert_eq!(est.estimate_bytes(400, Some("py")), 200);
+        assert_eq!(est.estimate_bytes(400, Some("PY")), 200);
+        assert_eq!(est.estimate_bytes(400, Some("rs")), 100);
+        assert_eq!(est.estimate_bytes(400, None), 100);
+    }
+
+    #[test]
+    fn zero_divisor_counts_bytes() {
+        assert_eq!(HeuristicEstimator::new(0.0).estimate_bytes(7, None), 7);
+    }
+}
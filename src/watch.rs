@@ -0,0 +1,232 @@
+//! Watch mode: re-slice the active target whenever files under it change.
+//!
+//! Rewrites `{output_dir}/active_context.*`, its meta JSON and `active_repo_map.json` (the
+//! repo map of the target) after each burst of changes, reporting which of them changed.
+//! Re-slices stay cheap because unchanged files are served from the slice cache
+//! (`.context-slicer/slices/`) and the analyze cache instead of being parsed again.
+
+use anyhow::{Context, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::mapper::build_repo_map_multi;
+use crate::slicer::{build_scan_options, slice_to_xml, SliceMeta};
+use crate::token_estimator::{HeuristicEstimator, TokenEstimator};
+
+/// How often the watch loop checks its stop flag while idle.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Quiet period that ends a burst of events (editor saves, `git checkout`, formatters).
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Directories whose changes never affect a slice; most are written by the slicer itself.
+const IGNORED_DIRS: &[&str] = &[".git", ".context-slicer"];
+
+/// What to re-slice and how.
+#[derive(Debug, Clone)]
+pub struct WatchSpec {
+    pub repo_root: PathBuf,
+    /// Target relative to `repo_root`, as for [`slice_to_xml`].
+    pub target: PathBuf,
+    pub budget_tokens: usize,
+    pub cfg: Config,
+    pub skeleton_only: bool,
+}
+
+/// A slice written to the output directory, with the repo map written next to it.
+#[derive(Debug, Clone)]
+pub struct WrittenSlice {
+    pub path: PathBuf,
+    pub doc: String,
+    pub meta: SliceMeta,
+    /// The slice differs from the one previously on disk.
+    pub changed: bool,
+    pub map_path: PathBuf,
+    /// The repo map differs from the one previously on disk (any node or edge field).
+    pub map_changed: bool,
+    /// Files or import edges were added or removed, not just edited.
+    pub structure_changed: bool,
+}
+
+impl WatchSpec {
+    /// `{output_dir}/active_context.<ext>` for the configured slice format.
+    pub fn slice_path(&self) -> PathBuf {
+        self.repo_root.join(&self.cfg.output_dir).join(format!(
+            "active_context.{}",
+            self.cfg.slice_format.extension()
+        ))
+    }
+
+    /// `{output_dir}/active_repo_map.json`.
+    pub fn map_path(&self) -> PathBuf {
+        self.repo_root
+            .join(&self.cfg.output_dir)
+            .join("active_repo_map.json")
+    }
+}
+
+/// Slice the target and write `active_context.*` plus `active_context.meta.json`, then the
+/// repo map of the target (its directory, for a file) and any `scan.extra_targets`.
+pub fn write_active_slice(spec: &WatchSpec) -> Result<WrittenSlice> {
+    let (doc, meta) = slice_to_xml(
+        &spec.repo_root,
+        &spec.target,
+        spec.budget_tokens,
+        &spec.cfg,
+        spec.skeleton_only,
+    )?;
+    let path = spec.slice_path();
+    let out_dir = path.parent().context("Slice path has no parent")?;
+    std::fs::create_dir_all(out_dir)?;
+    let changed = std::fs::read_to_string(&path).ok().as_deref() != Some(doc.as_str());
+    std::fs::write(&path, &doc)?;
+
+    let total_tokens =
+        HeuristicEstimator::from_config(&spec.cfg.token_estimator).estimate_text(&doc, None);
+    let meta_json = json!({
+        "repoRoot": spec.repo_root.to_string_lossy(),
+        "target": spec.target.to_string_lossy(),
+        "budgetTokens": spec.budget_tokens,
+        "totalTokens": total_tokens,
+        "totalChars": doc.len(),
+        "dropped": meta.dropped,
+        "redactions": meta.redactions,
+        "fingerprints": meta.fingerprints,
+        "scan": meta.scan
+    });
+    std::fs::write(
+        out_dir.join("active_context.meta.json"),
+        serde_json::to_vec_pretty(&meta_json)?,
+    )?;
+
+    let map_path = spec.map_path();
+    let mut scopes = vec![map_scope(spec)];
+    scopes.extend(spec.cfg.scan.extra_targets.iter().cloned());
+    let map = serde_json::to_value(build_repo_map_multi(
+        &spec.repo_root,
+        &scopes,
+        spec.cfg.scan.exclude_tests,
+    )?)?;
+    let previous: Option<serde_json::Value> = std::fs::read(&map_path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+    let map_changed = previous.as_ref() != Some(&map);
+    let structure_changed = previous.as_ref().map(structure) != Some(structure(&map));
+    if map_changed {
+        std::fs::write(&map_path, serde_json::to_vec_pretty(&map)?)?;
+    }
+
+    Ok(WrittenSlice {
+        path,
+        doc,
+        meta,
+        changed,
+        map_path,
+        map_changed,
+        structure_changed,
+    })
+}
+
+/// The directory the repo map covers: the target, or its parent for a file target.
+fn map_scope(spec: &WatchSpec) -> PathBuf {
+    if spec.repo_root.join(&spec.target).is_file() {
+        spec.target
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .map_or_else(|| PathBuf::from("."), |p| p.to_path_buf())
+    } else {
+        spec.target.clone()
+    }
+}
+
+/// Node and edge ids of a serialized repo map, which only change when files or imports
+/// come or go.
+fn structure(map: &serde_json::Value) -> Vec<&str> {
+    ["nodes", "edges"]
+        .iter()
+        .filter_map(|key| map.get(key).and_then(|v| v.as_array()))
+        .flatten()
+        .filter_map(|item| item.get("id").and_then(|id| id.as_str()))
+        .collect()
+}
+
+/// Block until `stop` is set, re-slicing the target after each burst of changes under it
+/// or under one of its `scan.extra_targets`.
+///
+/// `on_update` receives the outcome of every re-slice; a failed slice does not end the watch.
+/// The initial slice is not written here — call [`write_active_slice`] first.
+pub fn watch_slice(
+    spec: &WatchSpec,
+    stop: &AtomicBool,
+    on_update: &mut dyn FnMut(Result<WrittenSlice>),
+) -> Result<()> {
+    let roots = build_scan_options(&spec.repo_root, &spec.target, &spec.cfg)
+        .target_roots()
+        .into_iter()
+        .map(|root| {
+            root.canonicalize()
+                .with_context(|| format!("Failed to resolve watch target {}", root.display()))
+        })
+        .collect::<Result<Vec<PathBuf>>>()?;
+    let repo_root = spec
+        .repo_root
+        .canonicalize()
+        .unwrap_or_else(|_| spec.repo_root.clone());
+    let ignored: Vec<PathBuf> = IGNORED_DIRS
+        .iter()
+        .map(|d| repo_root.join(d))
+        .chain(std::iter::once(repo_root.join(&spec.cfg.output_dir)))
+        .collect();
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: RecommendedWatcher = notify::recommended_watcher(tx)?;
+    for root in &roots {
+        // Editors save by renaming over the file, which a watch on the file itself would lose.
+        let (watch_root, mode) = if root.is_dir() {
+            (root.as_path(), RecursiveMode::Recursive)
+        } else {
+            (root.parent().unwrap_or(root), RecursiveMode::NonRecursive)
+        };
+        watcher
+            .watch(watch_root, mode)
+            .with_context(|| format!("Failed to watch {}", watch_root.display()))?;
+    }
+
+    let relevant = |event: &notify::Event| {
+        !matches!(event.kind, EventKind::Access(_))
+            && event.paths.iter().any(|p| {
+                roots.iter().any(|root| p.starts_with(root))
+                    && !ignored.iter().any(|d| p.starts_with(d))
+            })
+    };
+
+    while !stop.load(Ordering::Relaxed) {
+        let event = match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(Ok(event)) => event,
+            Ok(Err(e)) => {
+                crate::debug_log!("[cortexast] watch error: {e}");
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+        if !relevant(&event) {
+            continue;
+        }
+        drain_burst(&rx);
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+        on_update(write_active_slice(spec));
+    }
+    Ok(())
+}
+
+/// Swallow events until none arrives for [`DEBOUNCE`].
+fn drain_burst(rx: &mpsc::Receiver<notify::Result<notify::Event>>) {
+    while rx.recv_timeout(DEBOUNCE).is_ok() {}
+}
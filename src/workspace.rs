@@ -2,7 +2,7 @@
 //!
 //! Handles:
 //!  - Cargo workspace members (Cargo.toml `[workspace] members = [...]`)
-//!  - npm/pnpm/yarn workspaces (package.json `"workspaces": [...]`)
+//!  - npm/pnpm/yarn workspaces (package.json `"workspaces": [...]`, `pnpm-workspace.yaml`)
 //!  - Auto-detected sub-projects (any sub-dir that contains its own manifest)
 //!  - Double / triple nested microservices (e.g. `services/foo/bar/Cargo.toml`)
 //!
@@ -73,6 +73,77 @@ fn parse_cargo_workspace_members(cargo_toml: &Path) -> Vec<String> {
         .unwrap_or_default()
 }
 
+/// Directories of the local crates a `Cargo.toml` depends on via `path = "..."`.
+///
+/// Covers `[dependencies]`, `[dev-dependencies]`, `[build-dependencies]` and their
+/// `[target.'cfg(..)'.*]` forms. `{ workspace = true }` entries are looked up in the
+/// `[workspace.dependencies]` of the nearest ancestor workspace that lists `crate_dir`
+/// as a member (or is `crate_dir` itself). Paths are joined, not canonicalized.
+pub fn cargo_path_dependencies(crate_dir: &Path) -> Vec<PathBuf> {
+    let Some(manifest) = read_toml(&crate_dir.join("Cargo.toml")) else {
+        return vec![];
+    };
+
+    let mut tables: Vec<&toml::value::Table> = dependency_tables(&manifest).collect();
+    if let Some(targets) = manifest.get("target").and_then(|t| t.as_table()) {
+        tables.extend(targets.values().flat_map(dependency_tables));
+    }
+
+    let mut workspace: Option<Option<(PathBuf, toml::Value)>> = None;
+    let mut out: Vec<PathBuf> = Vec::new();
+    for (name, spec) in tables.into_iter().flatten() {
+        let dir = if let Some(path) = spec.get("path").and_then(|p| p.as_str()) {
+            crate_dir.join(path)
+        } else if spec.get("workspace").and_then(|w| w.as_bool()) == Some(true) {
+            let ws = workspace.get_or_insert_with(|| enclosing_cargo_workspace(crate_dir));
+            let Some((ws_root, ws_manifest)) = ws else {
+                continue;
+            };
+            let Some(path) = ws_manifest
+                .get("workspace")
+                .and_then(|w| w.get("dependencies"))
+                .and_then(|d| d.get(name))
+                .and_then(|d| d.get("path"))
+                .and_then(|p| p.as_str())
+            else {
+                continue;
+            };
+            ws_root.join(path)
+        } else {
+            continue;
+        };
+        if !out.contains(&dir) {
+            out.push(dir);
+        }
+    }
+    out
+}
+
+fn dependency_tables(v: &toml::Value) -> impl Iterator<Item = &toml::value::Table> {
+    ["dependencies", "dev-dependencies", "build-dependencies"]
+        .into_iter()
+        .filter_map(|key| v.get(key)?.as_table())
+}
+
+fn read_toml(path: &Path) -> Option<toml::Value> {
+    std::fs::read_to_string(path).ok()?.parse().ok()
+}
+
+/// Nearest ancestor `[workspace]` manifest whose `members` cover `crate_dir`.
+fn enclosing_cargo_workspace(crate_dir: &Path) -> Option<(PathBuf, toml::Value)> {
+    crate_dir.ancestors().find_map(|dir| {
+        let manifest = read_toml(&dir.join("Cargo.toml"))?;
+        manifest.get("workspace")?;
+        let rel = crate_dir.strip_prefix(dir).ok()?;
+        let is_member = rel.as_os_str().is_empty()
+            || parse_cargo_workspace_members(&dir.join("Cargo.toml"))
+                .iter()
+                .filter_map(|m| Pattern::new(m.trim_end_matches('/')).ok())
+                .any(|m| m.matches_path(rel));
+        is_member.then(|| (dir.to_path_buf(), manifest))
+    })
+}
+
 /// Parse npm/yarn/pnpm `workspaces` field from `package.json`.
 /// Supports both array form and `{"workspaces": {"packages": [...]}}`.
 fn parse_npm_workspace_members(package_json: &Path) -> Vec<String> {
@@ -101,6 +172,61 @@ fn parse_npm_workspace_members(package_json: &Path) -> Vec<String> {
         .collect()
 }
 
+/// Parse the `packages` list from a `pnpm-workspace.yaml`. Exclusions (`!**/test`) are dropped.
+fn parse_pnpm_workspace_members(pnpm_yaml: &Path) -> Vec<String> {
+    let Ok(text) = std::fs::read_to_string(pnpm_yaml) else {
+        return vec![];
+    };
+    let Ok(v) = serde_yaml::from_str::<serde_yaml::Value>(&text) else {
+        return vec![];
+    };
+    v.get("packages")
+        .and_then(|p| p.as_sequence())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str())
+                .filter(|s| !s.starts_with('!'))
+                .map(|s| s.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// npm/yarn/pnpm workspace packages declared at `root`, as (package name, directory) pairs.
+///
+/// Unlike member discovery, globs are expanded as written (`packages/*` matches each
+/// package dir) and only directories with a named `package.json` count.
+pub fn npm_workspace_packages(root: &Path) -> Vec<(String, PathBuf)> {
+    let mut patterns = parse_npm_workspace_members(&root.join("package.json"));
+    patterns.extend(parse_pnpm_workspace_members(
+        &root.join("pnpm-workspace.yaml"),
+    ));
+
+    let mut out: Vec<(String, PathBuf)> = Vec::new();
+    for pat in patterns {
+        let abs_pattern = root
+            .join(pat.trim_end_matches('/'))
+            .to_string_lossy()
+            .to_string();
+        let Ok(paths) = glob::glob(&abs_pattern) else {
+            continue;
+        };
+        for dir in paths.flatten().filter(|p| p.is_dir()) {
+            let name = std::fs::read_to_string(dir.join("package.json"))
+                .ok()
+                .and_then(|t| serde_json::from_str::<serde_json::Value>(&t).ok())
+                .and_then(|v| v.get("name")?.as_str().map(|s| s.to_string()));
+            if let Some(name) = name {
+                if !out.iter().any(|(_, d)| d == &dir) {
+                    out.push((name, dir));
+                }
+            }
+        }
+    }
+    out.sort();
+    out
+}
+
 /// Resolve glob patterns (like `"services/*"`) relative to `root`, returning
 /// all matching subdirectory paths that also contain a manifest file.
 fn resolve_workspace_globs(root: &Path, patterns: &[String]) -> Vec<PathBuf> {
@@ -338,8 +464,11 @@ pub fn discover_workspace_members(
     }
 
     let root_pkg = root.join("package.json");
-    if root_pkg.exists() {
-        let globs = parse_npm_workspace_members(&root_pkg);
+    if root_pkg.exists() || root.join("pnpm-workspace.yaml").exists() {
+        let mut globs = parse_npm_workspace_members(&root_pkg);
+        globs.extend(parse_pnpm_workspace_members(
+            &root.join("pnpm-workspace.yaml"),
+        ));
         if !globs.is_empty() {
             for p in resolve_workspace_globs(root, &globs) {
                 if !seen.contains(&p) {
@@ -1,7 +1,13 @@
+use crate::analyze_cache::analyze_file_cached;
+use crate::config::{XmlMetadataConfig, ABSOLUTE_MAX_FILE_BYTES};
+use crate::markdown_builder::fence_language;
+use crate::token_estimator::{lang_hint, TokenEstimator};
 use anyhow::Result;
 use quick_xml::events::{BytesCData, BytesDecl, BytesEnd, BytesStart, Event};
 use quick_xml::Writer;
+use sha2::{Digest, Sha256};
 use std::io::Cursor;
+use std::path::Path;
 
 fn crunch_text_for_cdata(input: &str) -> String {
     // 1) Trim trailing whitespace on each line.
@@ -37,17 +43,38 @@ fn crunch_text_for_cdata(input: &str) -> String {
     out.trim_end().to_string()
 }
 
+/// The `<cortexast>` slice document. `metadata` selects the optional attributes and
+/// `<symbols>` child added to each `<file>`; `repo_root` and `estimator` are only used
+/// for those. Each `(name, text)` of `preamble` becomes a `<name>` element ahead of the
+/// repository map. `part` is `(part, parts)` (1-based) for one part of a split slice,
+/// recorded as attributes of the root element.
 pub fn build_context_xml(
+    repo_root: &Path,
+    estimator: &dyn TokenEstimator,
+    metadata: &XmlMetadataConfig,
+    preamble: &[(String, String)],
     repository_map: Option<&str>,
     files: &[(String, String)],
+    part: Option<(usize, usize)>,
 ) -> Result<String> {
     let mut writer = Writer::new(Cursor::new(Vec::new()));
 
     writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
 
-    let root = BytesStart::new("cortexast");
+    let mut root = BytesStart::new("cortexast");
+    if let Some((part, parts)) = part {
+        root.push_attribute(("part", part.to_string().as_str()));
+        root.push_attribute(("parts", parts.to_string().as_str()));
+    }
     writer.write_event(Event::Start(root))?;
 
+    for (name, text) in preamble {
+        writer.write_event(Event::Start(BytesStart::new(name.as_str())))?;
+        let text = crunch_text_for_cdata(text);
+        writer.write_event(Event::CData(BytesCData::new(text.as_str())))?;
+        writer.write_event(Event::End(BytesEnd::new(name.as_str())))?;
+    }
+
     if let Some(map_text) = repository_map {
         let map_el = BytesStart::new("repository_map");
         writer.write_event(Event::Start(map_el))?;
@@ -57,12 +84,28 @@ pub fn build_context_xml(
     }
 
     for (path, content) in files {
+        let content = crunch_text_for_cdata(content.as_str());
         let mut file_el = BytesStart::new("file");
         file_el.push_attribute(("path", path.as_str()));
+        if metadata.language && !fence_language(path).is_empty() {
+            file_el.push_attribute(("language", fence_language(path)));
+        }
+        if metadata.est_tokens {
+            let tokens = estimator.estimate_text(&content, lang_hint(Path::new(path)));
+            file_el.push_attribute(("est_tokens", tokens.to_string().as_str()));
+        }
+        if metadata.sha256 {
+            if let Some(digest) = file_sha256(&repo_root.join(path)) {
+                file_el.push_attribute(("sha256", digest.as_str()));
+            }
+        }
         writer.write_event(Event::Start(file_el))?;
 
+        if metadata.symbols {
+            write_symbols(&mut writer, repo_root, path)?;
+        }
+
         // Write CDATA content.
-        let content = crunch_text_for_cdata(content.as_str());
         writer.write_event(Event::CData(BytesCData::new(content.as_str())))?;
         writer.write_event(Event::End(BytesEnd::new("file")))?;
     }
@@ -72,3 +115,110 @@ pub fn build_context_xml(
     let bytes = writer.into_inner().into_inner();
     Ok(String::from_utf8(bytes)?)
 }
+
+/// Hex SHA-256 of a file on disk, streamed so oversized files are never loaded whole.
+fn file_sha256(path: &Path) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+/// `<symbols>` summary of a file on disk; nothing when it can't be analyzed or is past
+/// the parse cap (oversized files kept as a head are never parsed in full).
+fn write_symbols(writer: &mut Writer<Cursor<Vec<u8>>>, repo_root: &Path, path: &str) -> Result<()> {
+    let too_big = std::fs::metadata(repo_root.join(path))
+        .map(|m| m.len() > ABSOLUTE_MAX_FILE_BYTES)
+        .unwrap_or(true);
+    if too_big {
+        return Ok(());
+    }
+    let Ok(analyzed) = analyze_file_cached(repo_root, Path::new(path)) else {
+        return Ok(());
+    };
+    if analyzed.symbols.is_empty() {
+        return Ok(());
+    }
+    writer.write_event(Event::Start(BytesStart::new("symbols")))?;
+    for symbol in &analyzed.symbols {
+        let mut el = BytesStart::new("symbol");
+        el.push_attribute(("name", symbol.name.as_str()));
+        el.push_attribute(("kind", symbol.kind.as_str()));
+        el.push_attribute(("line", symbol.line.to_string().as_str()));
+        el.push_attribute(("line_end", symbol.line_end.to_string().as_str()));
+        writer.write_event(Event::Empty(el))?;
+    }
+    writer.write_event(Event::End(BytesEnd::new("symbols")))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token_estimator::HeuristicEstimator;
+
+    #[test]
+    fn metadata_is_opt_in_per_attribute() {
+        let tmp = tempfile::tempdir().unwrap();
+        let source = "pub fn alpha() {}\n";
+        std::fs::write(tmp.path().join("lib.rs"), source).unwrap();
+        let files = vec![("lib.rs".to_string(), source.to_string())];
+        let estimator = HeuristicEstimator::new(4.0);
+
+        let plain = build_context_xml(
+            tmp.path(),
+            &estimator,
+            &XmlMetadataConfig::default(),
+            &[],
+            None,
+            &files,
+            None,
+        )
+        .unwrap();
+        assert!(plain.contains("<file path=\"lib.rs\"><![CDATA["));
+
+        let all = XmlMetadataConfig {
+            language: true,
+            est_tokens: true,
+            sha256: true,
+            symbols: true,
+        };
+        let preamble = vec![("memory".to_string(), "- decided X".to_string())];
+        let xml =
+            build_context_xml(tmp.path(), &estimator, &all, &preamble, None, &files, None).unwrap();
+        assert!(xml.contains("<cortexast><memory><![CDATA[- decided X]]></memory><file "));
+        let digest = format!("{:x}", Sha256::digest(source.as_bytes()));
+        assert!(xml.contains(&format!(
+            "<file path=\"lib.rs\" language=\"rust\" est_tokens=\"5\" sha256=\"{digest}\">"
+        )));
+        assert!(xml.contains(
+            "<symbols><symbol name=\"alpha\" kind=\"function\" line=\"0\" line_end=\"0\"/></symbols><![CDATA["
+        ));
+    }
+
+    #[test]
+    fn oversized_files_are_hashed_but_not_parsed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let source = "pub fn alpha() {}\n".repeat(ABSOLUTE_MAX_FILE_BYTES as usize / 16);
+        std::fs::write(tmp.path().join("big.rs"), &source).unwrap();
+        let files = vec![("big.rs".to_string(), "pub fn alpha() {}\n".to_string())];
+        let metadata = XmlMetadataConfig {
+            sha256: true,
+            symbols: true,
+            ..XmlMetadataConfig::default()
+        };
+        let xml = build_context_xml(
+            tmp.path(),
+            &HeuristicEstimator::new(4.0),
+            &metadata,
+            &[],
+            None,
+            &files,
+            None,
+        )
+        .unwrap();
+        let digest = format!("{:x}", Sha256::digest(source.as_bytes()));
+        assert!(xml.contains(&format!("sha256=\"{digest}\"")));
+        assert!(!xml.contains("<symbols>"));
+    }
+}
@@ -0,0 +1,59 @@
+//! # Binary Detection Test
+//!
+//! Scans a fixture mixing text files with binary-ish ones that carry source extensions and
+//! checks that only the text files are kept.
+//!
+//! Run with:
+//! ```
+//! cargo test --test binary_detection
+//! ```
+
+use cortexast::scanner::{binary_reason, scan_workspace, ScanOptions};
+
+#[test]
+fn binary_content_is_skipped_whatever_the_extension() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::write(root.join("app.ts"), "export const app = 1;\n").unwrap();
+    std::fs::write(
+        root.join("notes.md"),
+        "Привет, 世界 — unicode text.\n".repeat(400),
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("blob.ts"),
+        b"export const x = \"\x00\x01\x02\";\n",
+    )
+    .unwrap();
+    // xorshift noise, standing in for compressed or encrypted data.
+    let mut state: u32 = 0x9e37_79b9;
+    let noise: Vec<u8> = (0..4096)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state % 255) as u8 + 1
+        })
+        .collect();
+    std::fs::write(root.join("data.json"), &noise).unwrap();
+    std::fs::write(root.join("bundle.js"), "var a=1;".repeat(2048)).unwrap();
+
+    assert_eq!(binary_reason(&root.join("app.ts")), None);
+    assert_eq!(
+        binary_reason(&root.join("blob.ts")),
+        Some("binary file (NUL bytes)")
+    );
+    assert!(binary_reason(&root.join("data.json")).is_some());
+    assert_eq!(
+        binary_reason(&root.join("bundle.js")),
+        Some("generated file (no line breaks)")
+    );
+
+    let opts = ScanOptions::new(root, ".");
+    let kept: Vec<String> = scan_workspace(&opts)
+        .unwrap()
+        .into_iter()
+        .map(|e| e.rel_path.to_string_lossy().to_string())
+        .collect();
+    assert_eq!(kept, ["app.ts", "notes.md"]);
+}
@@ -0,0 +1,115 @@
+//! # Budget Overflow Policy Test
+//!
+//! Slices a fixture repo whose large file cannot fit in the budget under each
+//! `budget_overflow` policy ("skip", "head", "outline_head").
+//!
+//! Run with:
+//! ```
+//! cargo test --test budget_overflow
+//! ```
+
+use cortexast::config::{Config, OverflowPolicy, TokenEstimatorConfig};
+use cortexast::slicer::{slice_to_xml, DropReason, DroppedFile};
+use std::path::Path;
+
+fn fixture() -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    let big: String = (0..400)
+        .map(|i| format!("export function handler{i}(x: number): number {{ return x + {i}; }}\n"))
+        .collect();
+    std::fs::write(tmp.path().join("big.ts"), big).unwrap();
+    tmp
+}
+
+fn config(policy: OverflowPolicy) -> Config {
+    let defaults = Config::default();
+    Config {
+        skeleton_mode: false,
+        token_estimator: TokenEstimatorConfig {
+            budget_overflow: policy,
+            overflow_head_lines: 10,
+            ..defaults.token_estimator.clone()
+        },
+        ..defaults
+    }
+}
+
+fn slice(dir: &Path, policy: OverflowPolicy, budget: usize) -> (String, Vec<DroppedFile>) {
+    let (xml, meta) = slice_to_xml(dir, Path::new("."), budget, &config(policy), false).unwrap();
+    (xml, meta.dropped)
+}
+
+#[test]
+fn skip_leaves_over_budget_files_out() {
+    let tmp = fixture();
+    let (xml, dropped) = slice(tmp.path(), OverflowPolicy::Skip, 2_000);
+    assert!(!xml.contains("<file path=\"big.ts\""));
+    assert_eq!(dropped.len(), 1);
+    assert_eq!(dropped[0].path, "big.ts");
+    assert_eq!(dropped[0].reason, DropReason::OverBudget);
+    assert!(dropped[0].est_tokens > 2_000);
+
+    let report = serde_json::to_value(&dropped).unwrap();
+    assert_eq!(report[0]["reason"], "over_budget");
+    assert_eq!(report[0]["estTokens"], dropped[0].est_tokens);
+}
+
+#[test]
+fn head_keeps_leading_lines_with_a_marker() {
+    let tmp = fixture();
+    let (xml, dropped) = slice(tmp.path(), OverflowPolicy::Head, 2_000);
+    assert!(dropped.is_empty());
+    assert!(xml.contains("export function handler9("));
+    assert!(!xml.contains("export function handler10("));
+    assert!(xml.contains("truncated to fit the budget: first 10 of 400 lines"));
+
+    // Fewer lines when even ten don't fit.
+    let (xml, _) = slice(tmp.path(), OverflowPolicy::Head, 200);
+    assert!(xml.contains("export function handler0("));
+    assert!(!xml.contains("export function handler9("));
+}
+
+#[test]
+fn outline_head_lists_symbols_before_the_head() {
+    let tmp = fixture();
+    let (xml, _) = slice(tmp.path(), OverflowPolicy::OutlineHead, 6_000);
+    let outline = xml.find("outline of over-budget file").expect("outline");
+    let last_symbol = xml.find("L400: ").expect("every symbol outlined");
+    let head = xml.find("export function handler0(").unwrap();
+    assert!(outline < last_symbol && last_symbol < head);
+
+    // Too tight for the outline: falls back to the plain head.
+    let (xml, _) = slice(tmp.path(), OverflowPolicy::OutlineHead, 2_000);
+    assert!(!xml.contains("outline of over-budget file"));
+    assert!(xml.contains("export function handler0("));
+}
+
+#[test]
+fn outline_head_redacts_secrets_in_the_outline() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut src: String = (0..400)
+        .map(|i| {
+            format!("def handler{i}(value, scale):\n    return value * scale + {i}  # offset\n\n")
+        })
+        .collect();
+    src.push_str("API_KEY = \"sk-abcdefghijklmnopqrstuvwxyz0123\"\n");
+    std::fs::write(tmp.path().join("big.py"), src).unwrap();
+
+    let (xml, meta) = slice_to_xml(
+        tmp.path(),
+        Path::new("."),
+        6_000,
+        &config(OverflowPolicy::OutlineHead),
+        false,
+    )
+    .unwrap();
+    assert!(xml.contains("outline of over-budget file"));
+    assert!(!xml.contains("sk-abcdefghijklmnopqrstuvwxyz0123"));
+    assert!(xml.contains("API_KEY = \"[REDACTED:"));
+    // Counted once, with the file content, not again for the outline.
+    assert!(!meta.redactions.is_empty());
+    assert!(meta
+        .redactions
+        .iter()
+        .all(|r| r.path == "big.py" && r.count == 1));
+}
@@ -0,0 +1,56 @@
+//! # Docs-First Packing Test
+//!
+//! Slices a fixture's `src` directory with `packing: "docs_first"` and checks that its
+//! READMEs, architecture notes and `docs/` pages are packed ahead of the source files,
+//! shallowest first.
+//!
+//! Run with:
+//! ```
+//! cargo test --test docs_first
+//! ```
+
+use cortexast::config::{Config, PackingStrategy};
+use cortexast::slicer::slice_to_xml;
+use std::path::Path;
+
+fn file_order(xml: &str) -> Vec<String> {
+    xml.match_indices("<file path=\"")
+        .map(|(at, m)| xml[at + m.len()..].split('"').next().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn orientation_docs_are_packed_before_sources() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::create_dir_all(root.join("src/net")).unwrap();
+    std::fs::create_dir_all(root.join("src/docs")).unwrap();
+    std::fs::write(root.join("src/main.rs"), "mod net;\nfn main() {}\n").unwrap();
+    std::fs::write(root.join("src/net/mod.rs"), "pub fn connect() {}\n").unwrap();
+    std::fs::write(root.join("src/README.md"), "# App\n").unwrap();
+    std::fs::write(root.join("src/ARCHITECTURE.md"), "# Layers\n").unwrap();
+    std::fs::write(root.join("src/net/README.md"), "# Networking\n").unwrap();
+    std::fs::write(root.join("src/docs/guide.md"), "# Guide\n").unwrap();
+    std::fs::write(root.join("src/notes.md"), "scratch\n").unwrap();
+
+    let cfg = Config {
+        skeleton_mode: false,
+        packing: PackingStrategy::DocsFirst,
+        ..Config::default()
+    };
+    let (xml, _meta) = slice_to_xml(root, Path::new("src"), 100_000, &cfg, false).unwrap();
+    let order = file_order(&xml);
+    let mut top: Vec<&str> = order[..2].iter().map(String::as_str).collect();
+    top.sort();
+    assert_eq!(top, ["src/ARCHITECTURE.md", "src/README.md"]);
+    assert_eq!(order[2..4], ["src/net/README.md", "src/docs/guide.md"]);
+    assert_eq!(order.len(), 7);
+
+    // Relevance packing starts with source.
+    let cfg = Config {
+        skeleton_mode: false,
+        ..Config::default()
+    };
+    let (xml, _meta) = slice_to_xml(root, Path::new("src"), 100_000, &cfg, false).unwrap();
+    assert!(file_order(&xml)[0].ends_with(".rs"));
+}
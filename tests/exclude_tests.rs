@@ -0,0 +1,85 @@
+//! # Exclude-Tests Toggle Test
+//!
+//! Slices and maps a fixture with `scan.exclude_tests` set and checks that test files and
+//! test directories are left out while production code is kept.
+//!
+//! Run with:
+//! ```
+//! cargo test --test exclude_tests
+//! ```
+
+use cortexast::config::{Config, ScanConfig};
+use cortexast::mapper::build_repo_map_filtered;
+use cortexast::scanner::is_test_path;
+use cortexast::slicer::slice_to_xml;
+use std::path::Path;
+
+#[test]
+fn recognizes_common_test_file_conventions() {
+    for path in [
+        "tests/e2e.rs",
+        "src/__tests__/app.tsx",
+        "spec/models/user_spec.rb",
+        "src/parser_test.rs",
+        "pkg/server_test.go",
+        "web/app.spec.ts",
+        "web/button.test.jsx",
+        "lib/test_utils.py",
+        "src/main/java/FooTest.java",
+    ] {
+        assert!(is_test_path(path), "{path}");
+    }
+    for path in [
+        "src/lib.rs",
+        "src/testing.rs",
+        "src/contest/main.rs",
+        "types/index.d.ts",
+        "lib/utils_test_data.json",
+        "src/Testable.java",
+    ] {
+        assert!(!is_test_path(path), "{path}");
+    }
+}
+
+#[test]
+fn excluded_tests_are_left_out_of_slices_and_maps() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::create_dir_all(root.join("src/__tests__")).unwrap();
+    std::fs::create_dir_all(root.join("tests")).unwrap();
+    std::fs::write(root.join("src/app.ts"), "export const app = 1;\n").unwrap();
+    std::fs::write(root.join("src/app.spec.ts"), "it('works', () => {});\n").unwrap();
+    std::fs::write(root.join("src/__tests__/deep.ts"), "export {};\n").unwrap();
+    std::fs::write(root.join("tests/e2e.ts"), "export {};\n").unwrap();
+
+    let cfg = Config {
+        skeleton_mode: false,
+        scan: ScanConfig {
+            exclude_tests: true,
+            ..ScanConfig::default()
+        },
+        ..Config::default()
+    };
+    let (xml, _meta) = slice_to_xml(root, Path::new("."), 100_000, &cfg, false).unwrap();
+    assert!(xml.contains("src/app.ts"));
+    for test_file in ["app.spec.ts", "deep.ts", "e2e.ts"] {
+        assert!(!xml.contains(test_file), "{test_file} should be excluded");
+    }
+
+    // Tests are sliced by default.
+    let (xml, _meta) =
+        slice_to_xml(root, Path::new("."), 100_000, &Config::default(), false).unwrap();
+    assert!(xml.contains("src/app.spec.ts"));
+
+    let ids = |exclude_tests: bool, scope: &str| -> Vec<String> {
+        build_repo_map_filtered(root, Path::new(scope), exclude_tests)
+            .unwrap()
+            .nodes
+            .into_iter()
+            .map(|n| n.id)
+            .collect()
+    };
+    assert!(ids(false, ".").contains(&"tests".to_string()));
+    assert!(!ids(true, ".").contains(&"tests".to_string()));
+    assert_eq!(ids(true, "src"), vec!["src", "src/app.ts"]);
+}
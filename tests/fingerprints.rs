@@ -0,0 +1,45 @@
+//! # File Fingerprint Test
+//!
+//! Scans, slices and maps a fixture and checks that the scanner's content fingerprint is the
+//! one reported by slice metadata and map nodes, and that it follows edits.
+//!
+//! Run with:
+//! ```
+//! cargo test --test fingerprints
+//! ```
+
+use cortexast::config::Config;
+use cortexast::mapper::build_repo_map_filtered;
+use cortexast::scanner::{scan_workspace, ScanOptions};
+use cortexast::slicer::slice_to_xml;
+use std::path::Path;
+
+fn scanned_fingerprint(root: &Path, rel: &str) -> String {
+    let opts = ScanOptions::new(root, rel);
+    let entries = scan_workspace(&opts).unwrap();
+    entries[0].fingerprint().unwrap().to_string()
+}
+
+#[test]
+fn scan_slice_and_map_share_one_fingerprint() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/lib.rs"), "pub fn lib() {}\n").unwrap();
+
+    let fp = scanned_fingerprint(root, "src/lib.rs");
+    assert_eq!(fp, blake3::hash(b"pub fn lib() {}\n").to_hex().to_string());
+
+    let (_xml, meta) =
+        slice_to_xml(root, Path::new("src"), 100_000, &Config::default(), false).unwrap();
+    assert_eq!(meta.fingerprints.get("src/lib.rs"), Some(&fp));
+
+    let map = build_repo_map_filtered(root, Path::new("src"), false).unwrap();
+    let node = map.nodes.iter().find(|n| n.id == "src/lib.rs").unwrap();
+    assert_eq!(node.fingerprint.as_ref(), Some(&fp));
+    let dir = map.nodes.iter().find(|n| n.kind == "directory").unwrap();
+    assert!(dir.fingerprint.is_none());
+
+    std::fs::write(root.join("src/lib.rs"), "pub fn lib() { 1 }\n").unwrap();
+    assert_ne!(scanned_fingerprint(root, "src/lib.rs"), fp);
+}
@@ -0,0 +1,58 @@
+//! # Symlink Traversal Test
+//!
+//! Scans a monorepo fixture that links a shared package into an app and links back to an
+//! ancestor, and checks that `follow_symlinks` picks up the linked package once without
+//! looping.
+//!
+//! Run with:
+//! ```
+//! cargo test --test follow_symlinks
+//! ```
+#![cfg(unix)]
+
+use cortexast::scanner::{scan_workspace, ScanOptions};
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+fn scanned(root: &Path, target: &str, follow_symlinks: bool) -> Vec<String> {
+    let opts = ScanOptions {
+        follow_symlinks,
+        ..ScanOptions::new(root, target)
+    };
+    scan_workspace(&opts)
+        .unwrap()
+        .into_iter()
+        .map(|e| e.rel_path.to_string_lossy().to_string())
+        .collect()
+}
+
+#[test]
+fn linked_packages_are_scanned_once_and_cycles_terminate() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::create_dir_all(root.join("packages/shared/src")).unwrap();
+    std::fs::create_dir_all(root.join("apps/web")).unwrap();
+    std::fs::write(
+        root.join("packages/shared/src/util.ts"),
+        "export const u = 1;\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("apps/web/main.ts"),
+        "import { u } from './shared';\n",
+    )
+    .unwrap();
+    symlink("../../packages/shared", root.join("apps/web/shared")).unwrap();
+    symlink("..", root.join("apps/web/up")).unwrap();
+
+    assert_eq!(scanned(root, "apps", false), ["apps/web/main.ts"]);
+    assert_eq!(
+        scanned(root, "apps", true),
+        ["apps/web/main.ts", "apps/web/shared/src/util.ts"]
+    );
+
+    // The package and its link are one directory: its file shows up once.
+    let all = scanned(root, ".", true);
+    assert_eq!(all.iter().filter(|p| p.ends_with("util.ts")).count(), 1);
+    assert!(all.contains(&"apps/web/main.ts".to_string()));
+}
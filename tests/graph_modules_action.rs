@@ -0,0 +1,124 @@
+//! # Module Graph Action Test
+//!
+//! Calls `cortex_code_explorer` with `action=graph_modules` over MCP stdio on a small
+//! multi-module fixture and checks the JSON graph, the Mermaid rendering, and that a
+//! diagram over `max_chars` drops its lightest edges instead of being cut mid-line.
+//!
+//! Run with:
+//! ```
+//! cargo test --test graph_modules_action
+//! ```
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+
+#[test]
+fn graph_modules_returns_json_and_mermaid() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    let write = |rel: &str, text: &str| {
+        std::fs::create_dir_all(root.join(rel).parent().unwrap()).unwrap();
+        std::fs::write(root.join(rel), text).unwrap();
+    };
+    write("core/index.ts", "export const x = 1;\n");
+    for module in ["ui", "util", "web"] {
+        write(&format!("{module}/index.ts"), "export const y = 2;\n");
+    }
+    write(
+        "app/index.ts",
+        "import { x } from '../core';\nimport { y } from '../web';\n\
+         import { y as u } from '../ui';\nimport { y as v } from '../util';\n",
+    );
+    write("app/main.ts", "import { x } from '../core';\n");
+
+    let calls = [
+        (1, serde_json::json!({})),
+        (2, serde_json::json!({ "format": "mermaid" })),
+        (
+            3,
+            serde_json::json!({ "format": "mermaid", "max_chars": 120 }),
+        ),
+        (4, serde_json::json!({ "format": "dot" })),
+    ];
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cortexast"))
+        .arg("mcp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn cortexast mcp");
+    {
+        let stdin = child.stdin.as_mut().unwrap();
+        for (id, extra) in &calls {
+            let mut arguments = serde_json::json!({
+                "repoPath": root,
+                "action": "graph_modules",
+                "target_dir": "."
+            });
+            for (k, v) in extra.as_object().unwrap() {
+                arguments[k] = v.clone();
+            }
+            let request = serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "tools/call",
+                "params": { "name": "cortex_code_explorer", "arguments": arguments }
+            });
+            writeln!(stdin, "{request}").unwrap();
+        }
+    }
+    drop(child.stdin.take());
+
+    let replies: Vec<serde_json::Value> = BufReader::new(child.stdout.take().unwrap())
+        .lines()
+        .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+        .collect();
+    assert!(child.wait().unwrap().success());
+    let result = |id: i64| -> (bool, String) {
+        let reply = replies.iter().find(|r| r["id"] == id).unwrap();
+        let text = reply["result"]["content"][0]["text"].as_str().unwrap();
+        (reply["result"]["isError"] == true, text.to_string())
+    };
+
+    let (is_error, json) = result(1);
+    assert!(!is_error, "{json}");
+    let graph: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let mut edges: Vec<(String, u64)> = graph["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            (
+                e["id"].as_str().unwrap().to_string(),
+                e["weight"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    edges.sort();
+    assert_eq!(
+        edges,
+        [
+            ("app->core".to_string(), 2),
+            ("app->ui".to_string(), 1),
+            ("app->util".to_string(), 1),
+            ("app->web".to_string(), 1)
+        ]
+    );
+
+    let (is_error, full) = result(2);
+    assert!(!is_error, "{full}");
+    assert!(full.starts_with("graph TD\n"));
+    assert!(full.contains("-->|2|") && full.contains("-->|1|"));
+    assert!(!full.contains("%%"));
+
+    let (is_error, trimmed) = result(3);
+    assert!(!is_error, "{trimmed}");
+    assert!(trimmed.len() <= 120, "{trimmed}");
+    assert!(trimmed.contains("%% 1/4 edges"), "{trimmed}");
+    assert!(trimmed.contains("-->|2|") && !trimmed.contains("-->|1|"));
+    assert!(!trimmed.contains("TRUNCATED"));
+
+    let (is_error, unknown) = result(4);
+    assert!(is_error);
+    assert!(unknown.contains("unknown format 'dot'"));
+}
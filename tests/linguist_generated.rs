@@ -0,0 +1,79 @@
+//! # Linguist Attributes Test
+//!
+//! Scans, slices and maps a fixture whose `.gitattributes` marks generated and vendored
+//! files, and checks that they are left out by default and kept with
+//! `scan.include_generated`.
+//!
+//! Run with:
+//! ```
+//! cargo test --test linguist_generated
+//! ```
+
+use cortexast::config::Config;
+use cortexast::mapper::build_repo_map_filtered;
+use cortexast::scanner::{scan_workspace, ScanOptions};
+use cortexast::slicer::slice_to_xml;
+use std::path::Path;
+
+fn scanned(root: &Path, target: &str, include_generated: bool) -> Vec<String> {
+    let opts = ScanOptions {
+        include_generated,
+        ..ScanOptions::new(root, target)
+    };
+    scan_workspace(&opts)
+        .unwrap()
+        .into_iter()
+        .map(|e| e.rel_path.to_string_lossy().replace('\\', "/"))
+        .collect()
+}
+
+#[test]
+fn linguist_marked_files_are_left_out_unless_included() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::create_dir_all(root.join("src/third_party")).unwrap();
+    std::fs::write(
+        root.join(".gitattributes"),
+        "*.generated.ts linguist-generated=true\n\
+         src/third_party/** linguist-vendored\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("src/app.ts"), "export const app = 1;\n").unwrap();
+    std::fs::write(root.join("src/api.generated.ts"), "export const api = 1;\n").unwrap();
+    std::fs::write(
+        root.join("src/third_party/lib.ts"),
+        "export const lib = 1;\n",
+    )
+    .unwrap();
+
+    assert_eq!(scanned(root, "src", false), ["src/app.ts"]);
+    assert!(scanned(root, "src/api.generated.ts", false).is_empty());
+    assert_eq!(
+        scanned(root, "src", true),
+        [
+            "src/api.generated.ts",
+            "src/app.ts",
+            "src/third_party/lib.ts"
+        ]
+    );
+
+    let mut cfg = Config {
+        skeleton_mode: false,
+        ..Config::default()
+    };
+    let (xml, _meta) = slice_to_xml(root, Path::new("src"), 100_000, &cfg, false).unwrap();
+    assert!(xml.contains("src/app.ts"));
+    assert!(!xml.contains("api.generated.ts") && !xml.contains("third_party"));
+    cfg.scan.include_generated = true;
+    let (xml, _meta) = slice_to_xml(root, Path::new("src"), 100_000, &cfg, false).unwrap();
+    assert!(xml.contains("src/api.generated.ts"));
+
+    let ids: Vec<String> = build_repo_map_filtered(root, Path::new("src"), false)
+        .unwrap()
+        .nodes
+        .into_iter()
+        .map(|n| n.id)
+        .collect();
+    assert!(ids.contains(&"src/app.ts".to_string()));
+    assert!(!ids.contains(&"src/api.generated.ts".to_string()));
+}
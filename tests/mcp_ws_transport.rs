@@ -0,0 +1,297 @@
+//! # MCP WebSocket Transport Test
+//!
+//! Starts `cortexast mcp --ws` on a free port, drives a session over one WebSocket (initialize,
+//! a watched `deep_slice`, a resource subscription), edits a watched file and checks that
+//! `notifications/resources/updated` arrives on the same socket, then adds a file for
+//! `notifications/tools/list_changed`. A page from another origin must be turned away at the
+//! handshake. Non-loopback binds need `--allow-remote` and `--token`, and a server with a token
+//! only serves sessions that present it.
+//!
+//! Run with:
+//! ```
+//! cargo test --test mcp_ws_transport
+//! ```
+
+use std::io::{BufRead, BufReader};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::{Message, WebSocket};
+
+struct Server(Child);
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Spawn the server and return it with the `host:port` it announced on stderr.
+fn spawn_server(root: &std::path::Path) -> (Server, String) {
+    spawn_server_with(root, &[])
+}
+
+fn spawn_server_with(root: &std::path::Path, args: &[&str]) -> (Server, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cortexast"))
+        .args(["mcp", "--ws", "127.0.0.1:0", "--root"])
+        .arg(root)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("spawn cortexast mcp --ws");
+    let stderr = BufReader::new(child.stderr.take().unwrap());
+    let addr = stderr
+        .lines()
+        .map_while(Result::ok)
+        .find_map(|line| line.split("ws://").nth(1).map(str::to_string))
+        .expect("listening address on stderr");
+    (Server(child), addr)
+}
+
+/// Open a session, or the HTTP status the server refused the handshake with.
+fn connect(addr: &str, origin: Option<&str>) -> Result<WebSocket<TcpStream>, u16> {
+    connect_with(addr, origin, None)
+}
+
+fn connect_with(
+    addr: &str,
+    origin: Option<&str>,
+    bearer: Option<&str>,
+) -> Result<WebSocket<TcpStream>, u16> {
+    let mut request = format!("ws://{addr}").into_client_request().unwrap();
+    if let Some(origin) = origin {
+        request
+            .headers_mut()
+            .insert("origin", origin.parse().unwrap());
+    }
+    if let Some(token) = bearer {
+        request
+            .headers_mut()
+            .insert("authorization", format!("Bearer {token}").parse().unwrap());
+    }
+    let stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_millis(200)))
+        .unwrap();
+    match tungstenite::client(request, stream) {
+        Ok((ws, _)) => Ok(ws),
+        Err(tungstenite::HandshakeError::Failure(tungstenite::Error::Http(resp))) => {
+            Err(resp.status().as_u16())
+        }
+        Err(e) => panic!("handshake failed: {e}"),
+    }
+}
+
+fn send(ws: &mut WebSocket<TcpStream>, msg: serde_json::Value) {
+    ws.send(Message::Text(msg.to_string())).unwrap();
+}
+
+/// Next JSON message matching `pred`, skipping others, within 15s.
+fn recv_until(
+    ws: &mut WebSocket<TcpStream>,
+    pred: impl Fn(&serde_json::Value) -> bool,
+) -> serde_json::Value {
+    let deadline = Instant::now() + Duration::from_secs(15);
+    while Instant::now() < deadline {
+        match ws.read() {
+            Ok(Message::Text(text)) => {
+                let v: serde_json::Value = serde_json::from_str(&text).unwrap();
+                if pred(&v) {
+                    return v;
+                }
+            }
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(e) => panic!("socket error: {e}"),
+        }
+    }
+    panic!("no matching message within 15s");
+}
+
+#[test]
+fn session_and_watch_notifications_share_one_socket() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().canonicalize().unwrap();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/lib.rs"), "pub fn before() {}\n").unwrap();
+    let (_server, addr) = spawn_server(&root);
+
+    assert_eq!(
+        connect(&addr, Some("https://example.com")).err(),
+        Some(403),
+        "foreign origin should be refused"
+    );
+
+    let mut ws = connect(&addr, Some("http://localhost:5173")).unwrap();
+    send(
+        &mut ws,
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "protocolVersion": "2024-11-05" }
+        }),
+    );
+    let init = recv_until(&mut ws, |v| v["id"] == 1);
+    assert_eq!(
+        init["result"]["capabilities"]["resources"]["listChanged"],
+        true
+    );
+
+    send(
+        &mut ws,
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {
+                "name": "cortex_code_explorer",
+                "arguments": {
+                    "repoPath": root,
+                    "action": "deep_slice",
+                    "target": "src",
+                    "watch": true
+                }
+            }
+        }),
+    );
+    let slice = recv_until(&mut ws, |v| v["id"] == 2);
+    assert_eq!(slice["result"]["isError"], false);
+    assert!(slice["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("before"));
+
+    recv_until(&mut ws, |v| {
+        v["method"] == "notifications/resources/list_changed"
+    });
+    send(
+        &mut ws,
+        serde_json::json!({ "jsonrpc": "2.0", "id": 3, "method": "resources/list" }),
+    );
+    let listed = recv_until(&mut ws, |v| v["id"] == 3);
+    let slice_uri = listed["result"]["resources"][0]["uri"].clone();
+    assert!(slice_uri.as_str().unwrap().ends_with("active_context.xml"));
+    send(
+        &mut ws,
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 4,
+            "method": "resources/subscribe",
+            "params": { "uri": slice_uri }
+        }),
+    );
+    recv_until(&mut ws, |v| v["id"] == 4);
+
+    // Give the watcher time to register before touching files.
+    std::thread::sleep(Duration::from_millis(300));
+    std::fs::write(root.join("src/lib.rs"), "pub fn after() {}\n").unwrap();
+    let updated = recv_until(&mut ws, |v| {
+        v["method"] == "notifications/resources/updated" && v.get("id").is_none()
+    });
+    assert_eq!(updated["params"]["uri"], slice_uri);
+
+    send(
+        &mut ws,
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 5,
+            "method": "resources/read",
+            "params": { "uri": slice_uri }
+        }),
+    );
+    let read = recv_until(&mut ws, |v| v["id"] == 5);
+    assert!(read["result"]["contents"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("after"));
+
+    std::fs::write(root.join("src/extra.rs"), "pub fn extra() {}\n").unwrap();
+    recv_until(&mut ws, |v| {
+        v["method"] == "notifications/tools/list_changed"
+    });
+}
+
+fn initialize(ws: &mut WebSocket<TcpStream>) -> serde_json::Value {
+    send(
+        ws,
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "protocolVersion": "2024-11-05" }
+        }),
+    );
+    recv_until(ws, |v| v["id"] == 1)
+}
+
+#[test]
+fn non_loopback_binds_need_allow_remote_and_a_token() {
+    let tmp = tempfile::tempdir().unwrap();
+    let refused = |args: &[&str]| {
+        let out = Command::new(env!("CARGO_BIN_EXE_cortexast"))
+            .args(["mcp", "--ws", "0.0.0.0:0", "--root"])
+            .arg(tmp.path())
+            .args(args)
+            .env_remove("CORTEXAST_WS_TOKEN")
+            .stdin(Stdio::null())
+            .output()
+            .unwrap();
+        assert!(!out.status.success(), "served with {args:?}");
+        String::from_utf8_lossy(&out.stderr).into_owned()
+    };
+    assert!(refused(&[]).contains("--allow-remote"));
+    assert!(refused(&["--allow-remote"]).contains("requires --token"));
+}
+
+#[test]
+fn sessions_must_present_the_token() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().canonicalize().unwrap();
+    let (_server, addr) = spawn_server_with(&root, &["--token", "s3cret"]);
+
+    assert_eq!(connect_with(&addr, None, Some("wrong")).err(), Some(401));
+
+    let mut ws = connect_with(&addr, None, Some("s3cret")).unwrap();
+    assert!(initialize(&mut ws)["result"].is_object());
+
+    // Browsers can't set headers, so the token may come as the first message instead.
+    let mut ws = connect(&addr, Some("http://localhost:5173")).unwrap();
+    send(&mut ws, serde_json::json!({ "token": "s3cret" }));
+    assert!(initialize(&mut ws)["result"].is_object());
+
+    // Anything else first ends the session unanswered.
+    let mut ws = connect(&addr, None).unwrap();
+    send(
+        &mut ws,
+        serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" }),
+    );
+    let deadline = Instant::now() + Duration::from_secs(15);
+    let closed = loop {
+        match ws.read() {
+            Ok(Message::Text(text)) => panic!("unauthenticated session was answered: {text}"),
+            Ok(Message::Close(_)) => break true,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                if Instant::now() > deadline {
+                    break false;
+                }
+            }
+            Err(_) => break true,
+        }
+    };
+    assert!(closed, "unauthenticated session stayed open");
+}
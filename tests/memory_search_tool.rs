@@ -0,0 +1,132 @@
+//! # Memory Search Tool Test
+//!
+//! Seeds a memory journal under a temporary `$HOME`, calls `cortex_memory_search` over MCP
+//! stdio and checks the ranked JSON results and the tag and project filters.
+//!
+//! Run with:
+//! ```
+//! cargo test --test memory_search_tool
+//! ```
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+
+fn entry(id: &str, project: &str, intent: &str, decision: &str, tags: &[&str]) -> String {
+    serde_json::json!({
+        "schema_version": "1.0",
+        "id": id,
+        "session_id": "s1",
+        "timestamp": "2026-10-01T12:00:00Z",
+        "source_ide": "vscode",
+        "project_path": project,
+        "intent": intent,
+        "decision": decision,
+        "tool_calls": ["cortex_code_explorer"],
+        "files_touched": [format!("{project}/src/{id}.rs")],
+        "tags": tags
+    })
+    .to_string()
+}
+
+#[test]
+fn memory_search_returns_ranked_json() {
+    let home = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(home.path().join(".cortexast")).unwrap();
+    let journal = [
+        entry(
+            "cache",
+            "/work/api",
+            "speed up slice cache invalidation",
+            "hash file contents with blake3 instead of mtimes",
+            &["perf"],
+        ),
+        entry(
+            "auth",
+            "/work/api",
+            "add token refresh",
+            "refresh tokens in the auth middleware",
+            &["feature"],
+        ),
+        entry(
+            "web-cache",
+            "/work/web",
+            "cache invalidation for the web bundle",
+            "use content hashes in asset names",
+            &["perf"],
+        ),
+    ]
+    .join("\n");
+    std::fs::write(home.path().join(".cortexast/global_memory.jsonl"), journal).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cortexast"))
+        .arg("mcp")
+        .env("HOME", home.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn cortexast mcp");
+    {
+        let stdin = child.stdin.as_mut().unwrap();
+        for (id, arguments) in [
+            (1, serde_json::json!({ "query": "cache invalidation" })),
+            (
+                2,
+                serde_json::json!({ "query": "cache invalidation", "project_path": "/work/web" }),
+            ),
+            (
+                3,
+                serde_json::json!({ "query": "tokens", "tags": ["perf"], "top_k": 1 }),
+            ),
+        ] {
+            let request = serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "tools/call",
+                "params": { "name": "cortex_memory_search", "arguments": arguments }
+            });
+            writeln!(stdin, "{request}").unwrap();
+        }
+    }
+    drop(child.stdin.take());
+
+    let replies: Vec<serde_json::Value> = BufReader::new(child.stdout.take().unwrap())
+        .lines()
+        .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+        .collect();
+    assert!(child.wait().unwrap().success());
+    let results = |id: i64| -> serde_json::Value {
+        let reply = replies.iter().find(|r| r["id"] == id).unwrap();
+        assert_eq!(reply["result"]["isError"], false);
+        serde_json::from_str(reply["result"]["content"][0]["text"].as_str().unwrap()).unwrap()
+    };
+
+    let all = results(1);
+    assert_eq!(all["searched"], 3);
+    let ranked = all["results"].as_array().unwrap();
+    let mut top_ids: Vec<&str> = ranked[..2]
+        .iter()
+        .map(|r| r["id"].as_str().unwrap())
+        .collect();
+    top_ids.sort();
+    assert_eq!(top_ids, ["cache", "web-cache"]);
+    assert_eq!(ranked[0]["rank"], 1);
+    assert!(ranked[0]["score"].as_f64() >= ranked[1]["score"].as_f64());
+    let cache = ranked.iter().find(|r| r["id"] == "cache").unwrap();
+    assert_eq!(
+        cache["decision"],
+        "hash file contents with blake3 instead of mtimes"
+    );
+    assert_eq!(cache["files_touched"][0], "/work/api/src/cache.rs");
+
+    let web = results(2);
+    assert!(web["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|r| r["project_path"] == "/work/web"));
+
+    let tagged = results(3);
+    assert_eq!(tagged["results"].as_array().unwrap().len(), 1);
+    assert_eq!(tagged["results"][0]["tags"][0], "perf");
+}
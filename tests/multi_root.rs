@@ -0,0 +1,83 @@
+//! # Multiple Scan Roots Test
+//!
+//! Scans, slices and maps `src/` together with `proto/` in one pass, with an overlapping
+//! root thrown in, and checks that both parts are covered once, that an import from one
+//! root into the other is a map edge, and that the rest of the repo stays out.
+//!
+//! Run with:
+//! ```
+//! cargo test --test multi_root
+//! ```
+
+use cortexast::config::Config;
+use cortexast::mapper::build_repo_map_multi;
+use cortexast::scanner::{scan_workspace, ScanOptions};
+use cortexast::slicer::slice_to_xml;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn extra_targets_are_scanned_sliced_and_mapped_once() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    for dir in ["src/api", "proto", "web"] {
+        std::fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    std::fs::write(root.join("src/lib.rs"), "pub mod api;\n").unwrap();
+    std::fs::write(root.join("src/api/mod.rs"), "pub fn api() {}\n").unwrap();
+    std::fs::write(root.join("proto/user.proto"), "message User {}\n").unwrap();
+    std::fs::write(root.join("proto/user.ts"), "export interface User {}\n").unwrap();
+    std::fs::write(
+        root.join("src/client.ts"),
+        "import { User } from '../proto/user';\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("web/app.ts"), "export const app = 1;\n").unwrap();
+
+    let opts = ScanOptions {
+        extra_targets: vec![PathBuf::from("proto"), PathBuf::from("src/api")],
+        ..ScanOptions::new(root, "src")
+    };
+    assert_eq!(opts.target_roots().len(), 2);
+    let scanned: Vec<String> = scan_workspace(&opts)
+        .unwrap()
+        .into_iter()
+        .map(|e| e.rel_path.to_string_lossy().replace('\\', "/"))
+        .collect();
+    assert_eq!(
+        scanned,
+        [
+            "proto/user.proto",
+            "proto/user.ts",
+            "src/api/mod.rs",
+            "src/client.ts",
+            "src/lib.rs"
+        ]
+    );
+
+    let mut cfg = Config {
+        skeleton_mode: false,
+        ..Config::default()
+    };
+    cfg.scan.extra_targets = vec![PathBuf::from("proto")];
+    let (xml, meta) = slice_to_xml(root, Path::new("src"), 100_000, &cfg, false).unwrap();
+    assert!(xml.contains("message User"));
+    assert!(!xml.contains("web/app.ts"));
+    assert_eq!(meta.total_files, 5);
+
+    let scopes = [
+        PathBuf::from("src"),
+        PathBuf::from("proto"),
+        PathBuf::from("src"),
+    ];
+    let map = build_repo_map_multi(root, &scopes, false).unwrap();
+    let ids: Vec<&str> = map.nodes.iter().map(|n| n.id.as_str()).collect();
+    assert!(ids.contains(&"src/lib.rs") && ids.contains(&"proto/user.proto"));
+    assert!(!ids.contains(&"web/app.ts"));
+    assert_eq!(ids.iter().filter(|id| **id == "src/lib.rs").count(), 1);
+    assert!(map
+        .edges
+        .iter()
+        .any(|e| e.id == "import:src/client.ts->proto/user.ts"));
+    let importance = |id: &str| map.nodes.iter().find(|n| n.id == id).unwrap().importance;
+    assert!(importance("proto/user.ts") > importance("src/client.ts"));
+}
@@ -0,0 +1,98 @@
+//! # Outline Slice Mode Test
+//!
+//! Slices a small Rust crate with `outline_mode` and checks that only the public API
+//! (trait impls included) survives: doc comments, signatures and type definitions,
+//! without bodies, private items or tests.
+//!
+//! Run with:
+//! ```
+//! cargo test --test outline_mode
+//! ```
+
+use cortexast::config::Config;
+use cortexast::slicer::slice_to_xml;
+use std::path::Path;
+
+const LIB_RS: &str = r#"//! Geometry helpers.
+
+use std::fmt;
+
+/// A point on the plane.
+#[derive(Debug, Clone)]
+pub struct Point {
+    /// Horizontal offset.
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    /// Distance to `other`.
+    pub fn distance(
+        &self,
+        other: &Point,
+    ) -> f64 {
+        let dx = self.x - other.x;
+        let dy = self.y - other.y;
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    fn scratch(&self) -> f64 {
+        self.x * 2.0
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+// Plain helper, not part of the API.
+fn helper() -> u32 {
+    42
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn it_works() {
+        assert_eq!(super::helper(), 42);
+    }
+}
+"#;
+
+fn file_content<'a>(xml: &'a str, path: &str) -> &'a str {
+    let open = format!("<file path=\"{path}\"><![CDATA[");
+    let start = xml.find(&open).expect("file in slice") + open.len();
+    let end = start + xml[start..].find("]]></file>").unwrap();
+    &xml[start..end]
+}
+
+#[test]
+fn outline_keeps_docs_signatures_and_types_only() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(tmp.path().join("src")).unwrap();
+    std::fs::write(tmp.path().join("src/lib.rs"), LIB_RS).unwrap();
+    std::fs::write(tmp.path().join("src/extra.rs"), "pub fn extra() {}\n").unwrap();
+
+    let cfg = Config {
+        outline_mode: true,
+        ..Config::default()
+    };
+    let (xml, _meta) = slice_to_xml(tmp.path(), Path::new("src"), 100_000, &cfg, false).unwrap();
+
+    assert_eq!(
+        file_content(&xml, "src/lib.rs"),
+        "/// A point on the plane.\n\
+         pub struct Point {\n\
+         \x20   /// Horizontal offset.\n\
+         \x20   pub x: f64,\n\
+         \x20   pub y: f64,\n\
+         }\n\
+         impl Point\n\
+         \x20   /// Distance to `other`.\n\
+         \x20   pub fn distance(&self, other: &Point) -> f64\n\
+         impl fmt::Display for Point\n\
+         \x20   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result"
+    );
+}
@@ -0,0 +1,229 @@
+//! # Oversized File Policy Test
+//!
+//! Builds a fixture repo with a small source file and a large generated client, then
+//! slices it under each `oversize_policy` ("skip", "head", "outline", "head_outline").
+//!
+//! A counting global allocator checks that "head" never materialises the whole file.
+//!
+//! Run with:
+//! ```
+//! cargo test --test oversize_policy -- --nocapture
+//! ```
+
+use cortexast::config::{Config, OversizePolicy, TokenEstimatorConfig};
+use cortexast::slicer::{slice_to_parts, slice_to_xml, slice_to_xml_huge, DropReason};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Allocation tracking
+// ─────────────────────────────────────────────────────────────────────────────
+
+struct CountingAlloc;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let p = System.alloc(layout);
+        if !p.is_null() {
+            let now = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(now, Ordering::Relaxed);
+        }
+        p
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Tests share the allocator counters, so run them one at a time.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Fixture helpers
+// ─────────────────────────────────────────────────────────────────────────────
+
+const MAX_FILE_BYTES: u64 = 16 * 1024;
+
+/// A TypeScript "generated client" with one small function per endpoint.
+fn generated_client(target_bytes: usize) -> String {
+    let mut out = String::with_capacity(target_bytes + 256);
+    let mut i = 0usize;
+    while out.len() < target_bytes {
+        out.push_str(&format!(
+            "export function getEndpoint{i}(id: string): Promise<Response> {{\n  return fetch(`/api/v1/endpoint{i}/${{id}}`);\n}}\n\n"
+        ));
+        i += 1;
+    }
+    out
+}
+
+fn write_fixture(dir: &Path, client_bytes: usize) {
+    std::fs::write(
+        dir.join("main.ts"),
+        "import { getEndpoint0 } from './api_client';\nexport function main() { return getEndpoint0('x'); }\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("api_client.ts"), generated_client(client_bytes)).unwrap();
+}
+
+fn config(policy: OversizePolicy) -> Config {
+    let defaults = Config::default();
+    Config {
+        skeleton_mode: false,
+        token_estimator: TokenEstimatorConfig {
+            max_file_bytes: MAX_FILE_BYTES,
+            oversize_policy: policy,
+            ..defaults.token_estimator.clone()
+        },
+        ..defaults
+    }
+}
+
+fn slice(dir: &Path, policy: OversizePolicy) -> String {
+    let (xml, _meta) =
+        slice_to_xml(dir, Path::new("."), 1_000_000, &config(policy), false).expect("slice_to_xml");
+    xml
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[test]
+fn skip_policy_drops_oversized_file() {
+    let _g = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let tmp = tempfile::tempdir().unwrap();
+    write_fixture(tmp.path(), 64 * 1024);
+
+    let cfg = config(OversizePolicy::Skip);
+    let (xml, meta) = slice_to_xml(tmp.path(), Path::new("."), 1_000_000, &cfg, false).unwrap();
+    assert!(xml.contains(r#"<file path="main.ts">"#));
+    assert!(!xml.contains(r#"<file path="api_client.ts">"#));
+
+    // The scan's size skip is still reported, estimated from the file's size.
+    assert_eq!(meta.dropped.len(), 1, "{:?}", meta.dropped);
+    assert_eq!(meta.dropped[0].path, "api_client.ts");
+    assert_eq!(meta.dropped[0].reason, DropReason::Oversized);
+    assert!(meta.dropped[0].est_tokens > 64 * 1024 / 8);
+    assert_eq!(meta.scan.skipped_by_size, 1);
+    assert_eq!(meta.scan.included, 1);
+
+    let parts = slice_to_parts(tmp.path(), Path::new("."), 1_000_000, &cfg, false).unwrap();
+    let (_xml, meta) = &parts[0];
+    assert_eq!(meta.dropped.len(), 1, "{:?}", meta.dropped);
+    assert_eq!(meta.dropped[0].reason, DropReason::Oversized);
+}
+
+#[test]
+fn head_policy_includes_truncated_prefix() {
+    let _g = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let tmp = tempfile::tempdir().unwrap();
+    write_fixture(tmp.path(), 64 * 1024);
+
+    let xml = slice(tmp.path(), OversizePolicy::Head);
+    assert!(xml.contains(r#"<file path="api_client.ts">"#));
+    assert!(xml.contains("getEndpoint0"));
+    assert!(xml.contains("truncated: first"));
+
+    // Only the head made it in: late endpoints must be absent.
+    let client = generated_client(64 * 1024);
+    let last = client
+        .lines()
+        .rev()
+        .find(|l| l.starts_with("export function"))
+        .unwrap();
+    assert!(!xml.contains(last));
+}
+
+#[test]
+fn outline_policy_lists_symbols_without_bodies() {
+    let _g = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let tmp = tempfile::tempdir().unwrap();
+    write_fixture(tmp.path(), 64 * 1024);
+
+    let xml = slice(tmp.path(), OversizePolicy::Outline);
+    assert!(xml.contains(r#"<file path="api_client.ts">"#));
+    assert!(xml.contains("outline of oversized file"));
+    // Symbols from the end of the file are listed even though the file exceeds max_file_bytes...
+    assert!(xml.contains("getEndpoint300"));
+    // ...but function bodies are not.
+    assert!(!xml.contains("/api/v1/endpoint300/"));
+}
+
+#[test]
+fn head_outline_policy_keeps_the_head_and_outlines_the_rest() {
+    let _g = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let tmp = tempfile::tempdir().unwrap();
+    write_fixture(tmp.path(), 64 * 1024);
+
+    let xml = slice(tmp.path(), OversizePolicy::HeadOutline);
+    assert!(xml.contains(r#"<file path="api_client.ts">"#));
+    // The head keeps its bodies...
+    assert!(xml.contains("/api/v1/endpoint0/"));
+    assert!(xml.contains("outline of the rest"));
+    // ...and symbols past it are outlined without theirs.
+    assert!(xml.contains("getEndpoint300"));
+    assert!(!xml.contains("/api/v1/endpoint300/"));
+}
+
+/// A multi-megabyte file under "head" must only cost roughly `max_file_bytes` of memory.
+#[test]
+fn head_policy_streams_without_full_read() {
+    let _g = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let tmp = tempfile::tempdir().unwrap();
+    let big = 6 * 1024 * 1024;
+    write_fixture(tmp.path(), big);
+
+    let baseline = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+
+    let xml = slice(tmp.path(), OversizePolicy::Head);
+    assert!(xml.contains(r#"<file path="api_client.ts">"#));
+
+    let peak_delta = PEAK.load(Ordering::Relaxed).saturating_sub(baseline);
+    assert!(
+        peak_delta < big / 4,
+        "peak allocation {} bytes suggests the oversized file was fully read",
+        peak_delta
+    );
+}
+
+/// The huge-codebase root section applies the policy to oversized root-level files too.
+#[test]
+fn huge_mode_root_files_follow_the_policy() {
+    let _g = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::write(root.join("api_client.ts"), generated_client(8 * 1024)).unwrap();
+    std::fs::create_dir_all(root.join("services/api")).unwrap();
+    std::fs::write(
+        root.join("services/api/package.json"),
+        "{\"name\": \"api\"}\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("services/api/index.ts"), "export const x = 1;\n").unwrap();
+
+    let mut cfg = config(OversizePolicy::Head);
+    cfg.token_estimator.max_file_bytes = 2 * 1024;
+    let (xml, _meta) = slice_to_xml_huge(root, 100_000, &cfg, false).expect("slice_to_xml_huge");
+    assert!(xml.contains(r#"<file path="api_client.ts">"#));
+    assert!(xml.contains("truncated: first"));
+
+    cfg.token_estimator.oversize_policy = OversizePolicy::Skip;
+    let (xml, meta) = slice_to_xml_huge(root, 100_000, &cfg, false).expect("slice_to_xml_huge");
+    assert!(!xml.contains(r#"<file path="api_client.ts">"#));
+    assert!(meta
+        .dropped
+        .iter()
+        .any(|d| d.path == "api_client.ts" && d.reason == DropReason::Oversized));
+}
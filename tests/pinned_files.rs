@@ -0,0 +1,63 @@
+//! # Pinned and Priority Files Test
+//!
+//! Slices a fixture's `src` directory with `always_include` and `priority_globs` set and
+//! checks that pinned files are pulled in from outside the target and packed first.
+//!
+//! Run with:
+//! ```
+//! cargo test --test pinned_files
+//! ```
+
+use cortexast::config::Config;
+use cortexast::slicer::slice_to_xml;
+use std::path::Path;
+
+fn file_order(xml: &str) -> Vec<String> {
+    xml.match_indices("<file path=\"")
+        .map(|(at, m)| xml[at + m.len()..].split('"').next().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn pinned_files_are_packed_first_in_list_order() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::create_dir_all(root.join("src/util")).unwrap();
+    std::fs::create_dir_all(root.join("db")).unwrap();
+    std::fs::write(root.join("README.md"), "# Demo\n").unwrap();
+    std::fs::write(root.join("db/schema.prisma"), "model User { id Int @id }\n").unwrap();
+    std::fs::write(
+        root.join("src/main.ts"),
+        "import { z } from './util/zz_types';\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("src/util/zz_types.ts"), "export type Z = 1;\n").unwrap();
+    std::fs::write(root.join("src/util/helpers.ts"), "export const h = 1;\n").unwrap();
+
+    let cfg = Config {
+        skeleton_mode: false,
+        always_include: vec!["README.md".into(), "db/*.prisma".into()],
+        priority_globs: vec!["helpers.ts".into(), "src/util/*.ts".into()],
+        ..Config::default()
+    };
+    let (xml, _meta) = slice_to_xml(root, Path::new("src"), 100_000, &cfg, false).unwrap();
+    assert_eq!(
+        file_order(&xml),
+        vec![
+            "README.md",
+            "db/schema.prisma",
+            "src/util/helpers.ts",
+            "src/util/zz_types.ts",
+            "src/main.ts",
+        ]
+    );
+
+    // Nothing is pinned by default.
+    let cfg = Config {
+        skeleton_mode: false,
+        ..Config::default()
+    };
+    let (xml, _meta) = slice_to_xml(root, Path::new("src"), 100_000, &cfg, false).unwrap();
+    assert!(!xml.contains("README.md"));
+    assert_eq!(file_order(&xml)[0], "src/main.ts");
+}
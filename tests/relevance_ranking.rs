@@ -0,0 +1,58 @@
+//! # Relevance-Ranked Budget Fitting Test
+//!
+//! Slices a small TypeScript project whose most imported module sorts last by path, and
+//! checks that it is packed first and still fits when the budget gets tight.
+//!
+//! Run with:
+//! ```
+//! cargo test --test relevance_ranking
+//! ```
+
+use cortexast::config::Config;
+use cortexast::slicer::slice_to_xml;
+use std::path::Path;
+
+fn fixture() -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    let src = tmp.path().join("src");
+    std::fs::create_dir_all(&src).unwrap();
+
+    // Alphabetically first, imported by nobody, and comparatively large.
+    let scratch: String = (0..120)
+        .map(|i| format!("export function scratch{i}(x: number): number {{ return x * {i}; }}\n"))
+        .collect();
+    std::fs::write(src.join("aaa_scratch.ts"), scratch).unwrap();
+
+    std::fs::write(
+        src.join("zz_core.ts"),
+        "export interface Order { id: string; total: number; }\n",
+    )
+    .unwrap();
+    for name in ["billing", "checkout", "invoice"] {
+        std::fs::write(
+            src.join(format!("{name}.ts")),
+            format!("import {{ Order }} from './zz_core';\nexport function {name}(o: Order) {{ return o.total; }}\n"),
+        )
+        .unwrap();
+    }
+    tmp
+}
+
+#[test]
+fn central_files_are_packed_before_alphabetical_ones() {
+    let tmp = fixture();
+    let cfg = Config::default();
+
+    let (xml, meta) = slice_to_xml(tmp.path(), Path::new("src"), 100_000, &cfg, false).unwrap();
+    assert!(meta.dropped.is_empty());
+    let core = xml.find("src/zz_core.ts\"").expect("core in slice");
+    let scratch = xml.find("src/aaa_scratch.ts\"").expect("scratch in slice");
+    assert!(core < scratch, "central file should come first");
+
+    // Too tight for the large unused file, but the central one still makes it in.
+    let budget = meta.total_tokens / 2;
+    let (xml, meta) = slice_to_xml(tmp.path(), Path::new("src"), budget, &cfg, false).unwrap();
+    assert!(xml.contains("src/zz_core.ts\""));
+    assert!(!xml.contains("src/aaa_scratch.ts\""));
+    assert!(meta.dropped.iter().any(|d| d.path == "src/aaa_scratch.ts"));
+}
@@ -0,0 +1,98 @@
+//! # Scan Statistics Test
+//!
+//! Scans a fixture with an oversized file, a file of NUL bytes, a test file, a
+//! `linguist-generated` file and files pruned by `.gitignore` and `.slicerignore`, and checks
+//! that each skip is counted under its reason, that pruned directories are counted without
+//! being walked, and that the counts reach the slice metadata.
+//!
+//! Run with:
+//! ```
+//! cargo test --test scan_stats
+//! ```
+
+use cortexast::config::Config;
+use cortexast::scanner::{scan_workspace_with_stats, ScanOptions, ScanStats};
+use cortexast::slicer::slice_to_xml;
+use std::path::Path;
+
+#[test]
+fn skipped_files_are_counted_by_reason() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::create_dir_all(root.join("src/tests")).unwrap();
+    std::fs::write(root.join(".gitattributes"), "*.gen.rs linguist-generated\n").unwrap();
+    std::fs::write(root.join("src/lib.rs"), "pub fn lib() {}\n").unwrap();
+    std::fs::write(root.join("src/api.gen.rs"), "pub fn api() {}\n").unwrap();
+    std::fs::write(root.join("src/tests/lib_test.rs"), "#[test]\nfn t() {}\n").unwrap();
+    std::fs::write(root.join("src/big.rs"), "// big\n".repeat(1_000)).unwrap();
+    std::fs::write(root.join("src/blob.dat"), [0x89, b'D', 0, 0, 0, 0]).unwrap();
+
+    let opts = ScanOptions {
+        max_file_bytes: 1_024,
+        exclude_tests: true,
+        ..ScanOptions::new(root, "src")
+    };
+    let (entries, stats) = scan_workspace_with_stats(&opts).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(
+        stats,
+        ScanStats {
+            files_seen: 5,
+            included: 1,
+            skipped_by_size: 1,
+            skipped_by_ignore: 2,
+            skipped_binary: 1,
+            pruned_dirs: 0,
+        }
+    );
+
+    let mut cfg = Config::default();
+    cfg.token_estimator.max_file_bytes = 1_024;
+    let (_xml, meta) = slice_to_xml(root, Path::new("src"), 100_000, &cfg, false).unwrap();
+    assert_eq!(meta.scan.files_seen, 5);
+    assert_eq!(meta.scan.skipped_binary, 1);
+    assert_eq!(
+        serde_json::to_value(meta.scan).unwrap()["skippedByIgnore"],
+        1
+    );
+}
+
+/// Pruned directories count once each without being walked, so an ignored `fixtures/` or
+/// `node_modules/` adds one entry however much it holds.
+#[test]
+fn files_pruned_by_ignore_files_are_counted() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::create_dir_all(root.join(".git")).unwrap();
+    std::fs::create_dir_all(root.join("src/fixtures")).unwrap();
+    std::fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+    std::fs::write(root.join(".slicerignore"), "fixtures/\n").unwrap();
+    std::fs::write(root.join("src/lib.rs"), "pub fn lib() {}\n").unwrap();
+    std::fs::write(root.join("src/debug.log"), "trace\n").unwrap();
+    std::fs::write(root.join("src/fixtures/a.json"), "{}\n").unwrap();
+    std::fs::write(root.join("src/fixtures/b.json"), "[]\n").unwrap();
+    std::fs::create_dir_all(root.join("src/node_modules/pkg/lib")).unwrap();
+    std::fs::write(
+        root.join("src/node_modules/pkg/lib/index.js"),
+        "exports.a = 1;\n",
+    )
+    .unwrap();
+
+    let opts = ScanOptions {
+        max_file_bytes: 1_024,
+        ..ScanOptions::new(root, "src")
+    };
+    let (entries, stats) = scan_workspace_with_stats(&opts).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(
+        stats,
+        ScanStats {
+            files_seen: 2,
+            included: 1,
+            skipped_by_size: 0,
+            skipped_by_ignore: 1,
+            skipped_binary: 0,
+            pruned_dirs: 2,
+        }
+    );
+}
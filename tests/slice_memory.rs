@@ -0,0 +1,75 @@
+//! # Slice Memory Section Test
+//!
+//! Slices a fixture with `memory.enabled` set and a project-local journal, and checks that
+//! only the entries related to the target land in the `<memory>` section.
+//!
+//! Run with:
+//! ```
+//! cargo test --test slice_memory
+//! ```
+
+use cortexast::config::{Config, SliceMemoryConfig};
+use cortexast::slicer::slice_to_xml;
+use std::path::{Path, PathBuf};
+
+fn journal_line(id: &str, project: &Path, intent: &str, files: &[&str]) -> String {
+    serde_json::json!({
+        "schema_version": "1.0",
+        "id": id,
+        "session_id": "s1",
+        "timestamp": "2026-03-01T10:00:00Z",
+        "source_ide": "cursor",
+        "project_path": project.to_string_lossy(),
+        "intent": intent,
+        "decision": format!("decision for {id}"),
+        "files_touched": files,
+    })
+    .to_string()
+}
+
+#[test]
+fn related_memory_entries_are_embedded_before_the_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::create_dir_all(root.join("src/billing")).unwrap();
+    std::fs::create_dir_all(root.join("web")).unwrap();
+    std::fs::write(root.join("src/billing/invoice.ts"), "export const a = 1;\n").unwrap();
+    std::fs::write(root.join("web/app.ts"), "export const b = 2;\n").unwrap();
+    let journal = [
+        journal_line(
+            "m1",
+            root,
+            "round invoice totals",
+            &["src/billing/invoice.ts"],
+        ),
+        journal_line("m2", root, "restyle the header", &["web/app.ts"]),
+    ]
+    .join("\n");
+    std::fs::write(root.join("journal.jsonl"), journal).unwrap();
+
+    let cfg = Config {
+        memory: SliceMemoryConfig {
+            enabled: true,
+            journal: Some(PathBuf::from("journal.jsonl")),
+            ..SliceMemoryConfig::default()
+        },
+        ..Config::default()
+    };
+    let (xml, _meta) = slice_to_xml(root, Path::new("src/billing"), 100_000, &cfg, false).unwrap();
+    let memory = xml.find("<memory><![CDATA[").expect("memory section");
+    assert!(memory < xml.find("<repository_map>").unwrap());
+    assert!(xml.contains("round invoice totals"));
+    assert!(xml.contains("decision: decision for m1"));
+    assert!(!xml.contains("restyle the header"));
+
+    // Off by default.
+    let cfg = Config {
+        memory: SliceMemoryConfig {
+            journal: Some(PathBuf::from("journal.jsonl")),
+            ..SliceMemoryConfig::default()
+        },
+        ..Config::default()
+    };
+    let (xml, _meta) = slice_to_xml(root, Path::new("src/billing"), 100_000, &cfg, false).unwrap();
+    assert!(!xml.contains("<memory>"));
+}
@@ -0,0 +1,86 @@
+//! # Slice Redaction Test
+//!
+//! Slices a fixture repo holding a secret and checks that the slice cache under
+//! `.context-slicer/slices` only ever stores the redacted text, while warm slices still
+//! report the redaction in their metadata.
+//!
+//! Run with:
+//! ```
+//! cargo test --test slice_redaction
+//! ```
+
+use cortexast::config::Config;
+use cortexast::slice_cache::SLICE_CACHE_DIR;
+use cortexast::slicer::{slice_to_parts, slice_to_xml};
+use std::path::Path;
+
+const SECRET: &str = "sk-abcdefghijklmnopqrstuvwxyz0123";
+
+fn fixture() -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(
+        tmp.path().join("settings.py"),
+        format!("API_KEY = \"{SECRET}\"\n\ndef load():\n    return API_KEY\n"),
+    )
+    .unwrap();
+    tmp
+}
+
+fn config() -> Config {
+    Config {
+        skeleton_mode: false,
+        ..Config::default()
+    }
+}
+
+fn assert_cache_is_redacted(root: &Path) {
+    let dir = root.join(SLICE_CACHE_DIR);
+    let files: Vec<_> = std::fs::read_dir(&dir).unwrap().flatten().collect();
+    assert!(
+        !files.is_empty(),
+        "no slice cache written to {}",
+        dir.display()
+    );
+    for f in files {
+        let raw = std::fs::read_to_string(f.path()).unwrap();
+        assert!(
+            !raw.contains(SECRET),
+            "secret cached in {}",
+            f.path().display()
+        );
+    }
+}
+
+#[test]
+fn the_slice_cache_never_stores_secrets() {
+    let tmp = fixture();
+    let cfg = config();
+
+    let (xml, cold) = slice_to_xml(tmp.path(), Path::new("."), 8_000, &cfg, false).unwrap();
+    assert!(!xml.contains(SECRET));
+    assert!(xml.contains("API_KEY = \"[REDACTED:"));
+    assert!(!cold.redactions.is_empty());
+    assert!(cold.redactions.iter().all(|r| r.path == "settings.py"));
+    assert_cache_is_redacted(tmp.path());
+
+    // A warm slice is served from the cache and still reports what was masked.
+    let (warm_xml, warm) = slice_to_xml(tmp.path(), Path::new("."), 8_000, &cfg, false).unwrap();
+    assert_eq!(warm_xml, xml);
+    assert_eq!(warm.redactions.len(), cold.redactions.len());
+    assert_cache_is_redacted(tmp.path());
+}
+
+#[test]
+fn the_parts_cache_never_stores_secrets() {
+    let tmp = fixture();
+    let cfg = config();
+
+    for _ in 0..2 {
+        let parts = slice_to_parts(tmp.path(), Path::new("."), 8_000, &cfg, false).unwrap();
+        assert_eq!(parts.len(), 1);
+        let (xml, meta) = &parts[0];
+        assert!(!xml.contains(SECRET));
+        assert!(!meta.redactions.is_empty());
+        assert_cache_is_redacted(tmp.path());
+    }
+}
@@ -0,0 +1,39 @@
+//! # Slice Rules Preamble Test
+//!
+//! Slices a fixture with a project `.cortex_rules.yml` and `slice_rules` set, and checks
+//! that the merged rules open the slice in a `<rules>` section.
+//!
+//! Run with:
+//! ```
+//! cargo test --test slice_rules
+//! ```
+
+use cortexast::config::Config;
+use cortexast::slicer::slice_to_xml;
+use std::path::Path;
+
+#[test]
+fn merged_rules_open_the_slice_when_enabled() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/lib.rs"), "pub fn a() {}\n").unwrap();
+    std::fs::write(
+        root.join(".cortex_rules.yml"),
+        "error_handling: use anyhow in binaries\n",
+    )
+    .unwrap();
+
+    let cfg = Config {
+        slice_rules: true,
+        ..Config::default()
+    };
+    let (xml, _meta) = slice_to_xml(root, Path::new("src"), 100_000, &cfg, false).unwrap();
+    let rules = xml.find("<rules><![CDATA[").expect("rules section");
+    assert!(rules < xml.find("<repository_map>").unwrap());
+    assert!(xml.contains("error_handling: use anyhow in binaries"));
+
+    let (xml, _meta) =
+        slice_to_xml(root, Path::new("src"), 100_000, &Config::default(), false).unwrap();
+    assert!(!xml.contains("<rules>"));
+}
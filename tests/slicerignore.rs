@@ -0,0 +1,61 @@
+//! # .slicerignore Test
+//!
+//! Scans, slices and maps a fixture with a repo-level `.slicerignore` and checks that the
+//! files it lists are left out everywhere while git-visible files stay in.
+//!
+//! Run with:
+//! ```
+//! cargo test --test slicerignore
+//! ```
+
+use cortexast::config::Config;
+use cortexast::mapper::build_repo_map_filtered;
+use cortexast::scanner::{scan_workspace, ScanOptions};
+use cortexast::slicer::slice_to_xml;
+use std::path::Path;
+
+fn scanned(root: &Path, target: &str) -> Vec<String> {
+    let opts = ScanOptions::new(root, target);
+    scan_workspace(&opts)
+        .unwrap()
+        .into_iter()
+        .map(|e| e.rel_path.to_string_lossy().replace('\\', "/"))
+        .collect()
+}
+
+#[test]
+fn slicerignored_files_are_left_out_of_scans_slices_and_maps() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::create_dir_all(root.join("src/fixtures")).unwrap();
+    std::fs::write(
+        root.join(".slicerignore"),
+        "fixtures/\n*.golden\n!keep.golden\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("src/lib.rs"), "pub fn lib() {}\n").unwrap();
+    std::fs::write(root.join("src/fixtures/big.json"), "{\"a\": 1}\n").unwrap();
+    std::fs::write(root.join("src/out.golden"), "expected\n").unwrap();
+    std::fs::write(root.join("src/keep.golden"), "kept\n").unwrap();
+
+    assert_eq!(scanned(root, "."), ["src/keep.golden", "src/lib.rs"]);
+    assert_eq!(scanned(root, "src"), ["src/keep.golden", "src/lib.rs"]);
+    assert!(scanned(root, "src/out.golden").is_empty());
+
+    let cfg = Config {
+        skeleton_mode: false,
+        ..Config::default()
+    };
+    let (xml, _meta) = slice_to_xml(root, Path::new("src"), 100_000, &cfg, false).unwrap();
+    assert!(xml.contains("src/lib.rs"));
+    assert!(!xml.contains("big.json") && !xml.contains("out.golden"));
+
+    let ids: Vec<String> = build_repo_map_filtered(root, Path::new("src"), false)
+        .unwrap()
+        .nodes
+        .into_iter()
+        .map(|n| n.id)
+        .collect();
+    assert!(ids.contains(&"src/lib.rs".to_string()));
+    assert!(!ids.contains(&"src/fixtures".to_string()));
+}
@@ -0,0 +1,72 @@
+//! # Multi-Part Slice Test
+//!
+//! Splits a fixture repo that is several budgets large into sequential parts and checks
+//! that every file lands in exactly one part, each part stays within budget, and the root
+//! element carries the part numbering.
+//!
+//! Run with:
+//! ```
+//! cargo test --test split_parts
+//! ```
+
+use cortexast::config::{Config, SliceFormat};
+use cortexast::slicer::slice_to_parts;
+use std::path::Path;
+
+const BUDGET: usize = 1_500;
+
+fn fixture() -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    for m in 0..12 {
+        let body: String = (0..20)
+            .map(|i| format!("export function m{m}f{i}(x: number): number {{ return x * {i}; }}\n"))
+            .collect();
+        std::fs::write(tmp.path().join(format!("mod{m:02}.ts")), body).unwrap();
+    }
+    tmp
+}
+
+#[test]
+fn parts_cover_every_file_in_order_within_budget() {
+    let tmp = fixture();
+    let cfg = Config {
+        skeleton_mode: false,
+        ..Config::default()
+    };
+    let parts = slice_to_parts(tmp.path(), Path::new("."), BUDGET, &cfg, false).unwrap();
+    assert!(
+        parts.len() > 1,
+        "expected several parts, got {}",
+        parts.len()
+    );
+
+    let count = parts.len();
+    let mut seen = Vec::new();
+    for (i, (xml, meta)) in parts.iter().enumerate() {
+        assert!(xml.contains(&format!("<cortexast part=\"{}\" parts=\"{count}\">", i + 1)));
+        assert_eq!(xml.contains("<repository_map>"), i == 0);
+        assert!(meta.total_tokens <= BUDGET, "part {} over budget", i + 1);
+        assert!(meta.dropped.is_empty());
+        seen.extend(
+            xml.match_indices("<file path=\"")
+                .map(|(at, m)| xml[at + m.len()..].split('"').next().unwrap().to_string()),
+        );
+    }
+    seen.sort();
+    let expected: Vec<String> = (0..12).map(|m| format!("mod{m:02}.ts")).collect();
+    assert_eq!(seen, expected);
+}
+
+#[test]
+fn non_xml_parts_are_numbered_too() {
+    let tmp = fixture();
+    let cfg = Config {
+        skeleton_mode: false,
+        slice_format: SliceFormat::Json,
+        ..Config::default()
+    };
+    let parts = slice_to_parts(tmp.path(), Path::new("."), BUDGET, &cfg, false).unwrap();
+    let last: serde_json::Value = serde_json::from_str(&parts.last().unwrap().0).unwrap();
+    assert_eq!(last["part"], parts.len());
+    assert_eq!(last["parts"], parts.len());
+}
@@ -0,0 +1,69 @@
+//! # Token Estimator Consistency Test
+//!
+//! The mapper's `est_tokens` and the slicer's `total_tokens` must come from the same
+//! estimator, so a per-extension divisor in `.cortexast.json` moves both by the same amount.
+//!
+//! Run with:
+//! ```
+//! cargo test --test token_estimator -- --nocapture
+//! ```
+
+use cortexast::config::load_config;
+use cortexast::mapper::build_repo_map_scoped;
+use cortexast::slicer::slice_to_xml;
+use std::path::Path;
+
+const SOURCE: &str = "export function handler(event: unknown) {\n  return { status: 200, body: event };\n}\n\nexport function main() {\n  console.log(handler({}));\n}\n";
+
+/// Fixture repos live under the crate dir: the mapper skips paths with components like
+/// `tmp` or `target`, which rules out the system temp dir and `CARGO_TARGET_TMPDIR`.
+fn fixture_dir() -> tempfile::TempDir {
+    tempfile::tempdir_in(env!("CARGO_MANIFEST_DIR")).unwrap()
+}
+
+fn write_repo(dir: &Path, per_extension: &str) {
+    std::fs::write(dir.join("app.ts"), SOURCE).unwrap();
+    std::fs::write(
+        dir.join(".cortexast.json"),
+        format!(
+            r#"{{"skeleton_mode": false, "token_estimator": {{"per_extension": {per_extension}}}}}"#
+        ),
+    )
+    .unwrap();
+}
+
+/// (mapper est_tokens for app.ts, slicer total_tokens for a slice of app.ts)
+fn measure(dir: &Path) -> (u64, usize) {
+    let map = build_repo_map_scoped(dir, dir).unwrap();
+    let node = map
+        .nodes
+        .iter()
+        .find(|n| n.id == "app.ts")
+        .expect("app.ts node");
+
+    let cfg = load_config(dir);
+    let (xml, meta) = slice_to_xml(dir, Path::new("app.ts"), 100_000, &cfg, false).unwrap();
+    assert!(xml.contains("export function handler"));
+    (node.est_tokens, meta.total_tokens)
+}
+
+#[test]
+fn mapper_and_slicer_agree_and_follow_config_divisor() {
+    let default_repo = fixture_dir();
+    write_repo(default_repo.path(), "{}");
+    let (map_default, slice_default) = measure(default_repo.path());
+    assert_eq!(map_default, SOURCE.len().div_ceil(4) as u64);
+
+    let calibrated_repo = fixture_dir();
+    write_repo(calibrated_repo.path(), r#"{"ts": 2.0}"#);
+    let (map_calibrated, slice_calibrated) = measure(calibrated_repo.path());
+    assert_eq!(map_calibrated, SOURCE.len().div_ceil(2) as u64);
+
+    // Only the file content is TypeScript; XML overhead keeps the default divisor, so the slice
+    // total must move by exactly as much as the mapper's per-file estimate.
+    assert!(slice_calibrated > slice_default);
+    assert_eq!(
+        slice_calibrated - slice_default,
+        (map_calibrated - map_default) as usize
+    );
+}
@@ -0,0 +1,133 @@
+//! # Tool Output Pagination Test
+//!
+//! Pages through a large `map_overview` over MCP stdio with `page_size` and the returned
+//! `nextCursor`s, checks that the pages add up to the untruncated output, and that a cursor
+//! stops working once the output changes underneath it.
+//!
+//! Run with:
+//! ```
+//! cargo test --test tool_pagination
+//! ```
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+struct Session {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: i64,
+}
+
+impl Session {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_cortexast"))
+            .arg("mcp")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("spawn cortexast mcp");
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Session {
+            child,
+            stdin,
+            stdout,
+            next_id: 1,
+        }
+    }
+
+    /// `tools/call` result of `map_overview` over `root` with extra arguments.
+    fn map_overview(&mut self, root: &Path, extra: serde_json::Value) -> serde_json::Value {
+        let mut arguments = serde_json::json!({
+            "repoPath": root,
+            "action": "map_overview",
+            "target_dir": "."
+        });
+        for (k, v) in extra.as_object().unwrap() {
+            arguments[k] = v.clone();
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": { "name": "cortex_code_explorer", "arguments": arguments }
+        });
+        writeln!(self.stdin, "{request}").unwrap();
+        let mut line = String::new();
+        self.stdout.read_line(&mut line).unwrap();
+        let reply: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(reply["id"], id);
+        reply["result"].clone()
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn text(result: &serde_json::Value) -> &str {
+    result["content"][0]["text"].as_str().unwrap()
+}
+
+#[test]
+fn pages_add_up_to_the_full_output() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    for i in 0..12 {
+        let body: String = (0..8)
+            .map(|j| format!("pub fn handler_{i}_{j}(input: &str) -> usize {{ input.len() }}\n"))
+            .collect();
+        std::fs::write(root.join(format!("src/module_{i}.rs")), body).unwrap();
+    }
+
+    let mut session = Session::start();
+    let full = session.map_overview(root, serde_json::json!({ "max_chars": 1_000_000 }));
+    let full = text(&full).to_string();
+    assert!(full.len() > 2_000, "fixture should need several pages");
+
+    let mut pages = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut extra = serde_json::json!({ "page_size": 600 });
+        if let Some(c) = &cursor {
+            extra["cursor"] = serde_json::json!(c);
+        }
+        let result = session.map_overview(root, extra);
+        assert_eq!(result["isError"], false, "{}", text(&result));
+        let page = text(&result);
+        match result["nextCursor"].as_str() {
+            Some(next) => {
+                let (body, footer) = page.rsplit_once("\n\n[PAGE: ").unwrap();
+                assert!(body.len() <= 600 && footer.contains(next));
+                pages.push(body.to_string());
+                cursor = Some(next.to_string());
+            }
+            None => {
+                pages.push(page.to_string());
+                break;
+            }
+        }
+    }
+    assert!(pages.len() > 3);
+    assert!(pages[..pages.len() - 1].iter().all(|p| p.ends_with('\n')));
+    assert_eq!(pages.concat(), full);
+
+    let first = session.map_overview(root, serde_json::json!({ "page_size": 600 }));
+    let stale = first["nextCursor"].as_str().unwrap().to_string();
+    std::fs::write(root.join("src/module_0.rs"), "pub fn replaced() {}\n").unwrap();
+    let expired = session.map_overview(
+        root,
+        serde_json::json!({ "page_size": 600, "cursor": stale }),
+    );
+    assert_eq!(expired["isError"], true);
+    assert!(text(&expired).contains("Cursor expired"));
+}
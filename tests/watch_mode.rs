@@ -0,0 +1,137 @@
+//! # Watch Mode Test
+//!
+//! Starts a watch on a small fixture, edits a file under the target and checks that the
+//! written `active_context.xml` picks up the edit, while writes outside the target are
+//! ignored, that edits under an extra target re-slice too, and that each write reports
+//! whether the slice and the repo map changed.
+//!
+//! Run with:
+//! ```
+//! cargo test --test watch_mode
+//! ```
+
+use cortexast::config::Config;
+use cortexast::watch::{watch_slice, write_active_slice, WatchSpec};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn edits_under_the_target_rewrite_the_active_slice() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().to_path_buf();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(root.join("other")).unwrap();
+    std::fs::write(root.join("src/lib.rs"), "pub fn before() {}\n").unwrap();
+
+    let spec = WatchSpec {
+        repo_root: root.clone(),
+        target: PathBuf::from("src"),
+        budget_tokens: 8_000,
+        cfg: Config::default(),
+        skeleton_only: false,
+    };
+    let written = write_active_slice(&spec).unwrap();
+    assert_eq!(written.path, root.join(".cortexast/active_context.xml"));
+    assert!(written.doc.contains("before"));
+    assert!(root.join(".cortexast/active_context.meta.json").exists());
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::channel();
+    let watcher = {
+        let stop = Arc::clone(&stop);
+        std::thread::spawn(move || {
+            watch_slice(&spec, &stop, &mut |update| {
+                let _ = tx.send(update.map(|w| w.doc));
+            })
+        })
+    };
+
+    // Give the watcher time to register before touching files.
+    std::thread::sleep(Duration::from_millis(300));
+    std::fs::write(root.join("other/notes.txt"), "unrelated\n").unwrap();
+    std::fs::write(root.join("src/lib.rs"), "pub fn after() {}\n").unwrap();
+
+    let doc = rx.recv_timeout(Duration::from_secs(10)).unwrap().unwrap();
+    assert!(doc.contains("after") && !doc.contains("notes.txt"));
+    let on_disk = std::fs::read_to_string(root.join(".cortexast/active_context.xml")).unwrap();
+    assert_eq!(on_disk, doc);
+
+    stop.store(true, Ordering::Relaxed);
+    watcher.join().unwrap().unwrap();
+}
+
+#[test]
+fn writes_report_what_changed() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().to_path_buf();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/lib.rs"), "pub fn lib() {}\n").unwrap();
+
+    let spec = WatchSpec {
+        repo_root: root.clone(),
+        target: PathBuf::from("src"),
+        budget_tokens: 8_000,
+        cfg: Config::default(),
+        skeleton_only: false,
+    };
+    let first = write_active_slice(&spec).unwrap();
+    assert!(first.changed && first.map_changed && first.structure_changed);
+    assert_eq!(first.map_path, root.join(".cortexast/active_repo_map.json"));
+    let map = std::fs::read_to_string(&first.map_path).unwrap();
+    assert!(map.contains("src/lib.rs"));
+
+    let again = write_active_slice(&spec).unwrap();
+    assert!(!again.changed && !again.map_changed && !again.structure_changed);
+
+    std::fs::write(root.join("src/lib.rs"), "pub fn lib(x: u8) {}\n").unwrap();
+    let edited = write_active_slice(&spec).unwrap();
+    assert!(edited.changed && edited.map_changed && !edited.structure_changed);
+
+    std::fs::write(root.join("src/extra.rs"), "pub fn extra() {}\n").unwrap();
+    let added = write_active_slice(&spec).unwrap();
+    assert!(added.changed && added.structure_changed);
+}
+
+#[test]
+fn edits_under_an_extra_target_rewrite_the_active_slice() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().to_path_buf();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(root.join("proto")).unwrap();
+    std::fs::write(root.join("src/lib.rs"), "pub fn lib() {}\n").unwrap();
+    std::fs::write(root.join("proto/user.proto"), "message Before {}\n").unwrap();
+
+    let mut cfg = Config::default();
+    cfg.scan.extra_targets = vec![PathBuf::from("proto")];
+    let spec = WatchSpec {
+        repo_root: root.clone(),
+        target: PathBuf::from("src"),
+        budget_tokens: 8_000,
+        cfg,
+        skeleton_only: false,
+    };
+    assert!(write_active_slice(&spec).unwrap().doc.contains("Before"));
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::channel();
+    let watcher = {
+        let stop = Arc::clone(&stop);
+        std::thread::spawn(move || {
+            watch_slice(&spec, &stop, &mut |update| {
+                let _ = tx.send(update.map(|w| w.doc));
+            })
+        })
+    };
+
+    std::thread::sleep(Duration::from_millis(300));
+    std::fs::write(root.join("proto/user.proto"), "message After {}\n").unwrap();
+
+    let doc = rx.recv_timeout(Duration::from_secs(10)).unwrap().unwrap();
+    assert!(doc.contains("After"));
+
+    stop.store(true, Ordering::Relaxed);
+    watcher.join().unwrap().unwrap();
+}